@@ -0,0 +1,109 @@
+//! Compares the parsers on torrents of different shapes.
+//!
+//! Run it with `cargo bench --bench parse`. The torrents are:
+//!
+//! - `small`: a single-file fixture of a few kilobytes.
+//! - `multi_file`: 10,000 files and a few pieces.
+//! - `huge_pieces`: a single file with 100,000 pieces, 2 MB of hashes.
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_bytes::ByteBuf;
+use torrust_parse_torrent::models::byte_string::ByteString;
+use torrust_parse_torrent::models::torrent_file::{Torrent, TorrentFile, TorrentInfo};
+use torrust_parse_torrent::models::torrent_ref::TorrentRef;
+use torrust_parse_torrent::parse_torrent_with_options;
+use torrust_parse_torrent::utils::bencode;
+use torrust_parse_torrent::utils::decoder::Backend;
+use torrust_parse_torrent::utils::parse_options::ParseOptions;
+use torrust_parse_torrent::utils::parse_torrent::decode_torrent;
+
+fn torrents() -> Vec<(&'static str, Vec<u8>)> {
+    let multi_file = Torrent {
+        info: TorrentInfo {
+            name: ByteString::from("multi-file"),
+            pieces: Some(ByteBuf::from(vec![0; 20 * 10])),
+            piece_length: 16384,
+            length: None,
+            files: Some(
+                (0..10_000)
+                    .map(|index| TorrentFile {
+                        path: vec![
+                            ByteString::from(format!("directory-{}", index / 100)),
+                            ByteString::from(format!("file-{index}.txt")),
+                        ],
+                        length: 16,
+                        ..TorrentFile::default()
+                    })
+                    .collect(),
+            ),
+            ..TorrentInfo::default()
+        },
+        ..Torrent::default()
+    };
+
+    let huge_pieces = Torrent {
+        info: TorrentInfo {
+            name: ByteString::from("huge-pieces"),
+            pieces: Some(ByteBuf::from(vec![0; 20 * 100_000])),
+            piece_length: 16384,
+            length: Some(16384 * 100_000),
+            ..TorrentInfo::default()
+        },
+        ..Torrent::default()
+    };
+
+    vec![
+        (
+            "small",
+            std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap(),
+        ),
+        ("multi_file", multi_file.encode().unwrap()),
+        ("huge_pieces", huge_pieces.encode().unwrap()),
+    ]
+}
+
+fn parsers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for (name, bytes) in torrents() {
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        for (parser, options) in [
+            ("serde", ParseOptions::new()),
+            ("verbose", ParseOptions::new().backend(Backend::Verbose)),
+            ("serde_skip_pieces", ParseOptions::new().skip_pieces(true)),
+            (
+                "verbose_skip_pieces",
+                ParseOptions::new()
+                    .backend(Backend::Verbose)
+                    .skip_pieces(true),
+            ),
+        ] {
+            group.bench_with_input(BenchmarkId::new(parser, name), &bytes, |b, bytes| {
+                b.iter(|| parse_torrent_with_options(black_box(bytes), &options).unwrap());
+            });
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("decode_torrent", name),
+            &bytes,
+            |b, bytes| {
+                b.iter(|| decode_torrent(black_box(bytes)).unwrap());
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("torrent_ref", name), &bytes, |b, bytes| {
+            b.iter(|| TorrentRef::parse(black_box(bytes)).unwrap());
+        });
+
+        group.bench_with_input(BenchmarkId::new("info_span", name), &bytes, |b, bytes| {
+            b.iter(|| bencode::info_span(black_box(bytes)).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parsers);
+criterion_main!(benches);
@@ -0,0 +1,1252 @@
+//! Creation of torrents from files on disk.
+//!
+//! ```no_run
+//! use torrust_parse_torrent::builder::TorrentBuilder;
+//!
+//! let torrent = TorrentBuilder::new("./content")
+//!     .announce("udp://tracker.example.com:6969/announce")
+//!     .private(true)
+//!     .build()
+//!     .unwrap();
+//!
+//! std::fs::write("content.torrent", torrent.encode().unwrap()).unwrap();
+//! ```
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use serde_bytes::ByteBuf;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use crate::models::byte_string::ByteString;
+use crate::models::file_tree::{FileTree, FileTreeFile, FileTreeNode};
+use crate::models::piece_layers::PieceLayers;
+use crate::models::torrent_file::{ProtocolVersion, Torrent, TorrentFile, TorrentInfo};
+use crate::progress::{NoProgress, ProgressHandler, ProgressTracker};
+use crate::utils::file_bytes;
+use crate::utils::merkle::{self, Hash};
+
+/// Smallest piece length allowed by BEP 52, also used for v1 torrents.
+pub const MIN_PIECE_LENGTH: u64 = 16 * 1024;
+
+/// Bytes of content read at once to be hashed in parallel.
+const BATCH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Largest piece length [`auto_piece_length`] chooses.
+pub const MAX_AUTO_PIECE_LENGTH: u64 = 16 * 1024 * 1024;
+
+/// Most pieces [`auto_piece_length`] aims for. Small enough to keep the
+/// torrent file small, large enough to give peers many pieces to exchange.
+const MAX_AUTO_PIECES: u64 = 2000;
+
+/// Returns the piece length for content of `total_size` bytes: the smallest
+/// power of two that gives at most 2000 pieces, so there are between 1000
+/// and 2000 of them, clamped between 16 KiB and 16 MiB.
+#[must_use]
+pub fn auto_piece_length(total_size: u64) -> u64 {
+    let mut piece_length = MIN_PIECE_LENGTH;
+    while piece_length < MAX_AUTO_PIECE_LENGTH
+        && total_size.div_ceil(piece_length) > MAX_AUTO_PIECES
+    {
+        piece_length *= 2;
+    }
+    piece_length
+}
+
+/// Errors that can occur while creating a torrent.
+#[derive(Error, Debug)]
+pub enum BuildError {
+    /// A file or directory of the content could not be read.
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// The path has no file name, like `/` or `..`.
+    #[error("{0} has no file name")]
+    NoName(PathBuf),
+    /// The content has no files or all of them are empty.
+    #[error("{0} has no content")]
+    EmptyContent(PathBuf),
+    /// The piece length is not a power of two of at least 16 KiB.
+    #[error("invalid piece length {0}: it must be a power of two of at least 16 KiB")]
+    InvalidPieceLength(u64),
+    /// An include or exclude pattern is not a valid glob.
+    #[error("invalid pattern: {0}")]
+    Pattern(#[from] globset::Error),
+    /// The thread pool to hash the pieces could not be created.
+    #[error("unable to start the hashing threads: {0}")]
+    ThreadPool(rayon::ThreadPoolBuildError),
+}
+
+/// Builder that walks a file or a directory, hashes its pieces and returns
+/// a v1, v2 or hybrid [`Torrent`].
+///
+/// Files of a directory are sorted bytewise by path, so building the same
+/// content twice gives the same info-hash. With
+/// [`reproducible`](TorrentBuilder::reproducible) the whole torrent is
+/// byte-identical.
+#[derive(Debug, Clone)]
+pub struct TorrentBuilder {
+    path: PathBuf,
+    piece_length: Option<u64>,
+    trackers: Vec<String>,
+    private: bool,
+    comment: Option<String>,
+    created_by: Option<String>,
+    creation_date: Option<i64>,
+    source: Option<String>,
+    web_seeds: Vec<String>,
+    threads: usize,
+    version: ProtocolVersion,
+    pad_files: bool,
+    reproducible: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    hidden: bool,
+    follow_symlinks: bool,
+}
+
+impl TorrentBuilder {
+    /// Creates a builder for the file or directory at `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            piece_length: None,
+            trackers: vec![],
+            private: false,
+            comment: None,
+            created_by: Some(format!(
+                "{}/{}",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            )),
+            creation_date: None,
+            source: None,
+            web_seeds: vec![],
+            threads: 0,
+            version: ProtocolVersion::V1,
+            pad_files: false,
+            reproducible: false,
+            include: vec![],
+            exclude: vec![],
+            hidden: true,
+            follow_symlinks: true,
+        }
+    }
+
+    /// Sets the number of bytes in each piece. By default it's chosen with
+    /// [`auto_piece_length`].
+    #[must_use]
+    pub fn piece_length(mut self, piece_length: u64) -> Self {
+        self.piece_length = Some(piece_length);
+        self
+    }
+
+    /// Adds a tracker. The first one is the `announce` URL. If there is more
+    /// than one, each of them gets its own tier in the `announce-list`.
+    #[must_use]
+    pub fn announce(mut self, url: impl Into<String>) -> Self {
+        self.trackers.push(url.into());
+        self
+    }
+
+    /// Sets the BEP 27 private flag.
+    #[must_use]
+    pub fn private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    /// Sets the free-form `comment` key.
+    #[must_use]
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Sets the `created by` key. It defaults to the name and version of this
+    /// crate; `None` leaves it out.
+    #[must_use]
+    pub fn created_by(mut self, created_by: Option<String>) -> Self {
+        self.created_by = created_by;
+        self
+    }
+
+    /// Sets the `creation date` key, as a Unix timestamp.
+    #[must_use]
+    pub fn creation_date(mut self, creation_date: i64) -> Self {
+        self.creation_date = Some(creation_date);
+        self
+    }
+
+    /// Sets the `source` key of the info dictionary, which private trackers
+    /// use to give each tracker a different info-hash.
+    #[must_use]
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Adds a BEP 19 web seed (`url-list`).
+    #[must_use]
+    pub fn web_seed(mut self, url: impl Into<String>) -> Self {
+        self.web_seeds.push(url.into());
+        self
+    }
+
+    /// Sets the number of threads that hash the pieces. `0`, the default,
+    /// uses one per CPU. `1` hashes them on the calling thread, which is
+    /// easier to debug.
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the protocol version of the torrent: v1 (the default), v2 or
+    /// hybrid. Hybrid torrents contain padding files so that every file
+    /// starts on a piece boundary in the v1 metadata too.
+    #[must_use]
+    pub fn version(mut self, version: ProtocolVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Adds BEP 47 padding files to the v1 metadata, so that every file
+    /// starts on a piece boundary and can be verified on its own. Hybrid
+    /// torrents always have them.
+    #[must_use]
+    pub fn pad_to_piece_boundary(mut self, pad_files: bool) -> Self {
+        self.pad_files = pad_files;
+        self
+    }
+
+    /// Makes the output depend only on the content and the options: the
+    /// `creation date` is left out and `created by` is the name of this crate,
+    /// without its version. Building the same content twice, on any machine,
+    /// gives byte-identical torrents.
+    #[must_use]
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// Adds a glob pattern, like `data/**`, matched against the paths of the
+    /// files relative to the directory. If there are any, only the files
+    /// that match one of them are added.
+    #[must_use]
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Adds a glob pattern, like `*.nfo`, of files or directories to leave
+    /// out. It's matched against the paths relative to the directory, and `*`
+    /// also matches `/`.
+    #[must_use]
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Sets whether the files and directories whose name starts with a dot
+    /// are added. They are by default.
+    #[must_use]
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Sets whether symbolic links in the directory are followed, the
+    /// default, or left out. Links to one of their parent directories are
+    /// always left out.
+    #[must_use]
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Reads the content and returns the torrent.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the piece length or a pattern is not valid,
+    /// the content can't be read or it's empty.
+    pub fn build(&self) -> Result<Torrent, BuildError> {
+        self.build_with_progress(&NoProgress)
+    }
+
+    /// Like [`build`](TorrentBuilder::build), but reports the progress of the
+    /// hashing to `progress`. Hybrid torrents read the content twice, once
+    /// for each version.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the piece length or a pattern is not valid,
+    /// the content can't be read or it's empty.
+    pub fn build_with_progress(
+        &self,
+        progress: &dyn ProgressHandler,
+    ) -> Result<Torrent, BuildError> {
+        if let Some(piece_length) = self.piece_length {
+            if piece_length < MIN_PIECE_LENGTH || !piece_length.is_power_of_two() {
+                return Err(BuildError::InvalidPieceLength(piece_length));
+            }
+        }
+
+        let name = self
+            .path
+            .file_name()
+            .ok_or_else(|| BuildError::NoName(self.path.clone()))?;
+
+        let metadata = fs::metadata(&self.path).map_err(|source| BuildError::Io {
+            path: self.path.clone(),
+            source,
+        })?;
+
+        let files = if metadata.is_dir() {
+            let filter = WalkFilter {
+                include: (!self.include.is_empty())
+                    .then(|| glob_set(&self.include))
+                    .transpose()?,
+                exclude: glob_set(&self.exclude)?,
+                hidden: self.hidden,
+                follow_symlinks: self.follow_symlinks,
+            };
+            let mut walker = Walker {
+                filter: &filter,
+                prefix: vec![],
+                ancestors: vec![],
+                files: vec![],
+            };
+            walker.walk(&self.path)?;
+            walker.files
+        } else {
+            vec![ContentFile {
+                path: self.path.clone(),
+                components: vec![],
+                length: metadata.len(),
+                padding: false,
+            }]
+        };
+
+        if files.iter().all(|file| file.length == 0) {
+            return Err(BuildError::EmptyContent(self.path.clone()));
+        }
+
+        let piece_length = self
+            .piece_length
+            .unwrap_or_else(|| auto_piece_length(files.iter().map(|file| file.length).sum()));
+
+        let pool = thread_pool(self.threads).map_err(BuildError::ThreadPool)?;
+
+        let mut info = TorrentInfo {
+            name: ByteString::from(os_str_bytes(name)),
+            pieces: None,
+            piece_length,
+            length: None,
+            private: self.private.then_some(1),
+            source: self.source.as_deref().map(ByteString::from),
+            ..TorrentInfo::default()
+        };
+        let mut piece_layers = None;
+
+        let v1_files = match self.version {
+            ProtocolVersion::V2 => None,
+            ProtocolVersion::Hybrid => Some(with_padding(files.clone(), piece_length)),
+            ProtocolVersion::V1 if self.pad_files => {
+                Some(with_padding(files.clone(), piece_length))
+            }
+            ProtocolVersion::V1 => Some(files.clone()),
+        };
+
+        let total_length = |files: &[ContentFile]| files.iter().map(|file| file.length).sum();
+        let progress = ProgressTracker::start(
+            progress,
+            v1_files.as_deref().map_or(0, total_length)
+                + if self.version == ProtocolVersion::V1 {
+                    0
+                } else {
+                    total_length(&files)
+                },
+        );
+
+        if let Some(v1_files) = v1_files {
+            info.pieces = Some(ByteBuf::from(hash_pieces(
+                &v1_files,
+                piece_length,
+                pool.as_ref(),
+                &progress,
+            )?));
+
+            if metadata.is_dir() {
+                info.files = Some(v1_files.iter().map(ContentFile::to_torrent_file).collect());
+            } else {
+                info.length = Some(files[0].length);
+            }
+        }
+
+        if self.version != ProtocolVersion::V1 {
+            let (file_tree, layers) =
+                hash_files_v2(&files, &info.name, piece_length, pool.as_ref(), &progress)?;
+            info.meta_version = Some(2);
+            info.file_tree = Some(file_tree);
+            piece_layers = Some(layers);
+        }
+
+        progress.finish();
+
+        let (creation_date, created_by) = if self.reproducible {
+            (None, Some(env!("CARGO_PKG_NAME").to_string()))
+        } else {
+            (self.creation_date, self.created_by.clone())
+        };
+
+        Ok(Torrent {
+            info,
+            announce: self.trackers.first().cloned(),
+            announce_list: (self.trackers.len() > 1)
+                .then(|| self.trackers.iter().map(|url| vec![url.clone()]).collect()),
+            creation_date,
+            comment: self.comment.as_deref().map(ByteString::from),
+            created_by: created_by.as_deref().map(ByteString::from),
+            url_list: (!self.web_seeds.is_empty()).then(|| self.web_seeds.clone()),
+            piece_layers,
+            ..Torrent::default()
+        })
+    }
+}
+
+/// A file of the content, in the order it's hashed.
+#[derive(Clone)]
+struct ContentFile {
+    path: PathBuf,
+    /// Path relative to the content directory. Empty for single-file torrents.
+    components: Vec<ByteString>,
+    length: u64,
+    /// BEP 47 padding file, read as zeros. It doesn't exist on disk.
+    padding: bool,
+}
+
+impl ContentFile {
+    fn to_torrent_file(&self) -> TorrentFile {
+        TorrentFile {
+            path: self.components.clone(),
+            length: self.length,
+            attr: self.padding.then(|| "p".to_string()),
+            ..TorrentFile::default()
+        }
+    }
+
+    fn open(&self, progress: &ProgressTracker) -> Result<Box<dyn Read>, BuildError> {
+        if self.padding {
+            return Ok(Box::new(io::repeat(0).take(self.length)));
+        }
+
+        progress.open_file(&self.path);
+        file_bytes::reader(&self.path).map_err(|source| self.io_error(source))
+    }
+
+    fn io_error(&self, source: io::Error) -> BuildError {
+        BuildError::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+/// Which entries of the directory are added to the torrent.
+struct WalkFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    hidden: bool,
+    follow_symlinks: bool,
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
+
+/// Collects the files of a directory recursively, sorted by path.
+struct Walker<'a> {
+    filter: &'a WalkFilter,
+    /// Path of the current directory, relative to the content directory.
+    prefix: Vec<ByteString>,
+    /// Canonical paths of the current directory and its parents, to detect
+    /// symbolic links to them.
+    ancestors: Vec<PathBuf>,
+    files: Vec<ContentFile>,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, directory: &Path) -> Result<(), BuildError> {
+        let io_error = |source| BuildError::Io {
+            path: directory.to_path_buf(),
+            source,
+        };
+
+        let canonical = fs::canonicalize(directory).map_err(io_error)?;
+        if self.ancestors.contains(&canonical) {
+            return Ok(());
+        }
+
+        let mut entries = fs::read_dir(directory)
+            .map_err(io_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io_error)?;
+        entries.sort_by_cached_key(|entry| os_str_bytes(&entry.file_name()));
+
+        self.ancestors.push(canonical);
+        for entry in entries {
+            let name = ByteString::from(os_str_bytes(&entry.file_name()));
+            if !self.filter.hidden && name.as_bytes().starts_with(b".") {
+                continue;
+            }
+
+            let path = entry.path();
+            let is_symlink = entry.file_type().is_ok_and(|kind| kind.is_symlink());
+            if is_symlink && !self.filter.follow_symlinks {
+                continue;
+            }
+            let metadata = fs::metadata(&path).map_err(|source| BuildError::Io {
+                path: path.clone(),
+                source,
+            })?;
+
+            self.prefix.push(name);
+            let relative = self
+                .prefix
+                .iter()
+                .map(|component| component.to_str_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if self.filter.exclude.is_match(&relative) {
+                // Left out.
+            } else if metadata.is_dir() {
+                self.walk(&path)?;
+            } else if self
+                .filter
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(&relative))
+            {
+                self.files.push(ContentFile {
+                    path,
+                    components: self.prefix.clone(),
+                    length: metadata.len(),
+                    padding: false,
+                });
+            }
+            self.prefix.pop();
+        }
+        self.ancestors.pop();
+
+        Ok(())
+    }
+}
+
+/// Adds a padding file after each file that doesn't end on a piece
+/// boundary, except the last one, like libtorrent does. The padding files
+/// are named `.pad/<length>`.
+fn with_padding(files: Vec<ContentFile>, piece_length: u64) -> Vec<ContentFile> {
+    let count = files.len();
+    let mut padded = Vec::with_capacity(count * 2);
+
+    for (index, file) in files.into_iter().enumerate() {
+        let remainder = file.length % piece_length;
+        padded.push(file);
+
+        if remainder != 0 && index + 1 < count {
+            let length = piece_length - remainder;
+            padded.push(ContentFile {
+                path: PathBuf::new(),
+                components: vec![
+                    ByteString::from(".pad"),
+                    ByteString::from(length.to_string()),
+                ],
+                length,
+                padding: true,
+            });
+        }
+    }
+
+    padded
+}
+
+/// Returns the pool that hashes the pieces, or `None` to hash them on the
+/// calling thread. `0` threads means one per CPU.
+pub(crate) fn thread_pool(
+    threads: usize,
+) -> Result<Option<rayon::ThreadPool>, rayon::ThreadPoolBuildError> {
+    if threads == 1 {
+        return Ok(None);
+    }
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map(Some)
+}
+
+/// Hashes the content as a single stream split in pieces, as BEP 3 does.
+///
+/// Pieces are read in order and, if there is a `pool`, hashed in batches on
+/// it. The result is the same in both cases.
+fn hash_pieces(
+    files: &[ContentFile],
+    piece_length: u64,
+    pool: Option<&rayon::ThreadPool>,
+    progress: &ProgressTracker,
+) -> Result<Vec<u8>, BuildError> {
+    let piece_length = usize::try_from(piece_length).expect("piece length should fit in usize");
+    let mut reader = PieceReader::new(files, piece_length, progress);
+    let mut pieces = vec![];
+
+    let hash_piece = |piece: &Vec<u8>| {
+        let hash = Sha1::digest(piece);
+        progress.advance(piece.len() as u64);
+        hash
+    };
+
+    let Some(pool) = pool else {
+        while let Some(piece) = reader.next_piece()? {
+            pieces.extend_from_slice(&hash_piece(&piece));
+        }
+        return Ok(pieces);
+    };
+
+    // Enough pieces to keep every thread busy, without holding more than
+    // `BATCH_SIZE` bytes in memory unless the pieces are very large.
+    let batch_len = pool.current_num_threads().max(BATCH_SIZE / piece_length);
+
+    loop {
+        let mut batch = Vec::with_capacity(batch_len);
+        while batch.len() < batch_len {
+            match reader.next_piece()? {
+                Some(piece) => batch.push(piece),
+                None => break,
+            }
+        }
+        if batch.is_empty() {
+            break;
+        }
+
+        let hashes: Vec<_> = pool.install(|| batch.par_iter().map(hash_piece).collect());
+        for hash in hashes {
+            pieces.extend_from_slice(&hash);
+        }
+    }
+
+    Ok(pieces)
+}
+
+/// Reads the content as a single stream split in pieces.
+struct PieceReader<'a> {
+    files: std::slice::Iter<'a, ContentFile>,
+    current: Option<(&'a ContentFile, Box<dyn Read>)>,
+    piece_length: usize,
+    progress: &'a ProgressTracker<'a>,
+}
+
+impl<'a> PieceReader<'a> {
+    fn new(
+        files: &'a [ContentFile],
+        piece_length: usize,
+        progress: &'a ProgressTracker<'a>,
+    ) -> Self {
+        Self {
+            files: files.iter(),
+            current: None,
+            piece_length,
+            progress,
+        }
+    }
+
+    /// Returns the next piece. The last one can be shorter.
+    fn next_piece(&mut self) -> Result<Option<Vec<u8>>, BuildError> {
+        let mut piece = vec![0; self.piece_length];
+        let mut filled = 0;
+
+        while filled < self.piece_length {
+            let (file, reader) = match &mut self.current {
+                Some(current) => current,
+                None => match self.files.next() {
+                    Some(file) => self.current.insert((file, file.open(self.progress)?)),
+                    None => break,
+                },
+            };
+
+            let read = reader
+                .read(&mut piece[filled..])
+                .map_err(|source| file.io_error(source))?;
+            if read == 0 {
+                self.current = None;
+            }
+            filled += read;
+        }
+
+        if filled == 0 {
+            return Ok(None);
+        }
+        piece.truncate(filled);
+        Ok(Some(piece))
+    }
+}
+
+/// Hashes each file on its own, as BEP 52 does, and returns the file tree
+/// and the piece layers.
+fn hash_files_v2(
+    files: &[ContentFile],
+    name: &ByteString,
+    piece_length: u64,
+    pool: Option<&rayon::ThreadPool>,
+    progress: &ProgressTracker,
+) -> Result<(FileTree, PieceLayers), BuildError> {
+    let hash_file = |file| hash_blocks(file, progress);
+    let leaves: Vec<Vec<Hash>> = match pool {
+        Some(pool) => pool.install(|| files.par_iter().map(hash_file).collect::<Result<_, _>>())?,
+        None => files.iter().map(hash_file).collect::<Result<_, _>>()?,
+    };
+
+    let mut file_tree = FileTree::default();
+    let mut piece_layers = PieceLayers::default();
+
+    for (file, leaves) in files.iter().zip(leaves) {
+        let pieces_root = (!leaves.is_empty()).then(|| merkle::pieces_root(&leaves));
+
+        if let Some(pieces_root) = pieces_root {
+            if file.length > piece_length {
+                let layer = merkle::piece_layer(
+                    &leaves,
+                    usize::try_from(piece_length).expect("piece length should fit in usize"),
+                );
+                piece_layers.0.insert(
+                    ByteBuf::from(pieces_root.to_vec()),
+                    ByteBuf::from(layer.concat()),
+                );
+            }
+        }
+
+        // Single-file torrents have the name of the file as the only path
+        // element.
+        let mut path: Vec<String> = file
+            .components
+            .iter()
+            .map(|component| component.to_str_lossy().into_owned())
+            .collect();
+        if path.is_empty() {
+            path.push(name.to_str_lossy().into_owned());
+        }
+
+        insert_file(
+            &mut file_tree,
+            &path,
+            FileTreeFile {
+                length: file.length,
+                pieces_root: pieces_root.map(|root| ByteBuf::from(root.to_vec())),
+                attr: None,
+                extra_fields: BTreeMap::new(),
+            },
+        );
+    }
+
+    Ok((file_tree, piece_layers))
+}
+
+/// Blocks hashed between two progress reports, 1 MiB.
+const BLOCKS_PER_REPORT: usize = 64;
+
+/// Returns the hashes of the 16 KiB blocks of a file.
+fn hash_blocks(file: &ContentFile, progress: &ProgressTracker) -> Result<Vec<Hash>, BuildError> {
+    let mut reader = file.open(progress)?;
+    let mut leaves = vec![];
+    let mut block = vec![0; merkle::BLOCK_SIZE];
+
+    loop {
+        let mut filled = 0;
+        while filled < block.len() {
+            let read = reader
+                .read(&mut block[filled..])
+                .map_err(|source| file.io_error(source))?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+
+        if filled == 0 {
+            break;
+        }
+        leaves.push(merkle::hash_block(&block[..filled]));
+        if leaves.len() % BLOCKS_PER_REPORT == 0 {
+            progress.advance((BLOCKS_PER_REPORT * merkle::BLOCK_SIZE) as u64);
+        }
+        if filled < block.len() {
+            break;
+        }
+    }
+
+    let reported =
+        (leaves.len() / BLOCKS_PER_REPORT * BLOCKS_PER_REPORT * merkle::BLOCK_SIZE) as u64;
+    progress.advance(file.length.saturating_sub(reported));
+
+    Ok(leaves)
+}
+
+fn insert_file(directory: &mut FileTree, path: &[String], file: FileTreeFile) {
+    match path {
+        [] => {}
+        [name] => {
+            directory.0.insert(name.clone(), FileTreeNode::File(file));
+        }
+        [name, rest @ ..] => {
+            let node = directory
+                .0
+                .entry(name.clone())
+                .or_insert_with(|| FileTreeNode::Directory(FileTree::default()));
+            if let FileTreeNode::Directory(subdirectory) = node {
+                insert_file(subdirectory, rest, file);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn os_str_bytes(os_str: &OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    os_str.as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes(os_str: &OsStr) -> Vec<u8> {
+    os_str.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use sha1::{Digest, Sha1};
+    use sha2::Sha256;
+
+    use super::{auto_piece_length, BuildError, TorrentBuilder};
+    use crate::models::file_tree::FileTreeNode;
+    use crate::models::torrent_file::ProtocolVersion;
+    use crate::parse_torrent;
+    use crate::progress::{Progress, ProgressHandler};
+    use crate::test_helpers::test_dir;
+    use crate::utils::validate::validate;
+
+    #[test]
+    fn it_should_create_a_single_file_torrent() {
+        let dir = test_dir("single-file");
+        let content = vec![7; 20000];
+        fs::write(dir.join("a.bin"), &content).unwrap();
+
+        let torrent = TorrentBuilder::new(dir.join("a.bin"))
+            .piece_length(16384)
+            .announce("udp://t.io:6969")
+            .build()
+            .unwrap();
+
+        assert_eq!(torrent.info.name, "a.bin");
+        assert_eq!(torrent.info.length, Some(20000));
+        assert_eq!(torrent.announce.as_deref(), Some("udp://t.io:6969"));
+        assert_eq!(torrent.announce_list, None);
+
+        let mut pieces = Sha1::digest(&content[..16384]).to_vec();
+        pieces.extend_from_slice(&Sha1::digest(&content[16384..]));
+        assert_eq!(torrent.info.pieces.unwrap().into_vec(), pieces);
+    }
+
+    #[test]
+    fn it_should_create_a_multi_file_torrent_that_parses_back() {
+        let dir = test_dir("multi-file");
+        fs::create_dir_all(dir.join("content/sub")).unwrap();
+        fs::write(dir.join("content/b.txt"), b"bb").unwrap();
+        fs::write(dir.join("content/sub/a.txt"), b"a").unwrap();
+
+        let torrent = TorrentBuilder::new(dir.join("content"))
+            .announce("udp://t1.io:6969")
+            .announce("udp://t2.io:6969")
+            .private(true)
+            .comment("test")
+            .build()
+            .unwrap();
+
+        let files = torrent.info.files.as_ref().unwrap();
+        assert_eq!(files[0].path, ["b.txt"]);
+        assert_eq!(files[1].path, ["sub", "a.txt"]);
+        assert_eq!(torrent.file_size(), 3);
+        assert_eq!(torrent.info.private, Some(1));
+        assert_eq!(torrent.announce_list.as_ref().unwrap().len(), 2);
+
+        let bytes = torrent.encode().unwrap();
+        let value = crate::utils::bencode::decode(&bytes).unwrap();
+        assert_eq!(validate(&value), vec![]);
+        assert_eq!(
+            parse_torrent(&bytes).unwrap().info_hash(),
+            torrent.info_hash()
+        );
+    }
+
+    #[test]
+    fn it_should_hash_the_pieces_in_parallel_like_sequentially() {
+        let dir = test_dir("parallel");
+        fs::create_dir_all(dir.join("content")).unwrap();
+        let mut content = vec![];
+        for (index, length) in [10_000, 50_000, 1, 70_000].into_iter().enumerate() {
+            let file: Vec<u8> = (0..length).map(|byte| (byte * (index + 1)) as u8).collect();
+            fs::write(dir.join(format!("content/{index}")), &file).unwrap();
+            content.extend(file);
+        }
+
+        let builder = TorrentBuilder::new(dir.join("content")).piece_length(16384);
+        let sequential = builder.clone().threads(1).build().unwrap();
+        let parallel = builder.threads(4).build().unwrap();
+
+        let expected: Vec<u8> = content
+            .chunks(16384)
+            .flat_map(|piece| Sha1::digest(piece).to_vec())
+            .collect();
+        assert_eq!(
+            sequential.info.pieces.as_ref().unwrap().as_slice(),
+            expected
+        );
+        assert_eq!(parallel.info.pieces, sequential.info.pieces);
+    }
+
+    #[test]
+    fn it_should_create_a_v2_torrent() {
+        let dir = test_dir("v2");
+        let content = vec![1; 20000];
+        fs::write(dir.join("a.bin"), &content).unwrap();
+
+        let torrent = TorrentBuilder::new(dir.join("a.bin"))
+            .piece_length(16384)
+            .version(ProtocolVersion::V2)
+            .build()
+            .unwrap();
+
+        assert_eq!(torrent.protocol_version(), ProtocolVersion::V2);
+        assert_eq!(torrent.info.pieces, None);
+        assert_eq!(torrent.info.length, None);
+
+        // Two blocks, so the root is the hash of both leaves, and the piece
+        // layer contains both leaves because pieces are one block long.
+        let leaves = [
+            Sha256::digest(&content[..16384]),
+            Sha256::digest(&content[16384..]),
+        ];
+        let root = Sha256::digest([leaves[0], leaves[1]].concat());
+
+        let Some(FileTreeNode::File(file)) =
+            torrent.info.file_tree.as_ref().unwrap().0.get("a.bin")
+        else {
+            panic!("a.bin should be a file");
+        };
+        assert_eq!(file.length, 20000);
+        assert_eq!(
+            file.pieces_root.as_ref().unwrap().as_slice(),
+            root.as_slice()
+        );
+        assert_eq!(
+            torrent.piece_layers.as_ref().unwrap().get(&root),
+            Some([leaves[0], leaves[1]].concat().as_slice())
+        );
+
+        let bytes = torrent.encode().unwrap();
+        assert_eq!(
+            parse_torrent(&bytes).unwrap().info_hash_v2(),
+            torrent.info_hash_v2()
+        );
+    }
+
+    #[test]
+    fn it_should_create_a_hybrid_torrent_with_padding_files() {
+        let dir = test_dir("hybrid");
+        fs::create_dir_all(dir.join("content")).unwrap();
+        fs::write(dir.join("content/a"), vec![1; 20000]).unwrap();
+        fs::write(dir.join("content/b"), b"bbbbb").unwrap();
+
+        let torrent = TorrentBuilder::new(dir.join("content"))
+            .piece_length(16384)
+            .version(ProtocolVersion::Hybrid)
+            .threads(2)
+            .build()
+            .unwrap();
+
+        assert_eq!(torrent.protocol_version(), ProtocolVersion::Hybrid);
+
+        let files = torrent.info.files.as_ref().unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[1].path, [".pad", "12768"]);
+        assert!(files[1].is_padding());
+        assert_eq!(torrent.content_size(), 20005);
+
+        let mut v1_content = vec![1; 20000];
+        v1_content.extend(vec![0; 12768]);
+        v1_content.extend(b"bbbbb");
+        let pieces: Vec<u8> = v1_content
+            .chunks(16384)
+            .flat_map(|piece| Sha1::digest(piece).to_vec())
+            .collect();
+        assert_eq!(torrent.info.pieces.as_ref().unwrap().as_slice(), pieces);
+
+        let file_tree = &torrent.info.file_tree.as_ref().unwrap().0;
+        assert_eq!(file_tree.keys().collect::<Vec<_>>(), ["a", "b"]);
+        let Some(FileTreeNode::File(b)) = file_tree.get("b") else {
+            panic!("b should be a file");
+        };
+        assert_eq!(
+            b.pieces_root.as_ref().unwrap().as_slice(),
+            Sha256::digest(b"bbbbb").as_slice()
+        );
+        // Only files bigger than a piece have a piece layer.
+        assert_eq!(torrent.piece_layers.as_ref().unwrap().0.len(), 1);
+
+        let bytes = torrent.encode().unwrap();
+        let value = crate::utils::bencode::decode(&bytes).unwrap();
+        assert_eq!(validate(&value), vec![]);
+    }
+
+    #[test]
+    fn it_should_pad_the_files_of_a_v1_torrent_like_the_hybrid_ones() {
+        let dir = test_dir("pad-files");
+        fs::create_dir_all(dir.join("content")).unwrap();
+        fs::write(dir.join("content/a"), vec![1; 20000]).unwrap();
+        fs::write(dir.join("content/b"), vec![2; 16384]).unwrap();
+        fs::write(dir.join("content/c"), b"c").unwrap();
+
+        let builder = TorrentBuilder::new(dir.join("content")).piece_length(16384);
+        let padded = builder.clone().pad_to_piece_boundary(true).build().unwrap();
+        let hybrid = builder.version(ProtocolVersion::Hybrid).build().unwrap();
+
+        let paths: Vec<_> = padded
+            .info
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| {
+                file.path
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect();
+        // `b` ends on a piece boundary and `c` is the last file.
+        assert_eq!(paths, ["a", ".pad/12768", "b", "c"]);
+        assert_eq!(padded.info.files, hybrid.info.files);
+        assert_eq!(padded.info.pieces, hybrid.info.pieces);
+    }
+
+    #[test]
+    fn it_should_create_byte_identical_torrents_in_reproducible_mode() {
+        let dir = test_dir("reproducible");
+        fs::create_dir_all(dir.join("content/sub")).unwrap();
+        fs::write(dir.join("content/c"), b"c").unwrap();
+        fs::write(dir.join("content/B"), b"B").unwrap();
+        fs::write(dir.join("content/sub/a"), b"a").unwrap();
+
+        let build = |creation_date| {
+            TorrentBuilder::new(dir.join("content"))
+                .creation_date(creation_date)
+                .reproducible(true)
+                .build()
+                .unwrap()
+        };
+        let first = build(1);
+        let second = build(2);
+
+        assert_eq!(first.encode().unwrap(), second.encode().unwrap());
+        assert_eq!(first.creation_date, None);
+        assert_eq!(first.created_by.unwrap(), "torrust-parse-torrent");
+
+        let paths: Vec<_> = first
+            .info
+            .files
+            .unwrap()
+            .iter()
+            .map(|file| {
+                file.path
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect();
+        assert_eq!(paths, ["B", "c", "sub/a"]);
+    }
+
+    #[test]
+    fn it_should_filter_the_files_with_globs_and_leave_out_hidden_ones() {
+        let dir = test_dir("filters");
+        fs::create_dir_all(dir.join("content/data/.cache")).unwrap();
+        fs::create_dir_all(dir.join("content/extra")).unwrap();
+        fs::write(dir.join("content/data/a.bin"), b"a").unwrap();
+        fs::write(dir.join("content/data/info.nfo"), b"nfo").unwrap();
+        fs::write(dir.join("content/data/.cache/b.bin"), b"b").unwrap();
+        fs::write(dir.join("content/data/.hidden"), b"h").unwrap();
+        fs::write(dir.join("content/extra/c.bin"), b"c").unwrap();
+
+        let paths = |builder: TorrentBuilder| -> Vec<String> {
+            builder
+                .build()
+                .unwrap()
+                .info
+                .files
+                .unwrap()
+                .iter()
+                .map(|file| {
+                    file.path
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .collect()
+        };
+        let builder = TorrentBuilder::new(dir.join("content"));
+
+        assert_eq!(
+            paths(builder.clone().include("data/**").exclude("*.nfo")),
+            ["data/.cache/b.bin", "data/.hidden", "data/a.bin"]
+        );
+        assert_eq!(
+            paths(builder.clone().exclude("extra").hidden(false)),
+            ["data/a.bin", "data/info.nfo"]
+        );
+        assert!(matches!(
+            builder.exclude("[").build(),
+            Err(BuildError::Pattern(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_should_follow_symlinks_but_not_loops() {
+        let dir = test_dir("symlinks");
+        fs::create_dir_all(dir.join("content/sub")).unwrap();
+        fs::write(dir.join("outside"), b"outside").unwrap();
+        fs::write(dir.join("content/sub/a"), b"a").unwrap();
+        std::os::unix::fs::symlink(dir.join("outside"), dir.join("content/link")).unwrap();
+        std::os::unix::fs::symlink(dir.join("content"), dir.join("content/sub/loop")).unwrap();
+
+        let builder = TorrentBuilder::new(dir.join("content"));
+        let count = |builder: TorrentBuilder| builder.build().unwrap().info.files.unwrap().len();
+
+        assert_eq!(count(builder.clone()), 2);
+        assert_eq!(count(builder.follow_symlinks(false)), 1);
+    }
+
+    #[test]
+    fn it_should_report_the_progress_of_the_hashing() {
+        #[derive(Default)]
+        struct Recorder {
+            total_bytes: Mutex<u64>,
+            files: Mutex<Vec<PathBuf>>,
+            finished: Mutex<Option<u64>>,
+        }
+
+        impl ProgressHandler for Recorder {
+            fn on_start(&self, total_bytes: u64) {
+                *self.total_bytes.lock().unwrap() = total_bytes;
+            }
+
+            fn on_progress(&self, progress: &Progress<'_>) {
+                let mut files = self.files.lock().unwrap();
+                if let Some(file) = progress.current_file {
+                    if files.last().map(PathBuf::as_path) != Some(file) {
+                        files.push(file.to_path_buf());
+                    }
+                }
+            }
+
+            fn on_finish(&self, progress: &Progress<'_>) {
+                *self.finished.lock().unwrap() = Some(progress.bytes);
+            }
+        }
+
+        let dir = test_dir("progress");
+        fs::create_dir_all(dir.join("content")).unwrap();
+        fs::write(dir.join("content/a"), vec![1; 20000]).unwrap();
+        fs::write(dir.join("content/b"), vec![2; 3 * 1024 * 1024]).unwrap();
+
+        let recorder = Recorder::default();
+        TorrentBuilder::new(dir.join("content"))
+            .piece_length(16384)
+            .threads(1)
+            .version(ProtocolVersion::Hybrid)
+            .build_with_progress(&recorder)
+            .unwrap();
+
+        // The v1 content has a padding file of 12768 bytes after `a`.
+        let v1_length = 20000 + 12768 + 3 * 1024 * 1024;
+        let v2_length = 20000 + 3 * 1024 * 1024;
+        assert_eq!(*recorder.total_bytes.lock().unwrap(), v1_length + v2_length);
+        assert_eq!(
+            *recorder.finished.lock().unwrap(),
+            Some(v1_length + v2_length)
+        );
+        assert_eq!(
+            *recorder.files.lock().unwrap(),
+            [
+                dir.join("content/a"),
+                dir.join("content/b"),
+                dir.join("content/a"),
+                dir.join("content/b")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_choose_a_piece_length_giving_1000_to_2000_pieces() {
+        const MIB: u64 = 1024 * 1024;
+
+        assert_eq!(auto_piece_length(1), 16 * 1024);
+        assert_eq!(auto_piece_length(30 * MIB), 16 * 1024);
+        assert_eq!(auto_piece_length(700 * MIB), 512 * 1024);
+        assert_eq!(auto_piece_length(4 * 1024 * MIB), 4 * MIB);
+        assert_eq!(auto_piece_length(1024 * 1024 * MIB), 16 * MIB);
+
+        for size in [100 * MIB, 1000 * MIB, 10_000 * MIB] {
+            let pieces = size.div_ceil(auto_piece_length(size));
+            assert!((1000..=2000).contains(&pieces), "{size}: {pieces}");
+        }
+    }
+
+    #[test]
+    fn it_should_reject_invalid_piece_lengths_and_empty_content() {
+        let dir = test_dir("invalid");
+        fs::write(dir.join("empty"), b"").unwrap();
+
+        assert!(matches!(
+            TorrentBuilder::new(dir.join("empty"))
+                .piece_length(1000)
+                .build(),
+            Err(BuildError::InvalidPieceLength(1000))
+        ));
+        assert!(matches!(
+            TorrentBuilder::new(dir.join("empty")).build(),
+            Err(BuildError::EmptyContent(_))
+        ));
+    }
+}
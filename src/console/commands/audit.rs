@@ -0,0 +1,50 @@
+//! `audit` subcommand: looks for file paths that could harm the computer
+//! that downloads a torrent.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::audit::audit;
+use torrust_parse_torrent::utils::diagnostics::{has_errors, Diagnostic, Severity};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{exit_code, read_and_decode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files to audit. `-`, the default, reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+}
+
+/// Prints the dangerous and suspicious paths found in each torrent, or `OK`
+/// if there are none.
+///
+/// The exit code is non-zero if any torrent can't be parsed or has dangerous
+/// paths. Suspicious paths alone only make the audit fail in strict mode.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let mut failures = 0;
+
+    for path in &args.paths {
+        let diagnostics = match read_and_decode(path, mode) {
+            Ok(torrent) => audit(&torrent),
+            Err(e) => vec![Diagnostic::error("", e.to_string())],
+        };
+
+        if diagnostics.is_empty() {
+            println!("{path}: OK");
+        }
+
+        for diagnostic in &diagnostics {
+            println!("{path}: {diagnostic}");
+        }
+
+        let has_warnings = diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Warning);
+
+        if has_errors(&diagnostics) || (mode.is_strict() && has_warnings) {
+            failures += 1;
+        }
+    }
+
+    exit_code(failures)
+}
@@ -0,0 +1,629 @@
+//! `batch` subcommand: parses every torrent in some directories and reports
+//! the result of each one.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use rayon::prelude::*;
+use serde_derive::Serialize;
+use sha1::{Digest, Sha1};
+use torrust_parse_torrent::models::torrent_file::Torrent;
+use torrust_parse_torrent::utils::hex;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::files::files;
+use super::{exit_code, read_input, FileDigests};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Number of torrents parsed at the same time. Defaults to one per CPU.
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        hide_default_value = true
+    )]
+    jobs: usize,
+    /// Only report the torrents with the same info-hash, and the ones with
+    /// the same files but different info-hashes, which are likely re-uploads
+    /// with other trackers or flags.
+    #[arg(long)]
+    duplicates: bool,
+    /// Also report the SHA-1 and SHA-256 of each whole `.torrent` file.
+    #[arg(long)]
+    file_digests: bool,
+    /// Torrent files, or directories searched recursively for `*.torrent`
+    /// files.
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<String>,
+}
+
+/// Output formats for the report.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    /// One line per torrent and a final count.
+    Text,
+    /// A single JSON array with one object per torrent.
+    Json,
+    /// CSV with a header row and one row per torrent.
+    Csv,
+}
+
+/// Columns of the CSV output.
+const CSV_HEADER: [&str; 15] = [
+    "path",
+    "status",
+    "info_hash",
+    "info_hash_v2",
+    "name",
+    "size",
+    "files",
+    "piece_length",
+    "private",
+    "trackers",
+    "creation_date",
+    "content_fingerprint",
+    "file_sha1",
+    "file_sha256",
+    "error",
+];
+
+/// The result of parsing one torrent.
+#[derive(Serialize, Debug)]
+struct Entry {
+    path: String,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info_hash_v2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    piece_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trackers: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    creation_date: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_fingerprint: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    file_digests: Option<FileDigests>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Torrents that share an info-hash or a content fingerprint.
+#[derive(Serialize, Debug)]
+struct Group<'a> {
+    key: &'a str,
+    torrents: Vec<Member<'a>>,
+}
+
+#[derive(Serialize, Debug)]
+struct Member<'a> {
+    path: &'a str,
+    info_hash: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct Duplicates<'a> {
+    same_info_hash: Vec<Group<'a>>,
+    same_content: Vec<Group<'a>>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Error,
+}
+
+impl Entry {
+    /// Parses a torrent. A panic while parsing it is reported as its error,
+    /// so it doesn't stop the other torrents.
+    fn parse(path: String, mode: ParseMode, file_digests: bool) -> Self {
+        let bytes = match read_input(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => return Self::error(path, e.to_string()),
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            torrust_parse_torrent::parse_torrent_with_mode(&bytes, mode)
+        }));
+
+        let entry = match result {
+            Ok(Ok(torrent)) => {
+                let info_hashes = torrent.info_hashes();
+                Self {
+                    status: Status::Ok,
+                    info_hash: info_hashes.v1.map(|hash| hash.to_hex_string()),
+                    info_hash_v2: info_hashes.v2.map(|hash| hash.to_hex_string()),
+                    name: Some(torrent.decode_name(None).0.into_owned()),
+                    size: Some(torrent.content_size()),
+                    files: Some(
+                        torrent
+                            .info
+                            .files
+                            .as_ref()
+                            .map_or(1, |_| torrent.content_files().len()),
+                    ),
+                    piece_length: Some(torrent.info.piece_length),
+                    private: Some(torrent.info.private == Some(1)),
+                    trackers: Some(torrent.announce_tiers().iter().count()),
+                    creation_date: torrent.creation_date,
+                    content_fingerprint: Some(content_fingerprint(&torrent)),
+                    file_digests: None,
+                    error: None,
+                    path,
+                }
+            }
+            Ok(Err(e)) => Self::error(path, e.to_string()),
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| (*message).to_owned())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Self::error(path, format!("the parser panicked: {message}"))
+            }
+        };
+
+        Self {
+            file_digests: file_digests.then(|| FileDigests::new(&bytes)),
+            ..entry
+        }
+    }
+
+    /// Returns the v1 info-hash, or the v2 one of the v2-only torrents.
+    fn any_info_hash(&self) -> Option<&str> {
+        self.info_hash.as_deref().or(self.info_hash_v2.as_deref())
+    }
+
+    fn error(path: String, error: String) -> Self {
+        Self {
+            path,
+            status: Status::Error,
+            info_hash: None,
+            info_hash_v2: None,
+            name: None,
+            size: None,
+            files: None,
+            piece_length: None,
+            private: None,
+            trackers: None,
+            creation_date: None,
+            content_fingerprint: None,
+            file_digests: None,
+            error: Some(error),
+        }
+    }
+
+    /// Returns the values of the [`CSV_HEADER`] columns. Missing values are
+    /// empty.
+    fn csv_record(&self) -> [String; CSV_HEADER.len()] {
+        fn optional<T: ToString>(value: Option<T>) -> String {
+            value.map(|value| value.to_string()).unwrap_or_default()
+        }
+
+        [
+            self.path.clone(),
+            match self.status {
+                Status::Ok => "ok".to_owned(),
+                Status::Error => "error".to_owned(),
+            },
+            optional(self.info_hash.as_ref()),
+            optional(self.info_hash_v2.as_ref()),
+            optional(self.name.as_ref()),
+            optional(self.size),
+            optional(self.files),
+            optional(self.piece_length),
+            optional(self.private),
+            optional(self.trackers),
+            optional(self.creation_date),
+            optional(self.content_fingerprint.as_ref()),
+            optional(self.file_digests.as_ref().map(|digests| &digests.sha1)),
+            optional(self.file_digests.as_ref().map(|digests| &digests.sha256)),
+            optional(self.error.as_ref()),
+        ]
+    }
+}
+
+/// Parses the torrents and prints one `<path>  ok  <info-hash>  <size>  <name>`
+/// or `<path>  error  <message>` line per torrent, in the order of the
+/// arguments and, inside a directory, of the paths.
+///
+/// The torrents are parsed by `--jobs` threads, but the report keeps that
+/// order. The exit code is non-zero if any torrent could not be parsed.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    if args.duplicates && args.format == Format::Csv {
+        eprintln!("Error: `--duplicates` can't be used with `--format csv`");
+        return ExitCode::FAILURE;
+    }
+
+    let pool = match rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs)
+        .build()
+    {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let entries = parse_all(inputs(&args.paths), mode, args.file_digests, &pool);
+
+    let failures = entries
+        .iter()
+        .filter(|entry| entry.status == Status::Error)
+        .count();
+
+    if args.duplicates {
+        print_duplicates(&duplicates(&entries), args.format);
+        return exit_code(failures);
+    }
+
+    match args.format {
+        Format::Text => {
+            for entry in &entries {
+                print_entry(entry);
+            }
+            println!("{} torrents, {failures} failed", entries.len());
+        }
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&entries).expect("entries should be serializable")
+        ),
+        Format::Csv => {
+            println!("{}", csv_row(&CSV_HEADER));
+            for entry in &entries {
+                println!("{}", csv_row(&entry.csv_record()));
+            }
+        }
+    }
+
+    exit_code(failures)
+}
+
+fn parse_all(
+    inputs: Vec<Result<PathBuf, (PathBuf, io::Error)>>,
+    mode: ParseMode,
+    file_digests: bool,
+    pool: &rayon::ThreadPool,
+) -> Vec<Entry> {
+    pool.install(|| {
+        inputs
+            .into_par_iter()
+            .map(|input| match input {
+                Ok(path) => Entry::parse(path.to_string_lossy().into_owned(), mode, file_digests),
+                Err((path, e)) => Entry::error(path.to_string_lossy().into_owned(), e.to_string()),
+            })
+            .collect()
+    })
+}
+
+fn print_entry(entry: &Entry) {
+    match &entry.error {
+        None => println!(
+            "{}  ok  {}  {}  {}",
+            entry.path,
+            entry.any_info_hash().unwrap_or_default(),
+            entry.size.unwrap_or_default(),
+            entry.name.as_deref().unwrap_or_default()
+        ),
+        Some(error) => println!("{}  error  {error}", entry.path),
+    }
+}
+
+/// Returns the groups of torrents with the same info-hash, and the groups
+/// with the same content fingerprint and more than one info-hash, in the
+/// order they were found.
+fn duplicates(entries: &[Entry]) -> Duplicates<'_> {
+    fn group_by<'a>(
+        entries: &'a [Entry],
+        key: impl Fn(&'a Entry) -> Option<&'a str>,
+    ) -> Vec<Group<'a>> {
+        let mut groups: Vec<Group<'a>> = vec![];
+        let mut positions = HashMap::new();
+
+        for entry in entries {
+            let (Some(key), Some(info_hash)) = (key(entry), entry.any_info_hash()) else {
+                continue;
+            };
+            let position = *positions.entry(key).or_insert_with(|| {
+                groups.push(Group {
+                    key,
+                    torrents: vec![],
+                });
+                groups.len() - 1
+            });
+            groups[position].torrents.push(Member {
+                path: &entry.path,
+                info_hash,
+            });
+        }
+
+        groups.retain(|group| group.torrents.len() > 1);
+        groups
+    }
+
+    let mut same_content = group_by(entries, |entry| entry.content_fingerprint.as_deref());
+    same_content.retain(|group| {
+        group
+            .torrents
+            .iter()
+            .any(|member| member.info_hash != group.torrents[0].info_hash)
+    });
+
+    Duplicates {
+        same_info_hash: group_by(entries, Entry::any_info_hash),
+        same_content,
+    }
+}
+
+fn print_duplicates(duplicates: &Duplicates, format: Format) {
+    if format == Format::Json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(duplicates).expect("duplicates should be serializable")
+        );
+        return;
+    }
+
+    for group in &duplicates.same_info_hash {
+        println!("same info-hash {}", group.key);
+        for member in &group.torrents {
+            println!("  {}", member.path);
+        }
+    }
+    for group in &duplicates.same_content {
+        println!("same content {}", group.key);
+        for member in &group.torrents {
+            println!("  {}  {}", member.info_hash, member.path);
+        }
+    }
+    println!(
+        "duplicates: {} by info-hash, {} by content",
+        duplicates.same_info_hash.len(),
+        duplicates.same_content.len()
+    );
+}
+
+/// Returns a SHA-1 hash of the paths and sizes of the files, sorted by path.
+/// Torrents of the same content have the same fingerprint even if their
+/// info-hashes differ, for example because they have another `source` or
+/// piece length.
+fn content_fingerprint(torrent: &Torrent) -> String {
+    let mut files = files(torrent, None);
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let mut hasher = Sha1::new();
+    for (length, path) in files {
+        hasher.update(format!("{length}\t{path}\n"));
+    }
+    hex::from_bytes(&hasher.finalize()).to_lowercase()
+}
+
+/// Joins the fields of a CSV row. Fields with commas, quotes or line breaks
+/// are quoted, as RFC 4180 describes.
+fn csv_row<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Returns the torrents to parse. Arguments that are not directories are
+/// returned as they are, so they can also be URLs or `-`.
+pub(super) fn inputs(paths: &[String]) -> Vec<Result<PathBuf, (PathBuf, io::Error)>> {
+    let mut inputs = vec![];
+    for path in paths {
+        let path = PathBuf::from(path);
+        if path.is_dir() {
+            walk(&path, &mut inputs);
+        } else {
+            inputs.push(Ok(path));
+        }
+    }
+    inputs
+}
+
+/// Adds the `*.torrent` files in a directory and its subdirectories, sorted
+/// by path. Symbolic links to directories are not followed, so they can't
+/// make the walk loop.
+fn walk(directory: &Path, inputs: &mut Vec<Result<PathBuf, (PathBuf, io::Error)>>) {
+    let entries = match fs::read_dir(directory).and_then(Iterator::collect::<Result<Vec<_>, _>>) {
+        Ok(entries) => entries,
+        Err(e) => {
+            inputs.push(Err((directory.to_path_buf(), e)));
+            return;
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries.iter().map(fs::DirEntry::path).collect();
+    paths.sort();
+
+    for path in paths {
+        let is_symlink = path
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink());
+        if path.is_dir() {
+            if !is_symlink {
+                walk(&path, inputs);
+            }
+        } else if is_torrent(&path) {
+            inputs.push(Ok(path));
+        }
+    }
+}
+
+fn is_torrent(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("torrent"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use torrust_parse_torrent::builder::TorrentBuilder;
+    use torrust_parse_torrent::models::torrent_file::ProtocolVersion;
+    use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+    use super::{csv_row, duplicates, inputs, parse_all, Entry, FileDigests};
+
+    #[test]
+    fn it_should_find_the_torrents_in_a_directory_recursively() {
+        let directory = std::env::temp_dir().join(format!(
+            "torrust-parse-torrent-batch-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("b")).unwrap();
+        fs::write(directory.join("b/c.TORRENT"), "").unwrap();
+        fs::write(directory.join("a.torrent"), "").unwrap();
+        fs::write(directory.join("notes.txt"), "").unwrap();
+
+        let found: Vec<PathBuf> = inputs(&[directory.to_string_lossy().into_owned(), "-".into()])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                directory.join("a.torrent"),
+                directory.join("b/c.TORRENT"),
+                PathBuf::from("-")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_keep_the_order_of_the_torrents_when_parsing_in_parallel() {
+        let paths = ["tests/fixtures/torrents".to_owned()];
+        let parse = |jobs| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build()
+                .unwrap();
+            parse_all(inputs(&paths), ParseMode::Lenient, false, &pool)
+                .into_iter()
+                .map(|entry| (entry.path, entry.status))
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = parse(1);
+
+        assert_eq!(sequential.len(), 6);
+        assert_eq!(parse(4), sequential);
+    }
+
+    #[test]
+    fn it_should_report_the_digests_of_the_whole_files_when_asked() {
+        let path = "tests/fixtures/torrents/signed.torrent".to_owned();
+        let bytes = fs::read(&path).unwrap();
+
+        let entry = Entry::parse(path.clone(), ParseMode::Lenient, true);
+
+        assert_eq!(entry.file_digests, Some(FileDigests::new(&bytes)));
+        assert_eq!(entry.csv_record()[12], entry.file_digests.unwrap().sha1);
+        assert_eq!(
+            Entry::parse(path, ParseMode::Lenient, false).file_digests,
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_quote_csv_fields_when_needed() {
+        assert_eq!(
+            csv_row(&["a", "b,c", "say \"hi\"", "two\nlines"]),
+            "a,\"b,c\",\"say \"\"hi\"\"\",\"two\nlines\""
+        );
+    }
+
+    #[test]
+    fn it_should_group_duplicates_and_re_uploads() {
+        let entry = |path: &str, info_hash: &str, fingerprint: &str| Entry {
+            info_hash: Some(info_hash.to_owned()),
+            content_fingerprint: Some(fingerprint.to_owned()),
+            error: None,
+            ..Entry::error(path.to_owned(), String::new())
+        };
+        let entries = [
+            entry("a.torrent", "1", "x"),
+            entry("b.torrent", "2", "y"),
+            entry("copy-of-a.torrent", "1", "x"),
+            entry("re-upload-of-b.torrent", "3", "y"),
+            Entry::error("broken.torrent".to_owned(), "invalid".to_owned()),
+        ];
+
+        let duplicates = duplicates(&entries);
+
+        assert_eq!(duplicates.same_info_hash.len(), 1);
+        assert_eq!(duplicates.same_info_hash[0].key, "1");
+        assert_eq!(duplicates.same_content.len(), 1);
+        assert_eq!(duplicates.same_content[0].key, "y");
+        assert_eq!(
+            duplicates.same_content[0].torrents[1].path,
+            "re-upload-of-b.torrent"
+        );
+    }
+
+    #[test]
+    fn it_should_report_and_group_v2_only_torrents_by_their_v2_info_hash() {
+        let content = std::env::temp_dir().join(format!(
+            "torrust-parse-torrent-batch-v2-{}",
+            std::process::id()
+        ));
+        fs::write(&content, vec![1; 20000]).unwrap();
+        let torrent = TorrentBuilder::new(&content)
+            .piece_length(16384)
+            .version(ProtocolVersion::V2)
+            .build()
+            .unwrap();
+        let path = content.with_extension("torrent");
+        fs::write(&path, torrent.encode().unwrap()).unwrap();
+
+        let path = path.to_string_lossy().into_owned();
+        let entries = [
+            Entry::parse(path.clone(), ParseMode::Lenient, false),
+            Entry::parse(path.clone(), ParseMode::Lenient, false),
+        ];
+        fs::remove_file(&content).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let v2 = torrent.info_hashes().v2.unwrap().to_hex_string();
+        assert_eq!(entries[0].info_hash, None);
+        assert_eq!(entries[0].info_hash_v2.as_ref(), Some(&v2));
+
+        let duplicates = duplicates(&entries);
+
+        assert_eq!(duplicates.same_info_hash.len(), 1);
+        assert_eq!(duplicates.same_info_hash[0].key, v2);
+    }
+}
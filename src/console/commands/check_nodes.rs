@@ -0,0 +1,75 @@
+//! `check-nodes` subcommand: tells which DHT nodes of a torrent still answer.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files whose `nodes` are checked. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+    /// Seconds to wait for each node.
+    #[arg(long, value_name = "SECONDS", default_value_t = 10)]
+    timeout: u64,
+}
+
+/// Prints one `<node>  alive|dead  <details>` line per node. The details of
+/// live nodes are the latency, the address that answered and the node ID.
+///
+/// A torrent fails if it has nodes and none of them is alive.
+#[cfg(feature = "dht")]
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    use std::time::Duration;
+
+    use torrust_parse_torrent::dht::check_nodes;
+    use torrust_parse_torrent::models::info_hash::InfoHash;
+
+    use super::{for_each_path, read_and_decode};
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for_each_path(&args.paths, |path| {
+        let torrent = read_and_decode(path, mode)?;
+
+        if args.paths.len() > 1 {
+            println!("{path}:");
+        }
+
+        let checks = runtime.block_on(check_nodes(&torrent, Duration::from_secs(args.timeout)));
+
+        for check in &checks {
+            match &check.result {
+                Ok(ping) => println!(
+                    "{}  alive  {} ms  {}  {}",
+                    check.node,
+                    ping.latency.as_millis(),
+                    ping.addr,
+                    InfoHash(ping.id).to_hex_string()
+                ),
+                Err(e) => println!("{}  dead  {e}", check.node),
+            }
+        }
+
+        if !checks.is_empty() && checks.iter().all(|check| check.result.is_err()) {
+            return Err("no node is alive".into());
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(not(feature = "dht"))]
+pub fn run(_args: &Args, _mode: ParseMode) -> ExitCode {
+    eprintln!("The `check-nodes` subcommand requires building with `--features dht`");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,78 @@
+//! `check-trackers` subcommand: tells which trackers of a torrent still answer.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files whose trackers are checked. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+    /// Seconds to wait for each tracker.
+    #[arg(long, value_name = "SECONDS", default_value_t = 10)]
+    timeout: u64,
+}
+
+/// Prints one `<tier>  <url>  alive|dead  <details>` line per tracker. The
+/// details of live trackers are the latency, the address that answered and,
+/// for HTTP trackers, the status code.
+///
+/// A torrent fails if it has trackers and none of them is alive.
+#[cfg(feature = "scrape")]
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    use std::time::Duration;
+
+    use torrust_parse_torrent::tracker::check::check;
+
+    use super::{for_each_path, read_and_decode};
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for_each_path(&args.paths, |path| {
+        let torrent = read_and_decode(path, mode)?;
+
+        if args.paths.len() > 1 {
+            println!("{path}:");
+        }
+
+        let checks = runtime.block_on(check(&torrent, Duration::from_secs(args.timeout)))?;
+
+        for check in &checks {
+            match &check.result {
+                Ok(liveness) => {
+                    let mut details = format!("{} ms", liveness.latency.as_millis());
+                    if let Some(addr) = liveness.addr {
+                        details.push_str(&format!("  {addr}"));
+                    }
+                    if let Some(status) = liveness.http_status {
+                        details.push_str(&format!("  HTTP {status}"));
+                    }
+                    println!("{}  {}  alive  {details}", check.tier, check.url);
+                }
+                Err(e) => println!("{}  {}  dead  {e}", check.tier, check.url),
+            }
+        }
+
+        if !checks.is_empty() && checks.iter().all(|check| check.result.is_err()) {
+            return Err("no tracker is alive".into());
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(not(feature = "scrape"))]
+pub fn run(_args: &Args, _mode: ParseMode) -> ExitCode {
+    eprintln!("The `check-trackers` subcommand requires building with `--features scrape`");
+    ExitCode::FAILURE
+}
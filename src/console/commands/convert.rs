@@ -0,0 +1,70 @@
+//! `convert` subcommand: converts hybrid torrents to a single protocol
+//! version.
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use torrust_parse_torrent::models::torrent_file::ProtocolVersion;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{read_and_decode, suffixed_path};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Hybrid torrent file to convert.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE")]
+    path: String,
+    /// Protocol version to keep.
+    #[arg(long, value_enum)]
+    to: Version,
+    /// Where to write the torrent. Defaults to `<NAME>.v1.torrent` or
+    /// `<NAME>.v2.torrent` next to the input.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Version {
+    /// Remove the `file tree`, `meta version` and `piece layers`.
+    V1,
+    /// Remove the `pieces`, `files` and `length`.
+    V2,
+}
+
+/// Writes the converted torrent and prints `<info-hash>  <output>`, with the
+/// info-hash of the version that was kept.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    match convert(args, mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error converting {}: {e}", args.path);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn convert(args: &Args, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let (version, suffix) = match args.to {
+        Version::V1 => (ProtocolVersion::V1, "v1"),
+        Version::V2 => (ProtocolVersion::V2, "v2"),
+    };
+
+    let torrent = read_and_decode(&args.path, mode)?.into_version(version)?;
+
+    let output = match &args.output {
+        Some(output) => output.clone(),
+        None => suffixed_path(&args.path, suffix),
+    };
+    fs::write(&output, torrent.encode()?)?;
+
+    let hashes = torrent.info_hashes();
+    let hash = match (hashes.v1, hashes.v2) {
+        (Some(v1), _) => v1.to_hex_string(),
+        (None, Some(v2)) => v2.to_hex_string(),
+        (None, None) => unreachable!("every torrent has at least one info-hash"),
+    };
+    println!("{hash}  {output}");
+
+    Ok(())
+}
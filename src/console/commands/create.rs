@@ -0,0 +1,161 @@
+//! `create` subcommand: creates a torrent from a file or directory.
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use torrust_parse_torrent::builder::TorrentBuilder;
+use torrust_parse_torrent::models::torrent_file::ProtocolVersion;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::parse_size;
+use crate::console::progress::ProgressBarHandler;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// File or directory to create the torrent for.
+    #[arg(value_name = "PATH")]
+    path: PathBuf,
+    /// Only add the files whose path in the directory matches this glob, like
+    /// `data/**`. Can be repeated.
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+    /// Leave out the files and directories whose path matches this glob, like
+    /// `*.nfo`. Can be repeated.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Leave out the files and directories whose name starts with a dot.
+    #[arg(long)]
+    exclude_hidden: bool,
+    /// Leave out symbolic links instead of adding what they point to.
+    #[arg(long)]
+    no_follow_symlinks: bool,
+    /// Tracker announce URL. Repeat it to add more trackers, each one in its
+    /// own tier.
+    #[arg(short, long, value_name = "URL")]
+    announce: Vec<String>,
+    /// Size of the pieces, in bytes or with a unit (`KiB`, `MiB`). By default
+    /// it's chosen to get between 1000 and 2000 pieces.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    piece_length: Option<u64>,
+    /// Protocol version of the torrent.
+    #[arg(long, value_enum, default_value_t = Protocol::V1)]
+    protocol: Protocol,
+    /// Add padding files so that every file starts on a piece boundary, like
+    /// qBittorrent and libtorrent do. Hybrid torrents always have them.
+    #[arg(long)]
+    pad_files: bool,
+    /// Create the same torrent every time for the same content: leave out the
+    /// creation date and don't include the version in `created by`.
+    #[arg(long)]
+    reproducible: bool,
+    /// Mark the torrent as private (BEP 27).
+    #[arg(long)]
+    private: bool,
+    /// Free-form comment.
+    #[arg(long)]
+    comment: Option<String>,
+    /// Value of the `source` key of the info dictionary.
+    #[arg(long)]
+    source: Option<String>,
+    /// Web seed URL (BEP 19). Can be repeated.
+    #[arg(long, value_name = "URL")]
+    web_seed: Vec<String>,
+    /// Number of threads that hash the pieces. Defaults to one per CPU. Use
+    /// `1` to hash them sequentially.
+    #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+    threads: usize,
+    /// Where to write the torrent. Defaults to `<NAME>.torrent` in the
+    /// current directory.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Protocol {
+    /// BEP 3 torrent.
+    V1,
+    /// BEP 52 torrent.
+    V2,
+    /// Torrent with both v1 and v2 metadata. Padding files are added so that
+    /// every file starts on a piece boundary.
+    Hybrid,
+}
+
+impl From<Protocol> for ProtocolVersion {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::V1 => ProtocolVersion::V1,
+            Protocol::V2 => ProtocolVersion::V2,
+            Protocol::Hybrid => ProtocolVersion::Hybrid,
+        }
+    }
+}
+
+/// Writes the torrent and prints `<info-hash>  <output>`, like `hash` does.
+pub fn run(args: &Args, _mode: ParseMode) -> ExitCode {
+    match create(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error creating torrent for {}: {e}", args.path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn create(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut builder = TorrentBuilder::new(&args.path)
+        .private(args.private)
+        .threads(args.threads)
+        .version(args.protocol.into())
+        .pad_to_piece_boundary(args.pad_files)
+        .reproducible(args.reproducible)
+        .hidden(!args.exclude_hidden)
+        .follow_symlinks(!args.no_follow_symlinks)
+        .creation_date(unix_now());
+
+    if let Some(piece_length) = args.piece_length {
+        builder = builder.piece_length(piece_length);
+    }
+    for url in &args.announce {
+        builder = builder.announce(url);
+    }
+    for pattern in &args.include {
+        builder = builder.include(pattern);
+    }
+    for pattern in &args.exclude {
+        builder = builder.exclude(pattern);
+    }
+    for url in &args.web_seed {
+        builder = builder.web_seed(url);
+    }
+    if let Some(comment) = &args.comment {
+        builder = builder.comment(comment);
+    }
+    if let Some(source) = &args.source {
+        builder = builder.source(source);
+    }
+
+    let torrent = builder.build_with_progress(&ProgressBarHandler::new())?;
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.torrent", torrent.info.name)));
+
+    fs::write(&output, torrent.encode()?)?;
+
+    println!("{}  {}", torrent.info_hash(), output.display());
+
+    Ok(())
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| {
+            i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)
+        })
+}
@@ -0,0 +1,77 @@
+//! `cross-seed-check` subcommand: tells whether two torrents can be seeded
+//! from the same data.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::diff::CrossSeedCheck;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::diff::trouble;
+use super::read_and_decode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Print the result as a JSON document.
+    #[arg(long)]
+    json: bool,
+    /// The torrent whose data is already downloaded.
+    #[arg(value_name = "FIRST_TORRENT_FILE")]
+    first: String,
+    /// The torrent to seed with the same data.
+    #[arg(value_name = "SECOND_TORRENT_FILE")]
+    second: String,
+}
+
+/// Prints `compatible` or `not compatible` and what prevents cross-seeding,
+/// followed by notes about the names and the piece hashes.
+///
+/// The exit code is 0 if the torrents are compatible, 1 if they aren't and 2
+/// if one of them can't be read.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let (first, second) = match (
+        read_and_decode(&args.first, mode),
+        read_and_decode(&args.second, mode),
+    ) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(e), _) => return trouble(&args.first, &*e),
+        (_, Err(e)) => return trouble(&args.second, &*e),
+    };
+
+    let check = CrossSeedCheck::new(&first, &second);
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&check).expect("the check should be serializable")
+        );
+    } else {
+        if check.is_compatible() {
+            println!("compatible");
+        } else {
+            println!("not compatible:");
+            for obstacle in &check.obstacles {
+                println!("  {obstacle}");
+            }
+        }
+
+        if check.renamed {
+            println!(
+                "note: the names differ, rename or link `{}` to `{}`",
+                first.decode_name(None).0,
+                second.decode_name(None).0
+            );
+        }
+        match check.same_pieces {
+            Some(true) => println!("note: the piece hashes are the same"),
+            Some(false) => {}
+            None => println!(
+                "note: the piece hashes can't be compared, check the data after adding the second torrent"
+            ),
+        }
+    }
+
+    if check.is_compatible() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
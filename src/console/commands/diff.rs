@@ -0,0 +1,145 @@
+//! `diff` subcommand: shows what changed between two torrents.
+use std::fmt::Display;
+use std::process::ExitCode;
+
+use torrust_parse_torrent::diff::{Change, TorrentDiff};
+use torrust_parse_torrent::utils::format::{format_piece_length, rfc3339};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::read_and_decode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Print the differences as a JSON document.
+    #[arg(long)]
+    json: bool,
+    /// The original torrent.
+    #[arg(value_name = "OLD_TORRENT_FILE")]
+    old: String,
+    /// The torrent compared with it.
+    #[arg(value_name = "NEW_TORRENT_FILE")]
+    new: String,
+}
+
+/// Prints one line per difference: `<field>: <old> -> <new>` for the
+/// changed values, and `+`, `-` or `~` followed by the added, removed or
+/// resized trackers and files.
+///
+/// Like `diff`, the exit code is 0 if the torrents are equal, 1 if they
+/// differ and 2 if one of them can't be read.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let (old, new) = match (
+        read_and_decode(&args.old, mode),
+        read_and_decode(&args.new, mode),
+    ) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) => return trouble(&args.old, &*e),
+        (_, Err(e)) => return trouble(&args.new, &*e),
+    };
+
+    let diff = TorrentDiff::new(&old, &new);
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&diff).expect("the diff should be serializable")
+        );
+    } else {
+        print_diff(&diff);
+    }
+
+    if diff.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+/// Reports a torrent that can't be read, with the exit code 2 of `diff`.
+pub(super) fn trouble(path: &str, e: &dyn std::error::Error) -> ExitCode {
+    eprintln!("Error parsing {path}: {e}");
+    ExitCode::from(2)
+}
+
+fn print_diff(diff: &TorrentDiff) {
+    print_change("info-hash", diff.info_hash.as_ref());
+    print_optional_change("info-hash v2", diff.info_hash_v2.as_ref());
+    print_change("name", diff.name.as_ref());
+    if let Some(change) = &diff.piece_length {
+        println!(
+            "piece length: {} -> {}",
+            format_piece_length(change.old),
+            format_piece_length(change.new)
+        );
+    }
+    print_change("private", diff.private.as_ref());
+    print_text_change("source", diff.source.as_ref());
+    print_text_change("comment", diff.comment.as_ref());
+    print_text_change("created by", diff.created_by.as_ref());
+    if let Some(change) = &diff.creation_date {
+        let show = |date: Option<i64>| {
+            date.map_or_else(
+                || "(none)".to_owned(),
+                |date| rfc3339(date).unwrap_or_else(|| date.to_string()),
+            )
+        };
+        println!(
+            "creation date: {} -> {}",
+            show(change.old),
+            show(change.new)
+        );
+    }
+
+    for url in &diff.trackers_added {
+        println!("+ tracker {url}");
+    }
+    for url in &diff.trackers_removed {
+        println!("- tracker {url}");
+    }
+    for (path, length) in &diff.files_added {
+        println!("+ file {path} ({length} bytes)");
+    }
+    for (path, length) in &diff.files_removed {
+        println!("- file {path} ({length} bytes)");
+    }
+    for (path, change) in &diff.files_resized {
+        println!("~ file {path} ({} -> {} bytes)", change.old, change.new);
+    }
+
+    if diff.is_empty() {
+        println!("no differences");
+    }
+}
+
+fn print_change<T: Display>(label: &str, change: Option<&Change<T>>) {
+    if let Some(change) = change {
+        println!("{label}: {} -> {}", change.old, change.new);
+    }
+}
+
+/// Prints a change of a value that can be missing, shown as `(none)`.
+fn print_optional_change<T: Display>(label: &str, change: Option<&Change<Option<T>>>) {
+    let show = |value: &Option<T>| {
+        value
+            .as_ref()
+            .map_or_else(|| "(none)".to_owned(), ToString::to_string)
+    };
+
+    if let Some(change) = change {
+        println!("{label}: {} -> {}", show(&change.old), show(&change.new));
+    }
+}
+
+/// Prints a change of a text, quoted and escaped so a multi-line comment
+/// stays on one line.
+fn print_text_change(label: &str, change: Option<&Change<Option<String>>>) {
+    let show = |value: &Option<String>| {
+        value
+            .as_ref()
+            .map_or_else(|| "(none)".to_owned(), |text| format!("{text:?}"))
+    };
+
+    if let Some(change) = change {
+        println!("{label}: {} -> {}", show(&change.old), show(&change.new));
+    }
+}
@@ -0,0 +1,133 @@
+//! `edit` subcommand: modifies the metadata of a torrent.
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use torrust_parse_torrent::models::byte_string::ByteString;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{read_and_decode, suffixed_path};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent file to edit.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE")]
+    path: String,
+    /// Set the comment. An empty comment removes it.
+    #[arg(long, value_name = "TEXT")]
+    set_comment: Option<String>,
+    /// Add a tracker in its own tier. Can be repeated.
+    #[arg(long, value_name = "URL")]
+    add_tracker: Vec<String>,
+    /// Remove a tracker from every tier. Can be repeated.
+    #[arg(long, value_name = "URL")]
+    remove_tracker: Vec<String>,
+    /// Set the `source` key of the info dictionary. An empty source removes
+    /// it. Changes the info-hash.
+    #[arg(long, value_name = "TEXT")]
+    set_source: Option<String>,
+    /// Copy the torrent for cross-seeding on another tracker: set the
+    /// `source` to this value and write it to `<NAME>.<SOURCE>.torrent` next
+    /// to the input, unless `--output` is given. Prints the old and new
+    /// info-hashes.
+    #[arg(long, value_name = "SOURCE", conflicts_with = "set_source")]
+    cross_seed: Option<String>,
+    /// Set or clear the BEP 27 private flag. Changes the info-hash.
+    #[arg(long, value_enum, value_name = "ON|OFF")]
+    private: Option<Switch>,
+    /// Where to write the torrent. Defaults to the input file, which is
+    /// overwritten, except with `--cross-seed`.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Switch {
+    On,
+    Off,
+}
+
+/// Writes the modified torrent and prints `<info-hash>  <output>`. A warning
+/// is printed if the info-hash changed, because the edited torrent is a
+/// different swarm.
+///
+/// With `--cross-seed`, it prints `<old-hash> -> <new-hash>  <output>` for
+/// each protocol version instead.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    match edit(args, mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error editing {}: {e}", args.path);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn edit(args: &Args, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let mut torrent = read_and_decode(&args.path, mode)?;
+    let original_hash = torrent.info_hash();
+    let original_hashes = torrent.info_hashes();
+
+    if let Some(comment) = &args.set_comment {
+        torrent.comment = (!comment.is_empty()).then(|| ByteString::from(comment.as_str()));
+    }
+    for url in &args.remove_tracker {
+        if !torrent.remove_tracker(url) {
+            eprintln!("Warning: {} has no tracker {url}", args.path);
+        }
+    }
+    for url in &args.add_tracker {
+        torrent.add_tracker(url);
+    }
+    if let Some(source) = &args.set_source {
+        torrent.info.source = (!source.is_empty()).then(|| ByteString::from(source.as_str()));
+    }
+    if let Some(private) = args.private {
+        torrent.info.private = match private {
+            Switch::On => Some(1),
+            Switch::Off => None,
+        };
+    }
+
+    if let Some(source) = &args.cross_seed {
+        let torrent = torrent.with_source(source);
+        let output = match &args.output {
+            Some(output) => output.clone(),
+            None => suffixed_path(&args.path, source),
+        };
+        fs::write(&output, torrent.encode()?)?;
+
+        let hashes = torrent.info_hashes();
+        for (old, new) in [
+            (
+                original_hashes.v1.map(|hash| hash.to_hex_string()),
+                hashes.v1.map(|hash| hash.to_hex_string()),
+            ),
+            (
+                original_hashes.v2.map(|hash| hash.to_hex_string()),
+                hashes.v2.map(|hash| hash.to_hex_string()),
+            ),
+        ] {
+            if let (Some(old), Some(new)) = (old, new) {
+                println!("{old} -> {new}  {output}");
+            }
+        }
+        return Ok(());
+    }
+
+    // The info dictionary is written from the model, with its keys sorted,
+    // so even an untouched one can get a different hash.
+    torrent.update_raw_info()?;
+    let hash = torrent.info_hash();
+
+    let output = args.output.as_deref().unwrap_or(&args.path);
+    fs::write(output, torrent.encode()?)?;
+
+    if hash != original_hash {
+        eprintln!("Warning: the info-hash changed from {original_hash} to {hash}");
+    }
+    println!("{hash}  {output}");
+
+    Ok(())
+}
@@ -0,0 +1,545 @@
+//! `files` subcommand: lists the files of a torrent.
+use std::error::Error;
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde_derive::Serialize;
+use torrust_parse_torrent::models::torrent_file::{Torrent, TorrentFile};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{exit_code, parse_charset, parse_size, read_and_decode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files whose content is listed. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+    /// Encoding of the names and paths, overriding the one declared in the
+    /// torrent (for example `GBK` or `Shift_JIS`).
+    #[arg(long, value_name = "LABEL", value_parser = parse_charset)]
+    charset: Option<String>,
+    /// Print the files as a directory tree, with the size of each directory
+    /// and a total.
+    #[arg(long, conflicts_with = "json")]
+    tree: bool,
+    /// Order of the files. By default they are listed in the order of the
+    /// torrent.
+    #[arg(long, value_enum)]
+    sort: Option<Sort>,
+    /// Add the index of each file and the range of pieces that cover it.
+    #[arg(long)]
+    long: bool,
+    /// Print a single JSON array with the files of each torrent.
+    #[arg(long)]
+    json: bool,
+    /// List only the files with this extension, like `mkv`. Can be repeated.
+    #[arg(long, value_name = "EXTENSION")]
+    ext: Vec<String>,
+    /// List only the files of at least this size, like `100MiB`.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+    /// List only the files whose path, relative to the root directory,
+    /// matches a glob pattern like `Sample/*`. Can be repeated.
+    #[arg(long, value_name = "PATTERN", value_parser = Glob::new)]
+    glob: Vec<Glob>,
+}
+
+/// Files selected with `--ext`, `--min-size` and `--glob`. A file is listed
+/// if it matches all the given options. Padding files are never selected.
+struct Filter {
+    extensions: Vec<String>,
+    min_size: Option<u64>,
+    patterns: Option<GlobSet>,
+}
+
+/// Orders of the listing.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Sort {
+    /// Largest first. Directories are sorted by their total size.
+    Size,
+    /// Alphabetical order of the paths.
+    Name,
+}
+
+/// Files of a torrent, or the error if it could not be decoded.
+#[derive(Serialize, Debug)]
+struct Listing {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<File>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A file of the torrent, with its position in the torrent and the pieces
+/// that contain its data.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct File {
+    index: usize,
+    path: String,
+    length: u64,
+    /// First and last piece, or `None` for empty files and v2-only torrents,
+    /// whose pieces belong to each file instead of the whole content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pieces: Option<Pieces>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    padding: bool,
+    /// Path without the root directory of multi-file torrents.
+    #[serde(skip)]
+    relative_path: String,
+}
+
+/// Indices of the first and last pieces of a file.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+struct Pieces {
+    first: u32,
+    last: u32,
+}
+
+/// Prints one `<length>  <path>` line per file, or the tree view. Paths of
+/// multi-file torrents start with the torrent name, which is the name of the
+/// root directory.
+///
+/// With `--ext`, `--min-size` or `--glob` only the matching files are listed,
+/// with their index in the torrent, so the pieces to download can be chosen.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let filter = match Filter::new(args) {
+        Ok(filter) => filter,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut listings: Vec<Listing> = args
+        .paths
+        .iter()
+        .map(|path| Listing::new(path, read_and_decode(path, mode), args.charset.as_deref()))
+        .collect();
+
+    for listing in &mut listings {
+        if let Some(files) = &mut listing.files {
+            if let Some(filter) = &filter {
+                files.retain(|file| filter.matches(file));
+                listing.size = Some(files.iter().map(|file| file.length).sum());
+            }
+            sort(files, args.sort);
+        }
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&listings).expect("listings should be serializable")
+        );
+    } else {
+        for listing in &listings {
+            match &listing.files {
+                Some(files) if args.tree => {
+                    for line in tree(files, args.sort, args.long) {
+                        println!("{line}");
+                    }
+                }
+                Some(files) => {
+                    for file in files {
+                        println!("{}", flat(file, args.long));
+                    }
+                }
+                None => eprintln!(
+                    "Error parsing {}: {}",
+                    listing.path,
+                    listing.error.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+    }
+
+    exit_code(listings.iter().filter(|l| l.error.is_some()).count())
+}
+
+impl Listing {
+    fn new(path: &str, result: Result<Torrent, Box<dyn Error>>, charset: Option<&str>) -> Self {
+        match result {
+            Ok(torrent) => Self {
+                path: path.to_owned(),
+                size: Some(torrent.content_size()),
+                files: Some(listing(&torrent, charset)),
+                error: None,
+            },
+            Err(e) => Self {
+                path: path.to_owned(),
+                size: None,
+                files: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+impl Filter {
+    /// Returns `None` if no file selection option is given.
+    fn new(args: &Args) -> Result<Option<Self>, globset::Error> {
+        if args.ext.is_empty() && args.min_size.is_none() && args.glob.is_empty() {
+            return Ok(None);
+        }
+
+        let patterns = if args.glob.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for glob in &args.glob {
+                builder.add(glob.clone());
+            }
+            Some(builder.build()?)
+        };
+
+        Ok(Some(Self {
+            extensions: args
+                .ext
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            min_size: args.min_size,
+            patterns,
+        }))
+    }
+
+    fn matches(&self, file: &File) -> bool {
+        let extension = file
+            .relative_path
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase());
+
+        !file.padding
+            && (self.extensions.is_empty()
+                || extension.is_some_and(|extension| self.extensions.contains(&extension)))
+            && self.min_size.is_none_or(|min_size| file.length >= min_size)
+            && self
+                .patterns
+                .as_ref()
+                .is_none_or(|patterns| patterns.is_match(&file.relative_path))
+    }
+}
+
+/// Files of the torrent, including the padding files, with the pieces they
+/// span in the content of v1 and hybrid torrents.
+fn listing(torrent: &Torrent, charset: Option<&str>) -> Vec<File> {
+    let (name, _) = torrent.decode_name(charset);
+    let root = format!("{name}/");
+
+    files(torrent, charset)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (length, path))| {
+            let pieces = torrent.file_pieces(index);
+            let pieces = (!pieces.is_empty()).then(|| Pieces {
+                first: pieces.start,
+                last: pieces.end - 1,
+            });
+            File {
+                index,
+                relative_path: path.strip_prefix(&root).unwrap_or(&path).to_owned(),
+                path,
+                length,
+                pieces,
+                padding: torrent
+                    .info
+                    .files
+                    .as_ref()
+                    .and_then(|files| files.get(index))
+                    .is_some_and(TorrentFile::is_padding),
+            }
+        })
+        .collect()
+}
+
+fn sort(files: &mut [File], order: Option<Sort>) {
+    match order {
+        Some(Sort::Size) => files.sort_by(|a, b| b.length.cmp(&a.length).then(a.path.cmp(&b.path))),
+        Some(Sort::Name) => files.sort_by(|a, b| a.path.cmp(&b.path)),
+        None => {}
+    }
+}
+
+/// `<length>  <path>` line, with the index and pieces columns when `long`.
+fn flat(file: &File, long: bool) -> String {
+    if long {
+        format!(
+            "{:>14}  {:>6}  {:<15}  {}",
+            file.length,
+            file.index,
+            pieces(file),
+            file.path
+        )
+    } else {
+        format!("{:>14}  {}", file.length, file.path)
+    }
+}
+
+fn pieces(file: &File) -> String {
+    file.pieces.map_or_else(
+        || "-".to_owned(),
+        |Pieces { first, last }| format!("{first}-{last}"),
+    )
+}
+
+/// A file or directory of the tree view.
+struct Node<'a> {
+    name: &'a str,
+    size: u64,
+    file: Option<&'a File>,
+    children: Vec<Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn insert(&mut self, components: &[&'a str], file: &'a File) {
+        self.size += file.length;
+
+        let Some((name, rest)) = components.split_first() else {
+            return;
+        };
+
+        let position = if rest.is_empty() {
+            None
+        } else {
+            self.children
+                .iter()
+                .position(|child| child.name == *name && child.file.is_none())
+        };
+        let position = position.unwrap_or_else(|| {
+            self.children.push(Node {
+                name,
+                size: 0,
+                file: rest.is_empty().then_some(file),
+                children: vec![],
+            });
+            self.children.len() - 1
+        });
+
+        self.children[position].insert(rest, file);
+    }
+
+    fn sort(&mut self, order: Option<Sort>) {
+        match order {
+            Some(Sort::Size) => self
+                .children
+                .sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(b.name))),
+            Some(Sort::Name) => self.children.sort_by(|a, b| a.name.cmp(b.name)),
+            None => {}
+        }
+        for child in &mut self.children {
+            child.sort(order);
+        }
+    }
+
+    fn lines(&self, prefix: &str, connector: &str, long: bool, lines: &mut Vec<String>) {
+        let columns = match (long, self.file) {
+            (true, Some(file)) => format!("{:>6}  {:<15}  ", file.index, pieces(file)),
+            (true, None) => format!("{:>6}  {:<15}  ", "", ""),
+            (false, _) => String::new(),
+        };
+        let slash = if self.file.is_some() { "" } else { "/" };
+        lines.push(format!(
+            "{:>14}  {columns}{prefix}{connector}{}{slash}",
+            self.size, self.name
+        ));
+
+        let prefix = match connector {
+            "├── " => format!("{prefix}│   "),
+            "└── " => format!("{prefix}    "),
+            _ => prefix.to_owned(),
+        };
+        for (position, child) in self.children.iter().enumerate() {
+            let connector = if position + 1 == self.children.len() {
+                "└── "
+            } else {
+                "├── "
+            };
+            child.lines(&prefix, connector, long, lines);
+        }
+    }
+}
+
+/// Lines of the tree view: one per directory and file, with its size, and a
+/// final total.
+fn tree(files: &[File], order: Option<Sort>, long: bool) -> Vec<String> {
+    let mut root = Node {
+        name: "",
+        size: 0,
+        file: None,
+        children: vec![],
+    };
+    for file in files {
+        let components: Vec<&str> = file.path.split('/').collect();
+        root.insert(&components, file);
+    }
+    root.sort(order);
+
+    let mut lines = vec![];
+    for child in &root.children {
+        child.lines("", "", long, &mut lines);
+    }
+    lines.push(format!(
+        "{:>14}  total, {} {}",
+        root.size,
+        files.len(),
+        if files.len() == 1 { "file" } else { "files" }
+    ));
+    lines
+}
+
+pub(super) fn files(torrent: &Torrent, charset: Option<&str>) -> Vec<(u64, String)> {
+    let (name, _) = torrent.decode_name(charset);
+
+    if let Some(files) = &torrent.info.files {
+        return files
+            .iter()
+            .map(|file| {
+                (
+                    file.length,
+                    format!("{name}/{}", torrent.decode_path(file, charset).0.join("/")),
+                )
+            })
+            .collect();
+    }
+
+    if let Some(length) = torrent.info.length {
+        return vec![(length, name.into_owned())];
+    }
+
+    // v2-only torrents describe their content only in the file tree.
+    torrent
+        .info
+        .file_tree
+        .iter()
+        .flat_map(|file_tree| file_tree.iter_files())
+        .map(|(path, file)| (file.length, format!("{name}/{}", path.join("/"))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use globset::{Glob, GlobSetBuilder};
+    use torrust_parse_torrent::parse_torrent;
+
+    use super::{listing, tree, File, Filter, Pieces, Sort};
+
+    fn file(index: usize, path: &str, length: u64) -> File {
+        File {
+            index,
+            path: path.to_owned(),
+            length,
+            pieces: None,
+            padding: false,
+            relative_path: path.split_once('/').unwrap().1.to_owned(),
+        }
+    }
+
+    #[test]
+    fn it_should_list_the_pieces_that_cover_each_file() {
+        let torrent = parse_torrent(
+            &std::fs::read("tests/fixtures/torrents/6c690018c5786dbbb00161f62b0712d69296df97_with_custom_info_dict_key.torrent")
+                .unwrap(),
+        )
+        .unwrap();
+
+        let pieces: Vec<Option<Pieces>> = listing(&torrent, None)
+            .into_iter()
+            .map(|file| file.pieces)
+            .collect();
+
+        assert_eq!(
+            pieces,
+            vec![
+                Some(Pieces { first: 0, last: 21 }),
+                Some(Pieces {
+                    first: 21,
+                    last: 22
+                }),
+                Some(Pieces {
+                    first: 22,
+                    last: 22
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_print_the_files_as_a_tree_with_the_directory_sizes() {
+        let files = vec![
+            file(0, "name/b.txt", 1),
+            file(1, "name/dir/a.txt", 2),
+            file(2, "name/dir/c.txt", 4),
+            file(3, "name/a.txt", 8),
+        ];
+
+        assert_eq!(
+            tree(&files, Some(Sort::Name), false),
+            vec![
+                "            15  name/",
+                "             8  ├── a.txt",
+                "             1  ├── b.txt",
+                "             6  └── dir/",
+                "             2      ├── a.txt",
+                "             4      └── c.txt",
+                "            15  total, 4 files",
+            ]
+        );
+
+        assert_eq!(
+            tree(&files, Some(Sort::Size), false),
+            vec![
+                "            15  name/",
+                "             8  ├── a.txt",
+                "             6  ├── dir/",
+                "             4  │   ├── c.txt",
+                "             2  │   └── a.txt",
+                "             1  └── b.txt",
+                "            15  total, 4 files",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_select_files_by_extension_size_and_path() {
+        let files = [
+            file(0, "name/Movie.MKV", 700),
+            file(1, "name/Sample/sample.mkv", 10),
+            file(2, "name/Sample/info.nfo", 1),
+            File {
+                padding: true,
+                ..file(3, "name/.pad/100", 100)
+            },
+        ];
+        let selected = |filter: &Filter| -> Vec<usize> {
+            files
+                .iter()
+                .filter(|file| filter.matches(file))
+                .map(|file| file.index)
+                .collect()
+        };
+
+        let mut filter = Filter {
+            extensions: vec!["mkv".to_owned()],
+            min_size: None,
+            patterns: None,
+        };
+        assert_eq!(selected(&filter), vec![0, 1]);
+
+        filter.min_size = Some(100);
+        assert_eq!(selected(&filter), vec![0]);
+
+        let mut patterns = GlobSetBuilder::new();
+        patterns.add(Glob::new("Sample/*").unwrap());
+        let filter = Filter {
+            extensions: vec![],
+            min_size: None,
+            patterns: Some(patterns.build().unwrap()),
+        };
+        assert_eq!(selected(&filter), vec![1, 2]);
+    }
+}
@@ -0,0 +1,42 @@
+//! `hash` subcommand: prints the info-hashes of each torrent.
+use std::process::ExitCode;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{for_each_path, read_and_decode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Print only the v1 info-hash.
+    #[arg(long, conflicts_with = "v2_only")]
+    v1_only: bool,
+    /// Print only the v2 info-hash.
+    #[arg(long)]
+    v2_only: bool,
+    /// Torrent files to hash. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+}
+
+/// Prints one `<v1>\t<v2>\t<path>` line per torrent, with `-` for the
+/// info-hash of a protocol version the torrent doesn't support.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    for_each_path(&args.paths, |path| {
+        let hashes = read_and_decode(path, mode)?.info_hashes();
+        let v1 = hashes
+            .v1
+            .map_or_else(|| "-".to_owned(), |hash| hash.to_string());
+        let v2 = hashes
+            .v2
+            .map_or_else(|| "-".to_owned(), |hash| hash.to_string());
+
+        if args.v1_only {
+            println!("{v1}\t{path}");
+        } else if args.v2_only {
+            println!("{v2}\t{path}");
+        } else {
+            println!("{v1}\t{v2}\t{path}");
+        }
+        Ok(())
+    })
+}
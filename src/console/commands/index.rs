@@ -0,0 +1,222 @@
+//! `index` subcommand: stores parsed torrents in a SQLite catalog.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// SQLite database to add the torrents to. It's created if it doesn't
+    /// exist.
+    #[arg(long, value_name = "PATH")]
+    db: String,
+    /// Torrent files, or directories searched recursively for `*.torrent`
+    /// files.
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<String>,
+}
+
+/// Tables of the catalog. Files and trackers reference their torrent, and
+/// are deleted with it when a torrent is indexed again.
+#[cfg(feature = "sqlite")]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS torrents (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL,
+    info_hash TEXT NOT NULL UNIQUE,
+    info_hash_v2 TEXT,
+    name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    piece_length INTEGER NOT NULL,
+    private INTEGER NOT NULL,
+    comment TEXT,
+    created_by TEXT,
+    creation_date INTEGER
+);
+CREATE TABLE IF NOT EXISTS files (
+    torrent_id INTEGER NOT NULL REFERENCES torrents (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    length INTEGER NOT NULL,
+    PRIMARY KEY (torrent_id, position)
+);
+CREATE TABLE IF NOT EXISTS trackers (
+    torrent_id INTEGER NOT NULL REFERENCES torrents (id) ON DELETE CASCADE,
+    tier INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (torrent_id, tier, url)
+);
+CREATE INDEX IF NOT EXISTS files_path ON files (path);
+CREATE INDEX IF NOT EXISTS trackers_url ON trackers (url);
+";
+
+/// Adds the torrents to the catalog, in a single transaction, and prints
+/// how many were indexed. A torrent already in the catalog, with the same
+/// info-hash, is replaced.
+///
+/// The torrents that can't be parsed are reported and skipped. The exit code
+/// is non-zero if there were any.
+#[cfg(feature = "sqlite")]
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    use super::batch::inputs;
+    use super::{exit_code, read_and_decode};
+
+    let mut connection = match open(&args.db) {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Error opening {}: {e}", args.db);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let transaction = match connection.transaction() {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut indexed = 0;
+    let mut failures = 0;
+
+    for input in inputs(&args.paths) {
+        let result = match input {
+            Ok(path) => {
+                let path = path.to_string_lossy().into_owned();
+                read_and_decode(&path, mode)
+                    .and_then(|torrent| Ok(insert(&transaction, &path, &torrent)?))
+                    .map_err(|e| (path, e.to_string()))
+            }
+            Err((path, e)) => Err((path.to_string_lossy().into_owned(), e.to_string())),
+        };
+
+        match result {
+            Ok(()) => indexed += 1,
+            Err((path, e)) => {
+                eprintln!("Error parsing {path}: {e}");
+                failures += 1;
+            }
+        }
+    }
+
+    if let Err(e) = transaction.commit() {
+        eprintln!("Error writing {}: {e}", args.db);
+        return ExitCode::FAILURE;
+    }
+
+    println!("{indexed} torrents indexed in {}", args.db);
+
+    exit_code(failures)
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn run(_args: &Args, _mode: ParseMode) -> ExitCode {
+    eprintln!("The `index` subcommand requires building with `--features sqlite`");
+    ExitCode::FAILURE
+}
+
+/// Opens the catalog and creates the tables that are missing.
+#[cfg(feature = "sqlite")]
+fn open(path: &str) -> rusqlite::Result<rusqlite::Connection> {
+    let connection = rusqlite::Connection::open(path)?;
+    connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+}
+
+/// Inserts a torrent with its files and trackers, replacing the one with the
+/// same info-hash.
+#[cfg(feature = "sqlite")]
+fn insert(
+    connection: &rusqlite::Connection,
+    path: &str,
+    torrent: &torrust_parse_torrent::models::torrent_file::Torrent,
+) -> rusqlite::Result<()> {
+    use rusqlite::params;
+
+    let info_hash = torrent.info_hash_hex();
+
+    connection.execute("DELETE FROM torrents WHERE info_hash = ?1", [&info_hash])?;
+    connection.execute(
+        "INSERT INTO torrents (path, info_hash, info_hash_v2, name, size, piece_length, private, comment, created_by, creation_date)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            path,
+            info_hash,
+            torrent.info_hash_v2().map(|hash| hash.to_hex_string()),
+            torrent.decode_name(None).0,
+            i64::try_from(torrent.content_size()).unwrap_or(i64::MAX),
+            i64::try_from(torrent.info.piece_length).unwrap_or(i64::MAX),
+            torrent.info.private == Some(1),
+            torrent
+                .comment
+                .as_ref()
+                .map(|comment| torrent.decode_text(comment, None).0),
+            torrent
+                .created_by
+                .as_ref()
+                .map(|created_by| torrent.decode_text(created_by, None).0),
+            torrent.creation_date,
+        ],
+    )?;
+    let torrent_id = connection.last_insert_rowid();
+
+    let mut insert_file = connection.prepare(
+        "INSERT INTO files (torrent_id, position, path, length) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (position, (file_path, length)) in (0_i64..).zip(torrent.content_paths(None)) {
+        insert_file.execute(params![
+            torrent_id,
+            position,
+            file_path,
+            i64::try_from(length).unwrap_or(i64::MAX)
+        ])?;
+    }
+
+    let mut insert_tracker = connection
+        .prepare("INSERT OR IGNORE INTO trackers (torrent_id, tier, url) VALUES (?1, ?2, ?3)")?;
+    for (tier, url) in torrent.announce_tiers().iter() {
+        insert_tracker.execute(params![
+            torrent_id,
+            i64::try_from(tier).unwrap_or(i64::MAX),
+            url
+        ])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use torrust_parse_torrent::parse_torrent;
+
+    use super::{insert, open};
+
+    #[test]
+    fn it_should_index_a_torrent_with_its_files_and_trackers() {
+        let path = "tests/fixtures/torrents/6c690018c5786dbbb00161f62b0712d69296df97_with_custom_info_dict_key.torrent";
+        let torrent = parse_torrent(&std::fs::read(path).unwrap()).unwrap();
+        let connection = open(":memory:").unwrap();
+
+        // Indexing it twice replaces the first one.
+        insert(&connection, path, &torrent).unwrap();
+        insert(&connection, path, &torrent).unwrap();
+
+        let count = |table: &str| -> i64 {
+            connection
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+        assert_eq!(count("torrents"), 1);
+        assert_eq!(
+            usize::try_from(count("files")).unwrap(),
+            torrent.content_files().len()
+        );
+        assert_eq!(
+            usize::try_from(count("trackers")).unwrap(),
+            torrent.announce_tiers().iter().count()
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! `info` subcommand: prints a summary of each torrent, also available as
+//! `summary`.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::process::ExitCode;
+
+use serde_derive::Serialize;
+use torrust_parse_torrent::models::torrent_file::Torrent;
+use torrust_parse_torrent::parse_torrent_with_mode;
+use torrust_parse_torrent::utils::format::rfc3339;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{check_signatures, exit_code, parse_charset, read_input, FileDigests, SignatureCheck};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Print a single JSON array with the summaries.
+    #[arg(long)]
+    json: bool,
+    /// Torrent files to summarize. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+    /// Encoding of the name and comment, overriding the one declared in the
+    /// torrent (for example `GBK` or `Shift_JIS`).
+    #[arg(long, value_name = "LABEL", value_parser = parse_charset)]
+    charset: Option<String>,
+    /// Also print the SHA-1 and SHA-256 of each whole `.torrent` file.
+    #[arg(long)]
+    file_digests: bool,
+}
+
+/// Summary of a torrent, or the error if it could not be decoded.
+#[derive(Serialize, Debug)]
+struct Summary {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info_hash_v2: Option<String>,
+    /// `v1`, `v2` or `hybrid`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    /// Total size of the content in bytes, padding files excluded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    piece_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pieces: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private: Option<bool>,
+    /// Tracker URLs by tier.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    trackers: Vec<Vec<String>>,
+    /// Creation date in RFC 3339 format, in UTC.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    creation_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    file_digests: Option<FileDigests>,
+    /// BEP 35 signatures, by signer.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    signatures: BTreeMap<String, SignatureCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The text printed without `--json`, see [`Torrent::write_summary`].
+    #[serde(skip)]
+    text: Option<String>,
+}
+
+impl Summary {
+    fn new(
+        path: &str,
+        result: Result<Torrent, Box<dyn Error>>,
+        file_digests: Option<FileDigests>,
+        charset: Option<&str>,
+    ) -> Self {
+        match result {
+            Ok(torrent) => {
+                let hashes = torrent.info_hashes();
+                let mut text = String::new();
+                torrent
+                    .write_summary(&mut text, charset)
+                    .expect("a summary can be written to a string");
+                Self {
+                    path: path.to_owned(),
+                    name: Some(torrent.decode_name(charset).0.into_owned()),
+                    info_hash: hashes.v1.map(|hash| hash.to_hex_string()),
+                    info_hash_v2: hashes.v2.map(|hash| hash.to_hex_string()),
+                    version: Some(hashes.version.to_string()),
+                    size: Some(torrent.content_size()),
+                    files: Some(torrent.info.file_count()),
+                    piece_length: Some(torrent.info.piece_length),
+                    pieces: Some(torrent.piece_count()),
+                    private: Some(torrent.info.private == Some(1)),
+                    trackers: torrent.announce_tiers().tiers().to_vec(),
+                    creation_date: torrent
+                        .creation_date
+                        .map(|date| rfc3339(date).unwrap_or_else(|| date.to_string())),
+                    created_by: torrent
+                        .created_by
+                        .as_ref()
+                        .map(|created_by| torrent.decode_text(created_by, charset).0.into_owned()),
+                    comment: torrent
+                        .comment
+                        .as_ref()
+                        .map(|comment| torrent.decode_text(comment, charset).0.into_owned()),
+                    file_digests,
+                    signatures: check_signatures(&torrent),
+                    error: None,
+                    text: Some(text),
+                }
+            }
+            Err(e) => Self {
+                path: path.to_owned(),
+                name: None,
+                info_hash: None,
+                info_hash_v2: None,
+                version: None,
+                size: None,
+                files: None,
+                piece_length: None,
+                pieces: None,
+                private: None,
+                trackers: vec![],
+                creation_date: None,
+                created_by: None,
+                comment: None,
+                file_digests,
+                signatures: BTreeMap::new(),
+                error: Some(e.to_string()),
+                text: None,
+            },
+        }
+    }
+}
+
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let summaries: Vec<Summary> = args
+        .paths
+        .iter()
+        .map(|path| {
+            let input = read_input(path);
+            let file_digests = match &input {
+                Ok(bytes) if args.file_digests => Some(FileDigests::new(bytes)),
+                _ => None,
+            };
+            let result = input.and_then(|bytes| Ok(parse_torrent_with_mode(&bytes, mode)?));
+            Summary::new(path, result, file_digests, args.charset.as_deref())
+        })
+        .collect();
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summaries).expect("summaries should be serializable")
+        );
+    } else {
+        for (index, summary) in summaries.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            print_summary(summary);
+        }
+    }
+
+    exit_code(summaries.iter().filter(|s| s.error.is_some()).count())
+}
+
+fn print_summary(summary: &Summary) {
+    println!("path:         {}", summary.path);
+
+    if let Some(error) = &summary.error {
+        eprintln!("Error parsing {}: {error}", summary.path);
+        print_file_digests(summary);
+        return;
+    }
+
+    if let Some(text) = &summary.text {
+        println!("{text}");
+    }
+
+    print_file_digests(summary);
+
+    for (signer, check) in &summary.signatures {
+        println!("signature:    {signer}: {check}");
+    }
+}
+
+fn print_file_digests(summary: &Summary) {
+    if let Some(file_digests) = &summary.file_digests {
+        println!("file sha1:    {}", file_digests.sha1);
+        println!("file sha256:  {}", file_digests.sha256);
+    }
+}
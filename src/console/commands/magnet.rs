@@ -0,0 +1,22 @@
+//! `magnet` subcommand: prints the magnet link of each torrent.
+use std::process::ExitCode;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{for_each_path, read_and_decode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files to convert. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+}
+
+/// Prints the magnet link of each torrent, one per line.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    for_each_path(&args.paths, |path| {
+        let torrent = read_and_decode(path, mode)?;
+        println!("{}", torrent.magnet_uri());
+        Ok(())
+    })
+}
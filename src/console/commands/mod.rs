@@ -0,0 +1,259 @@
+//! Subcommands of the command line interface.
+pub mod audit;
+pub mod batch;
+pub mod check_nodes;
+pub mod check_trackers;
+pub mod convert;
+pub mod create;
+pub mod cross_seed_check;
+pub mod diff;
+pub mod edit;
+pub mod files;
+pub mod hash;
+pub mod index;
+pub mod info;
+pub mod magnet;
+pub mod parse;
+pub mod pieces;
+pub mod retracker;
+pub mod scrape;
+pub mod scrub;
+pub mod trackers;
+pub mod tui;
+pub mod validate;
+pub mod verify;
+pub mod write;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde_derive::Serialize;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use torrust_parse_torrent::models::signature::Signature;
+use torrust_parse_torrent::models::torrent_file::Torrent;
+use torrust_parse_torrent::utils::file_bytes::{self, FileBytes};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+use torrust_parse_torrent::utils::{charset, hex};
+
+use crate::console::fetch;
+
+/// Reads a torrent from a path, or from the standard input if the path is
+/// `-`, or downloads it if it's a URL or a magnet link.
+pub(crate) fn read_input(path: &str) -> Result<FileBytes, Box<dyn Error>> {
+    if path == STDIN {
+        Ok(read_stdin()?.into())
+    } else if fetch::is_url(path) {
+        Ok(fetch::fetch(path)?.into())
+    } else if fetch::is_magnet(path) {
+        Ok(fetch::fetch_magnet(path)?.into())
+    } else {
+        Ok(file_bytes::read_file(path)?)
+    }
+}
+
+/// Path that stands for the standard input.
+const STDIN: &str = "-";
+
+/// Reads the standard input, unless it's a terminal. Without this check,
+/// running a subcommand without paths would wait for a torrent to be typed.
+fn read_stdin() -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut stdin = io::stdin().lock();
+    if stdin.is_terminal() {
+        return Err(
+            "no torrent given: pass a path or pipe a torrent into the standard input".into(),
+        );
+    }
+
+    let mut bytes = vec![];
+    stdin.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_and_decode(path: &str, mode: ParseMode) -> Result<Torrent, Box<dyn Error>> {
+    let bytes = read_input(path)?;
+    Ok(torrust_parse_torrent::parse_torrent_with_mode(
+        &bytes, mode,
+    )?)
+}
+
+/// Parses the value of a `--charset` argument.
+fn parse_charset(label: &str) -> Result<String, String> {
+    if charset::is_supported(label) {
+        Ok(label.to_owned())
+    } else if cfg!(feature = "charset") {
+        Err(format!("unknown encoding `{label}`"))
+    } else {
+        Err(format!(
+            "unsupported encoding `{label}`, build with the `charset` feature to decode legacy encodings"
+        ))
+    }
+}
+
+/// Parses a size like `16384`, `256KiB` or `2MiB`.
+fn parse_size(size: &str) -> Result<u64, String> {
+    let digits_end = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(digits_end);
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size `{size}`"))?;
+
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1024,
+        "m" | "mib" => 1024 * 1024,
+        "g" | "gib" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown unit in `{size}`, use KiB, MiB or GiB")),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size `{size}` is too large"))
+}
+
+/// Returns `<dir>/<stem>.<suffix>.torrent` for the torrent at `path`, to
+/// write a modified copy next to it.
+fn suffixed_path(path: &str, suffix: &str) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.{suffix}.torrent"))
+        .display()
+        .to_string()
+}
+
+/// Runs `f` for each path. A failure is reported on stderr and does not stop
+/// the remaining paths from being processed.
+///
+/// The exit code is non-zero if any of them fails.
+fn for_each_path<F>(paths: &[String], mut f: F) -> ExitCode
+where
+    F: FnMut(&str) -> Result<(), Box<dyn Error>>,
+{
+    let mut failures = 0;
+
+    for path in paths {
+        if let Err(e) = f(path) {
+            eprintln!("Error parsing {path}: {e}");
+            failures += 1;
+        }
+    }
+
+    exit_code(failures)
+}
+
+/// Digests of a whole `.torrent` file, not only of its info dictionary, to
+/// catalog the files themselves.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+struct FileDigests {
+    #[serde(rename = "file_sha1")]
+    sha1: String,
+    #[serde(rename = "file_sha256")]
+    sha256: String,
+}
+
+impl FileDigests {
+    fn new(bytes: &[u8]) -> Self {
+        Self {
+            sha1: hex::from_bytes(&Sha1::digest(bytes)).to_lowercase(),
+            sha256: hex::from_bytes(&Sha256::digest(bytes)).to_lowercase(),
+        }
+    }
+}
+
+/// Result of checking a BEP 35 signature with the certificate it carries.
+#[derive(Serialize, Debug)]
+#[serde(tag = "status", rename_all = "lowercase")]
+#[cfg_attr(not(feature = "signature"), allow(dead_code))]
+enum SignatureCheck {
+    Valid { subject: String },
+    Invalid,
+    Unverified { reason: String },
+}
+
+impl std::fmt::Display for SignatureCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureCheck::Valid { subject } => write!(f, "valid, signed by {subject}"),
+            SignatureCheck::Invalid => write!(f, "invalid"),
+            SignatureCheck::Unverified { reason } => write!(f, "not verified: {reason}"),
+        }
+    }
+}
+
+#[cfg(feature = "signature")]
+fn check_signature(torrent: &Torrent, signature: &Signature) -> SignatureCheck {
+    use torrust_parse_torrent::models::signature::SignatureError;
+
+    match signature.verify_with_certificate(torrent) {
+        Ok(subject) => SignatureCheck::Valid { subject },
+        Err(SignatureError::Invalid) => SignatureCheck::Invalid,
+        Err(e) => SignatureCheck::Unverified {
+            reason: e.to_string(),
+        },
+    }
+}
+
+#[cfg(not(feature = "signature"))]
+fn check_signature(_torrent: &Torrent, _signature: &Signature) -> SignatureCheck {
+    SignatureCheck::Unverified {
+        reason: "verifying signatures requires building with `--features signature`".to_owned(),
+    }
+}
+
+/// Checks the signatures of the torrent, by signer.
+fn check_signatures(torrent: &Torrent) -> BTreeMap<String, SignatureCheck> {
+    torrent
+        .signatures
+        .iter()
+        .flatten()
+        .map(|(signer, signature)| (signer.clone(), check_signature(torrent, signature)))
+        .collect()
+}
+
+fn exit_code(failures: usize) -> ExitCode {
+    if failures > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_size, suffixed_path, FileDigests};
+
+    #[test]
+    fn it_should_digest_the_whole_file() {
+        let digests = FileDigests::new(b"");
+
+        assert_eq!(digests.sha1, "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            digests.sha256,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn it_should_parse_sizes_with_binary_units() {
+        assert_eq!(parse_size("16384"), Ok(16384));
+        assert_eq!(parse_size("256KiB"), Ok(256 * 1024));
+        assert_eq!(parse_size("2MiB"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("1 GiB"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("2MB").is_err());
+        assert!(parse_size("MiB").is_err());
+    }
+
+    #[test]
+    fn it_should_add_the_suffix_before_the_extension() {
+        assert_eq!(
+            suffixed_path("torrents/a.b.torrent", "TRK"),
+            "torrents/a.b.TRK.torrent"
+        );
+    }
+}
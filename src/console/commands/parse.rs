@@ -0,0 +1,300 @@
+//! `parse` subcommand: dumps the decoded torrents.
+use std::error::Error;
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use torrust_parse_torrent::models::info_hash::InfoHash;
+use torrust_parse_torrent::models::info_hash_v2::InfoHashV2;
+use torrust_parse_torrent::models::torrent_file::Torrent;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+use torrust_parse_torrent::utils::parse_options::ParseOptions;
+use torrust_parse_torrent::utils::{
+    bencode, canonical_json, compare, json, parse_torrent, parse_torrent_verbose, validate,
+};
+
+use super::{exit_code, for_each_path, read_and_decode, read_input};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = Format::Debug)]
+    format: Format,
+    /// Pretty-print the JSON output.
+    #[arg(long)]
+    pretty: bool,
+    /// Fail if the info-hash of a torrent is not this one: a v1 info-hash in
+    /// hex or base32, or a v2 info-hash in hex.
+    #[arg(long, value_name = "HASH", value_parser = parse_expected_hash)]
+    expect_hash: Option<ExpectedHash>,
+    /// Instead of printing the torrents, parse them with both parsers and
+    /// print the fields where their outputs differ.
+    #[arg(long, conflicts_with_all = ["format", "pretty", "expect_hash"])]
+    compare: bool,
+    /// Torrent files to parse. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+}
+
+/// Output formats for the parsed torrents.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    /// Rust `Debug` output of both parser implementations.
+    Debug,
+    /// JSON document of the parsed torrent, one per line.
+    Json,
+    /// Normalized JSON document designed to be compared with `diff`.
+    CanonicalJson,
+}
+
+/// Info-hash given with `--expect-hash`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ExpectedHash {
+    V1(InfoHash),
+    V2(InfoHashV2),
+}
+
+fn parse_expected_hash(hash: &str) -> Result<ExpectedHash, String> {
+    if hash.len() == 64 {
+        hash.parse().map(ExpectedHash::V2)
+    } else {
+        hash.parse().map(ExpectedHash::V1)
+    }
+    .map_err(|_| format!("invalid info-hash `{hash}`"))
+}
+
+impl ExpectedHash {
+    /// Compares the expected info-hash with the one of the torrent for the
+    /// same protocol version.
+    fn check(&self, torrent: &Torrent) -> Result<(), String> {
+        let hashes = torrent.info_hashes();
+        let (expected, actual) = match self {
+            Self::V1(expected) => (expected.to_string(), hashes.v1.map(|hash| hash.to_string())),
+            Self::V2(expected) => (expected.to_string(), hashes.v2.map(|hash| hash.to_string())),
+        };
+
+        match actual {
+            Some(actual) if actual == expected => Ok(()),
+            Some(actual) => Err(format!(
+                "info-hash mismatch: expected {expected}, got {actual}"
+            )),
+            None => Err(format!(
+                "info-hash mismatch: expected {expected}, but the torrent doesn't have a {} info-hash",
+                if matches!(self, Self::V1(_)) { "v1" } else { "v2" }
+            )),
+        }
+    }
+}
+
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    if args.compare {
+        return compare_parsers(&args.paths);
+    }
+
+    let expected = args.expect_hash.as_ref();
+    match args.format {
+        Format::Debug => print_debug(&args.paths, mode, expected),
+        Format::Json => print_json(&args.paths, args.pretty, mode, expected),
+        Format::CanonicalJson => print_canonical_json(&args.paths, mode, expected),
+    }
+}
+
+/// Prints a `<path>: <difference>` line per difference between the parsers.
+/// A torrent they disagree on is a failure.
+fn compare_parsers(paths: &[String]) -> ExitCode {
+    for_each_path(paths, |path| {
+        let differences = compare::compare_parsers(&read_input(path)?);
+        for difference in &differences {
+            println!("{path}: {difference}");
+        }
+
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("the parsers disagree on {} fields", differences.len()).into())
+        }
+    })
+}
+
+/// Prints one JSON document per torrent, which `jq` reads as a stream.
+fn print_json(
+    paths: &[String],
+    pretty: bool,
+    mode: ParseMode,
+    expected: Option<&ExpectedHash>,
+) -> ExitCode {
+    for_each_path(paths, |path| {
+        let torrent = read_and_decode(path, mode)?;
+        if let Some(expected) = expected {
+            expected.check(&torrent)?;
+        }
+        let document = json::to_json(&torrent);
+
+        let output = if pretty {
+            serde_json::to_string_pretty(&document)
+        } else {
+            serde_json::to_string(&document)
+        };
+
+        println!("{}", output.expect("JSON should be serializable"));
+
+        Ok(())
+    })
+}
+
+fn print_debug(paths: &[String], mode: ParseMode, expected: Option<&ExpectedHash>) -> ExitCode {
+    for_each_path(paths, |path| {
+        if paths.len() > 1 {
+            println!("==> {path} <==\n");
+        }
+
+        let result = print_torrent(path, mode, expected);
+
+        if paths.len() > 1 {
+            println!();
+        }
+
+        result
+    })
+}
+
+fn print_canonical_json(
+    paths: &[String],
+    mode: ParseMode,
+    expected: Option<&ExpectedHash>,
+) -> ExitCode {
+    let mut documents = vec![];
+    let mut failures = 0;
+
+    for path in paths {
+        match read_canonical_json(path, mode, expected) {
+            Ok(document) => documents.push(document),
+            Err(e) => {
+                eprintln!("Error parsing {path}: {e}");
+                failures += 1;
+            }
+        }
+    }
+
+    // A single torrent is exported as a document, not as an array, so
+    // that exports of two different files can be compared with `diff`.
+    let output = match <[_; 1]>::try_from(documents) {
+        Ok([document]) => document,
+        Err(documents) => serde_json::Value::Array(documents),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).expect("canonical JSON should be serializable")
+    );
+
+    exit_code(failures)
+}
+
+fn read_canonical_json(
+    path: &str,
+    mode: ParseMode,
+    expected: Option<&ExpectedHash>,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let bytes = read_input(path)?;
+    if let Some(expected) = expected {
+        expected.check(&torrust_parse_torrent::parse_torrent_with_mode(
+            &bytes, mode,
+        )?)?;
+    }
+    Ok(canonical_json::to_canonical_json(&bencode::decode(&bytes)?))
+}
+
+/// Prints the torrent decoded with both the verbose and the standard serde
+/// implementations.
+fn print_torrent(
+    path: &str,
+    mode: ParseMode,
+    expected: Option<&ExpectedHash>,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_input(path)?;
+
+    println!("Decoding torrent with verbose implementation ...\n");
+
+    let decoded = bencode::decode_with_diagnostics(&bytes, &ParseOptions::from(mode))?;
+    let mut encoding_diagnostics = decoded.diagnostics;
+    encoding_diagnostics.extend(validate::validate_text(&decoded.value, mode));
+    encoding_diagnostics.extend(validate::validate_trailing_data(
+        bytes.len() - decoded.length,
+        mode,
+    ));
+
+    let (torrent, diagnostics) =
+        parse_torrent_verbose::decode_torrent_with_diagnostics(decoded.value, mode)?;
+    for diagnostic in encoding_diagnostics.iter().chain(&diagnostics) {
+        println!("{diagnostic}");
+    }
+    println!("\nFinal parsed torrent: \n\n{torrent:#?}");
+
+    println!("\nDecoding torrent with standard serde implementation ...\n");
+
+    let torrent = if mode.is_strict() {
+        torrust_parse_torrent::parse_torrent_with_mode(&bytes, mode)?
+    } else {
+        parse_torrent::decode_torrent(&bytes)?
+    };
+    println!("Final parsed torrent: \n\n{torrent:#?}");
+
+    // Checked against the torrent the other formats print, parsed with the
+    // limits of the mode.
+    if let Some(expected) = expected {
+        expected.check(&torrust_parse_torrent::parse_torrent_with_mode(
+            &bytes, mode,
+        )?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use torrust_parse_torrent::parse_torrent;
+    use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+    use super::{parse_expected_hash, print_torrent, ExpectedHash};
+
+    #[test]
+    fn it_should_check_the_info_hash_of_the_same_version() {
+        let torrent =
+            parse_torrent(&std::fs::read("tests/fixtures/torrents/large-5gib.torrent").unwrap())
+                .unwrap();
+        let v1 = torrent.info_hash();
+
+        let expected = parse_expected_hash(&v1.to_string()).unwrap();
+        assert_eq!(expected, ExpectedHash::V1(v1));
+        assert_eq!(expected.check(&torrent), Ok(()));
+        assert_eq!(
+            parse_expected_hash(&v1.to_base32())
+                .unwrap()
+                .check(&torrent),
+            Ok(())
+        );
+        assert!(parse_expected_hash(&"0".repeat(40))
+            .unwrap()
+            .check(&torrent)
+            .is_err());
+        assert!(parse_expected_hash(&"0".repeat(64))
+            .unwrap()
+            .check(&torrent)
+            .unwrap_err()
+            .contains("doesn't have a v2 info-hash"));
+        assert!(parse_expected_hash("abc").is_err());
+    }
+
+    #[test]
+    fn it_should_check_the_original_info_hash_in_the_debug_format() {
+        // cspell:disable-next-line
+        let path = "tests/fixtures/torrents/6c690018c5786dbbb00161f62b0712d69296df97_with_custom_info_dict_key.torrent";
+        // cspell:disable-next-line
+        let expected = parse_expected_hash("6c690018c5786dbbb00161f62b0712d69296df97").unwrap();
+
+        for mode in [ParseMode::Lenient, ParseMode::Strict] {
+            assert!(print_torrent(path, mode, Some(&expected)).is_ok());
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! `pieces` subcommand: prints the piece hashes of a torrent.
+use std::error::Error;
+use std::ops::Range;
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::hex;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::read_and_decode;
+
+/// Length of a v1 piece hash.
+const HASH_LENGTH: usize = 20;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent file whose piece hashes are printed. `-`, the default, reads
+    /// the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    path: String,
+    /// Print only these pieces: `100..200` (200 excluded), `100..`, `..200`
+    /// or a single index.
+    #[arg(long, value_name = "RANGE", value_parser = parse_range)]
+    range: Option<Range<usize>>,
+}
+
+/// Prints one `<index>  <hash>` line per piece, with the SHA-1 hash in
+/// lowercase hex.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    match print_pieces(args, mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error parsing {}: {e}", args.path);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_pieces(args: &Args, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let torrent = read_and_decode(&args.path, mode)?;
+
+    let pieces = torrent
+        .info
+        .pieces
+        .as_ref()
+        .ok_or("the torrent has no v1 pieces")?;
+    if pieces.len() % HASH_LENGTH != 0 {
+        return Err(format!(
+            "the length of `pieces` ({}) is not a multiple of {HASH_LENGTH}",
+            pieces.len()
+        )
+        .into());
+    }
+
+    let count = pieces.len() / HASH_LENGTH;
+    let range = args.range.clone().unwrap_or(0..count);
+    if range.start >= count && !range.is_empty() {
+        return Err(format!(
+            "piece {} is out of range, the torrent has {count} pieces",
+            range.start
+        )
+        .into());
+    }
+
+    for (index, hash) in pieces
+        .chunks(HASH_LENGTH)
+        .enumerate()
+        .take(range.end)
+        .skip(range.start)
+    {
+        println!("{index}  {}", hex::from_bytes(hash).to_lowercase());
+    }
+
+    Ok(())
+}
+
+/// Parses a range of piece indices like `100..200`, `100..`, `..200` or `7`.
+fn parse_range(range: &str) -> Result<Range<usize>, String> {
+    let index = |index: &str| {
+        index
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid piece index `{index}` in `{range}`"))
+    };
+
+    match range.split_once("..") {
+        Some((start, end)) => Ok(Range {
+            start: if start.is_empty() { 0 } else { index(start)? },
+            end: if end.is_empty() {
+                usize::MAX
+            } else {
+                index(end)?
+            },
+        }),
+        None => {
+            let index = index(range)?;
+            Ok(index..index.saturating_add(1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_range;
+
+    #[test]
+    fn it_should_parse_piece_ranges() {
+        assert_eq!(parse_range("100..200"), Ok(100..200));
+        assert_eq!(parse_range("100.."), Ok(100..usize::MAX));
+        assert_eq!(parse_range("..200"), Ok(0..200));
+        assert_eq!(parse_range("7"), Ok(7..8));
+        assert!(parse_range("a..b").is_err());
+        assert!(parse_range("1..=2").is_err());
+    }
+}
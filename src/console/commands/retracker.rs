@@ -0,0 +1,49 @@
+//! `retracker` subcommand: replaces trackers in many torrents at once.
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use globset::{Glob, GlobMatcher};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{for_each_path, read_and_decode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Trackers to replace. `*` matches any text, like in
+    /// `'http://old.tracker/*'`.
+    #[arg(long, value_name = "PATTERN", value_parser = parse_pattern)]
+    from: GlobMatcher,
+    /// Tracker URL to use instead.
+    #[arg(long, value_name = "URL")]
+    to: String,
+    /// Torrent files to rewrite in place.
+    #[arg(required = true, value_name = "PATH_TO_TORRENT_FILE")]
+    paths: Vec<String>,
+}
+
+fn parse_pattern(pattern: &str) -> Result<GlobMatcher, String> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| e.to_string())
+}
+
+/// Rewrites the `announce` and `announce-list` of each torrent and prints how
+/// many trackers were replaced. Torrents without matching trackers are left
+/// untouched. The info-hashes don't change.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    for_each_path(&args.paths, |path| retracker(path, args, mode))
+}
+
+fn retracker(path: &str, args: &Args, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let mut torrent = read_and_decode(path, mode)?;
+
+    let replaced = torrent.replace_trackers(|url| args.from.is_match(url), &args.to);
+    if replaced > 0 {
+        fs::write(path, torrent.encode()?)?;
+    }
+
+    println!("{path}: {replaced} trackers replaced");
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! `scrape` subcommand: asks the trackers of a torrent about its swarm.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files whose trackers are scraped. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+    /// Seconds to wait for each tracker.
+    #[arg(long, value_name = "SECONDS", default_value_t = 10)]
+    timeout: u64,
+}
+
+/// Prints one `<tier>  <url>  <stats>` line per tracker, or the error of the
+/// tracker instead of the statistics.
+///
+/// A torrent fails if it has trackers and none of them answered.
+#[cfg(feature = "scrape")]
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    use std::time::Duration;
+
+    use torrust_parse_torrent::tracker::scrape;
+
+    use super::{for_each_path, read_and_decode};
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for_each_path(&args.paths, |path| {
+        let torrent = read_and_decode(path, mode)?;
+
+        if args.paths.len() > 1 {
+            println!("{path}:");
+        }
+
+        let scrapes = runtime.block_on(scrape(&torrent, Duration::from_secs(args.timeout)))?;
+
+        for scrape in &scrapes {
+            match &scrape.result {
+                Ok(stats) => println!(
+                    "{}  {}  {} seeders, {} leechers, {} completed",
+                    scrape.tier, scrape.url, stats.seeders, stats.leechers, stats.completed
+                ),
+                Err(e) => println!("{}  {}  error: {e}", scrape.tier, scrape.url),
+            }
+        }
+
+        if !scrapes.is_empty() && scrapes.iter().all(|scrape| scrape.result.is_err()) {
+            return Err("no tracker answered".into());
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(not(feature = "scrape"))]
+pub fn run(_args: &Args, _mode: ParseMode) -> ExitCode {
+    eprintln!("The `scrape` subcommand requires building with `--features scrape`");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,49 @@
+//! `scrub` subcommand: removes private data from a torrent before sharing it.
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::read_and_decode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent file to scrub.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE")]
+    path: String,
+    /// Where to write the torrent. Defaults to the input file, which is
+    /// overwritten.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<String>,
+}
+
+/// Removes the comment, the creator, the creation date and the unknown keys
+/// outside the info dictionary, and prints the removed keys. The info-hash
+/// doesn't change.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    match scrub(args, mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error scrubbing {}: {e}", args.path);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn scrub(args: &Args, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let mut torrent = read_and_decode(&args.path, mode)?;
+    let removed = torrent.scrub();
+
+    let output = args.output.as_deref().unwrap_or(&args.path);
+    fs::write(output, torrent.encode()?)?;
+
+    if removed.is_empty() {
+        println!("{output}: nothing to remove");
+    }
+    for key in &removed {
+        println!("{output}: removed `{key}`");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! `trackers` subcommand: lists the trackers of a torrent.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::{for_each_path, read_and_decode};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files whose trackers are listed. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+}
+
+/// Prints one `<tier>  <url>` line per tracker. The `announce` URL is only
+/// printed, in tier 0, when the torrent has no `announce-list`, and repeated
+/// URLs are printed once.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    for_each_path(&args.paths, |path| {
+        let torrent = read_and_decode(path, mode)?;
+
+        for (tier, url) in torrent.announce_tiers().iter() {
+            println!("{tier}  {url}");
+        }
+
+        Ok(())
+    })
+}
@@ -0,0 +1,28 @@
+//! `tui` subcommand: interactive torrent browser.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent file to explore.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE")]
+    path: String,
+}
+
+#[cfg(feature = "tui")]
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    match crate::tui::run(&args.path, mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+pub fn run(_args: &Args, _mode: ParseMode) -> ExitCode {
+    eprintln!("The `tui` subcommand requires building with `--features tui`");
+    ExitCode::FAILURE
+}
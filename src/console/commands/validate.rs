@@ -0,0 +1,102 @@
+//! `validate` subcommand: checks that torrents follow the specification.
+use std::process::ExitCode;
+
+use torrust_parse_torrent::models::torrent_file::Torrent;
+use torrust_parse_torrent::utils::diagnostics::{has_errors, Diagnostic, Severity};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+use torrust_parse_torrent::utils::parse_options::ParseOptions;
+use torrust_parse_torrent::utils::{bencode, parse_torrent_verbose, validate};
+
+use super::{check_signatures, exit_code, read_input, SignatureCheck};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent files to validate. `-`, the default,
+    /// reads the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE", default_value = "-")]
+    paths: Vec<String>,
+}
+
+/// Prints the problems found in each torrent, or `OK` if there are none.
+///
+/// The exit code is non-zero if any torrent has errors. Warnings alone only
+/// make the validation fail in strict mode.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let mut failures = 0;
+
+    for path in &args.paths {
+        let diagnostics = validate(path, mode);
+
+        if diagnostics.is_empty() {
+            println!("{path}: OK");
+        }
+
+        for diagnostic in &diagnostics {
+            println!("{path}: {diagnostic}");
+        }
+
+        let has_warnings = diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Warning);
+
+        if has_errors(&diagnostics) || (mode.is_strict() && has_warnings) {
+            failures += 1;
+        }
+    }
+
+    exit_code(failures)
+}
+
+fn validate(path: &str, mode: ParseMode) -> Vec<Diagnostic> {
+    let bytes = match read_input(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return vec![Diagnostic::error("", e.to_string())],
+    };
+
+    let decoded = match bencode::decode_with_diagnostics(&bytes, &ParseOptions::from(mode)) {
+        Ok(decoded) => decoded,
+        Err(e) => return vec![Diagnostic::error("", format!("invalid bencode: {e}"))],
+    };
+    let value = decoded.value;
+
+    let mut diagnostics = decoded.diagnostics;
+    diagnostics.extend(validate::validate(&value));
+    diagnostics.extend(validate::validate_text(&value, mode));
+    diagnostics.extend(validate::validate_trailing_data(
+        bytes.len() - decoded.length,
+        mode,
+    ));
+
+    // The file can follow the specification and still be unreadable by one
+    // of the parsers.
+    if !has_errors(&diagnostics) {
+        if let Err(e) = parse_torrent_verbose::decode_torrent_with_mode(value, mode) {
+            diagnostics.push(Diagnostic::error("", e.to_string()));
+        }
+        match torrust_parse_torrent::parse_torrent_with_mode(&bytes, mode) {
+            Ok(torrent) => {
+                diagnostics.extend(validate::validate_piece_layers(&torrent));
+                diagnostics.extend(signature_diagnostics(&torrent));
+            }
+            Err(e) => diagnostics.push(Diagnostic::error("", e.to_string())),
+        }
+    }
+
+    diagnostics
+}
+
+/// A torrent with an invalid signature is an error. Valid and unverified
+/// signatures are reported for information.
+fn signature_diagnostics(torrent: &Torrent) -> Vec<Diagnostic> {
+    check_signatures(torrent)
+        .into_iter()
+        .map(|(signer, check)| {
+            let key = format!("signatures.{signer}");
+            let message = format!("signature {check}");
+            match check {
+                SignatureCheck::Invalid => Diagnostic::error(&key, message),
+                _ => Diagnostic::info(&key, message),
+            }
+        })
+        .collect()
+}
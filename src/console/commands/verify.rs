@@ -0,0 +1,217 @@
+//! `verify` subcommand: checks downloaded content against a torrent.
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use serde_derive::Serialize;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+use torrust_parse_torrent::verify::{FileReport, FileStatus, PieceStatus, Verifier, VerifyReport};
+
+use super::{parse_size, read_and_decode};
+use crate::console::progress::ProgressBarHandler;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Print a JSON report with the status of each piece and file.
+    #[arg(long)]
+    json: bool,
+    /// Torrent file to verify the content against.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE")]
+    torrent: String,
+    /// The downloaded file, for single-file torrents, or the directory with
+    /// the files of the torrent.
+    #[arg(value_name = "CONTENT")]
+    content: PathBuf,
+    /// Only check the files whose path in the torrent matches this glob, like
+    /// `'Season 01/*.mkv'`. Can be repeated.
+    #[arg(long, value_name = "GLOB")]
+    file: Vec<String>,
+    /// Number of threads that hash the pieces. Defaults to one per CPU. Use
+    /// `1` to read and hash them sequentially.
+    #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+    threads: usize,
+    /// Data read ahead of the hashing threads, in bytes or with a unit
+    /// (`KiB`, `MiB`). It bounds the memory used.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "64MiB")]
+    read_ahead: u64,
+}
+
+/// Prints the status of each file and the number of valid pieces, or the
+/// JSON report.
+///
+/// The exit code is non-zero if any piece or file doesn't match.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    let result = verify(args, mode);
+
+    if args.json {
+        let report = match &result {
+            Ok(report) => Report::new(args, report),
+            Err(e) => Report::error(args, e.to_string()),
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("the report should be serializable")
+        );
+    }
+
+    match result {
+        Ok(report) => {
+            if !args.json {
+                print_report(&report);
+            }
+            if report.is_ok() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("Error verifying {}: {e}", args.content.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn verify(args: &Args, mode: ParseMode) -> Result<VerifyReport, Box<dyn Error>> {
+    let torrent = read_and_decode(&args.torrent, mode)?;
+    let mut verifier = Verifier::new(&torrent, &args.content)
+        .threads(args.threads)
+        .read_ahead(usize::try_from(args.read_ahead).unwrap_or(usize::MAX));
+    for pattern in &args.file {
+        verifier = verifier.file(pattern);
+    }
+    Ok(verifier.verify_with_progress(&ProgressBarHandler::new())?)
+}
+
+fn print_report(report: &VerifyReport) {
+    for file in &report.files {
+        let bad_pieces = bad_pieces(report, file);
+        let status = match file.status {
+            FileStatus::Ok => "OK",
+            FileStatus::Missing => "MISSING",
+            FileStatus::Incomplete => "INCOMPLETE",
+            FileStatus::Corrupt => "CORRUPT",
+        };
+
+        if bad_pieces > 0 {
+            println!(
+                "{status:<10}  {}  ({bad_pieces} of {} pieces bad)",
+                file.path.display(),
+                file.pieces.len()
+            );
+        } else {
+            println!("{status:<10}  {}", file.path.display());
+        }
+    }
+
+    println!(
+        "{} of {} pieces OK ({:.1}%)",
+        report.ok_pieces(),
+        report.checked_pieces(),
+        report.completion() * 100.0
+    );
+}
+
+fn bad_pieces(report: &VerifyReport, file: &FileReport) -> usize {
+    report.pieces[file.pieces.clone()]
+        .iter()
+        .filter(|status| **status != PieceStatus::Ok)
+        .count()
+}
+
+/// Machine-readable verification report, or the error if the content could
+/// not be verified.
+#[derive(Serialize, Debug)]
+struct Report {
+    torrent: String,
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ok: Option<bool>,
+    /// Percentage of the checked pieces that are valid.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completion: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checked_pieces: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ok_pieces: Option<usize>,
+    /// Status of each piece, by index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pieces: Option<Vec<&'static str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<FileEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct FileEntry {
+    path: String,
+    status: &'static str,
+    length: u64,
+    actual_length: Option<u64>,
+    /// Index of the first piece with data of the file.
+    first_piece: usize,
+    /// Number of pieces with data of the file.
+    pieces: usize,
+    bad_pieces: usize,
+}
+
+impl Report {
+    fn new(args: &Args, report: &VerifyReport) -> Self {
+        Self {
+            torrent: args.torrent.clone(),
+            content: args.content.display().to_string(),
+            ok: Some(report.is_ok()),
+            completion: Some(report.completion() * 100.0),
+            checked_pieces: Some(report.checked_pieces()),
+            ok_pieces: Some(report.ok_pieces()),
+            pieces: Some(
+                report
+                    .pieces
+                    .iter()
+                    .map(|status| match status {
+                        PieceStatus::Ok => "ok",
+                        PieceStatus::Corrupt => "bad",
+                        PieceStatus::Missing => "missing",
+                        PieceStatus::Skipped => "skipped",
+                    })
+                    .collect(),
+            ),
+            files: Some(
+                report
+                    .files
+                    .iter()
+                    .map(|file| FileEntry {
+                        path: file.path.display().to_string(),
+                        status: match file.status {
+                            FileStatus::Ok => "ok",
+                            FileStatus::Missing => "missing",
+                            FileStatus::Incomplete => "incomplete",
+                            FileStatus::Corrupt => "corrupt",
+                        },
+                        length: file.length,
+                        actual_length: file.actual_length,
+                        first_piece: file.pieces.start,
+                        pieces: file.pieces.len(),
+                        bad_pieces: bad_pieces(report, file),
+                    })
+                    .collect(),
+            ),
+            error: None,
+        }
+    }
+
+    fn error(args: &Args, error: String) -> Self {
+        Self {
+            torrent: args.torrent.clone(),
+            content: args.content.display().to_string(),
+            ok: None,
+            completion: None,
+            checked_pieces: None,
+            ok_pieces: None,
+            pieces: None,
+            files: None,
+            error: Some(error),
+        }
+    }
+}
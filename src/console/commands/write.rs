@@ -0,0 +1,43 @@
+//! `write` subcommand: parses a torrent and bencodes it back.
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+use super::read_and_decode;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Torrent file to read, or `-` for the standard input.
+    #[arg(value_name = "PATH_TO_TORRENT_FILE")]
+    path: String,
+    /// File to write the torrent to. Defaults to the standard output.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<String>,
+}
+
+/// Writes the bencoded torrent, with sorted keys and the non-standard keys
+/// preserved.
+pub fn run(args: &Args, mode: ParseMode) -> ExitCode {
+    match write(args, mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error writing {}: {e}", args.path);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn write(args: &Args, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let torrent = read_and_decode(&args.path, mode)?;
+    let bytes = torrent.encode()?;
+
+    match &args.output {
+        Some(output) => fs::write(output, bytes)?,
+        None => io::stdout().lock().write_all(&bytes)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Downloads the torrents given as `http://` or `https://` URLs instead of
+//! paths, which requires the `fetch` feature, or as magnet links, which
+//! requires the `magnet` feature.
+use std::error::Error;
+
+/// Time to find peers and download the metadata of a magnet link.
+#[cfg(feature = "magnet")]
+const MAGNET_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Largest torrent that is downloaded. Torrents are rarely larger than a few
+/// megabytes, even with millions of pieces.
+#[cfg(feature = "fetch")]
+pub const MAX_TORRENT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Returns `true` if the argument is a URL instead of a path.
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Returns `true` if the argument is a magnet link instead of a path.
+pub fn is_magnet(path: &str) -> bool {
+    path.starts_with("magnet:")
+}
+
+/// Downloads the metadata of a magnet link from its peers and returns the
+/// torrent, with the trackers of the link.
+#[cfg(feature = "magnet")]
+pub fn fetch_magnet(link: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    use torrust_parse_torrent::metadata::fetch_metadata;
+    use torrust_parse_torrent::models::magnet_link::MagnetLink;
+
+    let magnet: MagnetLink = link.parse()?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    Ok(runtime.block_on(fetch_metadata(&magnet, MAGNET_TIMEOUT))?)
+}
+
+#[cfg(not(feature = "magnet"))]
+pub fn fetch_magnet(_link: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("reading magnet links requires building with `--features magnet`".into())
+}
+
+/// Downloads a torrent.
+///
+/// Responses with an error status, a `Content-Type` that can't be a torrent
+/// or more than [`MAX_TORRENT_SIZE`] bytes are rejected.
+#[cfg(feature = "fetch")]
+pub fn fetch(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(fetch_async(url))
+}
+
+#[cfg(not(feature = "fetch"))]
+pub fn fetch(_url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("downloading torrents requires building with `--features fetch`".into())
+}
+
+#[cfg(feature = "fetch")]
+async fn fetch_async(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut response = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()?
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    check_content_type(
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok()),
+    )?;
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_TORRENT_SIZE)
+    {
+        return Err(too_large());
+    }
+
+    let mut bytes = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        if (bytes.len() + chunk.len()) as u64 > MAX_TORRENT_SIZE {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(feature = "fetch")]
+fn too_large() -> Box<dyn Error> {
+    format!("the torrent is larger than {MAX_TORRENT_SIZE} bytes").into()
+}
+
+/// Rejects the media types that are never torrents, like the HTML page of a
+/// login form. Servers often send torrents as `application/octet-stream` or
+/// without a `Content-Type`, so only text types are rejected.
+#[cfg(feature = "fetch")]
+fn check_content_type(content_type: Option<&str>) -> Result<(), String> {
+    let Some(content_type) = content_type else {
+        return Ok(());
+    };
+
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    if media_type.starts_with("text/")
+        || media_type.ends_with("json")
+        || media_type.ends_with("xml")
+    {
+        Err(format!(
+            "unexpected content type `{media_type}`, expected `application/x-bittorrent`"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_magnet, is_url};
+
+    #[test]
+    fn it_should_tell_urls_and_magnet_links_from_paths() {
+        assert!(is_url("https://example.com/a.torrent"));
+        assert!(!is_url("./https/a.torrent"));
+        assert!(is_magnet(
+            "magnet:?xt=urn:btih:0000000000000000000000000000000000000000"
+        ));
+        assert!(!is_magnet("magnet.torrent"));
+    }
+
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn it_should_reject_content_types_that_are_not_torrents() {
+        use super::check_content_type;
+
+        assert!(check_content_type(None).is_ok());
+        assert!(check_content_type(Some("application/x-bittorrent")).is_ok());
+        assert!(check_content_type(Some("application/octet-stream")).is_ok());
+        assert!(check_content_type(Some("text/html; charset=utf-8")).is_err());
+        assert!(check_content_type(Some("application/json")).is_err());
+    }
+
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn it_should_download_a_torrent() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: application/x-bittorrent\r\nContent-Length: 4\r\nConnection: close\r\n\r\nd1:e",
+                )
+                .unwrap();
+        });
+
+        assert_eq!(
+            super::fetch(&format!("http://{addr}/a.torrent")).unwrap(),
+            b"d1:e"
+        );
+    }
+}
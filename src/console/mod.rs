@@ -0,0 +1,139 @@
+//! Command line interface.
+//!
+//! Every subcommand lives in its own module under [`commands`].
+pub mod commands;
+pub mod fetch;
+pub mod progress;
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+
+/// Parse and inspect `BitTorrent` metainfo (`.torrent`) files.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Reject torrents that violate the specification instead of decoding
+    /// them on a best-effort basis.
+    #[arg(long, global = true)]
+    strict: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Decode torrents with both parser implementations and print the result.
+    Parse(commands::parse::Args),
+    /// Print a summary of each torrent.
+    #[command(visible_alias = "summary")]
+    Info(commands::info::Args),
+    /// Parse every torrent in some directories and report the results.
+    Batch(commands::batch::Args),
+    /// Store torrents, their files and trackers in a SQLite database.
+    Index(commands::index::Args),
+    /// Print the info-hashes of each torrent.
+    Hash(commands::hash::Args),
+    /// List the files of a torrent.
+    Files(commands::files::Args),
+    /// Print the piece hashes of a torrent.
+    Pieces(commands::pieces::Args),
+    /// List the trackers of a torrent.
+    Trackers(commands::trackers::Args),
+    /// Check that torrents follow the specification.
+    Validate(commands::validate::Args),
+    /// Look for file paths that could harm the computer downloading a torrent.
+    Audit(commands::audit::Args),
+    /// Print the magnet link of each torrent.
+    Magnet(commands::magnet::Args),
+    /// Show what changed between two torrents.
+    Diff(commands::diff::Args),
+    /// Tell whether two torrents can be seeded from the same data.
+    CrossSeedCheck(commands::cross_seed_check::Args),
+    /// Explore a torrent in an interactive terminal user interface.
+    Tui(commands::tui::Args),
+    /// Bencode a parsed torrent back into a `.torrent` file.
+    Write(commands::write::Args),
+    /// Create a torrent from a file or directory.
+    Create(commands::create::Args),
+    /// Check downloaded content against the pieces of a torrent.
+    Verify(commands::verify::Args),
+    /// Modify the comment, trackers, source or private flag of a torrent.
+    Edit(commands::edit::Args),
+    /// Remove the comment, creator, creation date and unknown keys.
+    Scrub(commands::scrub::Args),
+    /// Replace trackers matching a pattern in many torrents.
+    Retracker(commands::retracker::Args),
+    /// Convert a hybrid torrent to a v1-only or v2-only torrent.
+    Convert(commands::convert::Args),
+    /// Ask the trackers of a torrent for its number of seeders and leechers.
+    Scrape(commands::scrape::Args),
+    /// Check which trackers of a torrent still answer.
+    CheckTrackers(commands::check_trackers::Args),
+    /// Check which DHT nodes of a torrent still answer.
+    CheckNodes(commands::check_nodes::Args),
+}
+
+/// Parses the command line arguments and runs the subcommand.
+#[must_use]
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    let mode = if cli.strict {
+        ParseMode::Strict
+    } else {
+        ParseMode::Lenient
+    };
+
+    match cli.command {
+        Command::Parse(args) => commands::parse::run(&args, mode),
+        Command::Info(args) => commands::info::run(&args, mode),
+        Command::Batch(args) => commands::batch::run(&args, mode),
+        Command::Index(args) => commands::index::run(&args, mode),
+        Command::Hash(args) => commands::hash::run(&args, mode),
+        Command::Files(args) => commands::files::run(&args, mode),
+        Command::Pieces(args) => commands::pieces::run(&args, mode),
+        Command::Trackers(args) => commands::trackers::run(&args, mode),
+        Command::Validate(args) => commands::validate::run(&args, mode),
+        Command::Audit(args) => commands::audit::run(&args, mode),
+        Command::Magnet(args) => commands::magnet::run(&args, mode),
+        Command::Diff(args) => commands::diff::run(&args, mode),
+        Command::CrossSeedCheck(args) => commands::cross_seed_check::run(&args, mode),
+        Command::Tui(args) => commands::tui::run(&args, mode),
+        Command::Write(args) => commands::write::run(&args, mode),
+        Command::Create(args) => commands::create::run(&args, mode),
+        Command::Verify(args) => commands::verify::run(&args, mode),
+        Command::Edit(args) => commands::edit::run(&args, mode),
+        Command::Scrub(args) => commands::scrub::run(&args, mode),
+        Command::Retracker(args) => commands::retracker::run(&args, mode),
+        Command::Convert(args) => commands::convert::run(&args, mode),
+        Command::Scrape(args) => commands::scrape::run(&args, mode),
+        Command::CheckTrackers(args) => commands::check_trackers::run(&args, mode),
+        Command::CheckNodes(args) => commands::check_nodes::run(&args, mode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::Cli;
+
+    #[test]
+    fn it_should_have_a_valid_command_line_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn it_should_read_the_standard_input_without_paths() {
+        let cli = Cli::try_parse_from(["parse-torrent", "hash"]).unwrap();
+
+        assert!(format!("{cli:?}").contains(r#"paths: ["-"]"#));
+    }
+
+    #[test]
+    fn it_should_not_accept_both_hash_versions_only() {
+        assert!(Cli::try_parse_from(["parse-torrent", "hash", "--v1-only", "--v2-only"]).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! Progress bar shown on the standard error while hashing content.
+use indicatif::{ProgressBar, ProgressStyle};
+use torrust_parse_torrent::progress::{Progress, ProgressHandler};
+
+/// Draws the progress of the hashing with [`indicatif`]. Nothing is drawn
+/// when the standard error is not a terminal.
+pub struct ProgressBarHandler {
+    bar: ProgressBar,
+}
+
+impl ProgressBarHandler {
+    #[must_use]
+    pub fn new() -> Self {
+        let bar = ProgressBar::new(0).with_style(
+            ProgressStyle::with_template(
+                "{bar:40} {binary_bytes}/{binary_total_bytes} ({binary_bytes_per_sec}, {eta} left) {wide_msg}",
+            )
+            .expect("the progress bar template should be valid"),
+        );
+        Self { bar }
+    }
+}
+
+impl Default for ProgressBarHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressHandler for ProgressBarHandler {
+    fn on_start(&self, total_bytes: u64) {
+        self.bar.set_length(total_bytes);
+    }
+
+    fn on_progress(&self, progress: &Progress<'_>) {
+        self.bar.set_position(progress.bytes);
+        if let Some(file) = progress.current_file {
+            self.bar.set_message(file.display().to_string());
+        }
+    }
+
+    fn on_finish(&self, _progress: &Progress<'_>) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,682 @@
+//! A minimal client of the `BitTorrent` DHT ([BEP 5](https://www.bittorrent.org/beps/bep_0005.html)),
+//! to find the peers of a swarm without trackers and to check the `nodes` of
+//! trackerless torrents.
+//!
+//! Requires the `dht` feature. Only the `ping` and `get_peers` queries are
+//! sent over IPv4. The client never answers the queries of other nodes, so
+//! it marks its queries as read-only ([BEP 43](https://www.bittorrent.org/beps/bep_0043.html)).
+//!
+//! ```no_run
+//! # async fn example() {
+//! use std::time::Duration;
+//!
+//! use torrust_parse_torrent::dht::{resolve, Dht, DEFAULT_BOOTSTRAP};
+//! use torrust_parse_torrent::models::info_hash::InfoHash;
+//!
+//! let bootstrap = resolve(&DEFAULT_BOOTSTRAP.map(String::from)).await;
+//! let dht = Dht::bind().await.unwrap();
+//! let peers = dht
+//!     .find_peers(&bootstrap, &InfoHash([0; 20]), Duration::from_secs(10))
+//!     .await;
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_bencode::value::Value as BValue;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::task::JoinSet;
+use tokio::time::{timeout_at, Instant};
+
+use crate::models::info_hash::InfoHash;
+use crate::models::torrent_file::Torrent;
+use crate::utils::bencode;
+
+/// Well-known nodes used to join the DHT when there are no others.
+pub const DEFAULT_BOOTSTRAP: [&str; 3] = [
+    "router.bittorrent.com:6881",
+    "dht.transmissionbt.com:6881",
+    "router.utorrent.com:6881",
+];
+
+/// Nodes queried at the same time during a lookup.
+const ALPHA: usize = 8;
+
+/// Time to wait for the answers of each round of a lookup.
+const ROUND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A lookup stops after finding this many peers or sending this many
+/// queries.
+const MAX_PEERS: usize = 100;
+const MAX_QUERIES: usize = 256;
+
+/// Nodes kept as candidates for the next rounds of a lookup.
+const MAX_CANDIDATES: usize = 64;
+
+/// Length of a node in a compact node list: ID, IPv4 address and port.
+const COMPACT_NODE_LEN: usize = 20 + 4 + 2;
+
+#[derive(Error, Debug)]
+pub enum DhtError {
+    #[error("could not resolve `{0}`")]
+    NoAddress(String),
+    #[error("timed out")]
+    Timeout,
+    #[error("invalid response: {0}")]
+    Protocol(String),
+    #[error("error {code}: {message}")]
+    Remote { code: i64, message: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A node of the DHT.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Node {
+    pub id: [u8; 20],
+    pub addr: SocketAddr,
+}
+
+/// The answer of a node to a `get_peers` query.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct GetPeers {
+    /// ID of the node that answered.
+    pub id: [u8; 20],
+    /// Peers of the swarm the node knows.
+    pub peers: Vec<SocketAddr>,
+    /// Nodes closer to the info-hash.
+    pub nodes: Vec<Node>,
+}
+
+/// How a live node answered a `ping`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct NodePing {
+    pub addr: SocketAddr,
+    pub id: [u8; 20],
+    /// Time from the start of the check until the answer, including the name
+    /// resolution.
+    pub latency: Duration,
+}
+
+/// The result of checking one of the `nodes` of a torrent.
+#[derive(Debug)]
+pub struct NodeCheck {
+    /// The node as `host:port`.
+    pub node: String,
+    pub result: Result<NodePing, DhtError>,
+}
+
+/// A decoded KRPC message that answers one of our queries.
+struct Message {
+    transaction: Vec<u8>,
+    result: Result<HashMap<Vec<u8>, BValue>, DhtError>,
+}
+
+/// A DHT client bound to a UDP socket.
+#[derive(Debug)]
+pub struct Dht {
+    socket: UdpSocket,
+    id: [u8; 20],
+    transaction: AtomicU16,
+}
+
+impl Dht {
+    /// Binds a client to a random port, with a random node ID.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the socket can't be bound.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if it's not called from a Tokio runtime.
+    pub async fn bind() -> Result<Self, DhtError> {
+        Ok(Self {
+            socket: UdpSocket::bind("0.0.0.0:0").await?,
+            id: random_id(),
+            transaction: AtomicU16::new(0),
+        })
+    }
+
+    /// Returns the node ID of the client.
+    #[must_use]
+    pub fn id(&self) -> [u8; 20] {
+        self.id
+    }
+
+    /// Pings a node and returns its ID.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the node doesn't answer before the timeout or
+    /// answers with an error.
+    pub async fn ping(&self, addr: SocketAddr, timeout: Duration) -> Result<[u8; 20], DhtError> {
+        let response = self.request(addr, "ping", vec![], timeout).await?;
+        node_id(&response)
+    }
+
+    /// Asks a node for the peers of a swarm, or the nodes closer to it.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the node doesn't answer before the timeout or
+    /// answers with an error.
+    pub async fn get_peers(
+        &self,
+        addr: SocketAddr,
+        info_hash: &InfoHash,
+        timeout: Duration,
+    ) -> Result<GetPeers, DhtError> {
+        let response = self
+            .request(addr, "get_peers", get_peers_args(info_hash), timeout)
+            .await?;
+        parse_get_peers(&response)
+    }
+
+    /// Looks up the peers of a swarm, starting from the `bootstrap` nodes
+    /// and asking the nodes closest to the info-hash in each round. Nodes
+    /// that fail are skipped.
+    pub async fn find_peers(
+        &self,
+        bootstrap: &[SocketAddr],
+        info_hash: &InfoHash,
+        timeout: Duration,
+    ) -> Vec<SocketAddr> {
+        let deadline = Instant::now() + timeout;
+        let target = info_hash.bytes();
+
+        // Closest to the info-hash first. The bootstrap nodes have no known
+        // ID, so they go before all the others.
+        let mut candidates: Vec<(Option<[u8; 20]>, SocketAddr)> =
+            bootstrap.iter().map(|addr| (None, *addr)).collect();
+        let mut queried = HashSet::new();
+        let mut peers = vec![];
+
+        while peers.len() < MAX_PEERS && queried.len() < MAX_QUERIES {
+            let batch: Vec<SocketAddr> = candidates
+                .drain(..candidates.len().min(ALPHA))
+                .map(|(_, addr)| addr)
+                .collect();
+            if batch.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+
+            let mut pending = HashMap::new();
+            for addr in batch {
+                queried.insert(addr);
+                let (transaction, query) = self.query("get_peers", get_peers_args(info_hash));
+                if self.socket.send_to(&query, addr).await.is_ok() {
+                    pending.insert(transaction, addr);
+                }
+            }
+
+            let round_deadline = deadline.min(Instant::now() + ROUND_TIMEOUT);
+            while !pending.is_empty() {
+                let Ok(Some((from, message))) = self.receive(round_deadline).await else {
+                    break;
+                };
+                if pending.get(&message.transaction) != Some(&from) {
+                    continue;
+                }
+                pending.remove(&message.transaction);
+
+                let Ok(response) = message.result.and_then(|r| parse_get_peers(&r)) else {
+                    continue;
+                };
+                for peer in response.peers {
+                    if !peers.contains(&peer) {
+                        peers.push(peer);
+                    }
+                }
+                for node in response.nodes {
+                    if !queried.contains(&node.addr)
+                        && !candidates.iter().any(|(_, addr)| *addr == node.addr)
+                    {
+                        candidates.push((Some(node.id), node.addr));
+                    }
+                }
+            }
+
+            candidates.sort_by_key(|(id, _)| id.map(|id| distance(&id, &target)));
+            candidates.truncate(MAX_CANDIDATES);
+        }
+
+        peers
+    }
+
+    /// Sends a query and waits for its answer.
+    async fn request(
+        &self,
+        addr: SocketAddr,
+        method: &str,
+        args: Vec<(&[u8], BValue)>,
+        timeout: Duration,
+    ) -> Result<HashMap<Vec<u8>, BValue>, DhtError> {
+        let deadline = Instant::now() + timeout;
+        let (transaction, query) = self.query(method, args);
+        self.socket.send_to(&query, addr).await?;
+
+        loop {
+            let (from, message) = self.receive(deadline).await?.ok_or(DhtError::Timeout)?;
+            if from == addr && message.transaction == transaction {
+                return message.result;
+            }
+        }
+    }
+
+    /// Returns the transaction ID and the bencoded query.
+    fn query(&self, method: &str, args: Vec<(&[u8], BValue)>) -> (Vec<u8>, Vec<u8>) {
+        let transaction = self
+            .transaction
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes()
+            .to_vec();
+
+        let mut args = dict(args);
+        if let BValue::Dict(args) = &mut args {
+            args.insert(b"id".to_vec(), BValue::Bytes(self.id.to_vec()));
+        }
+
+        let query = dict(vec![
+            (b"t", BValue::Bytes(transaction.clone())),
+            (b"y", BValue::Bytes(b"q".to_vec())),
+            (b"q", BValue::Bytes(method.as_bytes().to_vec())),
+            (b"a", args),
+            (b"ro", BValue::Int(1)),
+        ]);
+
+        (
+            transaction,
+            serde_bencode::to_bytes(&query).expect("bencode values can be serialized"),
+        )
+    }
+
+    /// Waits for the next answer until the deadline, and returns `None` if
+    /// there is none. Queries and messages that can't be decoded are
+    /// skipped.
+    async fn receive(&self, deadline: Instant) -> Result<Option<(SocketAddr, Message)>, DhtError> {
+        let mut buffer = [0; 2048];
+        loop {
+            let Ok(received) = timeout_at(deadline, self.socket.recv_from(&mut buffer)).await
+            else {
+                return Ok(None);
+            };
+            let (len, from) = received?;
+            if let Some(message) = parse_message(&buffer[..len]) {
+                return Ok(Some((from, message)));
+            }
+        }
+    }
+}
+
+/// Returns the `nodes` of a torrent as `host:port`, to use them as bootstrap
+/// nodes.
+#[must_use]
+pub fn bootstrap_hosts(torrent: &Torrent) -> Vec<String> {
+    torrent
+        .nodes
+        .iter()
+        .flatten()
+        .map(|node| {
+            if node.0.contains(':') && !node.0.starts_with('[') {
+                format!("[{}]:{}", node.0, node.1)
+            } else {
+                format!("{}:{}", node.0, node.1)
+            }
+        })
+        .collect()
+}
+
+/// Resolves `host:port` nodes to their first IPv4 address. The ones that
+/// can't be resolved are skipped.
+pub async fn resolve(hosts: &[String]) -> Vec<SocketAddr> {
+    let mut addrs = vec![];
+    for host in hosts {
+        if let Ok(addr) = resolve_node(host).await {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+    }
+    addrs
+}
+
+async fn resolve_node(host: &str) -> Result<SocketAddr, DhtError> {
+    lookup_host(host)
+        .await?
+        .find(SocketAddr::is_ipv4)
+        .ok_or_else(|| DhtError::NoAddress(host.to_owned()))
+}
+
+/// Pings all the `nodes` of a torrent concurrently, and returns the results
+/// in the order of the torrent.
+///
+/// # Panics
+///
+/// Will panic if it's not called from a Tokio runtime.
+pub async fn check_nodes(torrent: &Torrent, timeout: Duration) -> Vec<NodeCheck> {
+    let mut tasks = JoinSet::new();
+    for (index, node) in bootstrap_hosts(torrent).into_iter().enumerate() {
+        tasks.spawn(async move {
+            let result = check_node(&node, timeout).await;
+            (index, NodeCheck { node, result })
+        });
+    }
+
+    let mut checks = vec![];
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(check) => checks.push(check),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+    checks.sort_by_key(|(index, _)| *index);
+    checks.into_iter().map(|(_, check)| check).collect()
+}
+
+async fn check_node(node: &str, timeout: Duration) -> Result<NodePing, DhtError> {
+    let started = Instant::now();
+    let addr = timeout_at(started + timeout, resolve_node(node))
+        .await
+        .map_err(|_| DhtError::Timeout)??;
+
+    let id = Dht::bind()
+        .await?
+        .ping(addr, timeout.saturating_sub(started.elapsed()))
+        .await?;
+
+    Ok(NodePing {
+        addr,
+        id,
+        latency: started.elapsed(),
+    })
+}
+
+/// Decodes a compact node list: 20 bytes of ID, 4 of IPv4 address and 2 of
+/// port each.
+#[must_use]
+pub fn compact_nodes(bytes: &[u8]) -> Vec<Node> {
+    bytes
+        .chunks_exact(COMPACT_NODE_LEN)
+        .map(|node| Node {
+            id: node[..20].try_into().expect("20 bytes"),
+            addr: compact_addr(&node[20..]),
+        })
+        .collect()
+}
+
+fn compact_addr(bytes: &[u8]) -> SocketAddr {
+    let ip: [u8; 4] = bytes[..4].try_into().expect("4 bytes");
+    SocketAddr::from((ip, u16::from_be_bytes([bytes[4], bytes[5]])))
+}
+
+/// XOR distance between two IDs. Arrays compare like big-endian numbers.
+fn distance(a: &[u8; 20], b: &[u8; 20]) -> [u8; 20] {
+    std::array::from_fn(|i| a[i] ^ b[i])
+}
+
+fn get_peers_args(info_hash: &InfoHash) -> Vec<(&'static [u8], BValue)> {
+    vec![(b"info_hash", BValue::Bytes(info_hash.bytes().to_vec()))]
+}
+
+/// Decodes an answer (`y` is `r`) or an error (`y` is `e`).
+fn parse_message(bytes: &[u8]) -> Option<Message> {
+    let BValue::Dict(mut message) = bencode::decode(bytes).ok()? else {
+        return None;
+    };
+    let Some(BValue::Bytes(transaction)) = message.remove(b"t".as_slice()) else {
+        return None;
+    };
+
+    let result = match (
+        message.remove(b"y".as_slice()),
+        message.remove(b"r".as_slice()),
+        message.remove(b"e".as_slice()),
+    ) {
+        (Some(BValue::Bytes(y)), Some(BValue::Dict(response)), _) if y == b"r" => Ok(response),
+        (Some(BValue::Bytes(y)), _, Some(BValue::List(error))) if y == b"e" => {
+            Err(match error.as_slice() {
+                [BValue::Int(code), BValue::Bytes(message)] => DhtError::Remote {
+                    code: *code,
+                    message: String::from_utf8_lossy(message).into_owned(),
+                },
+                _ => invalid("malformed error"),
+            })
+        }
+        _ => return None,
+    };
+
+    Some(Message {
+        transaction,
+        result,
+    })
+}
+
+fn parse_get_peers(response: &HashMap<Vec<u8>, BValue>) -> Result<GetPeers, DhtError> {
+    let peers = match response.get(b"values".as_slice()) {
+        Some(BValue::List(values)) => values
+            .iter()
+            .filter_map(|value| match value {
+                BValue::Bytes(peer) if peer.len() == 6 => Some(compact_addr(peer)),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    let nodes = match response.get(b"nodes".as_slice()) {
+        Some(BValue::Bytes(nodes)) => compact_nodes(nodes),
+        _ => vec![],
+    };
+
+    Ok(GetPeers {
+        id: node_id(response)?,
+        peers,
+        nodes,
+    })
+}
+
+fn node_id(response: &HashMap<Vec<u8>, BValue>) -> Result<[u8; 20], DhtError> {
+    match response.get(b"id".as_slice()) {
+        Some(BValue::Bytes(id)) => id
+            .as_slice()
+            .try_into()
+            .map_err(|_| invalid("the node ID is not 20 bytes long")),
+        _ => Err(invalid("missing node ID")),
+    }
+}
+
+fn dict(entries: Vec<(&[u8], BValue)>) -> BValue {
+    BValue::Dict(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_vec(), value))
+            .collect(),
+    )
+}
+
+fn invalid(message: &str) -> DhtError {
+    DhtError::Protocol(message.to_owned())
+}
+
+/// Returns a node ID that changes with each call.
+fn random_id() -> [u8; 20] {
+    static CALLS: AtomicU16 = AtomicU16::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+
+    Sha1::new()
+        .chain_update(nanos.to_be_bytes())
+        .chain_update(std::process::id().to_be_bytes())
+        .chain_update(CALLS.fetch_add(1, Ordering::Relaxed).to_be_bytes())
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, UdpSocket};
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_bencode::value::Value as BValue;
+
+    use super::{bootstrap_hosts, compact_nodes, Dht, DhtError};
+    use crate::models::info_hash::InfoHash;
+    use crate::models::torrent_file::{Torrent, TorrentNode};
+    use crate::utils::bencode;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// A node that answers `queries` queries with the response built by
+    /// `answer` from the query name.
+    fn fake_node<F>(queries: usize, answer: F) -> (SocketAddr, thread::JoinHandle<()>)
+    where
+        F: Fn(&[u8]) -> Vec<u8> + Send + 'static,
+    {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        let node = thread::spawn(move || {
+            for _ in 0..queries {
+                let mut buffer = [0; 1024];
+                let (len, from) = socket.recv_from(&mut buffer).unwrap();
+                let BValue::Dict(query) = bencode::decode(&buffer[..len]).unwrap() else {
+                    panic!("the query is not a dictionary");
+                };
+                let (Some(BValue::Bytes(t)), Some(BValue::Bytes(q))) =
+                    (query.get(b"t".as_slice()), query.get(b"q".as_slice()))
+                else {
+                    panic!("the query has no transaction ID or name");
+                };
+
+                let response = [
+                    format!("d1:t{}:", t.len()).into_bytes(),
+                    t.clone(),
+                    answer(q),
+                ]
+                .concat();
+                socket.send_to(&response, from).unwrap();
+            }
+        });
+
+        (addr, node)
+    }
+
+    fn compact_node(id: u8, addr: SocketAddr) -> Vec<u8> {
+        let SocketAddr::V4(addr) = addr else {
+            panic!("not an IPv4 address");
+        };
+        [
+            vec![id; 20],
+            addr.ip().octets().to_vec(),
+            addr.port().to_be_bytes().to_vec(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn it_should_ping_a_node() {
+        let (addr, node) = fake_node(1, |q| {
+            assert_eq!(q, b"ping");
+            b"1:y1:r1:rd2:id20:nnnnnnnnnnnnnnnnnnnnee".to_vec()
+        });
+
+        let id = block_on(async {
+            Dht::bind()
+                .await
+                .unwrap()
+                .ping(addr, Duration::from_secs(5))
+                .await
+        })
+        .unwrap();
+
+        node.join().unwrap();
+        assert_eq!(id, [b'n'; 20]);
+    }
+
+    #[test]
+    fn it_should_return_the_errors_of_a_node() {
+        let (addr, node) = fake_node(1, |_| b"1:y1:e1:eli201e13:Generic Erroree".to_vec());
+
+        let result = block_on(async {
+            Dht::bind()
+                .await
+                .unwrap()
+                .ping(addr, Duration::from_secs(5))
+                .await
+        });
+
+        node.join().unwrap();
+        assert!(matches!(result, Err(DhtError::Remote { code: 201, .. })));
+    }
+
+    #[test]
+    fn it_should_follow_the_closer_nodes_to_find_peers() {
+        let (second, second_node) = fake_node(1, |q| {
+            assert_eq!(q, b"get_peers");
+            b"1:y1:r1:rd2:id20:bbbbbbbbbbbbbbbbbbbb6:valuesl6:\x0a\x00\x00\x01\x1a\xe1eee".to_vec()
+        });
+        let (first, first_node) = fake_node(1, move |_| {
+            let nodes = compact_node(b'b', second);
+            [
+                b"1:y1:r1:rd2:id20:aaaaaaaaaaaaaaaaaaaa5:nodes".to_vec(),
+                format!("{}:", nodes.len()).into_bytes(),
+                nodes,
+                b"ee".to_vec(),
+            ]
+            .concat()
+        });
+
+        let peers = block_on(async {
+            Dht::bind()
+                .await
+                .unwrap()
+                .find_peers(&[first], &InfoHash([b'b'; 20]), Duration::from_secs(5))
+                .await
+        });
+
+        first_node.join().unwrap();
+        second_node.join().unwrap();
+        assert_eq!(peers, vec!["10.0.0.1:6881".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn it_should_decode_compact_node_lists() {
+        let addr = "192.168.1.2:6881".parse().unwrap();
+
+        let nodes = compact_nodes(&[compact_node(7, addr), vec![0; 5]].concat());
+
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].id, [7; 20]);
+        assert_eq!(nodes[0].addr, addr);
+    }
+
+    #[test]
+    fn it_should_use_the_nodes_of_a_torrent_as_bootstrap_hosts() {
+        let torrent = Torrent {
+            nodes: Some(vec![
+                TorrentNode("router.test".to_owned(), 6881),
+                TorrentNode("::1".to_owned(), 6882),
+            ]),
+            ..Torrent::default()
+        };
+
+        assert_eq!(
+            bootstrap_hosts(&torrent),
+            vec!["router.test:6881", "[::1]:6882"]
+        );
+    }
+}
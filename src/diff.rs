@@ -0,0 +1,487 @@
+//! Differences between two torrents, to audit re-uploads and edited copies,
+//! whether they can be seeded from the same data ([`CrossSeedCheck`]) and
+//! how many files they share ([`file_similarity`]).
+//!
+//! ```
+//! use torrust_parse_torrent::diff::TorrentDiff;
+//! use torrust_parse_torrent::parse_torrent;
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//! let old = parse_torrent(&bytes).unwrap();
+//! let mut new = old.clone();
+//! new.add_tracker("udp://tracker.example.com:6969");
+//!
+//! let diff = TorrentDiff::new(&old, &new);
+//!
+//! assert_eq!(diff.trackers_added, ["udp://tracker.example.com:6969"]);
+//! assert!(diff.info_hash.is_none());
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde_derive::Serialize;
+
+use crate::models::info_hash::InfoHash;
+use crate::models::info_hash_v2::InfoHashV2;
+use crate::models::torrent_file::Torrent;
+
+/// A value that differs between the old and the new torrent.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    /// Returns the change, or `None` if both values are equal.
+    fn between(old: T, new: T) -> Option<Self> {
+        (old != new).then_some(Self { old, new })
+    }
+}
+
+/// A file of the content, as `(path, length)`. See
+/// [`Torrent::content_paths`].
+pub type File = (String, u64);
+
+/// The differences between two torrents. Fields are `None`, or empty, when
+/// both torrents are equal in that respect.
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize)]
+pub struct TorrentDiff {
+    pub info_hash: Option<Change<InfoHash>>,
+    pub info_hash_v2: Option<Change<Option<InfoHashV2>>>,
+    pub name: Option<Change<String>>,
+    pub piece_length: Option<Change<u64>>,
+    pub private: Option<Change<bool>>,
+    pub source: Option<Change<Option<String>>>,
+    pub comment: Option<Change<Option<String>>>,
+    pub created_by: Option<Change<Option<String>>>,
+    pub creation_date: Option<Change<Option<i64>>>,
+    /// Tracker URLs only in the new torrent, in any tier.
+    pub trackers_added: Vec<String>,
+    /// Tracker URLs only in the old torrent.
+    pub trackers_removed: Vec<String>,
+    /// Files only in the new torrent.
+    pub files_added: Vec<File>,
+    /// Files only in the old torrent.
+    pub files_removed: Vec<File>,
+    /// Files in both torrents, with their old and new length.
+    pub files_resized: Vec<(String, Change<u64>)>,
+}
+
+impl TorrentDiff {
+    /// Compares two torrents. Text is decoded with the encoding each one
+    /// declares.
+    #[must_use]
+    pub fn new(old: &Torrent, new: &Torrent) -> Self {
+        let text = |torrent: &Torrent, text: Option<&_>| {
+            text.map(|text| torrent.decode_text(text, None).0.into_owned())
+        };
+
+        let (trackers_removed, trackers_added) = differences(
+            &old.announce_tiers().all_urls(),
+            &new.announce_tiers().all_urls(),
+        );
+
+        let old_files = old.content_paths(None);
+        let new_files = new.content_paths(None);
+        let old_lengths: HashMap<&str, u64> = old_files
+            .iter()
+            .map(|(path, length)| (path.as_str(), *length))
+            .collect();
+        let new_lengths: HashMap<&str, u64> = new_files
+            .iter()
+            .map(|(path, length)| (path.as_str(), *length))
+            .collect();
+
+        Self {
+            info_hash: Change::between(old.info_hash(), new.info_hash()),
+            info_hash_v2: Change::between(old.info_hash_v2(), new.info_hash_v2()),
+            name: Change::between(
+                old.decode_name(None).0.into_owned(),
+                new.decode_name(None).0.into_owned(),
+            ),
+            piece_length: Change::between(old.info.piece_length, new.info.piece_length),
+            private: Change::between(old.info.private == Some(1), new.info.private == Some(1)),
+            source: Change::between(
+                text(old, old.info.source.as_ref()),
+                text(new, new.info.source.as_ref()),
+            ),
+            comment: Change::between(
+                text(old, old.comment.as_ref()),
+                text(new, new.comment.as_ref()),
+            ),
+            created_by: Change::between(
+                text(old, old.created_by.as_ref()),
+                text(new, new.created_by.as_ref()),
+            ),
+            creation_date: Change::between(old.creation_date, new.creation_date),
+            trackers_added,
+            trackers_removed,
+            files_added: new_files
+                .iter()
+                .filter(|(path, _)| !old_lengths.contains_key(path.as_str()))
+                .cloned()
+                .collect(),
+            files_removed: old_files
+                .iter()
+                .filter(|(path, _)| !new_lengths.contains_key(path.as_str()))
+                .cloned()
+                .collect(),
+            files_resized: old_files
+                .iter()
+                .filter_map(|(path, old_length)| {
+                    let new_length = new_lengths.get(path.as_str())?;
+                    Some((path.clone(), Change::between(*old_length, *new_length)?))
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if there are no differences.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Something that prevents seeding two torrents from the same data.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Obstacle {
+    /// One torrent is a single file and the other a directory.
+    DifferentLayout,
+    /// A file of the first torrent is missing in the second one.
+    MissingInSecond { path: String },
+    /// A file of the second torrent is missing in the first one.
+    MissingInFirst { path: String },
+    /// A file has different lengths.
+    DifferentLength {
+        path: String,
+        first: u64,
+        second: u64,
+    },
+    /// The pieces are aligned the same way but their hashes differ, so the
+    /// content is not the same.
+    DifferentPieces,
+}
+
+impl fmt::Display for Obstacle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Obstacle::DifferentLayout => {
+                write!(f, "one torrent is a single file and the other a directory")
+            }
+            Obstacle::MissingInSecond { path } => {
+                write!(f, "`{path}` is only in the first torrent")
+            }
+            Obstacle::MissingInFirst { path } => {
+                write!(f, "`{path}` is only in the second torrent")
+            }
+            Obstacle::DifferentLength {
+                path,
+                first,
+                second,
+            } => write!(
+                f,
+                "`{path}` has {first} bytes in the first torrent and {second} in the second"
+            ),
+            Obstacle::DifferentPieces => {
+                write!(f, "the piece hashes differ, so the content differs")
+            }
+        }
+    }
+}
+
+/// Whether two torrents, even with different info-hashes, describe the same
+/// files with the same lengths, so the data downloaded for one can be seeded
+/// with the other.
+///
+/// Paths are compared relative to the root directory: a different name
+/// only requires renaming or linking the root, and is reported in
+/// [`CrossSeedCheck::renamed`].
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
+pub struct CrossSeedCheck {
+    pub obstacles: Vec<Obstacle>,
+    /// The names of the torrents differ.
+    pub renamed: bool,
+    /// Whether the v1 piece hashes are equal. It's `None` when they can't be
+    /// compared: the piece lengths or the padding files differ, or a torrent
+    /// has no v1 pieces.
+    pub same_pieces: Option<bool>,
+}
+
+impl CrossSeedCheck {
+    /// Compares the content layout of two torrents.
+    #[must_use]
+    pub fn new(first: &Torrent, second: &Torrent) -> Self {
+        let mut obstacles = vec![];
+
+        match (relative_files(first), relative_files(second)) {
+            (Some(first_files), Some(second_files)) => {
+                let second_lengths: HashMap<&str, u64> = second_files
+                    .iter()
+                    .map(|(path, length)| (path.as_str(), *length))
+                    .collect();
+                let first_lengths: HashMap<&str, u64> = first_files
+                    .iter()
+                    .map(|(path, length)| (path.as_str(), *length))
+                    .collect();
+
+                for (path, length) in &first_files {
+                    match second_lengths.get(path.as_str()) {
+                        None => obstacles.push(Obstacle::MissingInSecond { path: path.clone() }),
+                        Some(second) if second != length => {
+                            obstacles.push(Obstacle::DifferentLength {
+                                path: path.clone(),
+                                first: *length,
+                                second: *second,
+                            });
+                        }
+                        Some(_) => {}
+                    }
+                }
+                for (path, _) in &second_files {
+                    if !first_lengths.contains_key(path.as_str()) {
+                        obstacles.push(Obstacle::MissingInFirst { path: path.clone() });
+                    }
+                }
+            }
+            (None, None) => {
+                let (first_length, second_length) = (first.content_size(), second.content_size());
+                if first_length != second_length {
+                    obstacles.push(Obstacle::DifferentLength {
+                        path: first.decode_name(None).0.into_owned(),
+                        first: first_length,
+                        second: second_length,
+                    });
+                }
+            }
+            _ => obstacles.push(Obstacle::DifferentLayout),
+        }
+
+        let same_pieces = same_pieces(first, second);
+        if obstacles.is_empty() && same_pieces == Some(false) {
+            obstacles.push(Obstacle::DifferentPieces);
+        }
+
+        Self {
+            obstacles,
+            renamed: first.decode_name(None) != second.decode_name(None),
+            same_pieces,
+        }
+    }
+
+    /// Returns `true` if nothing prevents cross-seeding.
+    #[must_use]
+    pub fn is_compatible(&self) -> bool {
+        self.obstacles.is_empty()
+    }
+}
+
+/// Returns the [Jaccard similarity](https://en.wikipedia.org/wiki/Jaccard_index)
+/// of the files of two torrents: the number of `(path, length)` pairs in
+/// both divided by the number of pairs in either. It goes from `0.0`, no
+/// common files, to `1.0`, the same files.
+///
+/// Paths are relative to the root directory, so renamed roots don't count.
+/// Near-duplicates, like a repack with an extra `.nfo` file, score close to
+/// `1.0`.
+///
+/// ```
+/// use torrust_parse_torrent::diff::file_similarity;
+/// use torrust_parse_torrent::utils::parse_torrent::decode_torrent;
+///
+/// let repack = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl5:a.mkveed6:lengthi2e4:pathl5:b.mkveed6:lengthi3e4:pathl5:c.nfoeee4:name6:repack12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+/// let original = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl5:a.mkveed6:lengthi2e4:pathl5:b.mkveee4:name8:original12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+///
+/// assert!((file_similarity(&repack, &original) - 2.0 / 3.0).abs() < f64::EPSILON);
+/// ```
+#[must_use]
+pub fn file_similarity(first: &Torrent, second: &Torrent) -> f64 {
+    let files = |torrent: &Torrent| -> HashSet<File> {
+        relative_files(torrent)
+            .unwrap_or_else(|| torrent.content_paths(None))
+            .into_iter()
+            .collect()
+    };
+
+    let (first, second) = (files(first), files(second));
+    let union = first.union(&second).count();
+    if union == 0 {
+        return 1.0;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let similarity = first.intersection(&second).count() as f64 / union as f64;
+    similarity
+}
+
+/// Returns the files of a multi-file torrent with their paths relative to
+/// the root directory, or `None` for single-file torrents.
+fn relative_files(torrent: &Torrent) -> Option<Vec<File>> {
+    let (name, _) = torrent.decode_name(None);
+    let files = torrent.content_paths(None);
+
+    if let [(path, _)] = files.as_slice() {
+        if *path == name {
+            return None;
+        }
+    }
+
+    let prefix = format!("{name}/");
+    Some(
+        files
+            .into_iter()
+            .map(|(path, length)| {
+                let relative = path.strip_prefix(&prefix).map(str::to_owned);
+                (relative.unwrap_or(path), length)
+            })
+            .collect(),
+    )
+}
+
+/// Compares the v1 piece hashes when the pieces cover the same bytes: the
+/// piece lengths are equal and so are the lengths of all the files,
+/// including the padding files.
+fn same_pieces(first: &Torrent, second: &Torrent) -> Option<bool> {
+    let lengths = |torrent: &Torrent| -> Vec<u64> {
+        match &torrent.info.files {
+            Some(files) => files.iter().map(|file| file.length).collect(),
+            None => vec![torrent.file_size()],
+        }
+    };
+
+    let (Some(first_pieces), Some(second_pieces)) = (&first.info.pieces, &second.info.pieces)
+    else {
+        return None;
+    };
+
+    (first.info.piece_length == second.info.piece_length && lengths(first) == lengths(second))
+        .then(|| first_pieces == second_pieces)
+}
+
+/// Returns the values only in `old` and the values only in `new`, in their
+/// order.
+fn differences(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let only_in = |a: &[String], b: &[String]| {
+        a.iter()
+            .filter(|value| !b.contains(value))
+            .cloned()
+            .collect()
+    };
+    (only_in(old, new), only_in(new, old))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_similarity, Change, CrossSeedCheck, Obstacle, TorrentDiff};
+    use crate::utils::parse_torrent::decode_torrent;
+
+    #[test]
+    fn it_should_find_no_differences_between_equal_torrents() {
+        let torrent = decode_torrent(
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        )
+        .unwrap();
+
+        assert!(TorrentDiff::new(&torrent, &torrent).is_empty());
+    }
+
+    #[test]
+    fn it_should_report_the_changed_files_and_trackers() {
+        let old = decode_torrent(b"d8:announce9:udp://a:17:comment3:old4:infod5:filesld6:lengthi1e4:pathl1:aeed6:lengthi2e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let new = decode_torrent(b"d8:announce9:udp://b:14:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi2e4:pathl1:ceee4:name1:d12:piece lengthi32768e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1eee").unwrap();
+
+        let diff = TorrentDiff::new(&old, &new);
+
+        assert!(diff.info_hash.is_some());
+        assert_eq!(diff.name, None);
+        assert_eq!(
+            diff.piece_length,
+            Some(Change {
+                old: 16384,
+                new: 32768
+            })
+        );
+        assert_eq!(
+            diff.private,
+            Some(Change {
+                old: false,
+                new: true
+            })
+        );
+        assert_eq!(
+            diff.comment,
+            Some(Change {
+                old: Some("old".to_owned()),
+                new: None
+            })
+        );
+        assert_eq!(diff.trackers_added, ["udp://b:1"]);
+        assert_eq!(diff.trackers_removed, ["udp://a:1"]);
+        assert_eq!(diff.files_added, [("d/c".to_owned(), 2)]);
+        assert_eq!(diff.files_removed, [("d/b".to_owned(), 2)]);
+        assert_eq!(
+            diff.files_resized,
+            [("d/a".to_owned(), Change { old: 1, new: 3 })]
+        );
+    }
+
+    #[test]
+    fn it_should_allow_cross_seeding_the_same_files_with_another_piece_length() {
+        let first = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed6:lengthi2e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let second = decode_torrent(b"d4:infod5:filesld6:lengthi2e4:pathl1:beed6:lengthi1e4:pathl1:aeee4:name1:e12:piece lengthi32768e6:pieces20:bbbbbbbbbbbbbbbbbbbb6:source1:xee").unwrap();
+
+        let check = CrossSeedCheck::new(&first, &second);
+
+        assert!(check.is_compatible());
+        assert!(check.renamed);
+        assert_eq!(check.same_pieces, None);
+    }
+
+    #[test]
+    fn it_should_report_what_prevents_cross_seeding() {
+        let first = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed6:lengthi2e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let second = decode_torrent(b"d4:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi2e4:pathl1:ceee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let single_file = decode_torrent(
+            b"d4:infod6:lengthi3e4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        )
+        .unwrap();
+        let same_layout = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed6:lengthi2e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbbee").unwrap();
+
+        assert_eq!(
+            CrossSeedCheck::new(&first, &second).obstacles,
+            [
+                Obstacle::DifferentLength {
+                    path: "a".to_owned(),
+                    first: 1,
+                    second: 3
+                },
+                Obstacle::MissingInSecond {
+                    path: "b".to_owned()
+                },
+                Obstacle::MissingInFirst {
+                    path: "c".to_owned()
+                },
+            ]
+        );
+        assert_eq!(
+            CrossSeedCheck::new(&first, &single_file).obstacles,
+            [Obstacle::DifferentLayout]
+        );
+        assert_eq!(
+            CrossSeedCheck::new(&first, &same_layout).obstacles,
+            [Obstacle::DifferentPieces]
+        );
+    }
+
+    #[test]
+    fn it_should_score_the_files_two_torrents_share() {
+        let first = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed6:lengthi2e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let renamed = decode_torrent(b"d4:infod5:filesld6:lengthi2e4:pathl1:beed6:lengthi1e4:pathl1:aeee4:name1:e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let resized = decode_torrent(b"d4:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi2e4:pathl1:ceee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+
+        assert!((file_similarity(&first, &renamed) - 1.0).abs() < f64::EPSILON);
+        assert!(file_similarity(&first, &resized).abs() < f64::EPSILON);
+    }
+}
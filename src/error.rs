@@ -0,0 +1,19 @@
+//! Errors returned by the top-level parsing functions.
+use thiserror::Error;
+
+use crate::utils::bencode::BencodeError;
+use crate::utils::parse_torrent_verbose::TorrentParseError;
+
+/// Errors that can occur while parsing a torrent file.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The bytes are not valid bencode.
+    #[error("invalid bencode: {0}")]
+    Bencode(#[from] BencodeError),
+    /// The bytes don't have the structure of a torrent file.
+    #[error("unable to decode torrent: {0}")]
+    Decode(#[from] serde_bencode::Error),
+    /// The torrent file has an unexpected structure (verbose parser).
+    #[error("unable to parse torrent: {0}")]
+    Parse(#[from] TorrentParseError),
+}
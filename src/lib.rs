@@ -0,0 +1,192 @@
+//! Parser for `BitTorrent` metainfo (`.torrent`) files.
+//!
+//! It was extracted from the [Torrust Index](https://github.com/torrust/torrust-index)
+//! so that other Torrust projects can reuse the same parsing code.
+//!
+//! # Stability
+//!
+//! The items re-exported from the [`prelude`] are the public API of the
+//! crate and follow [semantic versioning](https://semver.org/): they won't
+//! change in a backwards-incompatible way without a major version bump.
+//!
+//! Everything else is considered an implementation detail and can change in
+//! any release, including the public modules documented below, like
+//! [`utils`]. They are public for the command-line tool, the benchmarks and
+//! the users that need the lower level functions.
+//!
+//! # Usage
+//!
+//! ```
+//! use torrust_parse_torrent::parse_torrent;
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//!
+//! let torrent = parse_torrent(&bytes).unwrap();
+//!
+//! assert_eq!(torrent.info.name, "mandelbrot_set_01");
+//! ```
+//!
+//! The [`prelude`] also contains the verbose parser
+//! ([`prelude::decode_torrent_verbose`]). Use the [`prelude::TorrentBuilder`]
+//! to create torrents, [`prelude::verify`] to check downloaded content against
+//! them, and a [`prelude::ProgressHandler`] to follow the hashing.
+//! [`prelude::TorrentDiff`] compares two torrents. With the `scrape` feature,
+//! `scrape` asks the trackers about the swarm, and with the `magnet` feature
+//! `fetch_metadata` downloads the torrent of a magnet link from its peers,
+//! found through the trackers and the DHT (`dht` feature).
+#[doc(hidden)]
+pub mod builder;
+#[cfg(feature = "dht")]
+#[doc(hidden)]
+pub mod dht;
+#[doc(hidden)]
+pub mod diff;
+#[doc(hidden)]
+pub mod error;
+#[cfg(feature = "magnet")]
+#[doc(hidden)]
+pub mod metadata;
+#[doc(hidden)]
+pub mod models;
+pub mod prelude;
+#[doc(hidden)]
+pub mod progress;
+#[cfg(test)]
+mod test_helpers;
+#[cfg(feature = "scrape")]
+#[doc(hidden)]
+pub mod tracker;
+#[doc(hidden)]
+pub mod utils;
+#[doc(hidden)]
+pub mod verify;
+
+pub use crate::error::Error;
+use crate::models::torrent_file::Torrent;
+use crate::utils::parse_mode::ParseMode;
+use crate::utils::parse_options::ParseOptions;
+
+/// Parses a torrent file from its bencoded bytes.
+///
+/// Non-standard keys are ignored. Use
+/// [`utils::parse_torrent_verbose::decode_torrent`] if you need a more
+/// tolerant parser.
+///
+/// # Errors
+///
+/// Will return an [`Error::Bencode`] if the bytes are not valid bencode or
+/// an [`Error::Decode`] if they don't contain a valid torrent.
+pub fn parse_torrent(bytes: &[u8]) -> Result<Torrent, Error> {
+    parse_torrent_with_mode(bytes, ParseMode::Lenient)
+}
+
+/// Parses a torrent file from its bencoded bytes with the given
+/// [`ParseMode`] and the default limits.
+///
+/// # Errors
+///
+/// Will return an [`Error::Bencode`] if the bytes are not valid bencode or
+/// an [`Error::Decode`] if they don't contain a valid torrent. In strict mode, it will return an
+/// [`Error::Parse`] if the torrent violates the specification or there are
+/// bytes after it.
+pub fn parse_torrent_with_mode(bytes: &[u8], mode: ParseMode) -> Result<Torrent, Error> {
+    parse_torrent_with_options(bytes, &ParseOptions::from(mode))
+}
+
+/// Parses a torrent file from its bencoded bytes with the given
+/// [`ParseOptions`], using the parser of their
+/// [backend](ParseOptions::backend).
+///
+/// # Errors
+///
+/// Will return an [`Error::Bencode`] if the bytes are not valid bencode or
+/// exceed one of the limits of the options, or an [`Error::Decode`] if they
+/// don't contain a valid torrent. In strict mode, it will return an
+/// [`Error::Parse`] if the torrent violates the specification, has duplicate
+/// keys or there are bytes after it.
+pub fn parse_torrent_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Torrent, Error> {
+    options.backend.decoder().decode(bytes, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_torrent, parse_torrent_with_mode, parse_torrent_with_options, Error};
+    use crate::utils::bencode::BencodeErrorKind;
+    use crate::utils::parse_mode::ParseMode;
+    use crate::utils::parse_options::ParseOptions;
+    use crate::utils::parse_torrent_verbose::TorrentParseError;
+
+    #[test]
+    fn it_should_parse_a_torrent_file() {
+        let bytes = std::fs::read("tests/fixtures/torrents/working-with-one-node.torrent").unwrap();
+
+        let torrent = parse_torrent(&bytes).unwrap();
+
+        assert_eq!(torrent.info.name, "minimal.txt");
+    }
+
+    #[test]
+    fn it_should_keep_the_original_bytes_of_the_info_dictionary() {
+        let bytes = std::fs::read(
+            // cspell:disable-next-line
+            "tests/fixtures/torrents/6c690018c5786dbbb00161f62b0712d69296df97_with_custom_info_dict_key.torrent",
+        )
+        .unwrap();
+
+        let torrent = parse_torrent(&bytes).unwrap();
+
+        let raw_info = torrent.raw_info.as_ref().unwrap();
+        assert!(bytes
+            .windows(raw_info.len())
+            .any(|window| window == raw_info));
+        // cspell:disable-next-line
+        assert_eq!(
+            torrent.info_hash_hex(),
+            "6c690018c5786dbbb00161f62b0712d69296df97"
+        );
+    }
+
+    #[test]
+    fn it_should_fail_parsing_invalid_bencode() {
+        assert!(matches!(
+            parse_torrent(b"not bencode"),
+            Err(Error::Bencode(_))
+        ));
+    }
+
+    #[test]
+    fn it_should_reject_spec_violations_only_in_strict_mode() {
+        // `pieces` has 3 bytes instead of a multiple of 20.
+        let bytes = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces3:abcee";
+
+        assert!(parse_torrent_with_mode(bytes, ParseMode::Lenient).is_ok());
+        assert!(matches!(
+            parse_torrent_with_mode(bytes, ParseMode::Strict),
+            Err(Error::Parse(TorrentParseError::Invalid(_)))
+        ));
+    }
+
+    #[test]
+    fn it_should_reject_trailing_data_only_in_strict_mode() {
+        let bytes = std::fs::read("tests/fixtures/torrents/working-with-one-node.torrent").unwrap();
+        let bytes = [bytes, b"garbage".to_vec()].concat();
+
+        assert!(parse_torrent_with_mode(&bytes, ParseMode::Lenient).is_ok());
+        assert!(matches!(
+            parse_torrent_with_mode(&bytes, ParseMode::Strict),
+            Err(Error::Parse(TorrentParseError::Invalid(diagnostic)))
+                if diagnostic.message == "7 bytes of trailing data after the torrent"
+        ));
+    }
+
+    #[test]
+    fn it_should_reject_torrents_that_exceed_the_limits() {
+        let bytes = std::fs::read("tests/fixtures/torrents/working-with-one-node.torrent").unwrap();
+
+        assert!(parse_torrent_with_options(&bytes, &ParseOptions::new()).is_ok());
+        assert!(matches!(
+            parse_torrent_with_options(&bytes, &ParseOptions::new().max_string_length(10)),
+            Err(Error::Bencode(error)) if matches!(error.kind, BencodeErrorKind::StringTooLong { .. })
+        ));
+    }
+}
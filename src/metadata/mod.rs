@@ -0,0 +1,392 @@
+//! Downloads the info dictionary of a magnet link from the peers of the
+//! swarm ([BEP 9](https://www.bittorrent.org/beps/bep_0009.html)).
+//!
+//! Requires the `magnet` feature. The peers are the ones in the link
+//! (`x.pe`), the ones returned by its trackers and the ones found in the
+//! [DHT](crate::dht). Several peers are tried at
+//! the same time, and the first complete metadata that matches the info-hash
+//! is used.
+//!
+//! ```no_run
+//! # async fn example() {
+//! use std::time::Duration;
+//!
+//! use torrust_parse_torrent::metadata::fetch_metadata;
+//! use torrust_parse_torrent::models::magnet_link::MagnetLink;
+//!
+//! let magnet: MagnetLink = "magnet:?xt=urn:btih:...".parse().unwrap();
+//! let bytes = fetch_metadata(&magnet, Duration::from_secs(60)).await.unwrap();
+//! let torrent = torrust_parse_torrent::parse_torrent(&bytes).unwrap();
+//! # }
+//! ```
+pub mod peer;
+
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::net::lookup_host;
+use tokio::task::JoinSet;
+
+use crate::dht::{self, Dht};
+use crate::models::info_hash::InfoHash;
+use crate::models::magnet_link::MagnetLink;
+use crate::tracker::{self, Announce};
+
+/// Largest metadata accepted.
+pub const MAX_METADATA_SIZE: usize = 16 * 1024 * 1024;
+
+/// Peers contacted at the same time.
+const MAX_CONNECTIONS: usize = 16;
+
+/// Time to wait for each tracker, each peer and the DHT lookup.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Port sent to the trackers. Nothing listens on it.
+const PORT: u16 = 6881;
+
+#[derive(Error, Debug)]
+pub enum MetadataError {
+    #[error("the magnet link has no info-hash")]
+    NoInfoHash,
+    #[error("no peers found")]
+    NoPeers,
+    #[error("no peer sent the metadata: {0}")]
+    NoMetadata(Box<MetadataError>),
+    #[error("the peer does not support the ut_metadata extension")]
+    Unsupported,
+    #[error("the peer rejected the request")]
+    Rejected,
+    #[error("the metadata does not match the info-hash")]
+    HashMismatch,
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    #[error("timed out")]
+    Timeout,
+    #[error(transparent)]
+    Tracker(#[from] tracker::TrackerError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Downloads the metadata of a magnet link and returns a complete `.torrent`
+/// file, with the trackers of the link.
+///
+/// # Errors
+///
+/// Will return an error if the link has no info-hash, no peers are found, or
+/// none of them sends the metadata before the timeout.
+///
+/// # Panics
+///
+/// Will panic if it's not called from a Tokio runtime.
+pub async fn fetch_metadata(
+    magnet: &MagnetLink,
+    timeout: Duration,
+) -> Result<Vec<u8>, MetadataError> {
+    let bootstrap = dht::DEFAULT_BOOTSTRAP.map(String::from).to_vec();
+
+    let info = tokio::time::timeout(timeout, fetch_info(magnet, bootstrap))
+        .await
+        .map_err(|_| MetadataError::Timeout)??;
+
+    Ok(torrent_file(&info, &magnet.trackers))
+}
+
+/// Finds peers and downloads the info dictionary. The DHT lookup starts
+/// from the `bootstrap` nodes, and runs while the trackers are asked.
+async fn fetch_info(magnet: &MagnetLink, bootstrap: Vec<String>) -> Result<Vec<u8>, MetadataError> {
+    let info_hash = magnet
+        .v1_compatible_info_hash()
+        .ok_or(MetadataError::NoInfoHash)?;
+    let peer_id = peer_id();
+
+    let dht_peers = tokio::spawn(dht_peers(bootstrap, info_hash));
+
+    let mut peers = link_peers(magnet).await;
+    let found = tracker_peers(magnet, info_hash, peer_id).await?;
+    let found_in_dht = match dht_peers.await {
+        Ok(found) => found,
+        Err(error) => std::panic::resume_unwind(error.into_panic()),
+    };
+    for peer in found.into_iter().chain(found_in_dht) {
+        if !peers.contains(&peer) {
+            peers.push(peer);
+        }
+    }
+
+    let mut peers = peers.into_iter();
+    let mut tasks = JoinSet::new();
+    let mut last_error = MetadataError::NoPeers;
+
+    loop {
+        while tasks.len() < MAX_CONNECTIONS {
+            let Some(peer) = peers.next() else { break };
+            tasks.spawn(async move {
+                tokio::time::timeout(
+                    REQUEST_TIMEOUT,
+                    peer::fetch_metadata(peer, &info_hash, &peer_id),
+                )
+                .await
+                .map_err(|_| MetadataError::Timeout)?
+            });
+        }
+
+        let Some(joined) = tasks.join_next().await else {
+            return Err(match last_error {
+                MetadataError::NoPeers => MetadataError::NoPeers,
+                error => MetadataError::NoMetadata(Box::new(error)),
+            });
+        };
+
+        match joined {
+            Ok(Ok(info)) if matches_magnet(&info, magnet) => return Ok(info),
+            Ok(Ok(_)) => last_error = MetadataError::HashMismatch,
+            Ok(Err(error)) => last_error = error,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+}
+
+/// Resolves the `x.pe` peers of the link. The ones that can't be resolved
+/// are skipped.
+async fn link_peers(magnet: &MagnetLink) -> Vec<SocketAddr> {
+    let mut peers = vec![];
+    for peer in &magnet.peers {
+        if let Ok(mut addrs) = lookup_host(peer.as_str()).await {
+            peers.extend(addrs.next());
+        }
+    }
+    peers
+}
+
+/// Announces to all the trackers of the link and returns the peers they
+/// know. Trackers that fail are skipped.
+async fn tracker_peers(
+    magnet: &MagnetLink,
+    info_hash: InfoHash,
+    peer_id: [u8; 20],
+) -> Result<Vec<SocketAddr>, MetadataError> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(tracker::TrackerError::from)?;
+
+    let announce = Announce {
+        info_hash,
+        peer_id,
+        port: PORT,
+        left: 1,
+    };
+
+    let mut tasks = JoinSet::new();
+    for url in magnet.trackers.clone() {
+        let client = client.clone();
+        tasks.spawn(
+            async move { tracker::announce(&client, &url, &announce, REQUEST_TIMEOUT).await },
+        );
+    }
+
+    let mut peers = vec![];
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(Ok(found)) = joined {
+            peers.extend(found);
+        }
+    }
+    Ok(peers)
+}
+
+/// Looks up the peers in the DHT. Returns no peers if the client can't be
+/// bound.
+async fn dht_peers(bootstrap: Vec<String>, info_hash: InfoHash) -> Vec<SocketAddr> {
+    let bootstrap = dht::resolve(&bootstrap).await;
+    if bootstrap.is_empty() {
+        return vec![];
+    }
+
+    match Dht::bind().await {
+        Ok(dht) => {
+            dht.find_peers(&bootstrap, &info_hash, REQUEST_TIMEOUT)
+                .await
+        }
+        Err(_) => vec![],
+    }
+}
+
+/// Returns `true` if the info dictionary has the info-hash of the link.
+#[must_use]
+pub fn matches_magnet(info: &[u8], magnet: &MagnetLink) -> bool {
+    match (magnet.info_hash, magnet.info_hash_v2) {
+        (Some(info_hash), _) => Sha1::digest(info).as_slice() == info_hash.bytes(),
+        (None, Some(info_hash)) => Sha256::digest(info).as_slice() == info_hash.bytes(),
+        (None, None) => false,
+    }
+}
+
+/// Builds a `.torrent` file from the bencoded info dictionary, with each
+/// tracker in its own tier. The info dictionary is copied as is, so the
+/// info-hash doesn't change.
+#[must_use]
+pub fn torrent_file(info: &[u8], trackers: &[String]) -> Vec<u8> {
+    fn string(bytes: &mut Vec<u8>, value: &str) {
+        bytes.extend(format!("{}:{value}", value.len()).as_bytes());
+    }
+
+    let mut bytes = b"d".to_vec();
+
+    if let Some(first) = trackers.first() {
+        string(&mut bytes, "announce");
+        string(&mut bytes, first);
+    }
+    if trackers.len() > 1 {
+        string(&mut bytes, "announce-list");
+        bytes.push(b'l');
+        for tracker in trackers {
+            bytes.push(b'l');
+            string(&mut bytes, tracker);
+            bytes.push(b'e');
+        }
+        bytes.push(b'e');
+    }
+
+    string(&mut bytes, "info");
+    bytes.extend(info);
+    bytes.push(b'e');
+    bytes
+}
+
+/// Returns an Azureus-style peer ID: the client and version, and 12 bytes
+/// that change with each call.
+fn peer_id() -> [u8; 20] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+
+    let random = Sha1::new()
+        .chain_update(nanos.to_be_bytes())
+        .chain_update(std::process::id().to_be_bytes())
+        .finalize();
+
+    let mut peer_id = [0; 20];
+    peer_id[..8].copy_from_slice(b"-PT0100-");
+    peer_id[8..].copy_from_slice(&random[..12]);
+    peer_id
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use sha1::{Digest, Sha1};
+
+    use super::{matches_magnet, peer, torrent_file, MetadataError};
+    use crate::models::info_hash::InfoHash;
+    use crate::models::magnet_link::MagnetLink;
+    use crate::parse_torrent;
+
+    /// An info dictionary larger than a metadata piece.
+    fn info() -> Vec<u8> {
+        let name = "a".repeat(20_000);
+        format!(
+            "d6:lengthi1e4:name20000:{name}12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae"
+        )
+        .into_bytes()
+    }
+
+    fn write_message(stream: &mut TcpStream, payload: &[u8]) {
+        let len = u32::try_from(payload.len()).unwrap();
+        stream.write_all(&len.to_be_bytes()).unwrap();
+        stream.write_all(payload).unwrap();
+    }
+
+    fn read_message(stream: &mut TcpStream) -> Vec<u8> {
+        let mut len = [0; 4];
+        stream.read_exact(&mut len).unwrap();
+        let mut message = vec![0; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut message).unwrap();
+        message
+    }
+
+    /// A peer that sends the metadata in pieces, with a keep-alive and an
+    /// unrelated message in between.
+    fn serve_metadata(listener: &TcpListener, info: &[u8]) {
+        let (mut stream, _) = listener.accept().unwrap();
+
+        let mut handshake = [0; 68];
+        stream.read_exact(&mut handshake).unwrap();
+        assert_eq!(handshake[25] & 0x10, 0x10);
+        stream.write_all(&handshake).unwrap();
+
+        let their_handshake = read_message(&mut stream);
+        assert!(String::from_utf8_lossy(&their_handshake).contains("11:ut_metadatai1e"));
+
+        let ours = format!("d1:md11:ut_metadatai3ee13:metadata_sizei{}ee", info.len());
+        write_message(&mut stream, &[&[20, 0], ours.as_bytes()].concat());
+        stream.write_all(&[0; 4]).unwrap();
+        write_message(&mut stream, &[5, 0xff]);
+
+        for piece in 0..2 {
+            let request = read_message(&mut stream);
+            assert_eq!(request[..2], [20, 3]);
+            let header = format!(
+                "d8:msg_typei1e5:piecei{piece}e10:total_sizei{}ee",
+                info.len()
+            );
+            let data = &info[piece * 16384..info.len().min((piece + 1) * 16384)];
+            write_message(&mut stream, &[&[20, 1], header.as_bytes(), data].concat());
+        }
+    }
+
+    #[test]
+    fn it_should_download_the_metadata_from_a_peer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let info = info();
+        let info_hash = InfoHash(Sha1::digest(&info).into());
+
+        let server = {
+            let info = info.clone();
+            thread::spawn(move || serve_metadata(&listener, &info))
+        };
+
+        let downloaded = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(peer::fetch_metadata(addr, &info_hash, &[b'p'; 20]))
+            .unwrap();
+
+        server.join().unwrap();
+        assert_eq!(downloaded, info);
+        assert!(matches_magnet(&downloaded, &MagnetLink::from(info_hash)));
+    }
+
+    #[test]
+    fn it_should_fail_without_peers() {
+        let magnet = MagnetLink::from(InfoHash([b'a'; 20]));
+
+        let result = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(super::fetch_info(&magnet, vec![]));
+
+        assert!(matches!(result, Err(MetadataError::NoPeers)));
+    }
+
+    #[test]
+    fn it_should_build_a_torrent_with_the_same_info_hash() {
+        let info = info();
+        let trackers = ["udp://a.test:1".to_owned(), "udp://b.test:1".to_owned()];
+
+        let torrent = parse_torrent(&torrent_file(&info, &trackers)).unwrap();
+
+        assert_eq!(torrent.info_hash(), InfoHash(Sha1::digest(&info).into()));
+        assert_eq!(torrent.announce.as_deref(), Some("udp://a.test:1"));
+        assert_eq!(torrent.announce_tiers().tiers().len(), 2);
+    }
+}
@@ -0,0 +1,284 @@
+//! The parts of the peer wire protocol needed to download the metadata: the
+//! handshake, the extension protocol ([BEP 10](https://www.bittorrent.org/beps/bep_0010.html))
+//! and the `ut_metadata` extension ([BEP 9](https://www.bittorrent.org/beps/bep_0009.html)).
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use serde_bencode::value::Value as BValue;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::{MetadataError, MAX_METADATA_SIZE};
+use crate::models::info_hash::InfoHash;
+use crate::utils::bencode;
+
+const PROTOCOL: &[u8; 19] = b"BitTorrent protocol";
+
+/// Length of the handshake: protocol name length, protocol name, reserved
+/// bytes, info-hash and peer ID.
+const HANDSHAKE_LEN: usize = 1 + 19 + 8 + 20 + 20;
+
+/// Reserved byte and bit that announce support for the extension protocol.
+const EXTENSION_BYTE: usize = 5;
+const EXTENSION_BIT: u8 = 0x10;
+
+/// Message ID of the extension protocol messages.
+const MSG_EXTENDED: u8 = 20;
+
+/// Extended message ID of the extension handshake.
+const EXTENDED_HANDSHAKE: u8 = 0;
+
+/// Extended message ID the peer must use for the `ut_metadata` messages it
+/// sends to us.
+const UT_METADATA_ID: u8 = 1;
+
+const UT_METADATA_REQUEST: i64 = 0;
+const UT_METADATA_DATA: i64 = 1;
+const UT_METADATA_REJECT: i64 = 2;
+
+/// The metadata is sent in pieces of 16 KiB. The last one can be shorter.
+pub const METADATA_PIECE_LEN: usize = 16 * 1024;
+
+/// Largest message accepted. Other messages, like a `bitfield` of a torrent
+/// with many pieces or the `piece` messages, are skipped but must fit.
+const MAX_MESSAGE_LEN: usize = 1024 * 1024;
+
+/// Downloads the info dictionary from a peer. The caller has to check that
+/// it matches the info-hash.
+///
+/// # Errors
+///
+/// Will return an error if the connection fails, the peer doesn't support
+/// the `ut_metadata` extension, or it doesn't send the whole metadata.
+pub async fn fetch_metadata(
+    addr: SocketAddr,
+    info_hash: &InfoHash,
+    peer_id: &[u8; 20],
+) -> Result<Vec<u8>, MetadataError> {
+    let mut stream = TcpStream::connect(addr).await?;
+    download(&mut stream, info_hash, peer_id).await
+}
+
+/// Runs the protocol on a connected stream.
+pub(crate) async fn download<S>(
+    stream: &mut S,
+    info_hash: &InfoHash,
+    peer_id: &[u8; 20],
+) -> Result<Vec<u8>, MetadataError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(&handshake(info_hash, peer_id)).await?;
+
+    let mut response = [0; HANDSHAKE_LEN];
+    stream.read_exact(&mut response).await?;
+    check_handshake(&response, info_hash)?;
+
+    let mut payload = vec![MSG_EXTENDED, EXTENDED_HANDSHAKE];
+    payload.extend(to_bytes(&dict(vec![(
+        b"m".to_vec(),
+        dict(vec![(
+            b"ut_metadata".to_vec(),
+            BValue::Int(UT_METADATA_ID.into()),
+        )]),
+    )])));
+    write_message(stream, &payload).await?;
+
+    let mut pieces: Vec<Option<Vec<u8>>> = vec![];
+    let mut metadata_size = 0;
+
+    loop {
+        let message = read_message(stream).await?;
+
+        match message.as_slice() {
+            [MSG_EXTENDED, EXTENDED_HANDSHAKE, handshake @ ..] => {
+                let (their_id, size) = parse_extension_handshake(handshake)?;
+                metadata_size = size;
+                pieces = vec![None; size.div_ceil(METADATA_PIECE_LEN)];
+                for piece in 0..pieces.len() {
+                    write_message(stream, &request(their_id, piece)).await?;
+                }
+            }
+            [MSG_EXTENDED, UT_METADATA_ID, data @ ..] => {
+                let (piece, bytes) = parse_data(data, metadata_size)?;
+                let slot = pieces.get_mut(piece).ok_or_else(|| {
+                    MetadataError::Protocol(format!("unexpected metadata piece {piece}"))
+                })?;
+                *slot = Some(bytes.to_vec());
+
+                if pieces.iter().all(Option::is_some) {
+                    return Ok(pieces.into_iter().flatten().flatten().collect());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handshake(info_hash: &InfoHash, peer_id: &[u8; 20]) -> [u8; HANDSHAKE_LEN] {
+    let mut handshake = [0; HANDSHAKE_LEN];
+    handshake[0] = 19;
+    handshake[1..20].copy_from_slice(PROTOCOL);
+    handshake[20 + EXTENSION_BYTE] = EXTENSION_BIT;
+    handshake[28..48].copy_from_slice(&info_hash.bytes());
+    handshake[48..].copy_from_slice(peer_id);
+    handshake
+}
+
+fn check_handshake(
+    response: &[u8; HANDSHAKE_LEN],
+    info_hash: &InfoHash,
+) -> Result<(), MetadataError> {
+    if response[0] != 19 || &response[1..20] != PROTOCOL {
+        return Err(MetadataError::Protocol(
+            "not a BitTorrent handshake".to_owned(),
+        ));
+    }
+    if response[28..48] != info_hash.bytes() {
+        return Err(MetadataError::Protocol(
+            "the peer answered with another info-hash".to_owned(),
+        ));
+    }
+    if response[20 + EXTENSION_BYTE] & EXTENSION_BIT == 0 {
+        return Err(MetadataError::Unsupported);
+    }
+    Ok(())
+}
+
+/// Returns the ID the peer uses for `ut_metadata` and the metadata size.
+fn parse_extension_handshake(bytes: &[u8]) -> Result<(u8, usize), MetadataError> {
+    let BValue::Dict(handshake) = decode(bytes)? else {
+        return Err(invalid("extension handshake is not a dictionary"));
+    };
+
+    let id = match handshake.get(b"m".as_slice()) {
+        Some(BValue::Dict(m)) => int(m, b"ut_metadata"),
+        _ => None,
+    };
+    let id = id
+        .and_then(|id| u8::try_from(id).ok())
+        .filter(|id| *id != 0)
+        .ok_or(MetadataError::Unsupported)?;
+
+    let size = int(&handshake, b"metadata_size")
+        .and_then(|size| usize::try_from(size).ok())
+        .filter(|size| (1..=MAX_METADATA_SIZE).contains(size))
+        .ok_or_else(|| invalid("missing or invalid `metadata_size`"))?;
+
+    Ok((id, size))
+}
+
+fn request(their_id: u8, piece: usize) -> Vec<u8> {
+    let mut payload = vec![MSG_EXTENDED, their_id];
+    payload.extend(to_bytes(&dict(vec![
+        (b"msg_type".to_vec(), BValue::Int(UT_METADATA_REQUEST)),
+        (
+            b"piece".to_vec(),
+            BValue::Int(i64::try_from(piece).unwrap_or(i64::MAX)),
+        ),
+    ])));
+    payload
+}
+
+/// Returns the index and bytes of a `data` message.
+fn parse_data(message: &[u8], metadata_size: usize) -> Result<(usize, &[u8]), MetadataError> {
+    let (value, len) = bencode::decode_prefix(message).map_err(|e| invalid(&e.kind.to_string()))?;
+    let BValue::Dict(header) = value else {
+        return Err(invalid("ut_metadata message is not a dictionary"));
+    };
+
+    let piece = int(&header, b"piece")
+        .and_then(|piece| usize::try_from(piece).ok())
+        .ok_or_else(|| invalid("missing `piece`"))?;
+
+    match int(&header, b"msg_type") {
+        Some(UT_METADATA_DATA) => {}
+        Some(UT_METADATA_REJECT) => return Err(MetadataError::Rejected),
+        _ => return Err(invalid("unexpected ut_metadata message")),
+    }
+
+    if piece >= metadata_size.div_ceil(METADATA_PIECE_LEN) {
+        return Err(invalid(&format!("unexpected metadata piece {piece}")));
+    }
+
+    let data = &message[len..];
+    let expected = (metadata_size - piece * METADATA_PIECE_LEN).min(METADATA_PIECE_LEN);
+    if data.len() != expected {
+        return Err(invalid(&format!(
+            "metadata piece {piece} has {} bytes, expected {expected}",
+            data.len()
+        )));
+    }
+
+    Ok((piece, data))
+}
+
+async fn write_message<S>(stream: &mut S, payload: &[u8]) -> Result<(), MetadataError>
+where
+    S: AsyncWrite + Unpin,
+{
+    let len = u32::try_from(payload.len()).map_err(|_| invalid("message too long"))?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(payload).await?;
+    Ok(())
+}
+
+/// Reads the next message, skipping the keep-alives.
+async fn read_message<S>(stream: &mut S) -> Result<Vec<u8>, MetadataError>
+where
+    S: AsyncRead + Unpin,
+{
+    loop {
+        let len = stream.read_u32().await? as usize;
+        if len > MAX_MESSAGE_LEN {
+            return Err(invalid(&format!("message of {len} bytes")));
+        }
+        if len > 0 {
+            let mut message = vec![0; len];
+            stream.read_exact(&mut message).await?;
+            return Ok(message);
+        }
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<BValue, MetadataError> {
+    bencode::decode(bytes).map_err(|e| invalid(&e.kind.to_string()))
+}
+
+fn dict(entries: Vec<(Vec<u8>, BValue)>) -> BValue {
+    BValue::Dict(entries.into_iter().collect())
+}
+
+fn to_bytes(value: &BValue) -> Vec<u8> {
+    serde_bencode::to_bytes(value).expect("bencode values can be serialized")
+}
+
+fn int(dict: &HashMap<Vec<u8>, BValue>, key: &[u8]) -> Option<i64> {
+    match dict.get(key) {
+        Some(BValue::Int(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+fn invalid(message: &str) -> MetadataError {
+    MetadataError::Protocol(message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_data, MetadataError, METADATA_PIECE_LEN};
+
+    #[test]
+    fn it_should_reject_a_metadata_piece_beyond_the_metadata_size() {
+        let message = format!("d8:msg_typei1e5:piecei{}ee", i64::MAX);
+
+        assert!(matches!(
+            parse_data(message.as_bytes(), METADATA_PIECE_LEN + 1),
+            Err(MetadataError::Protocol(_))
+        ));
+        assert_eq!(
+            parse_data(b"d8:msg_typei1e5:piecei1eex", METADATA_PIECE_LEN + 1).unwrap(),
+            (1, b"x".as_slice())
+        );
+    }
+}
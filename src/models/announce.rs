@@ -0,0 +1,294 @@
+//! Trackers of a torrent grouped in tiers ([BEP 12](https://www.bittorrent.org/beps/bep_0012.html)).
+//!
+//! Clients try the trackers of a tier before moving to the next one. When a
+//! torrent has an `announce-list`, clients that support it ignore the
+//! `announce` key.
+//!
+//! [`TrackerUrl`] checks and classifies a single announce URL:
+//!
+//! - UDP trackers ([BEP 15](https://www.bittorrent.org/beps/bep_0015.html)):
+//!   `udp://tracker.example.com:6969/announce`. The port is required.
+//! - HTTP trackers: `http://` and `https://`.
+//! - `WebTorrent` trackers: `ws://` and `wss://`.
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+use url::Url;
+
+/// The protocol used to talk to a tracker.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TrackerKind {
+    /// `udp://` (BEP 15).
+    Udp,
+    /// `http://` and `https://` (BEP 3).
+    Http,
+    /// `ws://` and `wss://`, used by `WebTorrent` clients.
+    WebTorrent,
+}
+
+impl fmt::Display for TrackerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackerKind::Udp => write!(f, "udp"),
+            TrackerKind::Http => write!(f, "http"),
+            TrackerKind::WebTorrent => write!(f, "webtorrent"),
+        }
+    }
+}
+
+/// Reasons why an announce URL is not usable.
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum TrackerUrlError {
+    #[error("malformed URL: {0}")]
+    Malformed(#[from] url::ParseError),
+    #[error("unsupported scheme: {0}")]
+    UnsupportedScheme(String),
+    #[error("missing host")]
+    MissingHost,
+    #[error("missing port (required for UDP trackers)")]
+    MissingPort,
+    #[error("port 0 is not valid")]
+    InvalidPort,
+    #[error("unexpected fragment: #{0}")]
+    Fragment(String),
+}
+
+/// A well-formed announce URL.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TrackerUrl {
+    url: Url,
+    kind: TrackerKind,
+}
+
+impl TrackerUrl {
+    /// Parses and classifies an announce URL. Surrounding whitespace is
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the URL is malformed, its scheme is not one of
+    /// `udp`, `http`, `https`, `ws` or `wss`, it has no host, it has a
+    /// fragment, or it's a UDP URL without a port.
+    pub fn parse(url: &str) -> Result<Self, TrackerUrlError> {
+        let url = Url::parse(url.trim())?;
+
+        let kind = match url.scheme() {
+            "udp" => TrackerKind::Udp,
+            "http" | "https" => TrackerKind::Http,
+            "ws" | "wss" => TrackerKind::WebTorrent,
+            scheme => return Err(TrackerUrlError::UnsupportedScheme(scheme.to_owned())),
+        };
+
+        if url.host_str().is_none_or(str::is_empty) {
+            return Err(TrackerUrlError::MissingHost);
+        }
+
+        match url.port_or_known_default() {
+            Some(0) => return Err(TrackerUrlError::InvalidPort),
+            Some(_) => {}
+            None => return Err(TrackerUrlError::MissingPort),
+        }
+
+        if let Some(fragment) = url.fragment() {
+            return Err(TrackerUrlError::Fragment(fragment.to_owned()));
+        }
+
+        Ok(Self { url, kind })
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> TrackerKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    #[must_use]
+    pub fn host(&self) -> &str {
+        self.url.host_str().unwrap_or_default()
+    }
+
+    /// Returns the port, or the default one of the scheme if the URL has
+    /// none.
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.url.port_or_known_default().unwrap_or_default()
+    }
+}
+
+impl FromStr for TrackerUrl {
+    type Err = TrackerUrlError;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        Self::parse(url)
+    }
+}
+
+impl fmt::Display for TrackerUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+/// The tiers of trackers of a torrent, in order, without duplicates.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct AnnounceTiers {
+    tiers: Vec<Vec<String>>,
+}
+
+impl AnnounceTiers {
+    /// Builds the tiers from the `announce` and `announce-list` keys.
+    ///
+    /// The `announce-list` is used if it has any tracker, otherwise the
+    /// `announce` URL is the only tier. Empty and repeated URLs are dropped,
+    /// and so are the tiers left empty.
+    #[must_use]
+    pub fn new(announce: Option<&str>, announce_list: Option<&[Vec<String>]>) -> Self {
+        let mut tiers: Vec<Vec<String>> = vec![];
+        let mut seen: Vec<&str> = vec![];
+
+        let list = announce_list
+            .filter(|tiers| tiers.iter().flatten().any(|url| !url.is_empty()))
+            .map(<[Vec<String>]>::to_vec)
+            .unwrap_or_else(|| {
+                announce
+                    .map(|url| vec![vec![url.to_owned()]])
+                    .unwrap_or_default()
+            });
+
+        for tier in &list {
+            let mut urls = vec![];
+            for url in tier {
+                if !url.is_empty() && !seen.contains(&url.as_str()) {
+                    seen.push(url);
+                    urls.push(url.clone());
+                }
+            }
+            if !urls.is_empty() {
+                tiers.push(urls);
+            }
+        }
+
+        Self { tiers }
+    }
+
+    /// Returns the tiers, in order.
+    #[must_use]
+    pub fn tiers(&self) -> &[Vec<String>] {
+        &self.tiers
+    }
+
+    /// Returns every tracker URL, tier after tier. It's empty for trackerless
+    /// torrents.
+    #[must_use]
+    pub fn all_urls(&self) -> Vec<String> {
+        self.tiers.iter().flatten().cloned().collect()
+    }
+
+    /// Returns the tier index and URL of each tracker.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.tiers
+            .iter()
+            .enumerate()
+            .flat_map(|(tier, urls)| urls.iter().map(move |url| (tier, url.as_str())))
+    }
+
+    /// Returns `true` if the torrent has no trackers.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tiers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnnounceTiers, TrackerKind, TrackerUrl, TrackerUrlError};
+
+    fn tiers(tiers: &[&[&str]]) -> Vec<Vec<String>> {
+        tiers
+            .iter()
+            .map(|tier| tier.iter().map(ToString::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn it_should_use_the_announce_list_and_drop_duplicates() {
+        let list = tiers(&[
+            &["udp://a:1", "udp://b:1"],
+            &["udp://a:1"],
+            &["", "http://c/an"],
+        ]);
+
+        let announce_tiers = AnnounceTiers::new(Some("udp://ignored:1"), Some(&list));
+
+        assert_eq!(
+            announce_tiers.tiers(),
+            tiers(&[&["udp://a:1", "udp://b:1"], &["http://c/an"]])
+        );
+        assert_eq!(
+            announce_tiers.iter().collect::<Vec<_>>(),
+            [(0, "udp://a:1"), (0, "udp://b:1"), (1, "http://c/an")]
+        );
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_announce_url() {
+        let empty = tiers(&[&[]]);
+
+        assert_eq!(
+            AnnounceTiers::new(Some("udp://a:1"), Some(&empty)).all_urls(),
+            ["udp://a:1"]
+        );
+        assert!(AnnounceTiers::new(None, None).all_urls().is_empty());
+    }
+
+    #[test]
+    fn it_should_classify_tracker_urls_by_scheme() {
+        let kind = |url: &str| TrackerUrl::parse(url).unwrap().kind();
+
+        assert_eq!(
+            kind("udp://tracker.example.com:6969/announce"),
+            TrackerKind::Udp
+        );
+        assert_eq!(
+            kind("http://tracker.example.com/announce"),
+            TrackerKind::Http
+        );
+        assert_eq!(
+            kind(" https://tracker.example.com:443/a "),
+            TrackerKind::Http
+        );
+        assert_eq!(kind("wss://tracker.example.com"), TrackerKind::WebTorrent);
+
+        let url = TrackerUrl::parse("http://tracker.example.com/announce").unwrap();
+        assert_eq!((url.host(), url.port()), ("tracker.example.com", 80));
+    }
+
+    #[test]
+    fn it_should_reject_malformed_tracker_urls() {
+        let error = |url: &str| TrackerUrl::parse(url).unwrap_err();
+
+        assert!(matches!(error("tracker"), TrackerUrlError::Malformed(_)));
+        assert!(matches!(error("http://"), TrackerUrlError::Malformed(_)));
+        assert_eq!(
+            error("ftp://tracker.example.com/"),
+            TrackerUrlError::UnsupportedScheme("ftp".to_owned())
+        );
+        assert_eq!(
+            error("udp://tracker.example.com/announce"),
+            TrackerUrlError::MissingPort
+        );
+        assert_eq!(
+            error("udp://tracker.example.com:0"),
+            TrackerUrlError::InvalidPort
+        );
+        assert_eq!(
+            error("http://tracker.example.com/announce#x"),
+            TrackerUrlError::Fragment("x".to_owned())
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Bencoded byte strings that are usually, but not always, UTF-8 text.
+//!
+//! Bencode does not distinguish between text and binary data. Fields like
+//! `md5sum` or `source` are supposed to be text, but some generators emit raw
+//! bytes. Deserializing them into a `String` makes the whole torrent fail, so
+//! they are stored as raw bytes and converted to text only when needed.
+//!
+//! Names and paths are stored the same way: torrents created on systems
+//! with legacy encodings (Shift-JIS, GBK, ...) contain them in that encoding,
+//! and converting them to `String` on parsing would lose the original bytes.
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+/// A byte string that accepts both bencoded strings and raw bytes.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Default)]
+pub struct ByteString(Vec<u8>);
+
+impl ByteString {
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `false` if the bytes are not valid UTF-8, like the text fields
+    /// that [`validate_text`](crate::utils::validate::validate_text) reports.
+    #[must_use]
+    pub fn is_utf8(&self) -> bool {
+        std::str::from_utf8(&self.0).is_ok()
+    }
+
+    /// Returns the text, replacing invalid UTF-8 sequences with `U+FFFD`.
+    #[must_use]
+    pub fn to_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+
+    /// Returns the text like [`ByteString::to_str_lossy`] and whether any
+    /// invalid UTF-8 sequence had to be replaced, so callers can warn that
+    /// the text is not what the torrent contains.
+    #[must_use]
+    pub fn decode_utf8(&self) -> (Cow<'_, str>, bool) {
+        let text = String::from_utf8_lossy(&self.0);
+        let lossy = matches!(text, Cow::Owned(_));
+        (text, lossy)
+    }
+}
+
+impl PartialEq<str> for ByteString {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for ByteString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for ByteString {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<String> for ByteString {
+    fn from(text: String) -> Self {
+        Self(text.into_bytes())
+    }
+}
+
+impl From<&str> for ByteString {
+    fn from(text: &str) -> Self {
+        Self(text.as_bytes().to_vec())
+    }
+}
+
+impl fmt::Display for ByteString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str_lossy())
+    }
+}
+
+impl fmt::Debug for ByteString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match std::str::from_utf8(&self.0) {
+            Ok(text) => write!(f, "{text:?}"),
+            Err(_) => write!(f, "b\"{}\"", self.0.escape_ascii()),
+        }
+    }
+}
+
+impl serde::ser::Serialize for ByteString {
+    /// Text is serialized as a string so that human-readable formats like
+    /// JSON show it as such. Bencode output is the same in both cases.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(&self.0) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for ByteString {
+    fn deserialize<D: Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        des.deserialize_byte_buf(ByteStringVisitor)
+    }
+}
+
+struct ByteStringVisitor;
+
+impl Visitor<'_> for ByteStringVisitor {
+    type Value = ByteString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a byte string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(ByteString::from(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(ByteString::from(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(ByteString::from(v))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(ByteString::from(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::ByteString;
+
+    #[derive(PartialEq, Eq, Debug, Serialize, Deserialize)]
+    struct ContainingByteString {
+        pub source: ByteString,
+    }
+
+    #[test]
+    fn it_should_be_deserialized_from_a_bencoded_non_utf8_byte_string() {
+        let s: ContainingByteString = serde_bencode::from_bytes(b"d6:source2:\xff\xfee").unwrap();
+
+        assert_eq!(s.source.as_bytes(), b"\xff\xfe");
+        assert!(!s.source.is_utf8());
+        assert_eq!(s.source.to_str_lossy(), "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn it_should_tell_whether_the_text_conversion_was_lossy() {
+        assert_eq!(
+            ByteString::from("tracker").decode_utf8(),
+            ("tracker".into(), false)
+        );
+        assert_eq!(
+            ByteString::from(b"a\xff".as_slice()).decode_utf8(),
+            ("a\u{FFFD}".into(), true)
+        );
+    }
+
+    #[test]
+    fn it_should_be_deserialized_from_a_json_string() {
+        let s: ContainingByteString = serde_json::from_str(r#"{"source":"tracker"}"#).unwrap();
+
+        assert_eq!(s.source, ByteString::from("tracker"));
+        assert!(s.source.is_utf8());
+    }
+
+    #[test]
+    fn it_should_be_bencoded_back_to_the_original_bytes() {
+        let s = ContainingByteString {
+            source: ByteString::from(b"\xff\xfe".as_slice()),
+        };
+
+        assert_eq!(
+            serde_bencode::to_bytes(&s).unwrap(),
+            b"d6:source2:\xff\xfee".to_vec()
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! `BitTorrent` v2 file tree ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html)).
+//!
+//! The `file tree` key of the info dictionary describes the content as nested
+//! dictionaries. Directories are dictionaries where keys are path elements.
+//! Files are dictionaries with a single empty key (`""`) whose value contains
+//! the file properties:
+//!
+//! ```text
+//! {
+//!   "dir": {
+//!     "file.txt": {
+//!       "": { "length": 5, "pieces root": <32 bytes> }
+//!     }
+//!   }
+//! }
+//! ```
+use std::collections::{btree_map, BTreeMap};
+use std::fmt;
+
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+use serde_bencode::value::Value as BValue;
+use serde_bytes::ByteBuf;
+use serde_derive::{Deserialize, Serialize};
+
+/// The root of the file tree: a directory without a name.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct FileTree(pub BTreeMap<String, FileTreeNode>);
+
+/// A node in the file tree.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum FileTreeNode {
+    File(FileTreeFile),
+    Directory(FileTree),
+}
+
+/// The properties of a file in the file tree.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct FileTreeFile {
+    pub length: u64,
+    /// Root of the merkle tree of the file. Empty files don't have it.
+    #[serde(default)]
+    #[serde(rename = "pieces root")]
+    pub pieces_root: Option<ByteBuf>,
+    /// BEP 47 file attributes.
+    #[serde(default)]
+    pub attr: Option<String>,
+    /// File dictionary keys not defined by the specifications the model
+    /// supports, like the ones of [`TorrentFile`](crate::models::torrent_file::TorrentFile).
+    #[serde(flatten)]
+    pub extra_fields: BTreeMap<String, BValue>,
+}
+
+impl FileTree {
+    /// Returns an iterator over the files of the tree, depth-first and in the
+    /// order of the keys, with the path components of each one. Directories
+    /// without files are skipped.
+    #[must_use]
+    pub fn iter_files(&self) -> Files<'_> {
+        Files {
+            directories: vec![self.0.iter()],
+            path: vec![],
+        }
+    }
+
+    /// Returns the sum of the lengths of the files, like
+    /// [`Torrent::file_size`](crate::models::torrent_file::Torrent::file_size)
+    /// does for v1 torrents.
+    #[must_use]
+    pub fn file_size(&self) -> u64 {
+        self.iter_files()
+            .fold(0, |size, (_, file)| size.saturating_add(file.length))
+    }
+}
+
+/// Iterator over the files of a [`FileTree`], returned by
+/// [`FileTree::iter_files`].
+pub struct Files<'a> {
+    /// The entries left in each directory from the root to the current one.
+    directories: Vec<btree_map::Iter<'a, String, FileTreeNode>>,
+    /// Path of the current directory.
+    path: Vec<&'a str>,
+}
+
+impl<'a> Iterator for Files<'a> {
+    type Item = (Vec<&'a str>, &'a FileTreeFile);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.directories.last_mut()?.next() {
+                Some((name, FileTreeNode::File(file))) => {
+                    let mut path = self.path.clone();
+                    path.push(name);
+                    return Some((path, file));
+                }
+                Some((name, FileTreeNode::Directory(directory))) => {
+                    self.path.push(name);
+                    self.directories.push(directory.0.iter());
+                }
+                None => {
+                    self.directories.pop();
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+impl serde::ser::Serialize for FileTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(&self.0)
+    }
+}
+
+impl serde::ser::Serialize for FileTreeNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            FileTreeNode::File(file) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("", file)?;
+                map.end()
+            }
+            FileTreeNode::Directory(directory) => directory.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for FileTree {
+    fn deserialize<D: Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        match FileTreeNode::deserialize(des)? {
+            FileTreeNode::Directory(directory) => Ok(directory),
+            FileTreeNode::File(_) => Err(de::Error::custom(
+                "the root of the file tree must be a directory",
+            )),
+        }
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for FileTreeNode {
+    fn deserialize<D: Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        des.deserialize_map(FileTreeNodeVisitor)
+    }
+}
+
+struct FileTreeNodeVisitor;
+
+impl<'de> Visitor<'de> for FileTreeNodeVisitor {
+    type Value = FileTreeNode;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a file tree dictionary")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut file = None;
+        let mut children = BTreeMap::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key.is_empty() {
+                file = Some(map.next_value::<FileTreeFile>()?);
+            } else {
+                children.insert(key, map.next_value::<FileTreeNode>()?);
+            }
+        }
+
+        match file {
+            Some(_) if !children.is_empty() => Err(de::Error::custom(
+                "a file tree node cannot be both a file and a directory",
+            )),
+            Some(file) => Ok(FileTreeNode::File(file)),
+            None => Ok(FileTreeNode::Directory(FileTree(children))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_bencode::value::Value as BValue;
+    use serde_bytes::ByteBuf;
+
+    use super::{FileTree, FileTreeFile, FileTreeNode};
+
+    const FILE_TREE: &[u8] =
+        b"d3:dird8:file.txtd0:d6:lengthi5e5:mtimei1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee5:emptyd0:d6:lengthi0eeee";
+
+    #[test]
+    fn it_should_be_deserialized_from_a_bencoded_file_tree() {
+        let file_tree: FileTree = serde_bencode::from_bytes(FILE_TREE).unwrap();
+
+        let Some(FileTreeNode::Directory(dir)) = file_tree.0.get("dir") else {
+            panic!("`dir` should be a directory");
+        };
+
+        assert_eq!(
+            dir.0.get("file.txt"),
+            Some(&FileTreeNode::File(FileTreeFile {
+                length: 5,
+                pieces_root: Some(ByteBuf::from(vec![b'a'; 32])),
+                attr: None,
+                extra_fields: BTreeMap::from([("mtime".to_owned(), BValue::Int(1))]),
+            }))
+        );
+        assert_eq!(
+            file_tree.0.get("empty"),
+            Some(&FileTreeNode::File(FileTreeFile {
+                length: 0,
+                pieces_root: None,
+                attr: None,
+                extra_fields: BTreeMap::new(),
+            }))
+        );
+    }
+
+    #[test]
+    fn it_should_iterate_over_the_files_with_their_paths() {
+        let file_tree: FileTree = serde_bencode::from_bytes(
+            b"d1:ad1:bd1:cd0:d6:lengthi1eeee1:dd0:d6:lengthi2eeee1:ede1:fd0:d6:lengthi4eeee",
+        )
+        .unwrap();
+
+        let files: Vec<_> = file_tree
+            .iter_files()
+            .map(|(path, file)| (path.join("/"), file.length))
+            .collect();
+
+        assert_eq!(
+            files,
+            [
+                ("a/b/c".to_owned(), 1),
+                ("a/d".to_owned(), 2),
+                ("f".to_owned(), 4)
+            ]
+        );
+        assert_eq!(file_tree.file_size(), 7);
+    }
+
+    #[test]
+    fn it_should_be_bencoded_back_to_the_original_bytes() {
+        let file_tree: FileTree = serde_bencode::from_bytes(FILE_TREE).unwrap();
+
+        assert_eq!(serde_bencode::to_bytes(&file_tree).unwrap(), FILE_TREE);
+    }
+
+    #[test]
+    fn it_should_fail_when_a_node_is_both_a_file_and_a_directory() {
+        let result: Result<FileTree, _> =
+            serde_bencode::from_bytes(b"d1:ad0:d6:lengthi1ee1:bd0:d6:lengthi1eeeee");
+
+        assert!(result.is_err());
+    }
+}
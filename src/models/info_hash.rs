@@ -1,7 +1,18 @@
 use std::panic::Location;
 
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use thiserror::Error;
 
+use crate::utils::base32;
+
+/// Bytes that must be percent-encoded in the query of a tracker request: all
+/// except the RFC 3986 unreserved characters.
+pub(crate) const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
 /// `BitTorrent` Info Hash v1
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct InfoHash(pub [u8; 20]);
@@ -15,6 +26,7 @@ impl InfoHash {
     ///
     /// Will panic if byte slice does not contains the exact amount of bytes need for the `InfoHash`.
     #[must_use]
+    #[deprecated(note = "use `InfoHash::try_from` instead, which doesn't panic")]
     pub fn from_bytes(bytes: &[u8]) -> Self {
         assert_eq!(bytes.len(), INFO_HASH_BYTES_LEN);
         let mut ret = Self([0u8; INFO_HASH_BYTES_LEN]);
@@ -33,6 +45,46 @@ impl InfoHash {
     pub fn to_hex_string(&self) -> String {
         self.to_string()
     }
+
+    /// Returns the `InfoHash` as an uppercase RFC 4648 base32 string without
+    /// padding, the 32 characters form of old magnet links.
+    #[must_use]
+    pub fn to_base32(&self) -> String {
+        base32::encode(&self.0)
+    }
+
+    /// Create a new `InfoHash` from its base32 form. It's case-insensitive.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the string is not 32 characters long or
+    /// contains characters outside the base32 alphabet.
+    pub fn from_base32(s: &str) -> Result<Self, binascii::ConvertError> {
+        if s.len() != 32 {
+            return Err(binascii::ConvertError::InvalidInputLength);
+        }
+        let bytes = base32::decode(s).map_err(|_| binascii::ConvertError::InvalidInput)?;
+        Self::try_from(bytes).map_err(|_| binascii::ConvertError::InvalidInput)
+    }
+
+    /// Returns the raw bytes of the `InfoHash` percent-encoded, as in the
+    /// `info_hash` parameter of tracker announce and scrape requests. The
+    /// RFC 3986 unreserved characters are not encoded.
+    #[must_use]
+    pub fn to_url_encoded(&self) -> String {
+        percent_encode(&self.0, URL_ENCODE_SET).to_string()
+    }
+
+    /// Create a new `InfoHash` from the `info_hash` parameter of a tracker
+    /// request, the 20 raw bytes percent-encoded. Bytes that are not encoded
+    /// are taken as they are.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the decoded value is not 20 bytes long.
+    pub fn from_url_encoded(s: &str) -> Result<Self, ConversionError> {
+        Self::try_from(percent_decode_str(s).collect::<Vec<u8>>())
+    }
 }
 
 impl std::fmt::Display for InfoHash {
@@ -43,15 +95,39 @@ impl std::fmt::Display for InfoHash {
     }
 }
 
+/// Formats the `InfoHash` as lowercase hex, like [`Display`](std::fmt::Display).
+/// The alternate flag (`{:#x}`) adds the `0x` prefix.
+impl std::fmt::LowerHex for InfoHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// Formats the `InfoHash` as uppercase hex. The alternate flag (`{:#X}`) adds
+/// the `0x` prefix.
+impl std::fmt::UpperHex for InfoHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02X}"))
+    }
+}
+
+/// Parses the 40 characters hex form or the 32 characters base32 form.
 impl std::str::FromStr for InfoHash {
     type Err = binascii::ConvertError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut i = Self([0u8; 20]);
-        if s.len() != 40 {
-            return Err(binascii::ConvertError::InvalidInputLength);
-        }
-        binascii::hex2bin(s.as_bytes(), &mut i.0)?;
+        match s.len() {
+            40 => binascii::hex2bin(s.as_bytes(), &mut i.0)?,
+            32 => return Self::from_base32(s),
+            _ => return Err(binascii::ConvertError::InvalidInputLength),
+        };
         Ok(i)
     }
 }
@@ -68,31 +144,23 @@ impl std::cmp::PartialOrd<InfoHash> for InfoHash {
     }
 }
 
-impl std::convert::From<&[u8]> for InfoHash {
-    fn from(data: &[u8]) -> InfoHash {
-        assert_eq!(data.len(), 20);
-        let mut ret = InfoHash([0u8; 20]);
-        ret.0.clone_from_slice(data);
-        ret
-    }
-}
-
 impl std::convert::From<[u8; 20]> for InfoHash {
     fn from(val: [u8; 20]) -> Self {
         InfoHash(val)
     }
 }
 
-/// Errors that can occur when converting from a `Vec<u8>` to an `InfoHash`.
+/// Errors that can occur when converting from a `Vec<u8>` to an `InfoHash`
+/// or an `InfoHashV2`.
 #[derive(Error, Debug)]
 pub enum ConversionError {
-    /// Not enough bytes for info-hash. An info-hash is 20 bytes.
+    /// Not enough bytes for info-hash. An info-hash is 20 bytes (32 for v2).
     #[error("not enough bytes for info-hash: {message} {location}")]
     NotEnoughBytes {
         location: &'static Location<'static>,
         message: String,
     },
-    /// Too many bytes for info-hash. An info-hash is 20 bytes.
+    /// Too many bytes for info-hash. An info-hash is 20 bytes (32 for v2).
     #[error("too many bytes for info-hash: {message} {location}")]
     TooManyBytes {
         location: &'static Location<'static>,
@@ -100,23 +168,44 @@ pub enum ConversionError {
     },
 }
 
+impl ConversionError {
+    /// Checks that `length` bytes can be converted to a hash of `expected`
+    /// bytes.
+    #[track_caller]
+    pub(crate) fn check_length(length: usize, expected: usize) -> Result<(), Self> {
+        let message = || format!("got {length} bytes, expected {expected}");
+        match length.cmp(&expected) {
+            std::cmp::Ordering::Less => Err(Self::NotEnoughBytes {
+                location: Location::caller(),
+                message: message(),
+            }),
+            std::cmp::Ordering::Greater => Err(Self::TooManyBytes {
+                location: Location::caller(),
+                message: message(),
+            }),
+            std::cmp::Ordering::Equal => Ok(()),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for InfoHash {
+    type Error = ConversionError;
+
+    #[track_caller]
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        ConversionError::check_length(bytes.len(), INFO_HASH_BYTES_LEN)?;
+        let mut ret = Self([0u8; INFO_HASH_BYTES_LEN]);
+        ret.0.copy_from_slice(bytes);
+        Ok(ret)
+    }
+}
+
 impl TryFrom<Vec<u8>> for InfoHash {
     type Error = ConversionError;
 
+    #[track_caller]
     fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
-        if bytes.len() < INFO_HASH_BYTES_LEN {
-            return Err(ConversionError::NotEnoughBytes {
-                location: Location::caller(),
-                message: format! {"got {} bytes, expected {}", bytes.len(), INFO_HASH_BYTES_LEN},
-            });
-        }
-        if bytes.len() > INFO_HASH_BYTES_LEN {
-            return Err(ConversionError::TooManyBytes {
-                location: Location::caller(),
-                message: format! {"got {} bytes, expected {}", bytes.len(), INFO_HASH_BYTES_LEN},
-            });
-        }
-        Ok(Self::from_bytes(&bytes))
+        Self::try_from(bytes.as_slice())
     }
 }
 
@@ -164,6 +253,54 @@ impl<'v> serde::de::Visitor<'v> for InfoHashVisitor {
     }
 }
 
+/// Serializes an [`InfoHash`] as its 20 raw bytes instead of a hex string,
+/// for binary formats like bencode:
+///
+/// ```
+/// use serde_derive::{Deserialize, Serialize};
+/// use torrust_parse_torrent::models::info_hash::{self, InfoHash};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct ScrapeRequest {
+///     #[serde(with = "info_hash::binary")]
+///     info_hash: InfoHash,
+/// }
+///
+/// let request = ScrapeRequest { info_hash: InfoHash([b'a'; 20]) };
+/// let bytes = serde_bencode::to_bytes(&request).unwrap();
+///
+/// assert_eq!(bytes, b"d9:info_hash20:aaaaaaaaaaaaaaaaaaaae");
+/// ```
+pub mod binary {
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::Serializer;
+    use serde_bytes::ByteBuf;
+
+    use super::InfoHash;
+
+    /// Serializes the `InfoHash` as bytes.
+    ///
+    /// # Errors
+    ///
+    /// Will return the errors of the serializer.
+    pub fn serialize<S: Serializer>(
+        info_hash: &InfoHash,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&info_hash.0)
+    }
+
+    /// Deserializes an `InfoHash` from bytes.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the value is not exactly 20 bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<InfoHash, D::Error> {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        InfoHash::try_from(bytes.into_vec()).map_err(D::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -172,7 +309,7 @@ mod tests {
     use serde_derive::{Deserialize, Serialize};
     use serde_json::json;
 
-    use super::InfoHash;
+    use super::{ConversionError, InfoHash};
 
     #[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
     struct ContainingInfoHash {
@@ -202,6 +339,98 @@ mod tests {
         assert!(info_hash.is_err());
     }
 
+    #[test]
+    fn an_info_hash_can_be_created_from_a_32_char_base32_string() {
+        let info_hash = InfoHash::from_str("7777777777777777777777777777777777777777").unwrap();
+
+        assert_eq!(
+            InfoHash::from_str(&"7".repeat(32)).unwrap(),
+            InfoHash([0xFF; 20])
+        );
+        assert_eq!(InfoHash::from_base32(&info_hash.to_base32()), Ok(info_hash));
+        assert_eq!(
+            InfoHash::from_base32(&info_hash.to_base32().to_lowercase()),
+            Ok(info_hash)
+        );
+    }
+
+    #[test]
+    fn it_should_encode_an_info_hash_in_base32_without_padding() {
+        let info_hash = InfoHash::from_str("3b245504cf5f11bbdbe1201cea6a6bf45aee1bc0").unwrap();
+
+        assert_eq!(info_hash.to_base32(), "HMSFKBGPL4I3XW7BEAOOU2TL6RNO4G6A");
+    }
+
+    #[test]
+    fn an_info_hash_can_not_be_created_from_an_invalid_base32_string() {
+        assert!(InfoHash::from_base32(&"1".repeat(32)).is_err());
+        assert!(InfoHash::from_base32(&"A".repeat(31)).is_err());
+    }
+
+    #[test]
+    fn it_should_percent_encode_an_info_hash_for_tracker_requests() {
+        let info_hash = InfoHash::from_str("2d2e5f7e41ff00000000000000000000000000ff").unwrap();
+
+        assert_eq!(
+            info_hash.to_url_encoded(),
+            "-._~A%FF%00%00%00%00%00%00%00%00%00%00%00%00%00%FF"
+        );
+    }
+
+    #[test]
+    fn an_info_hash_can_be_created_from_its_percent_encoded_form() {
+        let info_hash = InfoHash::from_str("2d2e5f7e41ff00000000000000000000000000ff").unwrap();
+
+        assert_eq!(
+            InfoHash::from_url_encoded(&info_hash.to_url_encoded()).unwrap(),
+            info_hash
+        );
+        assert_eq!(
+            InfoHash::from_url_encoded(&"%ff".repeat(20)).unwrap(),
+            InfoHash([0xFF; 20])
+        );
+        assert!(InfoHash::from_url_encoded(&"%FF".repeat(19)).is_err());
+        assert!(InfoHash::from_url_encoded(&"a".repeat(21)).is_err());
+    }
+
+    #[test]
+    fn an_info_hash_can_be_formatted_as_lower_or_upper_hex() {
+        let info_hash = InfoHash::from_str("3b245504cf5f11bbdbe1201cea6a6bf45aee1bc0").unwrap();
+
+        assert_eq!(format!("{info_hash:x}"), info_hash.to_string());
+        assert_eq!(
+            format!("{info_hash:X}"),
+            "3B245504CF5F11BBDBE1201CEA6A6BF45AEE1BC0"
+        );
+        assert_eq!(
+            format!("{info_hash:#x}"),
+            "0x3b245504cf5f11bbdbe1201cea6a6bf45aee1bc0"
+        );
+    }
+
+    #[test]
+    fn an_info_hash_can_be_serialized_as_raw_bytes() {
+        #[derive(PartialEq, Eq, Debug, Serialize, Deserialize)]
+        struct Binary {
+            #[serde(with = "super::binary")]
+            info_hash: InfoHash,
+        }
+
+        let value = Binary {
+            info_hash: InfoHash([0xFF; 20]),
+        };
+        let bytes = serde_bencode::to_bytes(&value).unwrap();
+
+        assert_eq!(
+            bytes,
+            [b"d9:info_hash20:".as_slice(), &[0xFF; 20], b"e"].concat()
+        );
+        assert_eq!(serde_bencode::from_bytes::<Binary>(&bytes).unwrap(), value);
+        assert!(
+            serde_bencode::from_bytes::<Binary>(b"d9:info_hash19:aaaaaaaaaaaaaaaaaaae").is_err()
+        );
+    }
+
     #[test]
     fn an_info_hash_should_by_displayed_like_a_40_utf8_lowercased_char_hex_string() {
         let info_hash = InfoHash::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap();
@@ -223,7 +452,7 @@ mod tests {
 
     #[test]
     fn an_info_hash_can_be_created_from_a_valid_20_byte_array_slice() {
-        let info_hash: InfoHash = [255u8; 20].as_slice().into();
+        let info_hash: InfoHash = [255u8; 20].as_slice().try_into().unwrap();
 
         assert_eq!(
             info_hash,
@@ -251,6 +480,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_should_fail_trying_to_create_an_info_hash_from_a_byte_slice_with_the_wrong_length() {
+        assert!(matches!(
+            InfoHash::try_from([255u8; 19].as_slice()),
+            Err(ConversionError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            InfoHash::try_from([255u8; 21].as_slice()),
+            Err(ConversionError::TooManyBytes { .. })
+        ));
+    }
+
+    #[test]
+    fn it_should_report_the_location_of_the_caller_of_a_failed_conversion() {
+        let (slice, line) = (InfoHash::try_from([255u8; 19].as_slice()), line!());
+        let (vector, vector_line) = (InfoHash::try_from(vec![255u8; 21]), line!());
+
+        let Err(ConversionError::NotEnoughBytes { location, .. }) = slice else {
+            panic!("expected an error");
+        };
+        assert_eq!(location.line(), line);
+        let Err(ConversionError::TooManyBytes { location, .. }) = vector else {
+            panic!("expected an error");
+        };
+        assert_eq!(location.line(), vector_line);
+    }
+
     #[test]
     fn it_should_fail_trying_to_create_an_info_hash_from_a_byte_vector_with_less_than_20_bytes() {
         assert!(InfoHash::try_from([255u8; 19].to_vec()).is_err());
@@ -0,0 +1,255 @@
+use crate::models::info_hash::{ConversionError, InfoHash};
+
+/// `BitTorrent` Info Hash v2 ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html)).
+///
+/// It's the SHA-256 hash of the bencoded info dictionary.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct InfoHashV2(pub [u8; 32]);
+
+const INFO_HASH_V2_BYTES_LEN: usize = 32;
+
+impl InfoHashV2 {
+    /// Create a new `InfoHashV2` from a byte slice.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if byte slice does not contains the exact amount of bytes need for the `InfoHashV2`.
+    #[must_use]
+    #[deprecated(note = "use `InfoHashV2::try_from` instead, which doesn't panic")]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), INFO_HASH_V2_BYTES_LEN);
+        let mut ret = Self([0u8; INFO_HASH_V2_BYTES_LEN]);
+        ret.0.clone_from_slice(bytes);
+        ret
+    }
+
+    /// Returns the `InfoHashV2` internal byte array.
+    #[must_use]
+    pub fn bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Returns the `InfoHashV2` as a hex string.
+    #[must_use]
+    pub fn to_hex_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns the hash truncated to 20 bytes.
+    ///
+    /// BEP 52 uses it wherever the protocol has room only for a v1 info-hash,
+    /// for example in tracker announces and in the peer wire handshake.
+    #[must_use]
+    pub fn truncated(&self) -> InfoHash {
+        let mut bytes = [0u8; 20];
+        bytes.copy_from_slice(&self.0[..20]);
+        InfoHash(bytes)
+    }
+}
+
+impl std::fmt::Display for InfoHashV2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut chars = [0u8; 64];
+        binascii::bin2hex(&self.0, &mut chars).expect("failed to hexlify");
+        write!(f, "{}", std::str::from_utf8(&chars).unwrap())
+    }
+}
+
+impl std::str::FromStr for InfoHashV2 {
+    type Err = binascii::ConvertError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut i = Self([0u8; 32]);
+        if s.len() != 64 {
+            return Err(binascii::ConvertError::InvalidInputLength);
+        }
+        binascii::hex2bin(s.as_bytes(), &mut i.0)?;
+        Ok(i)
+    }
+}
+
+impl Ord for InfoHashV2 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl std::cmp::PartialOrd<InfoHashV2> for InfoHashV2 {
+    fn partial_cmp(&self, other: &InfoHashV2) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::convert::From<[u8; 32]> for InfoHashV2 {
+    fn from(val: [u8; 32]) -> Self {
+        InfoHashV2(val)
+    }
+}
+
+impl TryFrom<&[u8]> for InfoHashV2 {
+    type Error = ConversionError;
+
+    #[track_caller]
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        ConversionError::check_length(bytes.len(), INFO_HASH_V2_BYTES_LEN)?;
+        let mut ret = Self([0u8; INFO_HASH_V2_BYTES_LEN]);
+        ret.0.copy_from_slice(bytes);
+        Ok(ret)
+    }
+}
+
+impl TryFrom<Vec<u8>> for InfoHashV2 {
+    type Error = ConversionError;
+
+    #[track_caller]
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl serde::ser::Serialize for InfoHashV2 {
+    fn serialize<S: serde::ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut buffer = [0u8; 64];
+        let bytes_out = binascii::bin2hex(&self.0, &mut buffer).ok().unwrap();
+        let str_out = std::str::from_utf8(bytes_out).unwrap();
+        serializer.serialize_str(str_out)
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for InfoHashV2 {
+    fn deserialize<D: serde::de::Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        des.deserialize_str(InfoHashV2Visitor)
+    }
+}
+
+struct InfoHashV2Visitor;
+
+impl serde::de::Visitor<'_> for InfoHashV2Visitor {
+    type Value = InfoHashV2;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "a 64 character long hash")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        if v.len() != 64 {
+            return Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(v),
+                &"a 64 character long string",
+            ));
+        }
+
+        let mut res = InfoHashV2([0u8; 32]);
+
+        if binascii::hex2bin(v.as_bytes(), &mut res.0).is_err() {
+            return Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(v),
+                &"a hexadecimal string",
+            ));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::str::FromStr;
+
+    use serde_derive::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::InfoHashV2;
+    use crate::models::info_hash::InfoHash;
+
+    #[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+    struct ContainingInfoHashV2 {
+        pub info_hash: InfoHashV2,
+    }
+
+    #[test]
+    fn an_info_hash_v2_can_be_created_from_a_valid_64_utf8_char_string_representing_an_hexadecimal_value(
+    ) {
+        let info_hash = InfoHashV2::from_str(&"F".repeat(64));
+        assert!(info_hash.is_ok());
+    }
+
+    #[test]
+    fn an_info_hash_v2_can_not_be_created_from_a_utf8_string_representing_a_not_valid_hexadecimal_value(
+    ) {
+        let info_hash = InfoHashV2::from_str(&"G".repeat(64));
+        assert!(info_hash.is_err());
+    }
+
+    #[test]
+    fn an_info_hash_v2_can_only_be_created_from_a_64_utf8_char_string() {
+        assert!(InfoHashV2::from_str(&"F".repeat(63)).is_err());
+        assert!(InfoHashV2::from_str(&"F".repeat(65)).is_err());
+        assert!(InfoHashV2::from_str(&"F".repeat(40)).is_err());
+    }
+
+    #[test]
+    fn an_info_hash_v2_should_by_displayed_like_a_64_utf8_lowercased_char_hex_string() {
+        let info_hash = InfoHashV2::from_str(&"F".repeat(64)).unwrap();
+
+        assert_eq!(format!("{info_hash}"), "f".repeat(64));
+        assert_eq!(info_hash.to_hex_string(), "f".repeat(64));
+    }
+
+    #[test]
+    fn an_info_hash_v2_can_be_created_from_a_valid_32_byte_array() {
+        let info_hash: InfoHashV2 = [255u8; 32].into();
+
+        assert_eq!(info_hash, InfoHashV2::from_str(&"F".repeat(64)).unwrap());
+    }
+
+    #[test]
+    fn an_info_hash_v2_can_be_created_from_a_byte_vector() {
+        let info_hash: InfoHashV2 = [255u8; 32].to_vec().try_into().unwrap();
+
+        assert_eq!(info_hash, InfoHashV2::from_str(&"F".repeat(64)).unwrap());
+    }
+
+    #[test]
+    fn it_should_fail_trying_to_create_an_info_hash_v2_from_a_byte_vector_with_the_wrong_length() {
+        assert!(InfoHashV2::try_from([255u8; 31].to_vec()).is_err());
+        assert!(InfoHashV2::try_from([255u8; 33].to_vec()).is_err());
+        assert!(InfoHashV2::try_from([255u8; 31].as_slice()).is_err());
+        assert_eq!(
+            InfoHashV2::try_from([255u8; 32].as_slice()).unwrap(),
+            InfoHashV2([255u8; 32])
+        );
+    }
+
+    #[test]
+    fn an_info_hash_v2_should_be_truncated_to_the_first_20_bytes() {
+        let mut bytes = [0u8; 32];
+        bytes[..20].copy_from_slice(&[255u8; 20]);
+
+        let info_hash = InfoHashV2::from(bytes);
+
+        assert_eq!(
+            info_hash.truncated(),
+            InfoHash::from_str(&"F".repeat(40)).unwrap()
+        );
+    }
+
+    #[test]
+    fn an_info_hash_v2_can_be_serialized_and_deserialized() {
+        let s = ContainingInfoHashV2 {
+            info_hash: InfoHashV2::from_str(&"F".repeat(64)).unwrap(),
+        };
+
+        let json_serialized_value = serde_json::to_string(&s).unwrap();
+
+        assert_eq!(
+            json_serialized_value,
+            format!(r#"{{"info_hash":"{}"}}"#, "f".repeat(64))
+        );
+
+        let deserialized: ContainingInfoHashV2 =
+            serde_json::from_value(json!({ "info_hash": "f".repeat(64) })).unwrap();
+
+        assert_eq!(deserialized, s);
+    }
+}
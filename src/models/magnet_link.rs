@@ -0,0 +1,449 @@
+//! Magnet links ([BEP 9](https://www.bittorrent.org/beps/bep_0009.html)).
+//!
+//! A magnet link identifies a torrent by its info-hash. It can also contain
+//! the torrent name, trackers, web seeds and peers:
+//!
+//! ```text
+//! magnet:?xt=urn:btih:<info-hash>&dn=<name>&tr=<tracker-url>&ws=<web-seed>&x.pe=<host:port>
+//! ```
+//!
+//! v2 info-hashes ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html))
+//! are encoded as SHA-256 multihashes: `xt=urn:btmh:1220<hex>`.
+//!
+//! Mutable torrents ([BEP 46](https://www.bittorrent.org/beps/bep_0046.html))
+//! are identified by the public key of their publisher instead, and an
+//! optional salt, both hex-encoded: `xs=urn:btpk:<public-key>&s=<salt>`. The
+//! current info-hash is stored in the DHT under that key.
+use std::fmt;
+use std::str::FromStr;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use crate::models::info_hash::InfoHash;
+use crate::models::info_hash_v2::InfoHashV2;
+use crate::models::torrent_file::Torrent;
+use crate::utils::hex;
+
+/// Characters that must be percent-encoded in magnet link parameters: all
+/// except the RFC 3986 unreserved characters.
+const MAGNET_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Multihash prefix for SHA-256 digests: function code `0x12`, length `0x20`.
+const SHA256_MULTIHASH_PREFIX: &str = "1220";
+
+/// A parsed magnet link. Unknown parameters are ignored.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct MagnetLink {
+    /// v1 info-hash (`xt=urn:btih:`).
+    pub info_hash: Option<InfoHash>,
+    /// v2 info-hash (`xt=urn:btmh:`).
+    pub info_hash_v2: Option<InfoHashV2>,
+    /// Display name (`dn`).
+    pub display_name: Option<String>,
+    /// Tracker URLs (`tr`).
+    pub trackers: Vec<String>,
+    /// Web seed URLs (`ws`).
+    pub web_seeds: Vec<String>,
+    /// Peer addresses (`x.pe`).
+    pub peers: Vec<String>,
+    /// Exact sources (`xs`), usually URLs of the `.torrent` file.
+    pub exact_sources: Vec<String>,
+    /// Public key and salt of a mutable torrent (`xs=urn:btpk:` and `s`).
+    pub mutable_torrent: Option<MutableTorrent>,
+}
+
+/// A BEP 46 mutable torrent: the DHT item, signed by the publisher, that
+/// points to the current version of the torrent.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MutableTorrent {
+    /// ed25519 public key of the publisher.
+    pub public_key: [u8; 32],
+    /// Salt that tells apart the torrents published with the same key.
+    pub salt: Option<Vec<u8>>,
+}
+
+impl MutableTorrent {
+    /// Returns the DHT target of the item ([BEP 44](https://www.bittorrent.org/beps/bep_0044.html)):
+    /// the SHA-1 hash of the public key followed by the salt.
+    #[must_use]
+    pub fn target(&self) -> [u8; 20] {
+        let mut hasher = Sha1::new();
+        hasher.update(self.public_key);
+        if let Some(salt) = &self.salt {
+            hasher.update(salt);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Errors that can occur when parsing a magnet link.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MagnetLinkError {
+    #[error("not a magnet link: it should start with `magnet:?`")]
+    NotAMagnetLink,
+    #[error("the magnet link does not contain a `urn:btih` or `urn:btmh` exact topic, or a `urn:btpk` public key")]
+    MissingExactTopic,
+    #[error("invalid info-hash in exact topic: {value}")]
+    InvalidInfoHash { value: String },
+    #[error("unsupported multihash in exact topic, only SHA-256 is supported: {value}")]
+    UnsupportedMultihash { value: String },
+    #[error("invalid percent-encoded value for `{key}`")]
+    InvalidEncoding { key: String },
+    #[error("invalid public key, expected 64 hex digits: {value}")]
+    InvalidPublicKey { value: String },
+    #[error("invalid salt, expected hex digits: {value}")]
+    InvalidSalt { value: String },
+}
+
+impl MagnetLink {
+    /// Returns the info-hash to use where a 20-byte info-hash is required: the
+    /// v1 info-hash, or the truncated v2 info-hash for v2-only links.
+    #[must_use]
+    pub fn v1_compatible_info_hash(&self) -> Option<InfoHash> {
+        self.info_hash
+            .or_else(|| self.info_hash_v2.map(|info_hash| info_hash.truncated()))
+    }
+}
+
+impl From<InfoHash> for MagnetLink {
+    fn from(info_hash: InfoHash) -> Self {
+        Self {
+            info_hash: Some(info_hash),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<InfoHashV2> for MagnetLink {
+    fn from(info_hash_v2: InfoHashV2) -> Self {
+        Self {
+            info_hash_v2: Some(info_hash_v2),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Torrent> for MagnetLink {
+    fn from(torrent: &Torrent) -> Self {
+        let info_hashes = torrent.info_hashes();
+
+        let mut trackers: Vec<String> = vec![];
+        let tiers = torrent.announce_list.iter().flatten().flatten();
+        for tracker in torrent.announce.iter().chain(tiers) {
+            if !trackers.contains(tracker) {
+                trackers.push(tracker.clone());
+            }
+        }
+
+        Self {
+            info_hash: info_hashes.v1,
+            info_hash_v2: info_hashes.v2,
+            display_name: Some(torrent.info.display_name())
+                .filter(|name| !name.is_empty())
+                .map(|name| name.to_str_lossy().into_owned()),
+            trackers,
+            web_seeds: torrent
+                .web_seeds()
+                .into_iter()
+                .filter(|web_seed| web_seed.is_valid())
+                .map(|web_seed| web_seed.url)
+                .collect(),
+            peers: vec![],
+            exact_sources: vec![],
+            mutable_torrent: None,
+        }
+    }
+}
+
+impl FromStr for MagnetLink {
+    type Err = MagnetLinkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query = s
+            .trim()
+            .strip_prefix("magnet:?")
+            .ok_or(MagnetLinkError::NotAMagnetLink)?;
+
+        let mut magnet_link = MagnetLink::default();
+        let mut salt = None;
+
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+
+            // Parameters can be numbered when repeated, for example `tr.1`.
+            let key = match key.split_once('.') {
+                Some((name, index)) if index.chars().all(|c| c.is_ascii_digit()) => name,
+                _ => key,
+            };
+
+            let value = decode(key, value)?;
+
+            match key {
+                "xt" => parse_exact_topic(&value, &mut magnet_link)?,
+                "dn" => magnet_link.display_name = Some(value.replace('+', " ")),
+                "tr" => magnet_link.trackers.push(value),
+                "ws" => magnet_link.web_seeds.push(value),
+                "x.pe" => magnet_link.peers.push(value),
+                "xs" => match value.strip_prefix("urn:btpk:") {
+                    Some(public_key) => {
+                        let public_key = decode_hex(public_key)
+                            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                            .ok_or_else(|| MagnetLinkError::InvalidPublicKey {
+                                value: public_key.to_owned(),
+                            })?;
+                        magnet_link.mutable_torrent = Some(MutableTorrent {
+                            public_key,
+                            salt: None,
+                        });
+                    }
+                    None => magnet_link.exact_sources.push(value),
+                },
+                "s" => {
+                    salt = Some(decode_hex(&value).ok_or(MagnetLinkError::InvalidSalt { value })?);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(mutable_torrent) = &mut magnet_link.mutable_torrent {
+            mutable_torrent.salt = salt;
+        }
+
+        if magnet_link.info_hash.is_none()
+            && magnet_link.info_hash_v2.is_none()
+            && magnet_link.mutable_torrent.is_none()
+        {
+            return Err(MagnetLinkError::MissingExactTopic);
+        }
+
+        Ok(magnet_link)
+    }
+}
+
+impl fmt::Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params = vec![];
+
+        if let Some(info_hash) = &self.info_hash {
+            params.push(format!("xt=urn:btih:{info_hash}"));
+        }
+        if let Some(info_hash_v2) = &self.info_hash_v2 {
+            params.push(format!(
+                "xt=urn:btmh:{SHA256_MULTIHASH_PREFIX}{info_hash_v2}"
+            ));
+        }
+        if let Some(mutable_torrent) = &self.mutable_torrent {
+            params.push(format!(
+                "xs=urn:btpk:{}",
+                hex::from_bytes(&mutable_torrent.public_key).to_lowercase()
+            ));
+            if let Some(salt) = &mutable_torrent.salt {
+                params.push(format!("s={}", hex::from_bytes(salt).to_lowercase()));
+            }
+        }
+        if let Some(display_name) = &self.display_name {
+            params.push(format!("dn={}", encode(display_name)));
+        }
+
+        let lists = [
+            ("tr", &self.trackers),
+            ("ws", &self.web_seeds),
+            ("x.pe", &self.peers),
+            ("xs", &self.exact_sources),
+        ];
+        for (key, values) in lists {
+            for value in values {
+                params.push(format!("{key}={}", encode(value)));
+            }
+        }
+
+        write!(f, "magnet:?{}", params.join("&"))
+    }
+}
+
+fn parse_exact_topic(value: &str, magnet_link: &mut MagnetLink) -> Result<(), MagnetLinkError> {
+    let invalid = || MagnetLinkError::InvalidInfoHash {
+        value: value.to_owned(),
+    };
+
+    if let Some(hash) = value.strip_prefix("urn:btih:") {
+        magnet_link.info_hash = Some(InfoHash::from_str(hash).map_err(|_| invalid())?);
+    } else if let Some(multihash) = value.strip_prefix("urn:btmh:") {
+        let Some(hash) = multihash.strip_prefix(SHA256_MULTIHASH_PREFIX) else {
+            return Err(MagnetLinkError::UnsupportedMultihash {
+                value: value.to_owned(),
+            });
+        };
+        magnet_link.info_hash_v2 = Some(InfoHashV2::from_str(hash).map_err(|_| invalid())?);
+    }
+
+    // Other URNs (for example `urn:sha1:`) are not `BitTorrent` topics.
+    Ok(())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) || !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    hex::into_bytes(value).ok()
+}
+
+fn decode(key: &str, value: &str) -> Result<String, MagnetLinkError> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(std::borrow::Cow::into_owned)
+        .map_err(|_| MagnetLinkError::InvalidEncoding {
+            key: key.to_owned(),
+        })
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, MAGNET_ENCODE_SET).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{MagnetLink, MagnetLinkError};
+    use crate::models::info_hash::InfoHash;
+    use crate::models::info_hash_v2::InfoHashV2;
+    use crate::utils::hex;
+
+    #[test]
+    fn it_should_parse_a_magnet_link_with_a_hex_info_hash() {
+        let magnet_link = MagnetLink::from_str(
+            "magnet:?xt=urn:btih:3cd18ff2d3eec881207dcc5ca5a2c3a2a3afe462&dn=MC_GRID.zip&tr=udp%3A%2F%2Ft.io%3A80&tr.1=http://t2.io/announce&ws=https%3A%2F%2Fexample.com%2F&x.pe=10.0.0.1:6881&xs=http://example.com/a.torrent",
+        )
+        .unwrap();
+
+        assert_eq!(
+            magnet_link,
+            MagnetLink {
+                info_hash: Some(
+                    InfoHash::from_str("3cd18ff2d3eec881207dcc5ca5a2c3a2a3afe462").unwrap()
+                ),
+                info_hash_v2: None,
+                display_name: Some("MC_GRID.zip".to_string()),
+                trackers: vec![
+                    "udp://t.io:80".to_string(),
+                    "http://t2.io/announce".to_string()
+                ],
+                web_seeds: vec!["https://example.com/".to_string()],
+                peers: vec!["10.0.0.1:6881".to_string()],
+                exact_sources: vec!["http://example.com/a.torrent".to_string()],
+                mutable_torrent: None,
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_parse_a_magnet_link_with_a_base32_info_hash() {
+        // cspell:disable-next-line
+        let magnet_link =
+            MagnetLink::from_str("magnet:?xt=urn:btih:HTIY74WT53EICID5ZROKLIWDUKR27ZDC").unwrap();
+
+        assert_eq!(
+            magnet_link.info_hash,
+            Some(InfoHash::from_str("3cd18ff2d3eec881207dcc5ca5a2c3a2a3afe462").unwrap())
+        );
+    }
+
+    #[test]
+    fn it_should_parse_a_hybrid_magnet_link() {
+        let v2 = "f".repeat(64);
+        let magnet_link = MagnetLink::from_str(&format!(
+            "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{v2}",
+            "a".repeat(40)
+        ))
+        .unwrap();
+
+        assert!(magnet_link.info_hash.is_some());
+        assert_eq!(
+            magnet_link.info_hash_v2,
+            Some(InfoHashV2::from_str(&v2).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_should_use_the_truncated_v2_info_hash_for_v2_only_links() {
+        let magnet_link = MagnetLink::from(InfoHashV2::from([255u8; 32]));
+
+        assert_eq!(
+            magnet_link.v1_compatible_info_hash(),
+            Some(InfoHash::from([255u8; 20]))
+        );
+    }
+
+    #[test]
+    fn it_should_parse_and_generate_a_mutable_torrent_magnet_link() {
+        let public_key = "8543d3e6115f0f98c944077a4493dcd543e49c739fd998550a1f614ab36ed63e";
+        let uri = format!("magnet:?xs=urn:btpk:{public_key}&s=6e");
+
+        let magnet_link = MagnetLink::from_str(&uri).unwrap();
+        let mutable_torrent = magnet_link.mutable_torrent.as_ref().unwrap();
+
+        assert_eq!(mutable_torrent.public_key[0], 0x85);
+        assert_eq!(mutable_torrent.salt, Some(b"n".to_vec()));
+        assert!(magnet_link.exact_sources.is_empty());
+        assert_eq!(magnet_link.to_string(), uri);
+        assert!(matches!(
+            MagnetLink::from_str("magnet:?xs=urn:btpk:abcd"),
+            Err(MagnetLinkError::InvalidPublicKey { .. })
+        ));
+    }
+
+    #[test]
+    fn it_should_calculate_the_dht_target_of_a_mutable_torrent() {
+        // Test vectors of BEP 44.
+        let magnet_link = MagnetLink::from_str(
+            "magnet:?xs=urn:btpk:77ff84905a91936367c01360803104f92432fcd904a43511876df5cdf3e7e548&s=666f6f626172",
+        )
+        .unwrap();
+        let mut mutable_torrent = magnet_link.mutable_torrent.unwrap();
+
+        assert_eq!(
+            hex::from_bytes(&mutable_torrent.target()),
+            "411EBA73B6F087CA51A3795D9C8C938D365E32C1"
+        );
+
+        mutable_torrent.salt = None;
+        assert_eq!(
+            hex::from_bytes(&mutable_torrent.target()),
+            "4A533D47EC9C7D95B1AD75F576CFFC641853B750"
+        );
+    }
+
+    #[test]
+    fn it_should_fail_parsing_invalid_magnet_links() {
+        assert_eq!(
+            MagnetLink::from_str("http://example.com"),
+            Err(MagnetLinkError::NotAMagnetLink)
+        );
+        assert_eq!(
+            MagnetLink::from_str("magnet:?dn=name"),
+            Err(MagnetLinkError::MissingExactTopic)
+        );
+        assert!(matches!(
+            MagnetLink::from_str("magnet:?xt=urn:btih:1234"),
+            Err(MagnetLinkError::InvalidInfoHash { .. })
+        ));
+        assert!(matches!(
+            MagnetLink::from_str(&format!("magnet:?xt=urn:btmh:1114{}", "f".repeat(40))),
+            Err(MagnetLinkError::UnsupportedMultihash { .. })
+        ));
+    }
+
+    #[test]
+    fn it_should_be_converted_back_to_the_same_magnet_link() {
+        let uri = "magnet:?xt=urn:btih:3cd18ff2d3eec881207dcc5ca5a2c3a2a3afe462&dn=a%20b&tr=udp%3A%2F%2Ft.io%3A80&x.pe=10.0.0.1%3A6881";
+
+        assert_eq!(MagnetLink::from_str(uri).unwrap().to_string(), uri);
+    }
+}
@@ -1,2 +1,12 @@
+pub mod announce;
+pub mod byte_string;
+pub mod file_tree;
 pub mod info_hash;
+pub mod info_hash_v2;
+pub mod magnet_link;
+pub mod piece_layers;
+pub mod signature;
 pub mod torrent_file;
+pub mod torrent_ref;
+pub mod torrent_summary;
+pub mod web_seed;
@@ -0,0 +1,188 @@
+//! `BitTorrent` v2 piece layers ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html)).
+//!
+//! The top-level `piece layers` dictionary maps the `pieces root` of each
+//! file bigger than one piece to the concatenated SHA-256 hashes of the
+//! merkle tree layer whose nodes cover one piece each.
+use std::collections::BTreeMap;
+
+use serde_bytes::ByteBuf;
+use serde_derive::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::file_tree::FileTree;
+use crate::utils::merkle::{self, Hash, BLOCK_SIZE};
+
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PieceLayers(pub BTreeMap<ByteBuf, ByteBuf>);
+
+/// Why the layer of a file doesn't match its `pieces root`.
+#[derive(Error, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LayerError {
+    #[error("the file has no entry in the piece layers")]
+    Missing,
+    #[error("the layer has {found} hashes, expected {expected}")]
+    WrongLength { expected: usize, found: usize },
+    #[error("the merkle root of the layer is not the pieces root of the file")]
+    WrongRoot,
+}
+
+/// A file of the file tree whose piece layer is inconsistent.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct InconsistentLayer {
+    /// Path of the file in the file tree, with the components joined by `/`.
+    pub path: String,
+    pub error: LayerError,
+}
+
+impl PieceLayers {
+    /// Returns the layer hashes for the file with the given `pieces root`.
+    #[must_use]
+    pub fn get(&self, pieces_root: &[u8]) -> Option<&[u8]> {
+        self.0
+            .get(&ByteBuf::from(pieces_root))
+            .map(|layer| layer.as_slice())
+    }
+
+    /// Recomputes the merkle root of each file bigger than one piece from
+    /// its layer, and returns the files whose layer is missing, has the wrong
+    /// number of hashes or doesn't lead to their `pieces root`.
+    ///
+    /// Nothing is checked if the piece length is not a power of two multiple
+    /// of 16 KiB, which BEP 52 requires.
+    #[must_use]
+    pub fn check(&self, file_tree: &FileTree, piece_length: u64) -> Vec<InconsistentLayer> {
+        let mut inconsistent = vec![];
+
+        let Ok(piece_length) = usize::try_from(piece_length) else {
+            return inconsistent;
+        };
+        if piece_length < BLOCK_SIZE || !piece_length.is_power_of_two() {
+            return inconsistent;
+        }
+
+        for (path, file) in file_tree.iter_files() {
+            let length = usize::try_from(file.length).unwrap_or_default();
+            let Some(pieces_root) = file.pieces_root.as_ref().filter(|_| length > piece_length)
+            else {
+                continue;
+            };
+
+            if let Err(error) =
+                self.check_file(pieces_root, length.div_ceil(piece_length), piece_length)
+            {
+                inconsistent.push(InconsistentLayer {
+                    path: path.join("/"),
+                    error,
+                });
+            }
+        }
+        inconsistent
+    }
+
+    fn check_file(
+        &self,
+        pieces_root: &[u8],
+        pieces: usize,
+        piece_length: usize,
+    ) -> Result<(), LayerError> {
+        let layer = self.get(pieces_root).ok_or(LayerError::Missing)?;
+
+        let (hashes, rest) = layer.as_chunks::<32>();
+        if hashes.len() != pieces || !rest.is_empty() {
+            return Err(LayerError::WrongLength {
+                expected: pieces,
+                found: layer.len() / 32,
+            });
+        }
+
+        let root: Hash = merkle::layer_root(hashes, piece_length);
+        if root.as_slice() == pieces_root {
+            Ok(())
+        } else {
+            Err(LayerError::WrongRoot)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_bytes::ByteBuf;
+
+    use super::{InconsistentLayer, LayerError, PieceLayers};
+    use crate::models::file_tree::{FileTree, FileTreeFile, FileTreeNode};
+    use crate::utils::merkle::{self, BLOCK_SIZE};
+
+    const PIECE_LENGTH: usize = 2 * BLOCK_SIZE;
+
+    /// A file of 5 blocks in `dir/a`, in pieces of 2 blocks, and its layer.
+    fn torrent() -> (FileTree, PieceLayers) {
+        let leaves: Vec<_> = (0..5u8).map(|block| merkle::hash_block(&[block])).collect();
+        let root = merkle::pieces_root(&leaves);
+        let layer = merkle::piece_layer(&leaves, PIECE_LENGTH).concat();
+
+        let file = FileTreeNode::File(FileTreeFile {
+            length: 5 * 16384 - 100,
+            pieces_root: Some(ByteBuf::from(root.to_vec())),
+            attr: None,
+            extra_fields: BTreeMap::new(),
+        });
+        let file_tree = FileTree(BTreeMap::from([(
+            "dir".to_owned(),
+            FileTreeNode::Directory(FileTree(BTreeMap::from([("a".to_owned(), file)]))),
+        )]));
+
+        (
+            file_tree,
+            PieceLayers(BTreeMap::from([(
+                ByteBuf::from(root.to_vec()),
+                ByteBuf::from(layer),
+            )])),
+        )
+    }
+
+    #[test]
+    fn it_should_accept_layers_that_lead_to_the_pieces_root() {
+        let (file_tree, piece_layers) = torrent();
+
+        assert_eq!(piece_layers.check(&file_tree, 32768), vec![]);
+    }
+
+    #[test]
+    fn it_should_report_missing_truncated_and_wrong_layers() {
+        let (file_tree, mut piece_layers) = torrent();
+        let error = |piece_layers: &PieceLayers| {
+            piece_layers
+                .check(&file_tree, 32768)
+                .into_iter()
+                .map(|InconsistentLayer { path, error }| (path, error))
+                .collect::<Vec<_>>()
+        };
+
+        let layer = piece_layers.0.values_mut().next().unwrap();
+        layer[0] ^= 1;
+        assert_eq!(
+            error(&piece_layers),
+            [("dir/a".to_owned(), LayerError::WrongRoot)]
+        );
+
+        let layer = piece_layers.0.values_mut().next().unwrap();
+        layer.truncate(64);
+        assert_eq!(
+            error(&piece_layers),
+            [(
+                "dir/a".to_owned(),
+                LayerError::WrongLength {
+                    expected: 3,
+                    found: 2
+                }
+            )]
+        );
+
+        assert_eq!(
+            error(&PieceLayers::default()),
+            [("dir/a".to_owned(), LayerError::Missing)]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Torrent signatures ([BEP 35](https://www.bittorrent.org/beps/bep_0035.html)).
+//!
+//! The top-level `signatures` dictionary maps an identifier of each signer,
+//! like `org.example`, to an RSA signature of the info dictionary. The
+//! signature may carry the X.509 certificate of the signer and additional
+//! signed data in its own `info` dictionary.
+//!
+//! Verifying the signatures requires the `signature` feature. The
+//! certificates are not checked against any certificate authority: a valid
+//! signature only proves that the info dictionary was signed with the key of
+//! the certificate the torrent carries.
+use serde_bencode::value::Value as BValue;
+use serde_bytes::ByteBuf;
+use serde_derive::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::torrent_file::Torrent;
+
+/// A signature of the info dictionary.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Signature {
+    /// DER-encoded X.509 certificate of the signer. Without it, the public
+    /// key of the signer has to be known in advance.
+    #[serde(default)]
+    pub certificate: Option<ByteBuf>,
+    /// Additional data covered by the signature, like an expiration date.
+    #[serde(default)]
+    pub info: Option<BValue>,
+    /// RSA PKCS #1 v1.5 signature of the SHA-1 hash of the signed data (see
+    /// [`Signature::signed_data`]).
+    pub signature: ByteBuf,
+}
+
+/// Why a signature couldn't be verified.
+#[derive(Error, Debug)]
+pub enum SignatureError {
+    #[error("the signature has no certificate")]
+    NoCertificate,
+    #[error("invalid certificate: {0}")]
+    Certificate(String),
+    #[error("the signature doesn't match the info dictionary")]
+    Invalid,
+}
+
+impl Signature {
+    /// Returns the bytes that are signed: the bencoded info dictionary of the
+    /// torrent followed by the bencoded `info` of the signature, if any.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the `info` of the signature can't be bencoded, which
+    /// should never happen.
+    #[must_use]
+    pub fn signed_data(&self, torrent: &Torrent) -> Vec<u8> {
+        let mut data = torrent.info_bencoded().into_owned();
+        if let Some(info) = &self.info {
+            data.extend(serde_bencode::to_bytes(info).expect("the info should be bencodable"));
+        }
+        data
+    }
+
+    /// Checks the signature with the public key of the signer.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`SignatureError::Invalid`] if the signature doesn't match.
+    #[cfg(feature = "signature")]
+    pub fn verify(
+        &self,
+        torrent: &Torrent,
+        public_key: &rsa::RsaPublicKey,
+    ) -> Result<(), SignatureError> {
+        use sha1::{Digest, Sha1};
+
+        let hash = Sha1::digest(self.signed_data(torrent));
+        public_key
+            .verify(rsa::Pkcs1v15Sign::new::<Sha1>(), &hash, &self.signature)
+            .map_err(|_| SignatureError::Invalid)
+    }
+
+    /// Checks the signature with the public key of its certificate, and
+    /// returns the subject of the certificate, like `CN=example.org`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if there is no certificate, it's not a valid
+    /// X.509 certificate with an RSA key, or the signature doesn't match.
+    #[cfg(feature = "signature")]
+    pub fn verify_with_certificate(&self, torrent: &Torrent) -> Result<String, SignatureError> {
+        use rsa::pkcs8::DecodePublicKey;
+        use x509_cert::der::{Decode, Encode};
+
+        let certificate = self
+            .certificate
+            .as_ref()
+            .ok_or(SignatureError::NoCertificate)?;
+        let certificate = x509_cert::Certificate::from_der(certificate)
+            .map_err(|e| SignatureError::Certificate(e.to_string()))?;
+
+        let public_key = certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()
+            .map_err(|e| SignatureError::Certificate(e.to_string()))
+            .and_then(|key| {
+                rsa::RsaPublicKey::from_public_key_der(&key)
+                    .map_err(|e| SignatureError::Certificate(e.to_string()))
+            })?;
+
+        self.verify(torrent, &public_key)?;
+
+        Ok(certificate.tbs_certificate.subject.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_torrent;
+    use crate::utils::bencode;
+    use crate::utils::parse_torrent_verbose::decode_torrent;
+
+    const SIGNED: &str = "tests/fixtures/torrents/signed.torrent";
+
+    #[test]
+    fn it_should_parse_the_signatures_with_both_parsers() {
+        let bytes = std::fs::read(SIGNED).unwrap();
+
+        let torrent = parse_torrent(&bytes).unwrap();
+        let verbose = decode_torrent(bencode::decode(&bytes).unwrap()).unwrap();
+
+        let signatures = torrent.signatures.as_ref().unwrap();
+        assert_eq!(signatures.keys().collect::<Vec<_>>(), vec!["org.example"]);
+        assert!(signatures["org.example"].certificate.is_some());
+        assert_eq!(verbose.signatures, torrent.signatures);
+    }
+
+    #[cfg(feature = "signature")]
+    #[test]
+    fn it_should_verify_a_signature_with_its_certificate() {
+        use super::SignatureError;
+
+        let mut torrent = parse_torrent(&std::fs::read(SIGNED).unwrap()).unwrap();
+        let signature = torrent.signatures.as_ref().unwrap()["org.example"].clone();
+
+        assert_eq!(
+            signature.verify_with_certificate(&torrent).unwrap(),
+            "CN=example.org"
+        );
+
+        torrent.info.length = Some(6);
+        torrent.update_raw_info().unwrap();
+
+        assert!(matches!(
+            signature.verify_with_certificate(&torrent),
+            Err(SignatureError::Invalid)
+        ));
+    }
+}
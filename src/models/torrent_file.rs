@@ -1,9 +1,30 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
 use serde_bencode::ser;
+use serde_bencode::value::Value as BValue;
 use serde_bytes::ByteBuf;
 use serde_derive::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use thiserror::Error;
 
-use crate::utils::hex::from_bytes;
+use crate::models::announce::AnnounceTiers;
+use crate::models::byte_string::ByteString;
+use crate::models::file_tree::{FileTree, FileTreeNode};
+use crate::models::info_hash::InfoHash;
+use crate::models::info_hash_v2::InfoHashV2;
+use crate::models::magnet_link::MagnetLink;
+use crate::models::piece_layers::PieceLayers;
+use crate::models::signature::Signature;
+use crate::models::torrent_summary::TorrentSummary;
+use crate::models::web_seed::{self, WebSeed};
+use crate::utils::charset;
+use crate::utils::format::{format_piece_length, format_size, rfc3339};
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Torrent {
@@ -23,40 +44,90 @@ pub struct Torrent {
     #[serde(rename = "creation date")]
     pub creation_date: Option<i64>,
     #[serde(rename = "comment")]
-    pub comment: Option<String>,
+    pub comment: Option<ByteString>,
     #[serde(default)]
     #[serde(rename = "created by")]
-    pub created_by: Option<String>,
+    pub created_by: Option<ByteString>,
+    #[serde(default)]
+    #[serde(rename = "url-list")]
+    #[serde(deserialize_with = "deserialize_string_or_list")]
+    #[serde(serialize_with = "serialize_string_or_list")]
+    pub url_list: Option<Vec<String>>,
+    /// BEP 52 merkle tree layers, only present in v2 and hybrid torrents.
+    #[serde(default)]
+    #[serde(rename = "piece layers")]
+    pub piece_layers: Option<PieceLayers>,
+    /// BEP 35 signatures of the info dictionary, by signer.
+    #[serde(default)]
+    pub signatures: Option<BTreeMap<String, Signature>>,
+    /// Top-level keys not defined by the specifications the model supports,
+    /// for example `publisher` or `x_cross_seed`.
+    #[serde(flatten)]
+    pub extra_fields: BTreeMap<String, BValue>,
+    /// Original bencoded bytes of the info dictionary, when the torrent was
+    /// parsed from a file. The info-hashes are calculated from them.
+    ///
+    /// It's not updated when `info` changes: call
+    /// [`update_raw_info`](Torrent::update_raw_info) after changing `info` so
+    /// the info-hashes are calculated from the new values.
+    #[serde(skip)]
+    pub raw_info: Option<Vec<u8>>,
+    /// Byte range of the `pieces` in the parsed file, when they were not
+    /// loaded because of [`ParseOptions::skip_pieces`](crate::utils::parse_options::ParseOptions::skip_pieces).
+    #[serde(skip)]
+    pub pieces_span: Option<Range<usize>>,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub struct TorrentInfo {
-    pub name: String,
+    /// Name of the file or directory. It's usually UTF-8, but torrents made
+    /// on systems with legacy encodings may contain other bytes.
+    pub name: ByteString,
+    /// UTF-8 version of `name`, added by clients that store `name` in the
+    /// system's legacy encoding.
+    #[serde(default)]
+    #[serde(rename = "name.utf-8")]
+    pub name_utf8: Option<ByteString>,
     #[serde(default)]
     pub pieces: Option<ByteBuf>,
     #[serde(rename = "piece length")]
-    pub piece_length: i64,
+    pub piece_length: u64,
     #[serde(default)]
-    pub md5sum: Option<String>,
+    pub md5sum: Option<ByteString>,
     #[serde(default)]
-    pub length: Option<i64>,
+    pub length: Option<u64>,
     #[serde(default)]
     pub files: Option<Vec<TorrentFile>>,
     #[serde(default)]
     pub private: Option<u8>,
     #[serde(default)]
     pub path: Option<Vec<String>>,
+    /// BEP 30 root of the SHA-1 merkle tree of the pieces, which replaces
+    /// `pieces` in Merkle torrents (see [`crate::utils::merkle_torrent`]).
     #[serde(default)]
     #[serde(rename = "root hash")]
-    pub root_hash: Option<String>,
+    pub root_hash: Option<ByteBuf>,
     #[serde(default)]
-    pub source: Option<String>,
+    pub source: Option<ByteString>,
+    /// BEP 52 meta version. It's `2` for v2 and hybrid torrents.
+    #[serde(default)]
+    #[serde(rename = "meta version")]
+    pub meta_version: Option<i64>,
+    /// BEP 52 file tree, only present in v2 and hybrid torrents.
+    #[serde(default)]
+    #[serde(rename = "file tree")]
+    pub file_tree: Option<FileTree>,
+    /// Info dictionary keys not defined by the specifications the model
+    /// supports, for example `publisher-url` or `collections`.
+    #[serde(flatten)]
+    pub extra_fields: BTreeMap<String, BValue>,
 }
 
 impl Default for TorrentInfo {
     fn default() -> Self {
         Self {
-            name: String::new(),
+            name: ByteString::default(),
+            name_utf8: None,
             pieces: Some(ByteBuf::from(vec![])),
             piece_length: 0,
             md5sum: None,
@@ -66,6 +137,9 @@ impl Default for TorrentInfo {
             path: None,
             root_hash: None,
             source: None,
+            meta_version: None,
+            file_tree: None,
+            extra_fields: BTreeMap::new(),
         }
     }
 }
@@ -73,12 +147,177 @@ impl Default for TorrentInfo {
 #[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub struct TorrentNode(pub String, pub i64);
 
-#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TorrentFile {
-    pub path: Vec<String>,
-    pub length: i64,
+    /// Path components, with the same encoding caveats as
+    /// [`TorrentInfo::name`].
+    pub path: Vec<ByteString>,
+    /// UTF-8 version of `path`, see [`TorrentInfo::name_utf8`].
+    #[serde(default)]
+    #[serde(rename = "path.utf-8")]
+    pub path_utf8: Option<Vec<ByteString>>,
+    pub length: u64,
+    #[serde(default)]
+    pub md5sum: Option<ByteString>,
+    /// BEP 47 file attributes: `p` (padding), `x` (executable), `h` (hidden)
+    /// and `l` (symbolic link).
+    #[serde(default)]
+    pub attr: Option<String>,
+    /// BEP 47 SHA-1 of the whole file.
     #[serde(default)]
-    pub md5sum: Option<String>,
+    pub sha1: Option<ByteBuf>,
+    /// BEP 47 target of a symbolic link, relative to the torrent root.
+    #[serde(default)]
+    #[serde(rename = "symlink path")]
+    pub symlink_path: Option<Vec<ByteString>>,
+    /// File dictionary keys not defined by the specifications the model
+    /// supports, for example `mtime` or `ed2k`.
+    #[serde(flatten)]
+    pub extra_fields: BTreeMap<String, BValue>,
+}
+
+impl TorrentInfo {
+    /// It returns the name to show to users: `name.utf-8` if the torrent has
+    /// it, `name` otherwise.
+    ///
+    /// Use [`ByteString::decode_utf8`] to convert it to text and know whether
+    /// the conversion was lossy.
+    #[must_use]
+    pub fn display_name(&self) -> &ByteString {
+        self.name_utf8.as_ref().unwrap_or(&self.name)
+    }
+
+    /// It returns the size of the content without the padding files, taken
+    /// from the `file tree` for v2-only torrents. It saturates at `u64::MAX`
+    /// instead of overflowing.
+    #[must_use]
+    pub fn content_size(&self) -> u64 {
+        match (self.length, &self.files, &self.file_tree) {
+            (Some(length), _, _) => length,
+            (None, Some(files), _) => files
+                .iter()
+                .filter(|file| !file.is_padding())
+                .fold(0, |size, file| size.saturating_add(file.length)),
+            (None, None, Some(file_tree)) => file_tree.file_size(),
+            (None, None, None) => 0,
+        }
+    }
+
+    /// It returns the number of files without the padding files, one for
+    /// single-file torrents.
+    #[must_use]
+    pub fn file_count(&self) -> usize {
+        match (self.length, &self.files, &self.file_tree) {
+            (None, Some(files), _) => files.iter().filter(|file| !file.is_padding()).count(),
+            (None, None, Some(file_tree)) => file_tree.iter_files().count(),
+            _ => 1,
+        }
+    }
+
+    /// It returns the number of pieces: the hashes in `pieces`, or for v2-only
+    /// torrents the pieces of each file, as their files are aligned to pieces.
+    #[must_use]
+    pub fn piece_count(&self) -> u64 {
+        if let Some(pieces) = &self.pieces {
+            return pieces.len() as u64 / 20;
+        }
+
+        match &self.file_tree {
+            Some(file_tree) if self.piece_length > 0 => file_tree
+                .iter_files()
+                .map(|(_, file)| file.length.div_ceil(self.piece_length))
+                .sum(),
+            _ => 0,
+        }
+    }
+}
+
+/// A summary of the info dictionary, one `label: value` per line:
+///
+/// ```text
+/// name:         large.bin
+/// size:         5.00 GiB (5368709120 bytes)
+/// files:        1
+/// piece length: 4 MiB
+/// pieces:       1280
+/// private:      no
+/// ```
+impl fmt::Display for TorrentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_info_summary(f, self, self.display_name(), self.piece_count())
+    }
+}
+
+/// Writes the summary of the info dictionary with the name and the number
+/// of pieces, which the [`Torrent`] decodes and counts better.
+fn write_info_summary(
+    f: &mut dyn fmt::Write,
+    info: &TorrentInfo,
+    name: impl fmt::Display,
+    pieces: u64,
+) -> fmt::Result {
+    let size = info.content_size();
+    write_field(f, "name", name)?;
+    writeln!(f)?;
+    write_field(f, "size", format!("{} ({size} bytes)", format_size(size)))?;
+    writeln!(f)?;
+    write_field(f, "files", info.file_count())?;
+    writeln!(f)?;
+    write_field(f, "piece length", format_piece_length(info.piece_length))?;
+    writeln!(f)?;
+    write_field(f, "pieces", pieces)?;
+    writeln!(f)?;
+    write_field(
+        f,
+        "private",
+        if info.private == Some(1) { "yes" } else { "no" },
+    )
+}
+
+impl TorrentFile {
+    /// It returns the path to show to users: `path.utf-8` if the file has
+    /// it, `path` otherwise.
+    #[must_use]
+    pub fn display_path(&self) -> &[ByteString] {
+        self.path_utf8.as_deref().unwrap_or(&self.path)
+    }
+
+    /// It returns `true` for padding files, which only align the next file
+    /// to a piece boundary and are not part of the content.
+    ///
+    /// Besides the BEP 47 `p` attribute, it recognizes the names of the
+    /// padding files created by older clients (`_____padding_file_...`).
+    #[must_use]
+    pub fn is_padding(&self) -> bool {
+        self.has_attr('p')
+            || self
+                .path
+                .last()
+                .is_some_and(|name| name.as_bytes().starts_with(b"_____padding_file_"))
+    }
+
+    /// It returns `true` if the file has the BEP 47 `x` attribute.
+    #[must_use]
+    pub fn is_executable(&self) -> bool {
+        self.has_attr('x')
+    }
+
+    /// It returns `true` if the file has the BEP 47 `h` attribute.
+    #[must_use]
+    pub fn is_hidden(&self) -> bool {
+        self.has_attr('h')
+    }
+
+    /// It returns `true` if the file has the BEP 47 `l` attribute. The
+    /// target is in `symlink_path`.
+    #[must_use]
+    pub fn is_symlink(&self) -> bool {
+        self.has_attr('l')
+    }
+
+    fn has_attr(&self, flag: char) -> bool {
+        self.attr.as_ref().is_some_and(|attr| attr.contains(flag))
+    }
 }
 
 impl Default for Torrent {
@@ -93,20 +332,138 @@ impl Default for Torrent {
             nodes: None,
             encoding: None,
             httpseeds: None,
+            url_list: None,
+            piece_layers: None,
+            signatures: None,
+            extra_fields: BTreeMap::new(),
+            raw_info: None,
+            pieces_span: None,
         }
     }
 }
 
+/// The summary of [`Torrent::write_summary`], with the text decoded with the
+/// encoding declared in the torrent.
+impl fmt::Display for Torrent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_summary(f, None)
+    }
+}
+
+impl Torrent {
+    /// It writes the summary printed by the `info` command: the info-hashes
+    /// and protocol version, the [summary of the info
+    /// dictionary](TorrentInfo#impl-Display-for-TorrentInfo), the trackers
+    /// by tier, the creation date in RFC 3339 format, the creator and the
+    /// comment. The name, the creator and the comment are decoded like
+    /// [`Torrent::decode_text`] does.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if `f` fails to write.
+    pub fn write_summary(&self, f: &mut dyn fmt::Write, charset: Option<&str>) -> fmt::Result {
+        let hashes = self.info_hashes();
+        if let Some(v1) = hashes.v1 {
+            write_field(f, "info hash", v1)?;
+            writeln!(f)?;
+        }
+        if let Some(v2) = hashes.v2 {
+            write_field(f, "info hash v2", v2)?;
+            writeln!(f)?;
+        }
+        write_field(f, "version", hashes.version)?;
+        writeln!(f)?;
+        write_info_summary(
+            f,
+            &self.info,
+            &self.decode_name(charset).0,
+            self.piece_count(),
+        )?;
+
+        for (index, tier) in self.announce_tiers().tiers().iter().enumerate() {
+            writeln!(f)?;
+            write_field(f, &format!("tier {}", index + 1), tier.join(" "))?;
+        }
+        if let Some(date) = self.creation_date {
+            writeln!(f)?;
+            write_field(
+                f,
+                "created",
+                rfc3339(date).unwrap_or_else(|| date.to_string()),
+            )?;
+        }
+        if let Some(created_by) = &self.created_by {
+            writeln!(f)?;
+            write_field(f, "created by", self.decode_text(created_by, charset).0)?;
+        }
+        if let Some(comment) = &self.comment {
+            writeln!(f)?;
+            write_field(f, "comment", self.decode_text(comment, charset).0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a `label: value` line of a summary, with the values aligned.
+fn write_field(f: &mut dyn fmt::Write, label: &str, value: impl fmt::Display) -> fmt::Result {
+    write!(f, "{:<14}{value}", format!("{label}:"))
+}
+
+/// `BitTorrent` protocol versions a torrent can be used with.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ProtocolVersion {
+    /// BEP 3 torrent.
+    V1,
+    /// BEP 52 torrent.
+    V2,
+    /// Torrent with both v1 and v2 metadata describing the same content.
+    Hybrid,
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+            Self::Hybrid => "hybrid",
+        })
+    }
+}
+
+/// Error converting a torrent to a protocol version it doesn't have the
+/// metadata for. The content has to be hashed again instead.
+#[derive(Error, PartialEq, Eq, Debug, Clone, Copy)]
+#[error("a {from:?} torrent can't be converted to {to:?} without hashing the content again")]
+pub struct ConvertError {
+    pub from: ProtocolVersion,
+    pub to: ProtocolVersion,
+}
+
+/// The info-hashes of a torrent for the protocol versions it supports.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct InfoHashes {
+    pub version: ProtocolVersion,
+    /// SHA-1 of the info dictionary. `None` for v2-only torrents.
+    pub v1: Option<InfoHash>,
+    /// SHA-256 of the info dictionary. `None` for v1-only torrents.
+    pub v2: Option<InfoHashV2>,
+}
+
 impl Torrent {
     /// It calculates the info hash of the torrent file.
     ///
+    /// It hashes the original bytes of the info dictionary ([`Torrent::raw_info`])
+    /// if they are available. Otherwise it hashes the bencoded `info`, which
+    /// only covers the keys of the info dictionary the model supports: the
+    /// `extra_fields` of the info dictionary and its files are not included.
+    ///
     /// # Panics
     ///
     /// This function will panic if the `info` part of the torrent file cannot be serialized.
     #[must_use]
     pub fn calculate_info_hash_as_bytes(&self) -> [u8; 20] {
-        let info_bencoded =
-            ser::to_bytes(&self.info).expect("variable `info` was not able to be serialized.");
+        let info_bencoded = self.info_bencoded();
         let mut hasher = Sha1::new();
         hasher.update(info_bencoded);
         let sum_hex = hasher.finalize();
@@ -115,42 +472,1152 @@ impl Torrent {
         sum_bytes
     }
 
+    /// It calculates the v2 info hash (SHA-256) of the torrent file, from
+    /// the same bytes as [`Torrent::calculate_info_hash_as_bytes`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the `info` part of the torrent file cannot be serialized.
+    #[must_use]
+    pub fn calculate_info_hash_v2_as_bytes(&self) -> [u8; 32] {
+        let info_bencoded = self.info_bencoded();
+        let mut hasher = Sha256::new();
+        hasher.update(info_bencoded);
+        hasher.finalize().into()
+    }
+
+    /// Returns the original bytes of the info dictionary, or bencodes it
+    /// without the non-standard keys.
+    pub(crate) fn info_bencoded(&self) -> Cow<'_, [u8]> {
+        if let Some(raw_info) = &self.raw_info {
+            return Cow::Borrowed(raw_info);
+        }
+
+        let info = TorrentInfo {
+            files: self.info.files.as_ref().map(|files| {
+                files
+                    .iter()
+                    .map(|file| TorrentFile {
+                        extra_fields: BTreeMap::new(),
+                        ..file.clone()
+                    })
+                    .collect()
+            }),
+            extra_fields: BTreeMap::new(),
+            ..self.info.clone()
+        };
+        Cow::Owned(ser::to_bytes(&info).expect("variable `info` was not able to be serialized."))
+    }
+
+    /// It returns the v2 info hash, or `None` if the torrent doesn't contain
+    /// v2 metadata.
+    #[must_use]
+    pub fn info_hash_v2(&self) -> Option<InfoHashV2> {
+        self.has_v2_metadata()
+            .then(|| InfoHashV2(self.calculate_info_hash_v2_as_bytes()))
+    }
+
+    /// It returns the protocol versions the torrent supports.
+    ///
+    /// Torrents without `meta version` 2 and a `file tree` are considered v1
+    /// torrents.
+    #[must_use]
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        match (self.has_v1_metadata(), self.has_v2_metadata()) {
+            (true, true) => ProtocolVersion::Hybrid,
+            (false, true) => ProtocolVersion::V2,
+            _ => ProtocolVersion::V1,
+        }
+    }
+
+    /// It returns the info-hashes for all the protocol versions the torrent
+    /// supports.
+    #[must_use]
+    pub fn info_hashes(&self) -> InfoHashes {
+        let version = self.protocol_version();
+
+        InfoHashes {
+            version,
+            v1: (version != ProtocolVersion::V2).then(|| self.info_hash()),
+            v2: (version != ProtocolVersion::V1)
+                .then(|| InfoHashV2(self.calculate_info_hash_v2_as_bytes())),
+        }
+    }
+
+    /// It returns a [`TorrentSummary`], with the fields an index usually
+    /// stores and without the pieces and the file lists.
+    #[must_use]
+    pub fn summarize(&self) -> TorrentSummary {
+        TorrentSummary::from(self)
+    }
+
+    fn has_v1_metadata(&self) -> bool {
+        (self.info.pieces.is_some() || self.pieces_span.is_some())
+            && (self.info.length.is_some() || self.info.files.is_some())
+    }
+
+    fn has_v2_metadata(&self) -> bool {
+        self.info.meta_version == Some(2) && self.info.file_tree.is_some()
+    }
+
+    /// It returns the v1 info-hash, the SHA-1 of the info dictionary.
+    ///
+    /// Use [`Torrent::info_hashes`] to get only the info-hashes of the
+    /// protocol versions the torrent supports.
+    #[must_use]
+    pub fn info_hash(&self) -> InfoHash {
+        InfoHash(self.calculate_info_hash_as_bytes())
+    }
+
+    /// It returns the v1 info-hash as a lowercase hex string, like
+    /// `torrent.info_hash().to_string()`.
     #[must_use]
-    pub fn info_hash(&self) -> String {
-        // todo: return an InfoHash struct
-        from_bytes(&self.calculate_info_hash_as_bytes()).to_lowercase()
+    pub fn info_hash_hex(&self) -> String {
+        self.info_hash().to_hex_string()
     }
 
+    /// It returns the sum of the lengths of the files, including the padding
+    /// files. It saturates at `u64::MAX` instead of overflowing.
     #[must_use]
-    pub fn file_size(&self) -> i64 {
+    pub fn file_size(&self) -> u64 {
         match self.info.length {
             Some(length) => length,
-            None => match &self.info.files {
-                None => 0,
-                Some(files) => {
-                    let mut file_size = 0;
-                    for file in files {
-                        file_size += file.length;
-                    }
-                    file_size
+            None => self
+                .info
+                .files
+                .iter()
+                .flatten()
+                .fold(0, |size, file| size.saturating_add(file.length)),
+        }
+    }
+
+    /// It returns the files of a multi-file torrent without the padding
+    /// files (see [`TorrentFile::is_padding`]).
+    ///
+    /// It's empty for single-file torrents, whose only file is `info.name`.
+    #[must_use]
+    pub fn content_files(&self) -> Vec<&TorrentFile> {
+        self.info
+            .files
+            .iter()
+            .flatten()
+            .filter(|file| !file.is_padding())
+            .collect()
+    }
+
+    /// It returns the size of the content, like [`Torrent::file_size`] but
+    /// without the padding files, see [`TorrentInfo::content_size`].
+    #[must_use]
+    pub fn content_size(&self) -> u64 {
+        self.info.content_size()
+    }
+
+    /// It returns the number of pieces, like [`TorrentInfo::piece_count`],
+    /// also for the torrents parsed without their pieces, from the length
+    /// of [`Torrent::pieces_span`].
+    #[must_use]
+    pub fn piece_count(&self) -> u64 {
+        match (&self.info.pieces, &self.pieces_span) {
+            (None, Some(span)) => span.len() as u64 / 20,
+            _ => self.info.piece_count(),
+        }
+    }
+
+    /// It returns the path and length of each content file, without the
+    /// padding files. The path components are decoded like
+    /// [`Torrent::decode_path`] does and joined with `/`. The paths of
+    /// multi-file torrents start with the name, which is the name of the
+    /// root directory.
+    ///
+    /// The files of v2-only torrents are taken from the `file tree`.
+    #[must_use]
+    pub fn content_paths(&self, charset: Option<&str>) -> Vec<(String, u64)> {
+        let (name, _) = self.decode_name(charset);
+
+        if self.info.files.is_some() {
+            return self
+                .content_files()
+                .into_iter()
+                .map(|file| {
+                    let path = self.decode_path(file, charset).0.join("/");
+                    (format!("{name}/{path}"), file.length)
+                })
+                .collect();
+        }
+
+        if let Some(length) = self.info.length {
+            return vec![(name.into_owned(), length)];
+        }
+
+        let mut files = vec![];
+        if let Some(file_tree) = &self.info.file_tree {
+            match file_tree.0.iter().next() {
+                // A single file is at the root of the tree, with the name.
+                Some((file_name, FileTreeNode::File(file))) if file_tree.0.len() == 1 => {
+                    files.push((file_name.clone(), file.length));
                 }
-            },
+                _ => files.extend(
+                    file_tree
+                        .iter_files()
+                        .map(|(path, file)| (format!("{name}/{}", path.join("/")), file.length)),
+                ),
+            }
         }
+        files
     }
 
-    /// It returns the announce urls of the torrent file.
+    /// It returns the parts of the files that are in the piece `index`, as
+    /// `(file_index, offset, length)`: the position of the file in
+    /// `info.files` (`0` for single-file torrents), where the part starts in
+    /// the file and its length. Padding files are included, so the lengths
+    /// add up to the length of the piece.
     ///
-    /// # Panics
+    /// It's empty if the piece doesn't exist, and for v2-only torrents, whose
+    /// pieces belong to each file instead of the whole content.
+    #[must_use]
+    pub fn piece_span(&self, index: u32) -> Vec<(usize, u64, u64)> {
+        let piece_length = self.info.piece_length;
+        // Positions saturate, lengths near `u64::MAX` must not overflow.
+        let start = u64::from(index).saturating_mul(piece_length);
+        let end = start.saturating_add(piece_length);
+
+        let mut span = vec![];
+        let mut offset: u64 = 0;
+        for (file_index, length) in self.v1_file_lengths().into_iter().enumerate() {
+            let file_end = offset.saturating_add(length);
+            if file_end > start && offset < end {
+                let part_start = start.max(offset);
+                span.push((
+                    file_index,
+                    part_start - offset,
+                    end.min(file_end) - part_start,
+                ));
+            }
+            if file_end >= end {
+                break;
+            }
+            offset = file_end;
+        }
+        span
+    }
+
+    /// It returns the pieces with data of the file at position `file_index`
+    /// in `info.files` (`0` for single-file torrents). The first and last
+    /// ones may be shared with the neighbouring files.
+    ///
+    /// The range is empty for empty files, files that don't exist and the
+    /// files of v2-only torrents.
+    #[must_use]
+    pub fn file_pieces(&self, file_index: usize) -> Range<u32> {
+        let piece_length = self.info.piece_length;
+        let lengths = self.v1_file_lengths();
+        let Some(&length) = lengths.get(file_index) else {
+            return 0..0;
+        };
+        if piece_length == 0 {
+            return 0..0;
+        }
+
+        let offset = lengths[..file_index]
+            .iter()
+            .fold(0, |offset: u64, length| offset.saturating_add(*length));
+        let piece = |position: u64| u32::try_from(position / piece_length).unwrap_or(u32::MAX);
+        let first = piece(offset);
+
+        if length == 0 {
+            first..first
+        } else {
+            first..piece(offset.saturating_add(length - 1)).saturating_add(1)
+        }
+    }
+
+    /// Lengths of the files in the v1 content, including the padding files.
+    fn v1_file_lengths(&self) -> Vec<u64> {
+        match (self.info.length, &self.info.files) {
+            (Some(single), _) => vec![single],
+            (None, Some(files)) => files.iter().map(|file| file.length).collect(),
+            (None, None) => vec![],
+        }
+    }
+
+    /// It returns the web seeds of the torrent file.
     ///
-    /// This function will panic if both the `announce_list` and the `announce` are `None`.
+    /// `url-list` (BEP 19) and `httpseeds` (BEP 17) entries are merged,
+    /// normalized and de-duplicated. Invalid URLs are kept but flagged, so
+    /// callers can decide whether to report or drop them.
+    #[must_use]
+    pub fn web_seeds(&self) -> Vec<WebSeed> {
+        web_seed::merge(
+            self.url_list.as_deref().unwrap_or_default(),
+            self.httpseeds.as_deref().unwrap_or_default(),
+            &self.info.name.to_str_lossy(),
+            self.info.files.is_some(),
+        )
+    }
+
+    /// It returns the trackers of the torrent file, grouped in tiers.
+    #[must_use]
+    pub fn announce_tiers(&self) -> AnnounceTiers {
+        AnnounceTiers::new(self.announce.as_deref(), self.announce_list.as_deref())
+    }
+
+    /// It returns the announce urls of the torrent file. It's empty for
+    /// trackerless torrents.
+    #[deprecated(note = "use `Torrent::announce_tiers` instead, which keeps the tiers")]
     #[must_use]
     pub fn announce_urls(&self) -> Vec<String> {
-        match &self.announce_list {
-            Some(list) => list.clone().into_iter().flatten().collect::<Vec<String>>(),
-            None => vec![self
-                .announce
-                .clone()
-                .expect("variable `announce` should not be None")],
+        self.announce_tiers().all_urls()
+    }
+
+    /// It returns a magnet link for the torrent.
+    ///
+    /// The link contains the info-hashes (`xt`), the name (`dn`), all the
+    /// trackers (`tr`) and the valid web seeds (`ws`). v2 info-hashes are
+    /// encoded as SHA-256 multihashes (`urn:btmh:1220...`).
+    #[must_use]
+    pub fn magnet_uri(&self) -> String {
+        MagnetLink::from(self).to_string()
+    }
+
+    /// It decodes text from the torrent, like the `comment`, with the
+    /// `charset` encoding or, if it's `None`, with the one declared in the
+    /// `encoding` key. See [`charset::decode`].
+    ///
+    /// It also returns whether the conversion was lossy.
+    #[must_use]
+    pub fn decode_text<'a>(
+        &self,
+        text: &'a ByteString,
+        charset: Option<&str>,
+    ) -> (Cow<'a, str>, bool) {
+        charset::decode(text.as_bytes(), charset.or(self.encoding.as_deref()))
+    }
+
+    /// It decodes the name to show to users. `name.utf-8` is always UTF-8,
+    /// `name` is decoded like [`Torrent::decode_text`] does.
+    #[must_use]
+    pub fn decode_name(&self, charset: Option<&str>) -> (Cow<'_, str>, bool) {
+        match &self.info.name_utf8 {
+            Some(name_utf8) => name_utf8.decode_utf8(),
+            None => self.decode_text(&self.info.name, charset),
         }
     }
+
+    /// It decodes the path of a file of the torrent, like
+    /// [`Torrent::decode_name`] does.
+    #[must_use]
+    pub fn decode_path<'a>(
+        &self,
+        file: &'a TorrentFile,
+        charset: Option<&str>,
+    ) -> (Vec<Cow<'a, str>>, bool) {
+        let decoded: Vec<_> = match &file.path_utf8 {
+            Some(path_utf8) => path_utf8.iter().map(ByteString::decode_utf8).collect(),
+            None => file
+                .path
+                .iter()
+                .map(|component| self.decode_text(component, charset))
+                .collect(),
+        };
+
+        let lossy = decoded.iter().any(|(_, lossy)| *lossy);
+        (decoded.into_iter().map(|(text, _)| text).collect(), lossy)
+    }
+
+    /// It bencodes the torrent, so it can be written back to a `.torrent`
+    /// file.
+    ///
+    /// Dictionary keys are sorted, as the specification requires, and the
+    /// non-standard keys kept in the `extra_fields` are included. The info
+    /// dictionary is bencoded from the model, not copied from
+    /// [`Torrent::raw_info`], so a file whose info keys were not sorted gets
+    /// a different info-hash.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the torrent cannot be bencoded.
+    pub fn encode(&self) -> Result<Vec<u8>, serde_bencode::Error> {
+        ser::to_bytes(self)
+    }
+
+    /// It bencodes the info dictionary again into [`Torrent::raw_info`], so
+    /// that the info-hashes are the ones of the torrent [`Torrent::encode`]
+    /// writes. Call it after modifying the `info`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the info dictionary cannot be bencoded.
+    pub fn update_raw_info(&mut self) -> Result<(), serde_bencode::Error> {
+        self.raw_info = Some(ser::to_bytes(&self.info)?);
+        Ok(())
+    }
+
+    /// It returns the torrent with a different `source` key in the info
+    /// dictionary, and its info-hashes updated.
+    ///
+    /// Private trackers require a `source` of their own, so that the same
+    /// content can be cross-seeded on several trackers with a different
+    /// info-hash on each.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the `info` part of the torrent file cannot be serialized.
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.info.source = Some(ByteString::from(source));
+        self.update_raw_info()
+            .expect("variable `info` was not able to be serialized.");
+        self
+    }
+
+    /// It converts a hybrid torrent to a v1-only or v2-only torrent, by
+    /// removing the metadata of the other version, and updates its
+    /// info-hashes. The padding files of the v1 metadata are kept.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the torrent doesn't have the metadata of
+    /// `version`, like a v1 torrent converted to v2.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the `info` part of the torrent file cannot be serialized.
+    pub fn into_version(mut self, version: ProtocolVersion) -> Result<Self, ConvertError> {
+        let from = self.protocol_version();
+        if from == version {
+            return Ok(self);
+        }
+        if from != ProtocolVersion::Hybrid {
+            return Err(ConvertError { from, to: version });
+        }
+
+        match version {
+            ProtocolVersion::V1 => {
+                self.info.meta_version = None;
+                self.info.file_tree = None;
+                self.piece_layers = None;
+            }
+            ProtocolVersion::V2 => {
+                self.info.pieces = None;
+                self.info.files = None;
+                self.info.length = None;
+            }
+            ProtocolVersion::Hybrid => unreachable!("hybrid torrents were returned unchanged"),
+        }
+
+        self.update_raw_info()
+            .expect("variable `info` was not able to be serialized.");
+        Ok(self)
+    }
+
+    /// It removes the keys that can identify who created the torrent before
+    /// sharing it: `comment`, `created by`, `creation date` and the unknown
+    /// keys outside the info dictionary. The info-hash doesn't change.
+    ///
+    /// Returns the removed keys.
+    pub fn scrub(&mut self) -> Vec<String> {
+        let mut removed = vec![];
+
+        if self.comment.take().is_some() {
+            removed.push("comment".to_string());
+        }
+        if self.created_by.take().is_some() {
+            removed.push("created by".to_string());
+        }
+        if self.creation_date.take().is_some() {
+            removed.push("creation date".to_string());
+        }
+        removed.extend(std::mem::take(&mut self.extra_fields).into_keys());
+
+        removed
+    }
+
+    /// It adds a tracker in its own tier, after the existing ones. It's the
+    /// `announce` URL if the torrent has no trackers.
+    ///
+    /// Returns `false` if the torrent already had the tracker.
+    pub fn add_tracker(&mut self, url: &str) -> bool {
+        let Some(announce) = &self.announce else {
+            if self.announce_list.is_none() {
+                self.announce = Some(url.to_owned());
+                return true;
+            }
+            return self.push_tier(url);
+        };
+
+        if self.announce_list.is_none() {
+            if announce == url {
+                return false;
+            }
+            self.announce_list = Some(vec![vec![announce.clone()]]);
+        }
+        self.push_tier(url)
+    }
+
+    fn push_tier(&mut self, url: &str) -> bool {
+        let tiers = self.announce_list.get_or_insert_with(Vec::new);
+        if tiers.iter().flatten().any(|tracker| tracker == url) {
+            return false;
+        }
+        tiers.push(vec![url.to_owned()]);
+        true
+    }
+
+    /// It replaces the trackers for which `matches` returns `true` with
+    /// `replacement`, in the `announce` key and the `announce-list`. If that
+    /// leaves the same tracker twice, only the first one is kept.
+    ///
+    /// Returns the number of replaced URLs.
+    pub fn replace_trackers<F>(&mut self, mut matches: F, replacement: &str) -> usize
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut replaced = 0;
+        let mut replace = |url: &mut String| {
+            if url != replacement && matches(url) {
+                replacement.clone_into(url);
+                replaced += 1;
+            }
+        };
+
+        if let Some(announce) = &mut self.announce {
+            replace(announce);
+        }
+        if let Some(tiers) = &mut self.announce_list {
+            tiers.iter_mut().flatten().for_each(&mut replace);
+
+            let mut seen = std::collections::HashSet::new();
+            for tier in tiers.iter_mut() {
+                tier.retain(|url| seen.insert(url.clone()));
+            }
+            tiers.retain(|tier| !tier.is_empty());
+        }
+
+        replaced
+    }
+
+    /// It removes a tracker from the `announce` key and the `announce-list`.
+    /// Empty tiers are dropped, and the first remaining tracker becomes the
+    /// `announce` URL if it was the removed one.
+    ///
+    /// Returns `false` if the torrent didn't have the tracker.
+    pub fn remove_tracker(&mut self, url: &str) -> bool {
+        let mut removed = false;
+
+        if let Some(tiers) = &mut self.announce_list {
+            for tier in tiers.iter_mut() {
+                let len = tier.len();
+                tier.retain(|tracker| tracker != url);
+                removed |= tier.len() != len;
+            }
+            tiers.retain(|tier| !tier.is_empty());
+            if tiers.is_empty() {
+                self.announce_list = None;
+            }
+        }
+
+        if self.announce.as_deref() == Some(url) {
+            removed = true;
+            self.announce = self
+                .announce_list
+                .as_ref()
+                .and_then(|tiers| tiers.iter().flatten().next().cloned());
+        }
+
+        removed
+    }
+}
+
+/// The `url-list` key can be either a single string or a list of strings.
+fn deserialize_string_or_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct StringOrList;
+
+    impl<'de> Visitor<'de> for StringOrList {
+        type Value = Option<Vec<String>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(formatter, "a string or a list of strings")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(Some(vec![v.to_owned()]))
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(Some(vec![String::from_utf8_lossy(v).into_owned()]))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut list = vec![];
+            while let Some(item) = seq.next_element::<ByteBuf>()? {
+                list.push(String::from_utf8_lossy(&item).into_owned());
+            }
+            Ok(Some(list))
+        }
+    }
+
+    deserializer.deserialize_any(StringOrList)
+}
+
+/// A `url-list` with a single URL is written as a string, like most torrent
+/// creators do.
+fn serialize_string_or_list<S>(
+    url_list: &Option<Vec<String>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match url_list.as_deref() {
+        None => serializer.serialize_none(),
+        Some([url]) => serializer.serialize_str(url),
+        Some(urls) => serializer.serialize_some(urls),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bencode::value::Value as BValue;
+
+    use super::{ConvertError, ProtocolVersion, Torrent};
+    use crate::parse_torrent;
+    use crate::utils::parse_torrent::decode_torrent;
+    use crate::utils::{bencode, parse_torrent_verbose};
+
+    #[test]
+    fn it_should_only_calculate_the_v1_info_hash_for_v1_torrents() {
+        let torrent =
+            decode_torrent(&std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap()).unwrap();
+
+        let info_hashes = torrent.info_hashes();
+
+        assert_eq!(info_hashes.version, ProtocolVersion::V1);
+        assert_eq!(info_hashes.v1, Some(torrent.info_hash()));
+        assert_eq!(info_hashes.v2, None);
+        assert_eq!(torrent.info_hash_hex(), torrent.info_hash().to_string());
+        assert_eq!(torrent.info_hash_v2(), None);
+    }
+
+    #[test]
+    fn it_should_keep_the_non_standard_keys_in_the_extra_fields() {
+        let bytes = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa9:publisher3:fooe12:x_cross_seed3:bare";
+
+        let torrent = decode_torrent(bytes).unwrap();
+
+        assert_eq!(
+            torrent.extra_fields.get("x_cross_seed"),
+            Some(&BValue::Bytes(b"bar".to_vec()))
+        );
+        assert_eq!(
+            torrent.info.extra_fields.get("publisher"),
+            Some(&BValue::Bytes(b"foo".to_vec()))
+        );
+        assert_eq!(serde_bencode::to_bytes(&torrent).unwrap(), bytes);
+    }
+
+    #[test]
+    fn it_should_parse_the_same_torrent_after_encoding_the_fixtures() {
+        let mut fixtures = 0;
+
+        for dir in ["torrents", "tests/fixtures/torrents"] {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path
+                    .extension()
+                    .is_none_or(|extension| extension != "torrent")
+                {
+                    continue;
+                }
+
+                let bytes = std::fs::read(&path).unwrap();
+                // Some fixtures show torrents the serde parser can't decode.
+                let Ok(torrent) = parse_torrent(&bytes) else {
+                    continue;
+                };
+
+                let encoded = torrent.encode().unwrap();
+                assert_eq!(encoded, bytes, "{}", path.display());
+                let reparsed = parse_torrent(&encoded).unwrap();
+
+                assert_eq!(
+                    Torrent {
+                        raw_info: None,
+                        ..reparsed.clone()
+                    },
+                    Torrent {
+                        raw_info: None,
+                        ..torrent.clone()
+                    },
+                    "{}",
+                    path.display()
+                );
+                assert_eq!(
+                    reparsed.info_hash(),
+                    torrent.info_hash(),
+                    "{}",
+                    path.display()
+                );
+                assert_eq!(reparsed.encode().unwrap(), encoded, "{}", path.display());
+
+                fixtures += 1;
+            }
+        }
+
+        assert!(fixtures >= 3);
+    }
+
+    #[test]
+    fn it_should_encode_the_keys_sorted() {
+        let torrent = parse_torrent(
+            b"d4:infod4:name1:a6:lengthi1e6:pieces20:aaaaaaaaaaaaaaaaaaaa12:piece lengthi16384ee1:zi1e8:announce4:http1:ai2ee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            torrent.encode().unwrap(),
+            b"d1:ai2e8:announce4:http4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae1:zi1ee"
+        );
+    }
+
+    #[test]
+    fn it_should_prefer_the_utf8_names_and_paths() {
+        let torrent = decode_torrent(
+            b"d4:infod5:filesld6:lengthi1e4:pathl1:ae10:path.utf-8l1:beed6:lengthi1e4:pathl1:ceee4:name1:d10:name.utf-81:e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        )
+        .unwrap();
+
+        assert_eq!(torrent.info.name, "d");
+        assert_eq!(torrent.info.display_name(), "e");
+
+        let files = torrent.info.files.unwrap();
+        assert_eq!(files[0].path, ["a"]);
+        assert_eq!(files[0].display_path(), ["b"]);
+        assert_eq!(files[1].display_path(), ["c"]);
+    }
+
+    #[test]
+    fn it_should_keep_the_bytes_of_non_utf8_names_and_paths() {
+        // GBK encoded names.
+        let bytes = b"d7:comment2:\xb0\xa14:infod5:filesld6:lengthi1e4:pathl2:\xb0\xa1eee4:name2:\xb0\xa112:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+
+        let torrent = decode_torrent(bytes).unwrap();
+
+        assert_eq!(torrent.info.name.as_bytes(), b"\xb0\xa1");
+        assert_eq!(
+            torrent.info.display_name().decode_utf8(),
+            ("\u{FFFD}\u{FFFD}".into(), true)
+        );
+        assert_eq!(
+            torrent.info.files.as_ref().unwrap()[0].path[0].as_bytes(),
+            b"\xb0\xa1"
+        );
+        assert_eq!(torrent.comment.as_ref().unwrap().as_bytes(), b"\xb0\xa1");
+        assert_eq!(torrent.encode().unwrap(), bytes);
+    }
+
+    #[test]
+    fn it_should_decode_the_names_with_the_declared_encoding() {
+        let torrent = decode_torrent(b"d8:encoding3:GBK4:infod5:filesld6:lengthi1e4:pathl2:\xb0\xa1eee4:name2:\xb0\xa112:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let file = &torrent.info.files.as_ref().unwrap()[0];
+
+        // The declared encoding is only honored with the `charset` feature.
+        let (name, lossy) = torrent.decode_name(None);
+        assert_eq!(cfg!(feature = "charset"), name == "\u{554a}" && !lossy);
+
+        assert_eq!(
+            torrent.decode_path(file, Some("UTF-8")),
+            (vec!["\u{FFFD}\u{FFFD}".into()], true)
+        );
+    }
+
+    #[test]
+    fn it_should_decode_the_bep_47_file_attributes() {
+        let bytes = b"d4:infod5:filesld4:attr1:x6:lengthi1e4:pathl1:ae4:sha120:bbbbbbbbbbbbbbbbbbbbed4:attr1:p6:lengthi16383e4:pathl4:.pad5:16383eed4:attr1:l6:lengthi0e4:pathl1:be12:symlink pathl1:aeed6:lengthi1e4:pathl24:_____padding_file_0_____eee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+
+        let torrent = decode_torrent(bytes).unwrap();
+        let files = torrent.info.files.as_ref().unwrap();
+
+        assert!(files[0].is_executable());
+        assert_eq!(files[0].sha1.as_ref().unwrap().as_slice(), [b'b'; 20]);
+        assert!(!files[0].is_padding());
+        assert!(files[1].is_padding());
+        assert!(files[2].is_symlink());
+        assert_eq!(files[2].symlink_path.as_ref().unwrap(), &["a"]);
+        assert!(files[3].is_padding());
+        assert_eq!(torrent.encode().unwrap(), bytes);
+    }
+
+    #[test]
+    fn it_should_exclude_the_padding_files_from_the_content() {
+        let torrent = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed4:attr1:p6:lengthi16383e4:pathl4:.pad5:16383eed6:lengthi2e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces40:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee").unwrap();
+
+        assert_eq!(torrent.file_size(), 16386);
+        assert_eq!(torrent.content_size(), 3);
+        assert_eq!(
+            torrent
+                .content_files()
+                .iter()
+                .map(|file| file.path[0].to_string())
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn it_should_list_the_paths_of_the_content_files() {
+        let multi_file = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed4:attr1:p6:lengthi16383e4:pathl4:.pad5:16383eed6:lengthi2e4:pathl3:dir1:beee4:name1:d12:piece lengthi16384e6:pieces40:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee").unwrap();
+        let v2_only = decode_torrent(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee12:meta versioni2e4:name1:a12:piece lengthi16384eee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            multi_file.content_paths(None),
+            [("d/a".to_owned(), 1), ("d/dir/b".to_owned(), 2)]
+        );
+        assert_eq!(v2_only.content_paths(None), [("a".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn it_should_map_pieces_to_files_and_files_to_pieces() {
+        // Files `a` (1 byte) and `dir/b` (2 bytes), with 16383 bytes of
+        // padding between them, in 16 KiB pieces.
+        let torrent = decode_torrent(b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed4:attr1:p6:lengthi16383e4:pathl4:.pad5:16383eed6:lengthi2e4:pathl3:dir1:beee4:name1:d12:piece lengthi16384e6:pieces40:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee").unwrap();
+
+        assert_eq!(torrent.piece_span(0), [(0, 0, 1), (1, 0, 16383)]);
+        assert_eq!(torrent.piece_span(1), [(2, 0, 2)]);
+        assert_eq!(torrent.piece_span(2), []);
+
+        assert_eq!(torrent.file_pieces(0), 0..1);
+        assert_eq!(torrent.file_pieces(1), 0..1);
+        assert_eq!(torrent.file_pieces(2), 1..2);
+        assert_eq!(torrent.file_pieces(3), 0..0);
+    }
+
+    #[test]
+    fn it_should_keep_the_form_of_the_url_list() {
+        for bytes in [
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae8:url-list14:http://a.io/a/e".as_slice(),
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae8:url-listl14:http://a.io/a/14:http://b.io/b/ee".as_slice(),
+        ] {
+            let torrent = decode_torrent(bytes).unwrap();
+
+            assert_eq!(torrent.encode().unwrap(), bytes);
+            assert!(torrent.magnet_uri().contains("&ws=http%3A%2F%2Fa.io%2Fa%2F"));
+        }
+    }
+
+    #[test]
+    fn it_should_decode_the_urls_of_the_url_list_that_are_not_utf8_lossily() {
+        for bytes in [
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae8:url-list13:http://a.io/\xffe".as_slice(),
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae8:url-listl13:http://a.io/\xff14:http://b.io/b/ee".as_slice(),
+        ] {
+            let serde = decode_torrent(bytes).unwrap();
+            let verbose =
+                parse_torrent_verbose::decode_torrent(bencode::decode(bytes).unwrap()).unwrap();
+
+            assert_eq!(serde.url_list.as_ref().unwrap()[0], "http://a.io/\u{fffd}");
+            assert_eq!(serde.url_list, verbose.url_list);
+        }
+    }
+
+    #[test]
+    fn it_should_add_and_remove_trackers() {
+        let mut torrent = decode_torrent(
+            b"d8:announce13:udp://t.io:804:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        )
+        .unwrap();
+
+        assert!(!torrent.add_tracker("udp://t.io:80"));
+        assert!(torrent.add_tracker("http://t2.io/an"));
+        assert_eq!(
+            torrent.announce_list,
+            Some(vec![
+                vec!["udp://t.io:80".to_string()],
+                vec!["http://t2.io/an".to_string()]
+            ])
+        );
+
+        assert!(torrent.remove_tracker("udp://t.io:80"));
+        assert_eq!(torrent.announce.as_deref(), Some("http://t2.io/an"));
+        assert_eq!(
+            torrent.announce_list,
+            Some(vec![vec!["http://t2.io/an".to_string()]])
+        );
+
+        assert!(torrent.remove_tracker("http://t2.io/an"));
+        assert!(!torrent.remove_tracker("http://t2.io/an"));
+        assert_eq!(torrent.announce, None);
+        assert_eq!(torrent.announce_list, None);
+    }
+
+    #[test]
+    fn it_should_update_the_info_hash_after_modifying_the_info() {
+        let bytes = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa9:publisher3:fooee";
+        let mut torrent = parse_torrent(bytes).unwrap();
+
+        torrent.info.private = Some(1);
+        torrent.update_raw_info().unwrap();
+
+        let reparsed = parse_torrent(&torrent.encode().unwrap()).unwrap();
+        assert_eq!(torrent.info_hashes(), reparsed.info_hashes());
+        assert_ne!(
+            torrent.info_hash(),
+            parse_torrent(bytes).unwrap().info_hash()
+        );
+    }
+
+    #[test]
+    fn it_should_keep_the_non_standard_keys_of_the_file_tree_when_updating_the_info_hash() {
+        let bytes = b"d4:infod9:file treed1:ad0:d6:lengthi1e5:mtimei1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee12:meta versioni2e4:name1:a12:piece lengthi16384eee";
+
+        for mut torrent in [
+            decode_torrent(bytes).unwrap(),
+            parse_torrent_verbose::decode_torrent(bencode::decode(bytes).unwrap()).unwrap(),
+        ] {
+            let original = torrent.info_hashes();
+
+            torrent.update_raw_info().unwrap();
+
+            assert_eq!(torrent.info_hashes(), original);
+            assert_eq!(torrent.encode().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn it_should_change_both_info_hashes_with_the_source() {
+        let bytes = b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee6:lengthi1e12:meta versioni2e4:name1:a12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbb6:source3:oldee";
+        let torrent = parse_torrent(bytes).unwrap();
+
+        let cross_seed = torrent.clone().with_source("new");
+
+        assert_eq!(cross_seed.info.source.as_ref().unwrap(), "new");
+        let (before, after) = (torrent.info_hashes(), cross_seed.info_hashes());
+        assert_ne!(before.v1, after.v1);
+        assert_ne!(before.v2, after.v2);
+        assert_eq!(
+            parse_torrent(&cross_seed.encode().unwrap())
+                .unwrap()
+                .info_hashes(),
+            after
+        );
+    }
+
+    #[test]
+    fn it_should_replace_trackers_and_drop_the_duplicates() {
+        let mut torrent = decode_torrent(
+            b"d8:announce16:http://old.io/an13:announce-listll16:http://old.io/an15:udp://keep.io:1el16:http://old.io/b2ee4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        )
+        .unwrap();
+
+        let replaced =
+            torrent.replace_trackers(|url| url.starts_with("http://old.io/"), "https://new.io/an");
+
+        assert_eq!(replaced, 3);
+        assert_eq!(torrent.announce.as_deref(), Some("https://new.io/an"));
+        assert_eq!(
+            torrent.announce_list,
+            Some(vec![vec![
+                "https://new.io/an".to_string(),
+                "udp://keep.io:1".to_string()
+            ]])
+        );
+    }
+
+    #[test]
+    fn it_should_convert_hybrid_torrents_to_a_single_version() {
+        let hybrid = decode_torrent(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee6:lengthi1e12:meta versioni2e4:name1:a12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbbee",
+        )
+        .unwrap();
+
+        let v1 = hybrid.clone().into_version(ProtocolVersion::V1).unwrap();
+        let v2 = hybrid.clone().into_version(ProtocolVersion::V2).unwrap();
+
+        assert_eq!(v1.protocol_version(), ProtocolVersion::V1);
+        assert_eq!(v1.info.file_tree, None);
+        assert_eq!(v2.protocol_version(), ProtocolVersion::V2);
+        assert_eq!((v2.info.pieces.as_ref(), v2.info.length), (None, None));
+        assert_ne!(v1.info_hash(), hybrid.info_hash());
+        assert_eq!(
+            v1.into_version(ProtocolVersion::V2),
+            Err(ConvertError {
+                from: ProtocolVersion::V1,
+                to: ProtocolVersion::V2
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_scrub_the_private_data_without_changing_the_info_hash() {
+        let mut torrent = parse_torrent(
+            b"d7:comment2:hi10:created by3:foo13:creation datei1e4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae7:x_owner2:mee",
+        )
+        .unwrap();
+        let info_hash = torrent.info_hash();
+
+        let removed = torrent.scrub();
+
+        assert_eq!(
+            removed,
+            ["comment", "created by", "creation date", "x_owner"]
+        );
+        assert_eq!(
+            parse_torrent(&torrent.encode().unwrap())
+                .unwrap()
+                .info_hash(),
+            info_hash
+        );
+        assert!(torrent.scrub().is_empty());
+    }
+
+    #[test]
+    fn it_should_generate_a_magnet_link_with_trackers_and_web_seeds() {
+        let torrent = decode_torrent(
+            b"d8:announce13:udp://t.io:8013:announce-listll13:udp://t.io:80el15:http://t2.io/anee4:infod6:lengthi1e4:name5:a b.c12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbbe8:url-list20:https://example.com/e",
+        )
+        .unwrap();
+
+        assert_eq!(
+            torrent.magnet_uri(),
+            format!(
+                "magnet:?xt=urn:btih:{}&dn=a%20b.c&tr=udp%3A%2F%2Ft.io%3A80&tr=http%3A%2F%2Ft2.io%2Fan&ws=https%3A%2F%2Fexample.com%2Fa%2520b.c",
+                torrent.info_hash()
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_calculate_both_info_hashes_for_hybrid_torrents() {
+        let torrent = decode_torrent(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee6:lengthi1e12:meta versioni2e4:name1:a12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbbee",
+        )
+        .unwrap();
+
+        let info_hashes = torrent.info_hashes();
+
+        assert_eq!(info_hashes.version, ProtocolVersion::Hybrid);
+        assert!(info_hashes.v1.is_some());
+        assert_eq!(info_hashes.v2, torrent.info_hash_v2());
+        assert!(info_hashes.v2.is_some());
+    }
+
+    #[test]
+    fn it_should_only_calculate_the_v2_info_hash_for_v2_torrents() {
+        let torrent = decode_torrent(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee12:meta versioni2e4:name1:a12:piece lengthi16384eee",
+        )
+        .unwrap();
+
+        let info_hashes = torrent.info_hashes();
+
+        assert_eq!(info_hashes.version, ProtocolVersion::V2);
+        assert_eq!(info_hashes.v1, None);
+        assert!(info_hashes.v2.is_some());
+    }
+
+    #[test]
+    fn it_should_display_a_summary_of_the_torrent() {
+        let torrent = decode_torrent(
+            b"d8:announce13:udp://t.io:807:comment2:hi10:created by3:foo13:creation datei1693571400e4:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi5e4:pathl1:beee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1eee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            torrent.to_string(),
+            format!(
+                "info hash:    {}
+version:      v1
+name:         dir
+size:         8 B (8 bytes)
+files:        2
+piece length: 16 KiB
+pieces:       1
+private:      yes
+tier 1:       udp://t.io:80
+created:      2023-09-01T12:30:00Z
+created by:   foo
+comment:      hi",
+                torrent.info_hash()
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_decode_the_text_of_the_summary_with_the_charset() {
+        let torrent = decode_torrent(b"d7:comment2:\xb0\xa14:infod6:lengthi1e4:name2:\xb0\xa112:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+
+        let mut summary = String::new();
+        torrent.write_summary(&mut summary, Some("GBK")).unwrap();
+
+        // The text is only decoded from GBK with the `charset` feature.
+        let text = torrent.decode_name(Some("GBK")).0;
+        assert!(summary.contains(&format!("\nname:         {text}\n")));
+        assert!(summary.ends_with(&format!("\ncomment:      {text}")));
+    }
+
+    #[test]
+    fn it_should_count_the_files_and_pieces_of_v2_torrents() {
+        let torrent = decode_torrent(
+            b"d4:infod9:file treed1:ad0:d6:lengthi20000e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee1:bd0:d6:lengthi1e11:pieces root32:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbeee12:meta versioni2e4:name3:dir12:piece lengthi16384eee",
+        )
+        .unwrap();
+
+        assert_eq!(torrent.info.content_size(), 20001);
+        assert_eq!(torrent.info.file_count(), 2);
+        assert_eq!(torrent.info.piece_count(), 3);
+        assert!(torrent.to_string().starts_with("info hash v2: "));
+    }
+
+    #[test]
+    fn it_should_parse_lengths_larger_than_4_gib() {
+        let bytes = std::fs::read("tests/fixtures/torrents/large-5gib.torrent").unwrap();
+
+        let torrent = parse_torrent(&bytes).unwrap();
+
+        assert_eq!(torrent.info.length, Some(5 * 1024 * 1024 * 1024));
+        assert_eq!(torrent.file_pieces(0), 0..1280);
+        assert_eq!(torrent.encode().unwrap(), bytes);
+    }
+
+    #[test]
+    fn it_should_not_overflow_with_lengths_near_the_largest_integer() {
+        let bytes = std::fs::read("tests/fixtures/torrents/pathological-sizes.torrent").unwrap();
+
+        let torrent = parse_torrent(&bytes).unwrap();
+        let verbose = crate::utils::parse_torrent_verbose::decode_torrent(
+            crate::utils::bencode::decode(&bytes).unwrap(),
+        )
+        .unwrap();
+
+        // Each file is `i64::MAX` bytes long, their sum only fits in a `u64`.
+        assert_eq!(torrent.file_size(), u64::MAX - 1);
+        assert_eq!(verbose.file_size(), torrent.file_size());
+        assert_eq!(torrent.file_pieces(1), 1..4);
+        assert_eq!(
+            torrent.piece_span(1),
+            vec![(0, 1 << 62, (1 << 62) - 1), (1, 0, 1)]
+        );
+        assert_eq!(torrent.encode().unwrap(), bytes);
+    }
+
+    #[test]
+    fn it_should_reject_negative_lengths() {
+        let bytes =
+            b"d4:infod6:lengthi-1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+
+        assert!(parse_torrent(bytes).is_err());
+        assert_eq!(
+            crate::utils::parse_torrent_verbose::decode_torrent(
+                crate::utils::bencode::decode(bytes).unwrap()
+            )
+            .unwrap_err()
+            .to_string(),
+            "unexpected value for `info.length`: found -1, expected a non-negative integer"
+        );
+    }
 }
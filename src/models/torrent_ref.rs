@@ -0,0 +1,428 @@
+//! A torrent that borrows its byte strings from the input instead of copying
+//! them.
+//!
+//! [`TorrentRef`] has the fields most tools read: the name, the pieces, the
+//! files, the trackers and the texts. Decoding it doesn't copy any byte
+//! string, so it's much cheaper than [`parse_torrent`](crate::parse_torrent)
+//! when processing many torrents. Use [`TorrentRef::to_owned`] to get the
+//! whole [`Torrent`].
+//!
+//! ```
+//! use torrust_parse_torrent::models::torrent_ref::TorrentRef;
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//!
+//! let torrent = TorrentRef::parse(&bytes).unwrap();
+//!
+//! assert_eq!(torrent.info.name, b"mandelbrot_set_01");
+//! assert_eq!(torrent.to_owned().unwrap().info.name, "mandelbrot_set_01");
+//! ```
+use std::ops::Range;
+
+use sha1::{Digest, Sha1};
+
+use crate::error::Error;
+use crate::models::info_hash::InfoHash;
+use crate::models::torrent_file::Torrent;
+use crate::utils::bencode_events::{self, Handler};
+use crate::utils::parse_options::ParseOptions;
+use crate::utils::parse_torrent_verbose::TorrentParseError;
+
+/// A torrent whose byte strings borrow from the input, see the
+/// [module](self) documentation.
+///
+/// The fields have the same meaning as the ones of [`Torrent`].
+#[derive(PartialEq, Eq, Debug)]
+pub struct TorrentRef<'a> {
+    pub info: TorrentInfoRef<'a>,
+    pub announce: Option<&'a [u8]>,
+    pub announce_list: Option<Vec<Vec<&'a [u8]>>>,
+    pub creation_date: Option<i64>,
+    pub comment: Option<&'a [u8]>,
+    pub created_by: Option<&'a [u8]>,
+    /// The original bytes of the `info` dictionary.
+    pub raw_info: Option<&'a [u8]>,
+    /// The whole input, for [`to_owned`](Self::to_owned).
+    bytes: &'a [u8],
+}
+
+/// The `info` dictionary of a [`TorrentRef`].
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct TorrentInfoRef<'a> {
+    pub name: &'a [u8],
+    pub pieces: Option<&'a [u8]>,
+    pub piece_length: u64,
+    pub length: Option<u64>,
+    pub files: Option<Vec<TorrentFileRef<'a>>>,
+    pub private: Option<u8>,
+    pub meta_version: Option<i64>,
+}
+
+/// A file of a multi-file [`TorrentRef`].
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct TorrentFileRef<'a> {
+    pub path: Vec<&'a [u8]>,
+    pub length: u64,
+}
+
+impl<'a> TorrentRef<'a> {
+    /// Decodes a torrent from its bencoded bytes, with the default limits.
+    ///
+    /// # Errors
+    ///
+    /// Will return an [`Error::Bencode`] if the bytes are not valid bencode,
+    /// or an [`Error::Parse`] if the torrent isn't a dictionary or one of the
+    /// fields of [`TorrentRef`] has an unexpected type or value.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_with_options(bytes, &ParseOptions::default())
+    }
+
+    /// Decodes a torrent like [`parse`](Self::parse), with the limits of the
+    /// options. The torrent is not validated, even in strict mode.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`parse`](Self::parse), and an [`Error::Bencode`] if the bytes
+    /// exceed one of the limits.
+    pub fn parse_with_options(bytes: &'a [u8], options: &ParseOptions) -> Result<Self, Error> {
+        let mut builder = TreeBuilder::default();
+        bencode_events::parse(bytes, options, &mut builder)?;
+
+        let Some(Value::Dict(dict)) = builder.root else {
+            return Err(unexpected_type("", builder.root.as_ref(), "dictionary").into());
+        };
+
+        let mut torrent = TorrentRef {
+            info: TorrentInfoRef::default(),
+            announce: None,
+            announce_list: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            raw_info: builder.info_span.map(|span| &bytes[span]),
+            bytes,
+        };
+
+        for (key, value) in dict {
+            match key {
+                b"info" => torrent.info = decode_info(value)?,
+                b"announce" => torrent.announce = Some(value.bytes("announce")?),
+                b"announce-list" => {
+                    torrent.announce_list = Some(
+                        value
+                            .list("announce-list")?
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, tier)| {
+                                string_list(tier, &format!("announce-list[{index}]"))
+                            })
+                            .collect::<Result<_, _>>()?,
+                    );
+                }
+                b"creation date" => torrent.creation_date = Some(value.int("creation date")?),
+                b"comment" => torrent.comment = Some(value.bytes("comment")?),
+                b"created by" => torrent.created_by = Some(value.bytes("created by")?),
+                _ => {}
+            }
+        }
+
+        Ok(torrent)
+    }
+
+    /// It returns the v1 info-hash, the SHA-1 of the original bytes of the
+    /// `info` dictionary, or `None` if the torrent doesn't have one.
+    #[must_use]
+    pub fn info_hash(&self) -> Option<InfoHash> {
+        self.raw_info
+            .map(|info| InfoHash(Sha1::digest(info).into()))
+    }
+
+    /// Parses the input again into an owned [`Torrent`], with all its
+    /// fields, including the ones `TorrentRef` doesn't have.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`parse_torrent`](crate::parse_torrent).
+    pub fn to_owned(&self) -> Result<Torrent, Error> {
+        crate::parse_torrent(self.bytes)
+    }
+}
+
+fn decode_info(value: Value<'_>) -> Result<TorrentInfoRef<'_>, TorrentParseError> {
+    let mut info = TorrentInfoRef::default();
+
+    for (key, value) in value.dict("info")? {
+        match key {
+            b"name" => info.name = value.bytes("info.name")?,
+            b"pieces" => info.pieces = Some(value.bytes("info.pieces")?),
+            b"piece length" => info.piece_length = value.length("info.piece length")?,
+            b"length" => info.length = Some(value.length("info.length")?),
+            b"files" => {
+                info.files = Some(
+                    value
+                        .list("info.files")?
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, file)| decode_file(file, &format!("info.files[{index}]")))
+                        .collect::<Result<_, _>>()?,
+                );
+            }
+            b"private" => {
+                info.private = match value.int("info.private")? {
+                    0 => Some(0),
+                    1 => Some(1),
+                    private => {
+                        return Err(TorrentParseError::UnexpectedValue {
+                            key: "info.private".to_owned(),
+                            found: private.to_string(),
+                            expected: "0 or 1",
+                        })
+                    }
+                };
+            }
+            b"meta version" => info.meta_version = Some(value.int("info.meta version")?),
+            _ => {}
+        }
+    }
+
+    Ok(info)
+}
+
+fn decode_file<'a>(value: Value<'a>, key: &str) -> Result<TorrentFileRef<'a>, TorrentParseError> {
+    let mut file = TorrentFileRef::default();
+
+    for (file_key, value) in value.dict(key)? {
+        match file_key {
+            b"path" => file.path = string_list(value, &format!("{key}.path"))?,
+            b"length" => file.length = value.length(&format!("{key}.length"))?,
+            _ => {}
+        }
+    }
+
+    Ok(file)
+}
+
+fn string_list<'a>(value: Value<'a>, key: &str) -> Result<Vec<&'a [u8]>, TorrentParseError> {
+    value
+        .list(key)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| item.bytes(&format!("{key}[{index}]")))
+        .collect()
+}
+
+fn unexpected_type(
+    key: &str,
+    value: Option<&Value<'_>>,
+    expected: &'static str,
+) -> TorrentParseError {
+    TorrentParseError::UnexpectedType {
+        key: key.to_owned(),
+        found: value.map_or("nothing", Value::type_name),
+        expected,
+    }
+}
+
+/// A bencoded value whose byte strings borrow from the input.
+enum Value<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    Dict(Vec<(&'a [u8], Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Bytes(_) => "byte string",
+            Value::List(_) => "list",
+            Value::Dict(_) => "dictionary",
+        }
+    }
+
+    fn int(self, key: &str) -> Result<i64, TorrentParseError> {
+        match self {
+            Value::Int(int) => Ok(int),
+            value => Err(unexpected_type(key, Some(&value), "integer")),
+        }
+    }
+
+    /// An integer that can't be negative.
+    fn length(self, key: &str) -> Result<u64, TorrentParseError> {
+        let int = self.int(key)?;
+        u64::try_from(int).map_err(|_| TorrentParseError::UnexpectedValue {
+            key: key.to_owned(),
+            found: int.to_string(),
+            expected: "a non-negative integer",
+        })
+    }
+
+    fn bytes(self, key: &str) -> Result<&'a [u8], TorrentParseError> {
+        match self {
+            Value::Bytes(bytes) => Ok(bytes),
+            value => Err(unexpected_type(key, Some(&value), "byte string")),
+        }
+    }
+
+    fn list(self, key: &str) -> Result<Vec<Value<'a>>, TorrentParseError> {
+        match self {
+            Value::List(list) => Ok(list),
+            value => Err(unexpected_type(key, Some(&value), "list")),
+        }
+    }
+
+    fn dict(self, key: &str) -> Result<Vec<(&'a [u8], Value<'a>)>, TorrentParseError> {
+        match self {
+            Value::Dict(dict) => Ok(dict),
+            value => Err(unexpected_type(key, Some(&value), "dictionary")),
+        }
+    }
+}
+
+/// A list or a dictionary being built, with the offset where it starts.
+enum Container<'a> {
+    List(usize, Vec<Value<'a>>),
+    Dict(usize, Vec<(&'a [u8], Value<'a>)>, Option<&'a [u8]>),
+}
+
+/// Builds the [`Value`] tree from the events of the decoder and finds the
+/// span of the top-level `info` dictionary.
+#[derive(Default)]
+struct TreeBuilder<'a> {
+    containers: Vec<Container<'a>>,
+    root: Option<Value<'a>>,
+    info_span: Option<Range<usize>>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn push(&mut self, value: Value<'a>, span: Range<usize>) {
+        let is_top_level = self.containers.len() == 1;
+        match self.containers.last_mut() {
+            None => self.root = Some(value),
+            Some(Container::List(_, list)) => list.push(value),
+            Some(Container::Dict(_, dict, key)) => {
+                // The decoder reports a key before each value.
+                let key = key.take().unwrap_or_default();
+                if is_top_level && key == b"info" {
+                    self.info_span = Some(span);
+                }
+                dict.push((key, value));
+            }
+        }
+    }
+}
+
+impl<'a> Handler<'a> for TreeBuilder<'a> {
+    fn on_int(&mut self, value: i64, span: Range<usize>) {
+        self.push(Value::Int(value), span);
+    }
+
+    fn on_bytes(&mut self, value: &'a [u8], span: Range<usize>) {
+        self.push(Value::Bytes(value), span);
+    }
+
+    fn on_list_start(&mut self, offset: usize) {
+        self.containers.push(Container::List(offset, vec![]));
+    }
+
+    fn on_list_end(&mut self, end: usize) {
+        if let Some(Container::List(start, list)) = self.containers.pop() {
+            self.push(Value::List(list), start..end);
+        }
+    }
+
+    fn on_dict_start(&mut self, offset: usize) {
+        self.containers.push(Container::Dict(offset, vec![], None));
+    }
+
+    fn on_key(&mut self, key: &'a [u8]) {
+        if let Some(Container::Dict(_, _, next_key)) = self.containers.last_mut() {
+            *next_key = Some(key);
+        }
+    }
+
+    fn on_dict_end(&mut self, end: usize) {
+        if let Some(Container::Dict(start, dict, _)) = self.containers.pop() {
+            self.push(Value::Dict(dict), start..end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TorrentFileRef, TorrentRef};
+    use crate::error::Error;
+    use crate::parse_torrent;
+    use crate::utils::parse_torrent_verbose::TorrentParseError;
+
+    #[test]
+    fn it_should_borrow_the_fields_from_the_input() {
+        let bytes = b"d8:announce3:url13:announce-listll1:a1:bel1:cee7:comment2:hi4:infod5:filesld6:lengthi3e4:pathl3:dir1:feee4:name4:root12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1eee";
+
+        let torrent = TorrentRef::parse(bytes).unwrap();
+
+        assert_eq!(torrent.announce, Some(&b"url"[..]));
+        assert_eq!(
+            torrent.announce_list,
+            Some(vec![vec![&b"a"[..], b"b"], vec![b"c"]])
+        );
+        assert_eq!(torrent.comment, Some(&b"hi"[..]));
+        assert_eq!(torrent.info.name, b"root");
+        assert_eq!(torrent.info.piece_length, 16384);
+        assert_eq!(torrent.info.private, Some(1));
+        assert_eq!(
+            torrent.info.files,
+            Some(vec![TorrentFileRef {
+                path: vec![b"dir", b"f"],
+                length: 3
+            }])
+        );
+
+        let pieces = torrent.info.pieces.unwrap();
+        assert!(bytes.as_ptr_range().contains(&pieces.as_ptr()));
+    }
+
+    #[test]
+    fn it_should_match_the_owned_torrent() {
+        for path in [
+            "torrents/mandelbrot_set_01.torrent",
+            "tests/fixtures/torrents/working-with-one-node.torrent",
+        ] {
+            let bytes = std::fs::read(path).unwrap();
+            let owned = parse_torrent(&bytes).unwrap();
+
+            let torrent = TorrentRef::parse(&bytes).unwrap();
+
+            assert_eq!(torrent.info.name, owned.info.name.as_bytes());
+            assert_eq!(
+                torrent.info.pieces,
+                owned.info.pieces.as_ref().map(|pieces| pieces.as_slice())
+            );
+            assert_eq!(torrent.info.length, owned.info.length);
+            assert_eq!(torrent.raw_info, owned.raw_info.as_deref());
+            assert_eq!(torrent.info_hash(), Some(owned.info_hash()));
+            assert_eq!(torrent.to_owned().unwrap(), owned);
+        }
+    }
+
+    #[test]
+    fn it_should_reject_fields_with_unexpected_types() {
+        assert!(matches!(
+            TorrentRef::parse(b"d4:infod4:namei1eee"),
+            Err(Error::Parse(TorrentParseError::UnexpectedType { key, found: "integer", .. }))
+                if key == "info.name"
+        ));
+        assert!(matches!(
+            TorrentRef::parse(b"le"),
+            Err(Error::Parse(TorrentParseError::UnexpectedType {
+                found: "list",
+                ..
+            }))
+        ));
+        assert!(matches!(
+            TorrentRef::parse(b"d4:info"),
+            Err(Error::Bencode(_))
+        ));
+    }
+}
@@ -0,0 +1,76 @@
+//! A compact summary of a torrent, for indexing many of them.
+//!
+//! A [`TorrentSummary`] only has the fields an index usually stores, so it
+//! doesn't keep the pieces or the file lists of the torrent in memory. Create
+//! it with [`Torrent::summarize`], from a torrent parsed with
+//! [`ParseOptions::skip_pieces`](crate::utils::parse_options::ParseOptions::skip_pieces)
+//! to never load the pieces:
+//!
+//! ```
+//! use torrust_parse_torrent::parse_torrent_with_options;
+//! use torrust_parse_torrent::utils::parse_options::ParseOptions;
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//! let options = ParseOptions::new().skip_pieces(true);
+//!
+//! let summary = parse_torrent_with_options(&bytes, &options).unwrap().summarize();
+//!
+//! assert_eq!(summary.name, "mandelbrot_set_01");
+//! assert_eq!(summary.file_count, 1);
+//! ```
+use crate::models::torrent_file::{InfoHashes, Torrent};
+
+/// The fields of a torrent an index usually stores.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TorrentSummary {
+    /// The name to show to users, see [`Torrent::decode_name`].
+    pub name: String,
+    pub info_hashes: InfoHashes,
+    /// The size of the content without the padding files.
+    pub size: u64,
+    /// The number of files without the padding files.
+    pub file_count: usize,
+    pub piece_length: u64,
+    pub private: bool,
+    /// The tracker URLs, tier after tier.
+    pub trackers: Vec<String>,
+}
+
+impl From<&Torrent> for TorrentSummary {
+    fn from(torrent: &Torrent) -> Self {
+        Self {
+            name: torrent.decode_name(None).0.into_owned(),
+            info_hashes: torrent.info_hashes(),
+            size: torrent.content_size(),
+            file_count: torrent.info.file_count(),
+            piece_length: torrent.info.piece_length,
+            private: torrent.info.private == Some(1),
+            trackers: torrent.announce_tiers().all_urls(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::parse_options::ParseOptions;
+    use crate::{parse_torrent, parse_torrent_with_options};
+
+    #[test]
+    fn it_should_summarize_a_torrent_parsed_without_its_pieces() {
+        let bytes = std::fs::read("tests/fixtures/torrents/working-with-one-node.torrent").unwrap();
+        let torrent = parse_torrent(&bytes).unwrap();
+
+        let summary = parse_torrent_with_options(&bytes, &ParseOptions::new().skip_pieces(true))
+            .unwrap()
+            .summarize();
+
+        assert_eq!(summary, torrent.summarize());
+        assert_eq!(summary.name, "minimal.txt");
+        assert_eq!(summary.info_hashes, torrent.info_hashes());
+        assert_eq!(summary.size, torrent.content_size());
+        assert_eq!(summary.file_count, 1);
+        assert_eq!(summary.piece_length, torrent.info.piece_length);
+        assert!(!summary.private);
+        assert_eq!(summary.trackers, torrent.announce_tiers().all_urls());
+    }
+}
@@ -0,0 +1,204 @@
+//! Web seeds declared in a torrent file.
+//!
+//! There are two web seeding extensions:
+//!
+//! - [BEP 19](https://www.bittorrent.org/beps/bep_0019.html) (`url-list`),
+//!   also known as `GetRight` style. The URL points to the content itself.
+//! - [BEP 17](https://www.bittorrent.org/beps/bep_0017.html) (`httpseeds`),
+//!   also known as Hoffman style. The URL points to a seeding script.
+use url::Url;
+
+/// The extension a web seed was declared with.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum WebSeedKind {
+    /// BEP 19 `url-list` entry.
+    UrlList,
+    /// BEP 17 `httpseeds` entry.
+    HttpSeed,
+}
+
+/// A normalized web seed URL.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WebSeed {
+    /// The normalized URL. For invalid entries it's the original value.
+    pub url: String,
+    pub kind: WebSeedKind,
+    /// The reason why the URL is not a usable web seed, if it isn't.
+    pub error: Option<String>,
+}
+
+impl WebSeed {
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Merges the `url-list` and `httpseeds` entries into a single list of
+/// normalized web seeds without duplicates.
+///
+/// `url-list` URLs follow the BEP 19 base-URL semantics:
+///
+/// - Multi-file torrents: the URL is the base directory, so it's normalized
+///   to end with a trailing slash. Clients append `name/path`.
+/// - Single-file torrents: a URL ending with a slash is a directory and the
+///   torrent name is appended to it. Otherwise the URL is the file itself.
+#[must_use]
+pub(crate) fn merge(
+    url_list: &[String],
+    httpseeds: &[String],
+    name: &str,
+    multi_file: bool,
+) -> Vec<WebSeed> {
+    let url_list_seeds = url_list
+        .iter()
+        .map(|url| normalize_url_list_entry(url, name, multi_file));
+
+    let httpseeds_seeds = httpseeds.iter().map(|url| normalize_http_seed(url));
+
+    let mut web_seeds: Vec<WebSeed> = vec![];
+
+    for web_seed in url_list_seeds.chain(httpseeds_seeds) {
+        if !web_seeds
+            .iter()
+            .any(|seed| seed.url == web_seed.url && seed.kind == web_seed.kind)
+        {
+            web_seeds.push(web_seed);
+        }
+    }
+
+    web_seeds
+}
+
+fn normalize_url_list_entry(url: &str, name: &str, multi_file: bool) -> WebSeed {
+    let mut parsed = match parse_http_url(url) {
+        Ok(parsed) => parsed,
+        Err(error) => return invalid(url, WebSeedKind::UrlList, error),
+    };
+
+    let is_directory = parsed.path().ends_with('/');
+
+    if multi_file && !is_directory {
+        parsed.set_path(&format!("{}/", parsed.path()));
+    } else if !multi_file && is_directory {
+        if let Ok(mut segments) = parsed.path_segments_mut() {
+            segments.pop_if_empty().push(name);
+        }
+    }
+
+    WebSeed {
+        url: parsed.to_string(),
+        kind: WebSeedKind::UrlList,
+        error: None,
+    }
+}
+
+fn normalize_http_seed(url: &str) -> WebSeed {
+    match parse_http_url(url) {
+        Ok(parsed) => WebSeed {
+            url: parsed.to_string(),
+            kind: WebSeedKind::HttpSeed,
+            error: None,
+        },
+        Err(error) => invalid(url, WebSeedKind::HttpSeed, error),
+    }
+}
+
+fn parse_http_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| e.to_string())?;
+
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(format!("unsupported scheme: {scheme}")),
+    }
+}
+
+fn invalid(url: &str, kind: WebSeedKind, error: String) -> WebSeed {
+    WebSeed {
+        url: url.to_owned(),
+        kind,
+        error: Some(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, WebSeedKind};
+
+    fn urls(list: &[&str]) -> Vec<String> {
+        list.iter().map(|url| (*url).to_string()).collect()
+    }
+
+    #[test]
+    fn it_should_add_a_trailing_slash_to_url_list_entries_of_multi_file_torrents() {
+        let web_seeds = merge(&urls(&["https://archive.org/download"]), &[], "name", true);
+
+        assert_eq!(web_seeds[0].url, "https://archive.org/download/");
+    }
+
+    #[test]
+    fn it_should_append_the_name_to_url_list_directories_of_single_file_torrents() {
+        let web_seeds = merge(
+            &urls(&["https://example.com/files/"]),
+            &[],
+            "my file.iso",
+            false,
+        );
+
+        assert_eq!(web_seeds[0].url, "https://example.com/files/my%20file.iso");
+    }
+
+    #[test]
+    fn it_should_keep_url_list_file_urls_of_single_file_torrents() {
+        let web_seeds = merge(
+            &urls(&["https://example.com/file.iso"]),
+            &[],
+            "file.iso",
+            false,
+        );
+
+        assert_eq!(web_seeds[0].url, "https://example.com/file.iso");
+    }
+
+    #[test]
+    fn it_should_drop_duplicate_web_seeds_after_normalization() {
+        let web_seeds = merge(
+            &urls(&["https://example.com/dir", "https://example.com/dir/"]),
+            &[],
+            "name",
+            true,
+        );
+
+        assert_eq!(web_seeds.len(), 1);
+    }
+
+    #[test]
+    fn it_should_merge_url_list_and_httpseeds_entries() {
+        let web_seeds = merge(
+            &urls(&["https://example.com/dir/"]),
+            &urls(&["http://example.com/seed.php"]),
+            "name",
+            true,
+        );
+
+        assert_eq!(web_seeds.len(), 2);
+        assert_eq!(web_seeds[0].kind, WebSeedKind::UrlList);
+        assert_eq!(web_seeds[1].kind, WebSeedKind::HttpSeed);
+    }
+
+    #[test]
+    fn it_should_flag_invalid_web_seed_urls() {
+        let web_seeds = merge(
+            &urls(&[
+                "magnet:?xt=urn:btih:HTIY74WT53EICID5ZROKLIWDUKR27ZDC",
+                "not a url",
+            ]),
+            &[],
+            "name",
+            false,
+        );
+
+        assert_eq!(web_seeds.len(), 2);
+        assert!(web_seeds.iter().all(|web_seed| !web_seed.is_valid()));
+    }
+}
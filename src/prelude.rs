@@ -0,0 +1,49 @@
+//! Stable public API of the crate.
+//!
+//! Import everything with:
+//!
+//! ```
+//! use torrust_parse_torrent::prelude::*;
+//! ```
+//!
+//! The items in this module are covered by semantic versioning.
+pub use crate::builder::{BuildError, TorrentBuilder};
+pub use crate::diff::{file_similarity, Change, CrossSeedCheck, Obstacle, TorrentDiff};
+pub use crate::error::Error;
+#[cfg(feature = "magnet")]
+pub use crate::metadata::{fetch_metadata, MetadataError};
+pub use crate::models::announce::{AnnounceTiers, TrackerKind, TrackerUrl, TrackerUrlError};
+pub use crate::models::byte_string::ByteString;
+pub use crate::models::file_tree::{FileTree, FileTreeFile, FileTreeNode};
+pub use crate::models::info_hash::{ConversionError, InfoHash};
+pub use crate::models::info_hash_v2::InfoHashV2;
+pub use crate::models::magnet_link::{MagnetLink, MagnetLinkError};
+pub use crate::models::piece_layers::PieceLayers;
+pub use crate::models::torrent_file::{
+    ConvertError, InfoHashes, ProtocolVersion, Torrent, TorrentFile, TorrentInfo, TorrentNode,
+};
+pub use crate::models::torrent_summary::TorrentSummary;
+pub use crate::models::web_seed::{WebSeed, WebSeedKind};
+pub use crate::progress::{NoProgress, Progress, ProgressHandler};
+#[cfg(feature = "scrape")]
+pub use crate::tracker::{announce, scrape, Announce, ScrapeStats, TrackerError, TrackerScrape};
+pub use crate::utils::canonical_json::{to_canonical_json, to_canonical_json_string};
+pub use crate::utils::decoder::{Backend, SerdeDecoder, TorrentDecoder, VerboseDecoder};
+pub use crate::utils::diagnostics::{Diagnostic, Severity};
+pub use crate::utils::json::to_json;
+pub use crate::utils::parse_mode::ParseMode;
+pub use crate::utils::parse_options::ParseOptions;
+pub use crate::utils::parse_torrent::{
+    calculate_info_hash, calculate_info_hash_v2, decode_torrent, encode_torrent,
+};
+pub use crate::utils::parse_torrent_verbose::{
+    decode_torrent as decode_torrent_verbose,
+    decode_torrent_with_diagnostics as decode_torrent_verbose_with_diagnostics,
+    decode_torrent_with_mode as decode_torrent_verbose_with_mode, TorrentParseError,
+};
+pub use crate::utils::validate::validate;
+pub use crate::verify::{
+    verify, verify_with_progress, FileReport, FileStatus, PieceStatus, Verifier, VerifyError,
+    VerifyReport,
+};
+pub use crate::{parse_torrent, parse_torrent_with_mode, parse_torrent_with_options};
@@ -0,0 +1,153 @@
+//! Progress of the operations that hash the content of a torrent, like
+//! creating it with the [`builder`](crate::builder).
+//!
+//! ```
+//! use torrust_parse_torrent::progress::{Progress, ProgressHandler};
+//!
+//! struct Log;
+//!
+//! impl ProgressHandler for Log {
+//!     fn on_progress(&self, progress: &Progress<'_>) {
+//!         eprintln!("{} of {} bytes hashed", progress.bytes, progress.total_bytes);
+//!     }
+//! }
+//! ```
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A snapshot of the progress of an operation.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// Bytes hashed so far.
+    pub bytes: u64,
+    /// Bytes to hash in total.
+    pub total_bytes: u64,
+    /// File being read, if any. When files are hashed in parallel, it's the
+    /// last one that was opened.
+    pub current_file: Option<&'a Path>,
+    /// Time since the operation started.
+    pub elapsed: Duration,
+}
+
+impl Progress<'_> {
+    /// Returns the estimated time left, assuming the rest of the content is
+    /// hashed at the same speed. `None` until some bytes have been hashed.
+    #[must_use]
+    pub fn eta(&self) -> Option<Duration> {
+        if self.bytes == 0 {
+            return None;
+        }
+        let left = self.total_bytes.saturating_sub(self.bytes);
+        Some(self.elapsed.mul_f64(left as f64 / self.bytes as f64))
+    }
+}
+
+/// Receives the progress of an operation.
+///
+/// It can be called from several threads, but never concurrently.
+pub trait ProgressHandler: Send + Sync {
+    /// Called once before the first byte is hashed.
+    fn on_start(&self, _total_bytes: u64) {}
+
+    /// Called when a file is opened and after each chunk of content is
+    /// hashed.
+    fn on_progress(&self, progress: &Progress<'_>);
+
+    /// Called once when the operation finishes successfully.
+    fn on_finish(&self, _progress: &Progress<'_>) {}
+}
+
+/// A handler that ignores the progress.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressHandler for NoProgress {
+    fn on_progress(&self, _progress: &Progress<'_>) {}
+}
+
+/// Counts the hashed bytes and reports them to a [`ProgressHandler`].
+pub(crate) struct ProgressTracker<'a> {
+    handler: &'a dyn ProgressHandler,
+    total_bytes: u64,
+    bytes: AtomicU64,
+    started: Instant,
+    /// Also serializes the calls to the handler.
+    current_file: Mutex<Option<PathBuf>>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub(crate) fn start(handler: &'a dyn ProgressHandler, total_bytes: u64) -> Self {
+        handler.on_start(total_bytes);
+        Self {
+            handler,
+            total_bytes,
+            bytes: AtomicU64::new(0),
+            started: Instant::now(),
+            current_file: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn open_file(&self, path: &Path) {
+        let mut current_file = self.lock();
+        *current_file = Some(path.to_path_buf());
+        self.handler
+            .on_progress(&self.progress(current_file.as_deref()));
+    }
+
+    pub(crate) fn advance(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        let current_file = self.lock();
+        self.handler
+            .on_progress(&self.progress(current_file.as_deref()));
+    }
+
+    pub(crate) fn finish(&self) {
+        let current_file = self.lock();
+        self.handler
+            .on_finish(&self.progress(current_file.as_deref()));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<PathBuf>> {
+        self.current_file
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn progress<'b>(&self, current_file: Option<&'b Path>) -> Progress<'b> {
+        Progress {
+            bytes: self.bytes.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes,
+            current_file,
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Progress;
+
+    #[test]
+    fn it_should_estimate_the_time_left_from_the_speed_so_far() {
+        let progress = Progress {
+            bytes: 25,
+            total_bytes: 100,
+            current_file: None,
+            elapsed: Duration::from_secs(10),
+        };
+
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+        assert_eq!(
+            Progress {
+                bytes: 0,
+                ..progress
+            }
+            .eta(),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Liveness checks, to find the dead trackers of old torrents.
+//!
+//! A tracker is alive if it answers:
+//!
+//! - HTTP trackers: a `HEAD` request to the announce URL gets any HTTP
+//!   response, even an error status. Trackers usually reject requests without
+//!   the announce parameters.
+//! - UDP trackers: a BEP 15 `connect` request gets a connection ID.
+//! - `WebTorrent` trackers: a TCP connection to the host is accepted.
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+
+use super::{for_each_tracker, resolve, udp, user_agent, TrackerError};
+use crate::models::announce::{TrackerKind, TrackerUrl};
+use crate::models::torrent_file::Torrent;
+
+/// How a live tracker answered.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Liveness {
+    /// Address that answered, if known.
+    pub addr: Option<SocketAddr>,
+    /// Time from the start of the check until the answer, including the name
+    /// resolution.
+    pub latency: Duration,
+    /// Status code of the response, for HTTP trackers.
+    pub http_status: Option<u16>,
+}
+
+/// The result of checking one of the trackers of a torrent.
+#[derive(Debug)]
+pub struct TrackerCheck {
+    /// Tier of the tracker in the `announce-list`.
+    pub tier: usize,
+    pub url: String,
+    pub result: Result<Liveness, TrackerError>,
+}
+
+/// Checks all the trackers of a torrent concurrently, and returns the
+/// results in the order of the tiers.
+///
+/// # Errors
+///
+/// Will return an error if the HTTP client can't be created. The errors of
+/// each tracker are in its [`TrackerCheck::result`].
+///
+/// # Panics
+///
+/// Will panic if it's not called from a Tokio runtime.
+pub async fn check(
+    torrent: &Torrent,
+    timeout: Duration,
+) -> Result<Vec<TrackerCheck>, TrackerError> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(user_agent())
+        .build()?;
+
+    let checks = for_each_tracker(torrent, move |url| {
+        let client = client.clone();
+        async move { check_tracker(&client, &url, timeout).await }
+    })
+    .await;
+
+    Ok(checks
+        .into_iter()
+        .map(|(tier, url, result)| TrackerCheck { tier, url, result })
+        .collect())
+}
+
+/// Checks whether a single tracker answers. The `client` is only used for
+/// HTTP trackers, and its own timeout applies to them.
+///
+/// # Errors
+///
+/// Will return an error if the URL is not a supported tracker URL, the host
+/// can't be resolved, or the tracker doesn't answer before the timeout.
+pub async fn check_tracker(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Duration,
+) -> Result<Liveness, TrackerError> {
+    let url = TrackerUrl::parse(url)?;
+    let started = Instant::now();
+    let deadline = started + timeout;
+
+    match url.kind() {
+        TrackerKind::Http => {
+            let response = client.head(url.url().clone()).send().await?;
+            Ok(Liveness {
+                addr: response.remote_addr(),
+                latency: started.elapsed(),
+                http_status: Some(response.status().as_u16()),
+            })
+        }
+        TrackerKind::Udp => {
+            let connection = udp::connect(&url, deadline).await?;
+            Ok(Liveness {
+                addr: connection.socket.peer_addr().ok(),
+                latency: started.elapsed(),
+                http_status: None,
+            })
+        }
+        TrackerKind::WebTorrent => {
+            let addr = timeout_at(deadline, connect_tcp(&url))
+                .await
+                .map_err(|_| TrackerError::Timeout)??;
+            Ok(Liveness {
+                addr: Some(addr),
+                latency: started.elapsed(),
+                http_status: None,
+            })
+        }
+    }
+}
+
+async fn connect_tcp(url: &TrackerUrl) -> Result<SocketAddr, TrackerError> {
+    let addr = resolve(url).await?;
+    TcpStream::connect(addr).await?;
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, UdpSocket};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::check_tracker;
+    use crate::tracker::TrackerError;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn it_should_report_a_live_websocket_tracker() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let liveness = block_on(check_tracker(
+            &reqwest::Client::new(),
+            &format!("ws://{addr}/"),
+            Duration::from_secs(5),
+        ))
+        .unwrap();
+
+        assert_eq!(liveness.addr, Some(addr));
+        assert_eq!(liveness.http_status, None);
+    }
+
+    #[test]
+    fn it_should_time_out_when_a_udp_tracker_does_not_answer() {
+        let tracker = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = tracker.local_addr().unwrap();
+        let silent = thread::spawn(move || {
+            let mut request = [0; 16];
+            tracker.recv_from(&mut request).unwrap();
+        });
+
+        let result = block_on(check_tracker(
+            &reqwest::Client::new(),
+            &format!("udp://{addr}"),
+            Duration::from_millis(200),
+        ));
+
+        assert!(matches!(result, Err(TrackerError::Timeout)));
+        silent.join().unwrap();
+    }
+}
@@ -0,0 +1,346 @@
+//! HTTP trackers: announce ([BEP 3](https://www.bittorrent.org/beps/bep_0003.html),
+//! with the compact peer lists of [BEP 23](https://www.bittorrent.org/beps/bep_0023.html))
+//! and the scrape convention ([BEP 48](https://www.bittorrent.org/beps/bep_0048.html)).
+//!
+//! The scrape URL is derived from the announce URL by replacing `announce` in
+//! the last path segment with `scrape`:
+//!
+//! ```text
+//! http://example.com/announce.php?key=1  ->  http://example.com/scrape.php?key=1
+//! ```
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use percent_encoding::percent_encode;
+use serde_bencode::value::Value as BValue;
+use url::Url;
+
+use super::{compact_peers_v4, compact_peers_v6, Announce, ScrapeStats, TrackerError};
+use crate::models::announce::TrackerUrl;
+use crate::models::info_hash::{InfoHash, URL_ENCODE_SET};
+
+/// Sends an announce request to an HTTP tracker and returns the peers.
+///
+/// # Errors
+///
+/// Will return an error if the request fails or the tracker answers with a
+/// failure.
+pub async fn announce(
+    client: &reqwest::Client,
+    url: &TrackerUrl,
+    announce: &Announce,
+) -> Result<Vec<SocketAddr>, TrackerError> {
+    let response = client
+        .get(announce_url(url.url(), announce))
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    parse_announce_response(&response)
+}
+
+/// Returns the announce URL with the query parameters of the request.
+#[must_use]
+pub fn announce_url(announce_url: &Url, announce: &Announce) -> Url {
+    let parameters = format!(
+        "info_hash={}&peer_id={}&port={}&uploaded=0&downloaded=0&left={}&compact=1&event=started",
+        announce.info_hash.to_url_encoded(),
+        percent_encode(&announce.peer_id, URL_ENCODE_SET),
+        announce.port,
+        announce.left,
+    );
+
+    let mut url = announce_url.clone();
+    let query = match announce_url.query() {
+        Some(query) if !query.is_empty() => format!("{query}&{parameters}"),
+        _ => parameters,
+    };
+    url.set_query(Some(&query));
+    url
+}
+
+/// Extracts the peers from a bencoded announce response. Both the compact
+/// and the dictionary peer lists are accepted.
+///
+/// # Errors
+///
+/// Will return an error if the response is not a bencoded dictionary or it
+/// has a `failure reason`.
+pub fn parse_announce_response(bytes: &[u8]) -> Result<Vec<SocketAddr>, TrackerError> {
+    let root = response_dict(bytes)?;
+
+    let mut peers = match root.get(b"peers".as_slice()) {
+        Some(BValue::Bytes(compact)) => compact_peers_v4(compact),
+        Some(BValue::List(peers)) => peers.iter().filter_map(dictionary_peer).collect(),
+        _ => vec![],
+    };
+
+    if let Some(BValue::Bytes(compact)) = root.get(b"peers6".as_slice()) {
+        peers.extend(compact_peers_v6(compact));
+    }
+
+    Ok(peers)
+}
+
+/// Decodes a `{ip, port}` peer. Peers with a host name instead of an address
+/// are skipped.
+fn dictionary_peer(peer: &BValue) -> Option<SocketAddr> {
+    let BValue::Dict(peer) = peer else {
+        return None;
+    };
+    let Some(BValue::Bytes(ip)) = peer.get(b"ip".as_slice()) else {
+        return None;
+    };
+    let Some(BValue::Int(port)) = peer.get(b"port".as_slice()) else {
+        return None;
+    };
+
+    let ip: IpAddr = std::str::from_utf8(ip).ok()?.parse().ok()?;
+    Some(SocketAddr::new(ip, u16::try_from(*port).ok()?))
+}
+
+/// Decodes a tracker response, returning an error for a `failure reason`.
+fn response_dict(bytes: &[u8]) -> Result<HashMap<Vec<u8>, BValue>, TrackerError> {
+    let value: BValue = serde_bencode::from_bytes(bytes)
+        .map_err(|e| TrackerError::InvalidResponse(e.to_string()))?;
+
+    let BValue::Dict(root) = value else {
+        return Err(TrackerError::InvalidResponse("not a dictionary".to_owned()));
+    };
+
+    if let Some(BValue::Bytes(reason)) = root.get(b"failure reason".as_slice()) {
+        return Err(TrackerError::Failure(
+            String::from_utf8_lossy(reason).into_owned(),
+        ));
+    }
+
+    Ok(root)
+}
+
+/// Sends a scrape request for one torrent to an HTTP tracker.
+///
+/// # Errors
+///
+/// Will return an error if the tracker has no scrape URL, the request fails,
+/// or the response doesn't contain the statistics of the torrent.
+pub async fn scrape(
+    client: &reqwest::Client,
+    url: &TrackerUrl,
+    info_hash: &InfoHash,
+) -> Result<ScrapeStats, TrackerError> {
+    let scrape_url = scrape_url(url.url(), info_hash).ok_or(TrackerError::NoScrapeUrl)?;
+
+    let response = client
+        .get(scrape_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    parse_response(&response, info_hash)
+}
+
+/// Returns the scrape URL for the announce URL, with the `info_hash`
+/// parameter. `None` if the tracker doesn't follow the convention.
+#[must_use]
+pub fn scrape_url(announce: &Url, info_hash: &InfoHash) -> Option<Url> {
+    let (dir, file) = announce.path().rsplit_once('/')?;
+    let rest = file.strip_prefix("announce")?;
+
+    let mut url = announce.clone();
+    url.set_path(&format!("{dir}/scrape{rest}"));
+
+    let info_hash = format!("info_hash={}", info_hash.to_url_encoded());
+    let query = match announce.query() {
+        Some(query) if !query.is_empty() => format!("{query}&{info_hash}"),
+        _ => info_hash,
+    };
+    url.set_query(Some(&query));
+
+    Some(url)
+}
+
+/// Extracts the statistics of the torrent from a bencoded scrape response.
+///
+/// # Errors
+///
+/// Will return an error if the response is not a bencoded dictionary, it has
+/// a `failure reason`, or it doesn't have an entry for the torrent.
+pub fn parse_response(bytes: &[u8], info_hash: &InfoHash) -> Result<ScrapeStats, TrackerError> {
+    let root = response_dict(bytes)?;
+
+    let Some(BValue::Dict(files)) = root.get(b"files".as_slice()) else {
+        return Err(TrackerError::InvalidResponse(
+            "missing `files` dictionary".to_owned(),
+        ));
+    };
+
+    let Some(BValue::Dict(file)) = files.get(info_hash.bytes().as_slice()) else {
+        return Err(TrackerError::UnknownTorrent);
+    };
+
+    let count = |key: &[u8]| match file.get(key) {
+        Some(BValue::Int(count)) => u64::try_from(*count).unwrap_or_default(),
+        _ => 0,
+    };
+
+    Ok(ScrapeStats {
+        seeders: count(b"complete"),
+        leechers: count(b"incomplete"),
+        completed: count(b"downloaded"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use url::Url;
+
+    use super::{announce_url, parse_announce_response, parse_response, scrape, scrape_url};
+    use crate::models::announce::TrackerUrl;
+    use crate::models::info_hash::InfoHash;
+    use crate::tracker::{Announce, ScrapeStats, TrackerError};
+
+    fn info_hash() -> InfoHash {
+        InfoHash([b'a'; 20])
+    }
+
+    fn response(entries: &str) -> Vec<u8> {
+        format!("d5:filesd20:{}d{entries}eee", "a".repeat(20)).into_bytes()
+    }
+
+    #[test]
+    fn it_should_replace_announce_with_scrape_in_the_last_path_segment() {
+        let scrape = |announce: &str| {
+            scrape_url(&Url::parse(announce).unwrap(), &InfoHash([0xff; 20])).map(String::from)
+        };
+
+        assert_eq!(
+            scrape("http://t.test/x/announce.php?key=1").unwrap(),
+            format!(
+                "http://t.test/x/scrape.php?key=1&info_hash={}",
+                "%FF".repeat(20)
+            )
+        );
+        assert_eq!(scrape("http://t.test/a"), None);
+        assert_eq!(scrape("http://t.test/announce/x"), None);
+    }
+
+    #[test]
+    fn it_should_parse_the_statistics_of_the_torrent() {
+        let stats = parse_response(
+            &response("8:completei5e10:downloadedi50e10:incompletei3e"),
+            &info_hash(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            stats,
+            ScrapeStats {
+                seeders: 5,
+                leechers: 3,
+                completed: 50
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_report_failures_and_unknown_torrents() {
+        assert!(matches!(
+            parse_response(b"d14:failure reason6:bannede", &info_hash()),
+            Err(TrackerError::Failure(reason)) if reason == "banned"
+        ));
+        assert!(matches!(
+            parse_response(&response(""), &InfoHash([b'b'; 20])),
+            Err(TrackerError::UnknownTorrent)
+        ));
+        assert!(matches!(
+            parse_response(b"le", &info_hash()),
+            Err(TrackerError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn it_should_scrape_an_http_tracker() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).unwrap();
+            let body = response("8:completei1e10:downloadedi2e10:incompletei3e");
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+            String::from_utf8_lossy(&request[..read]).into_owned()
+        });
+
+        let url = TrackerUrl::parse(&format!("http://127.0.0.1:{port}/announce")).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let stats = runtime
+            .block_on(scrape(&reqwest::Client::new(), &url, &info_hash()))
+            .unwrap();
+
+        assert_eq!(stats.completed, 2);
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with(&format!("GET /scrape?info_hash={} ", "a".repeat(20))));
+    }
+
+    #[test]
+    fn it_should_add_the_announce_parameters_to_the_query() {
+        let url = announce_url(
+            &Url::parse("http://t.test/announce?key=1").unwrap(),
+            &Announce {
+                info_hash: InfoHash([0xff; 20]),
+                peer_id: [b'p'; 20],
+                port: 6881,
+                left: 1,
+            },
+        );
+
+        let query = url.query().unwrap();
+        assert!(query.starts_with(&format!("key=1&info_hash={}&peer_id=", "%FF".repeat(20))));
+        assert!(query.contains("&port=6881&") && query.contains("&compact=1"));
+    }
+
+    #[test]
+    fn it_should_decode_compact_and_dictionary_peer_lists() {
+        let peers = |response: &[u8]| {
+            parse_announce_response(response)
+                .unwrap()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let mut compact = b"d5:peers6:".to_vec();
+        compact.extend([127, 0, 0, 1, 0x1a, 0xe1]);
+        compact.extend(b"6:peers618:");
+        compact.extend([0; 15]);
+        compact.extend([1, 0, 80]);
+        compact.push(b'e');
+        assert_eq!(peers(&compact), ["127.0.0.1:6881", "[::1]:80"]);
+
+        assert_eq!(
+            peers(b"d5:peersld2:ip8:10.0.0.14:porti80eed2:ip4:host4:porti1eeee"),
+            ["10.0.0.1:80"]
+        );
+    }
+}
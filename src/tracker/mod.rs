@@ -0,0 +1,259 @@
+//! Tracker clients, to ask the trackers of a torrent about its swarm.
+//!
+//! Requires the `scrape` feature. The scrape request returns the number of
+//! seeders, leechers and completed downloads without joining the swarm. The
+//! announce request returns the addresses of some peers, and the [`check`]
+//! module tells whether the trackers are still alive.
+//!
+//! ```no_run
+//! # async fn example(torrent: &torrust_parse_torrent::models::torrent_file::Torrent) {
+//! use std::time::Duration;
+//!
+//! use torrust_parse_torrent::tracker::scrape;
+//!
+//! for scrape in scrape(torrent, Duration::from_secs(10)).await.unwrap() {
+//!     match scrape.result {
+//!         Ok(stats) => println!("{}: {} seeders", scrape.url, stats.seeders),
+//!         Err(e) => println!("{}: {e}", scrape.url),
+//!     }
+//! }
+//! # }
+//! ```
+pub mod check;
+pub mod http;
+pub mod udp;
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::net::lookup_host;
+use tokio::task::JoinSet;
+
+use crate::models::announce::{TrackerKind, TrackerUrl, TrackerUrlError};
+use crate::models::info_hash::InfoHash;
+use crate::models::torrent_file::Torrent;
+
+/// Statistics of the swarm of a torrent, as reported by a tracker.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct ScrapeStats {
+    /// Peers with the complete content (`complete`).
+    pub seeders: u64,
+    /// Peers still downloading (`incomplete`).
+    pub leechers: u64,
+    /// Number of times the download was completed (`downloaded`).
+    pub completed: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum TrackerError {
+    #[error(transparent)]
+    InvalidUrl(#[from] TrackerUrlError),
+    #[error("{0} trackers are not supported")]
+    UnsupportedTracker(TrackerKind),
+    #[error(
+        "the tracker does not support scraping (the announce URL path must end with `announce`)"
+    )]
+    NoScrapeUrl,
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("tracker failure: {0}")]
+    Failure(String),
+    #[error("the tracker does not know the torrent")]
+    UnknownTorrent,
+    #[error("no address found for {0}")]
+    NoAddress(String),
+    #[error("timed out")]
+    Timeout,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// An announce request of a client that only wants to find peers: it
+/// doesn't upload or download anything.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Announce {
+    pub info_hash: InfoHash,
+    pub peer_id: [u8; 20],
+    /// Port the client would listen on.
+    pub port: u16,
+    /// Bytes left to download. Some trackers only return seeders to peers
+    /// that are not seeding, so it should not be 0.
+    pub left: u64,
+}
+
+/// The answer of one of the trackers of a torrent.
+#[derive(Debug)]
+pub struct TrackerScrape {
+    /// Tier of the tracker in the `announce-list`.
+    pub tier: usize,
+    pub url: String,
+    pub result: Result<ScrapeStats, TrackerError>,
+}
+
+/// Scrapes all the trackers of a torrent concurrently, and returns their
+/// answers in the order of the tiers.
+///
+/// # Errors
+///
+/// Will return an error if the HTTP client can't be created. The errors of
+/// each tracker are in its [`TrackerScrape::result`].
+///
+/// # Panics
+///
+/// Will panic if it's not called from a Tokio runtime.
+pub async fn scrape(
+    torrent: &Torrent,
+    timeout: Duration,
+) -> Result<Vec<TrackerScrape>, TrackerError> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(user_agent())
+        .build()?;
+
+    let info_hash = swarm_info_hash(torrent);
+
+    let scrapes = for_each_tracker(torrent, move |url| {
+        let client = client.clone();
+        async move { scrape_tracker(&client, &url, &info_hash, timeout).await }
+    })
+    .await;
+
+    Ok(scrapes
+        .into_iter()
+        .map(|(tier, url, result)| TrackerScrape { tier, url, result })
+        .collect())
+}
+
+fn user_agent() -> &'static str {
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"))
+}
+
+/// Runs `f` concurrently for each tracker of the torrent, and returns the
+/// tier, URL and result of each one in the order of the tiers.
+async fn for_each_tracker<F, Fut, T>(torrent: &Torrent, f: F) -> Vec<(usize, String, T)>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let trackers: Vec<(usize, String)> = torrent
+        .announce_tiers()
+        .iter()
+        .map(|(tier, url)| (tier, url.to_owned()))
+        .collect();
+
+    let mut tasks = JoinSet::new();
+    for (index, (_, url)) in trackers.iter().enumerate() {
+        let task = f(url.clone());
+        tasks.spawn(async move { (index, task.await) });
+    }
+
+    let mut results: Vec<Option<T>> = trackers.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+
+    trackers
+        .into_iter()
+        .zip(results)
+        .filter_map(|((tier, url), result)| Some((tier, url, result?)))
+        .collect()
+}
+
+/// Scrapes a single tracker. The `client` is only used for HTTP trackers,
+/// and its own timeout applies to them.
+///
+/// # Errors
+///
+/// Will return an error if the URL is not a supported tracker URL, the
+/// tracker can't be reached, or it doesn't return the statistics of the
+/// torrent.
+pub async fn scrape_tracker(
+    client: &reqwest::Client,
+    url: &str,
+    info_hash: &InfoHash,
+    timeout: Duration,
+) -> Result<ScrapeStats, TrackerError> {
+    let url = TrackerUrl::parse(url)?;
+
+    match url.kind() {
+        TrackerKind::Http => http::scrape(client, &url, info_hash).await,
+        TrackerKind::Udp => udp::scrape(&url, info_hash, timeout).await,
+        kind => Err(TrackerError::UnsupportedTracker(kind)),
+    }
+}
+
+/// Sends an announce request to a single tracker and returns the peers it
+/// knows. The `client` is only used for HTTP trackers, and its own timeout
+/// applies to them.
+///
+/// # Errors
+///
+/// Will return an error if the URL is not a supported tracker URL, the
+/// tracker can't be reached, or it answers with an error.
+pub async fn announce(
+    client: &reqwest::Client,
+    url: &str,
+    announce: &Announce,
+    timeout: Duration,
+) -> Result<Vec<SocketAddr>, TrackerError> {
+    let url = TrackerUrl::parse(url)?;
+
+    match url.kind() {
+        TrackerKind::Http => http::announce(client, &url, announce).await,
+        TrackerKind::Udp => udp::announce(&url, announce, timeout).await,
+        kind @ TrackerKind::WebTorrent => Err(TrackerError::UnsupportedTracker(kind)),
+    }
+}
+
+/// Returns the info-hash the trackers know the torrent by: the v1 one, or
+/// the truncated v2 one for v2-only torrents (BEP 52).
+#[must_use]
+pub fn swarm_info_hash(torrent: &Torrent) -> InfoHash {
+    let hashes = torrent.info_hashes();
+    hashes
+        .v1
+        .or_else(|| hashes.v2.map(|hash| hash.truncated()))
+        .unwrap_or_else(|| torrent.info_hash())
+}
+
+/// Returns the first address of the tracker host.
+pub(crate) async fn resolve(url: &TrackerUrl) -> Result<SocketAddr, TrackerError> {
+    lookup_host((url.host(), url.port()))
+        .await?
+        .next()
+        .ok_or_else(|| TrackerError::NoAddress(url.host().to_owned()))
+}
+
+/// Decodes a compact list of IPv4 peers: 4 bytes of address and 2 of port
+/// each (BEP 23).
+#[must_use]
+pub fn compact_peers_v4(bytes: &[u8]) -> Vec<SocketAddr> {
+    bytes
+        .chunks_exact(6)
+        .map(|peer| {
+            let ip: [u8; 4] = peer[..4].try_into().expect("4 bytes");
+            SocketAddr::from((ip, u16::from_be_bytes([peer[4], peer[5]])))
+        })
+        .collect()
+}
+
+/// Decodes a compact list of IPv6 peers: 16 bytes of address and 2 of port
+/// each (BEP 7).
+#[must_use]
+pub fn compact_peers_v6(bytes: &[u8]) -> Vec<SocketAddr> {
+    bytes
+        .chunks_exact(18)
+        .map(|peer| {
+            let ip: [u8; 16] = peer[..16].try_into().expect("16 bytes");
+            SocketAddr::from((ip, u16::from_be_bytes([peer[16], peer[17]])))
+        })
+        .collect()
+}
@@ -0,0 +1,438 @@
+//! UDP tracker protocol ([BEP 15](https://www.bittorrent.org/beps/bep_0015.html)).
+//!
+//! A scrape takes two round trips: a `connect` request returns a connection
+//! ID, which is then sent with the `scrape` request. Lost packets are sent
+//! again, waiting twice as long each time, until the timeout expires.
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+
+use super::{compact_peers_v4, compact_peers_v6, resolve, Announce, ScrapeStats, TrackerError};
+use crate::models::announce::TrackerUrl;
+use crate::models::info_hash::InfoHash;
+
+/// Magic constant that identifies a `connect` request.
+const PROTOCOL_ID: u64 = 0x0417_2710_1980;
+
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_SCRAPE: u32 = 2;
+const ACTION_ERROR: u32 = 3;
+
+/// Time to wait for the first answer before sending the request again.
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+
+/// Largest response expected: a scrape of a single torrent is 20 bytes, but
+/// error messages can be longer.
+const MAX_RESPONSE_LEN: usize = 2048;
+
+/// Sends a scrape request for one torrent to a UDP tracker.
+///
+/// # Errors
+///
+/// Will return an error if the host can't be resolved, the tracker doesn't
+/// answer before the timeout, or it answers with an error.
+pub async fn scrape(
+    url: &TrackerUrl,
+    info_hash: &InfoHash,
+    timeout: Duration,
+) -> Result<ScrapeStats, TrackerError> {
+    let deadline = Instant::now() + timeout;
+
+    let connection = connect(url, deadline).await?;
+
+    let transaction_id = connection.transaction_id.wrapping_add(1);
+    let request = scrape_request(connection.connection_id, transaction_id, info_hash);
+    let response = exchange(&connection.socket, &request, transaction_id, deadline).await?;
+
+    parse_scrape_response(&response, transaction_id)
+}
+
+/// Sends an announce request to a UDP tracker and returns the peers.
+///
+/// # Errors
+///
+/// Will return an error if the host can't be resolved, the tracker doesn't
+/// answer before the timeout, or it answers with an error.
+pub async fn announce(
+    url: &TrackerUrl,
+    announce: &Announce,
+    timeout: Duration,
+) -> Result<Vec<SocketAddr>, TrackerError> {
+    let deadline = Instant::now() + timeout;
+
+    let connection = connect(url, deadline).await?;
+
+    let transaction_id = connection.transaction_id.wrapping_add(1);
+    let request = announce_request(connection.connection_id, transaction_id, announce);
+    let response = exchange(&connection.socket, &request, transaction_id, deadline).await?;
+
+    let ipv6 = connection.socket.peer_addr()?.is_ipv6();
+    parse_announce_response(&response, transaction_id, ipv6)
+}
+
+/// A socket connected to a UDP tracker that accepted a `connect` request.
+pub(crate) struct Connection {
+    pub(crate) socket: UdpSocket,
+    pub(crate) connection_id: u64,
+    /// Transaction ID of the `connect` request.
+    transaction_id: u32,
+}
+
+/// Sends a `connect` request to the tracker.
+pub(crate) async fn connect(
+    url: &TrackerUrl,
+    deadline: Instant,
+) -> Result<Connection, TrackerError> {
+    let addr = timeout_at(deadline, resolve(url))
+        .await
+        .map_err(|_| TrackerError::Timeout)??;
+
+    let socket = UdpSocket::bind(unspecified_addr(&addr)).await?;
+    socket.connect(addr).await?;
+
+    let transaction_id = transaction_id();
+    let response = exchange(
+        &socket,
+        &connect_request(transaction_id),
+        transaction_id,
+        deadline,
+    )
+    .await?;
+
+    Ok(Connection {
+        socket,
+        connection_id: parse_connect_response(&response, transaction_id)?,
+        transaction_id,
+    })
+}
+
+fn unspecified_addr(addr: &SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+        SocketAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+    }
+}
+
+/// Sends the request until a response for the transaction arrives or the
+/// deadline is reached. Late answers to previous requests are ignored.
+async fn exchange(
+    socket: &UdpSocket,
+    request: &[u8],
+    transaction_id: u32,
+    deadline: Instant,
+) -> Result<Vec<u8>, TrackerError> {
+    let mut wait = FIRST_RETRY;
+    let mut buffer = vec![0; MAX_RESPONSE_LEN];
+
+    loop {
+        socket.send(request).await?;
+
+        let retry_at = (Instant::now() + wait).min(deadline);
+        while let Ok(received) = timeout_at(retry_at, socket.recv(&mut buffer)).await {
+            let response = &buffer[..received?];
+            if response.len() < 8 || read_u32(response, 4) == transaction_id {
+                return Ok(response.to_vec());
+            }
+        }
+
+        if retry_at >= deadline {
+            return Err(TrackerError::Timeout);
+        }
+        wait *= 2;
+    }
+}
+
+/// Returns a transaction ID that is unlikely to repeat.
+fn transaction_id() -> u32 {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.subsec_nanos());
+
+    nanos ^ COUNTER.fetch_add(0x9E37_79B9, Ordering::Relaxed)
+}
+
+#[must_use]
+pub fn connect_request(transaction_id: u32) -> [u8; 16] {
+    let mut request = [0; 16];
+    request[..8].copy_from_slice(&PROTOCOL_ID.to_be_bytes());
+    request[8..12].copy_from_slice(&ACTION_CONNECT.to_be_bytes());
+    request[12..].copy_from_slice(&transaction_id.to_be_bytes());
+    request
+}
+
+#[must_use]
+pub fn scrape_request(connection_id: u64, transaction_id: u32, info_hash: &InfoHash) -> [u8; 36] {
+    let mut request = [0; 36];
+    request[..8].copy_from_slice(&connection_id.to_be_bytes());
+    request[8..12].copy_from_slice(&ACTION_SCRAPE.to_be_bytes());
+    request[12..16].copy_from_slice(&transaction_id.to_be_bytes());
+    request[16..].copy_from_slice(&info_hash.bytes());
+    request
+}
+
+#[must_use]
+pub fn announce_request(connection_id: u64, transaction_id: u32, announce: &Announce) -> [u8; 98] {
+    /// `started` event.
+    const EVENT: u32 = 2;
+
+    let mut request = [0; 98];
+    request[..8].copy_from_slice(&connection_id.to_be_bytes());
+    request[8..12].copy_from_slice(&ACTION_ANNOUNCE.to_be_bytes());
+    request[12..16].copy_from_slice(&transaction_id.to_be_bytes());
+    request[16..36].copy_from_slice(&announce.info_hash.bytes());
+    request[36..56].copy_from_slice(&announce.peer_id);
+    // Downloaded: 0.
+    request[64..72].copy_from_slice(&announce.left.to_be_bytes());
+    // Uploaded: 0.
+    request[80..84].copy_from_slice(&EVENT.to_be_bytes());
+    // IP address: 0, the sender's. Key: 0.
+    request[92..96].copy_from_slice(&(-1i32).to_be_bytes());
+    request[96..].copy_from_slice(&announce.port.to_be_bytes());
+    request
+}
+
+/// Returns the connection ID of a `connect` response.
+///
+/// # Errors
+///
+/// Will return an error if the response is an error, or it's not a
+/// `connect` response for the transaction.
+pub fn parse_connect_response(response: &[u8], transaction_id: u32) -> Result<u64, TrackerError> {
+    let body = check_header(response, ACTION_CONNECT, transaction_id)?;
+
+    body.get(..8)
+        .and_then(|id| id.try_into().ok())
+        .map(u64::from_be_bytes)
+        .ok_or_else(|| TrackerError::InvalidResponse("truncated connect response".to_owned()))
+}
+
+/// Returns the statistics of the first torrent of a `scrape` response.
+///
+/// # Errors
+///
+/// Will return an error if the response is an error, or it's not a `scrape`
+/// response for the transaction.
+pub fn parse_scrape_response(
+    response: &[u8],
+    transaction_id: u32,
+) -> Result<ScrapeStats, TrackerError> {
+    let body = check_header(response, ACTION_SCRAPE, transaction_id)?;
+
+    if body.len() < 12 {
+        return Err(TrackerError::InvalidResponse(
+            "truncated scrape response".to_owned(),
+        ));
+    }
+
+    Ok(ScrapeStats {
+        seeders: read_u32(body, 0).into(),
+        completed: read_u32(body, 4).into(),
+        leechers: read_u32(body, 8).into(),
+    })
+}
+
+/// Returns the peers of an `announce` response. Trackers return IPv6 peers
+/// to requests sent over IPv6.
+///
+/// # Errors
+///
+/// Will return an error if the response is an error, or it's not an
+/// `announce` response for the transaction.
+pub fn parse_announce_response(
+    response: &[u8],
+    transaction_id: u32,
+    ipv6: bool,
+) -> Result<Vec<SocketAddr>, TrackerError> {
+    let body = check_header(response, ACTION_ANNOUNCE, transaction_id)?;
+
+    // Interval, leechers and seeders.
+    let peers = body
+        .get(12..)
+        .ok_or_else(|| TrackerError::InvalidResponse("truncated announce response".to_owned()))?;
+
+    Ok(if ipv6 {
+        compact_peers_v6(peers)
+    } else {
+        compact_peers_v4(peers)
+    })
+}
+
+/// Checks the action and transaction ID of a response and returns the rest.
+fn check_header(response: &[u8], action: u32, transaction_id: u32) -> Result<&[u8], TrackerError> {
+    if response.len() < 8 {
+        return Err(TrackerError::InvalidResponse(
+            "truncated response".to_owned(),
+        ));
+    }
+
+    if read_u32(response, 4) != transaction_id {
+        return Err(TrackerError::InvalidResponse(
+            "unexpected transaction ID".to_owned(),
+        ));
+    }
+
+    match read_u32(response, 0) {
+        found if found == action => Ok(&response[8..]),
+        ACTION_ERROR => Err(TrackerError::Failure(
+            String::from_utf8_lossy(&response[8..]).into_owned(),
+        )),
+        found => Err(TrackerError::InvalidResponse(format!(
+            "unexpected action {found}"
+        ))),
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_be_bytes(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{
+        announce_request, connect_request, parse_announce_response, parse_connect_response,
+        parse_scrape_response, scrape, scrape_request,
+    };
+    use crate::models::announce::TrackerUrl;
+    use crate::models::info_hash::InfoHash;
+    use crate::tracker::{Announce, ScrapeStats, TrackerError};
+
+    fn response(action: u32, transaction_id: u32, body: &[u8]) -> Vec<u8> {
+        [
+            action.to_be_bytes().as_slice(),
+            &transaction_id.to_be_bytes(),
+            body,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn it_should_build_the_requests_in_network_byte_order() {
+        assert_eq!(
+            connect_request(7),
+            [0, 0, 4, 23, 39, 16, 25, 128, 0, 0, 0, 0, 0, 0, 0, 7]
+        );
+
+        let request = scrape_request(1, 2, &InfoHash([b'a'; 20]));
+        assert_eq!(
+            &request[..16],
+            [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 2]
+        );
+        assert_eq!(&request[16..], [b'a'; 20]);
+    }
+
+    #[test]
+    fn it_should_parse_the_responses() {
+        assert_eq!(
+            parse_connect_response(&response(0, 9, &42u64.to_be_bytes()), 9).unwrap(),
+            42
+        );
+
+        let body = [5u32, 50, 3].map(u32::to_be_bytes).concat();
+        assert_eq!(
+            parse_scrape_response(&response(2, 9, &body), 9).unwrap(),
+            ScrapeStats {
+                seeders: 5,
+                leechers: 3,
+                completed: 50
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_report_error_responses_and_unexpected_transactions() {
+        assert!(matches!(
+            parse_scrape_response(&response(3, 9, b"unknown torrent"), 9),
+            Err(TrackerError::Failure(message)) if message == "unknown torrent"
+        ));
+        assert!(matches!(
+            parse_connect_response(&response(0, 8, &[0; 8]), 9),
+            Err(TrackerError::InvalidResponse(_))
+        ));
+        assert!(matches!(
+            parse_scrape_response(&response(2, 9, &[0; 4]), 9),
+            Err(TrackerError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn it_should_scrape_a_udp_tracker() {
+        let tracker = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = tracker.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            let mut request = [0; 64];
+
+            let (_, client) = tracker.recv_from(&mut request).unwrap();
+            let transaction_id = u32::from_be_bytes(request[12..16].try_into().unwrap());
+            let reply = response(0, transaction_id, &77u64.to_be_bytes());
+            tracker.send_to(&reply, client).unwrap();
+
+            let (_, client) = tracker.recv_from(&mut request).unwrap();
+            assert_eq!(&request[..8], 77u64.to_be_bytes());
+            let transaction_id = u32::from_be_bytes(request[12..16].try_into().unwrap());
+            let body = [1u32, 2, 3].map(u32::to_be_bytes).concat();
+            tracker
+                .send_to(&response(2, transaction_id, &body), client)
+                .unwrap();
+        });
+
+        let url = TrackerUrl::parse(&format!("udp://127.0.0.1:{port}/announce")).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let stats = runtime
+            .block_on(scrape(&url, &InfoHash([b'a'; 20]), Duration::from_secs(5)))
+            .unwrap();
+
+        assert_eq!(
+            stats,
+            ScrapeStats {
+                seeders: 1,
+                leechers: 3,
+                completed: 2
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_announce_and_decode_the_peers() {
+        let request = announce_request(
+            1,
+            2,
+            &Announce {
+                info_hash: InfoHash([b'a'; 20]),
+                peer_id: [b'p'; 20],
+                port: 6881,
+                left: 3,
+            },
+        );
+        assert_eq!(&request[8..12], 1u32.to_be_bytes());
+        assert_eq!(&request[36..56], [b'p'; 20]);
+        assert_eq!(&request[64..72], 3u64.to_be_bytes());
+        assert_eq!(&request[96..], 6881u16.to_be_bytes());
+
+        let body = [
+            [0u32, 0, 0].map(u32::to_be_bytes).concat(),
+            vec![127, 0, 0, 1, 0x1a, 0xe1],
+        ]
+        .concat();
+        assert_eq!(
+            parse_announce_response(&response(1, 9, &body), 9, false).unwrap(),
+            ["127.0.0.1:6881".parse().unwrap()]
+        );
+    }
+}
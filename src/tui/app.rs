@@ -0,0 +1,574 @@
+//! State of the torrent browser: the tree views and the keyboard handling.
+use std::collections::HashMap;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::ListState;
+use serde_bencode::value::Value as BValue;
+
+use torrust_parse_torrent::models::byte_string::ByteString;
+use torrust_parse_torrent::models::torrent_file::Torrent;
+use torrust_parse_torrent::utils::format::{format_piece_length, format_size};
+use torrust_parse_torrent::utils::hex::from_bytes;
+
+const PAGE_SIZE: usize = 10;
+
+/// A node in a tree view.
+#[derive(Debug)]
+pub struct Node {
+    pub label: String,
+    pub children: Vec<usize>,
+    pub parent: Option<usize>,
+    pub depth: usize,
+    pub expanded: bool,
+}
+
+/// An arena-based tree where nodes are referenced by their index.
+#[derive(Debug, Default)]
+pub struct Tree {
+    pub nodes: Vec<Node>,
+    pub roots: Vec<usize>,
+}
+
+impl Tree {
+    /// Adds a node and returns its index.
+    pub fn add(&mut self, parent: Option<usize>, label: impl Into<String>) -> usize {
+        let id = self.nodes.len();
+        let depth = parent.map_or(0, |parent| self.nodes[parent].depth + 1);
+
+        self.nodes.push(Node {
+            label: label.into(),
+            children: vec![],
+            parent,
+            depth,
+            expanded: false,
+        });
+
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(id),
+            None => self.roots.push(id),
+        }
+
+        id
+    }
+
+    /// All the nodes in depth-first order, including the collapsed ones.
+    #[must_use]
+    pub fn preorder(&self) -> Vec<usize> {
+        self.walk(false)
+    }
+
+    /// The nodes that are not hidden by a collapsed ancestor.
+    #[must_use]
+    pub fn visible(&self) -> Vec<usize> {
+        self.walk(true)
+    }
+
+    fn walk(&self, only_visible: bool) -> Vec<usize> {
+        let mut ids = vec![];
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+
+        while let Some(id) = stack.pop() {
+            ids.push(id);
+            let node = &self.nodes[id];
+            if !only_visible || node.expanded {
+                stack.extend(node.children.iter().rev());
+            }
+        }
+
+        ids
+    }
+
+    pub fn expand_ancestors(&mut self, id: usize) {
+        let mut parent = self.nodes[id].parent;
+        while let Some(id) = parent {
+            self.nodes[id].expanded = true;
+            parent = self.nodes[id].parent;
+        }
+    }
+}
+
+/// One of the tabs of the browser.
+#[derive(Debug)]
+pub struct View {
+    pub title: &'static str,
+    pub tree: Tree,
+    /// Cached list of visible nodes. It's the content of the list widget.
+    pub rows: Vec<usize>,
+    pub state: ListState,
+}
+
+impl View {
+    fn new(title: &'static str, tree: Tree) -> Self {
+        let rows = tree.visible();
+        let mut state = ListState::default();
+        if !rows.is_empty() {
+            state.select(Some(0));
+        }
+        Self {
+            title,
+            tree,
+            rows,
+            state,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.rows = self.tree.visible();
+    }
+
+    #[must_use]
+    pub fn selected_node(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|row| self.rows.get(row).copied())
+    }
+
+    fn select_node(&mut self, id: usize) {
+        if let Some(row) = self.rows.iter().position(|row| *row == id) {
+            self.state.select(Some(row));
+        }
+    }
+
+    fn move_by(&mut self, offset: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let last = self.rows.len() - 1;
+        let current = self.state.selected().unwrap_or(0);
+        let next = current.saturating_add_signed(offset).min(last);
+        self.state.select(Some(next));
+    }
+
+    fn set_expanded(&mut self, expanded: bool) {
+        let Some(id) = self.selected_node() else {
+            return;
+        };
+
+        let node = &mut self.tree.nodes[id];
+
+        if node.children.is_empty() || node.expanded == expanded {
+            // Collapsing a leaf or an already collapsed node goes to the parent
+            if !expanded {
+                if let Some(parent) = node.parent {
+                    self.select_node(parent);
+                }
+            }
+            return;
+        }
+
+        node.expanded = expanded;
+        self.refresh();
+        self.select_node(id);
+    }
+
+    fn toggle(&mut self) {
+        if let Some(id) = self.selected_node() {
+            let expanded = self.tree.nodes[id].expanded;
+            self.set_expanded(!expanded);
+        }
+    }
+
+    /// Selects the next node (in depth-first order and wrapping around) whose
+    /// label contains the query. Collapsed ancestors are expanded.
+    fn search(&mut self, query: &str, forward: bool) -> bool {
+        let query = query.to_lowercase();
+        let order = self.tree.preorder();
+
+        if order.is_empty() || query.is_empty() {
+            return false;
+        }
+
+        let start = self
+            .selected_node()
+            .and_then(|id| order.iter().position(|node| *node == id))
+            .unwrap_or(0);
+
+        let len = order.len();
+
+        for step in 1..=len {
+            let position = if forward {
+                (start + step) % len
+            } else {
+                (start + len - step % len) % len
+            };
+            let id = order[position];
+            if self.tree.nodes[id].label.to_lowercase().contains(&query) {
+                self.tree.expand_ancestors(id);
+                self.refresh();
+                self.select_node(id);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Whether the keys are used to navigate or to type the search query.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Search,
+}
+
+#[derive(Debug)]
+pub struct App {
+    pub title: String,
+    pub views: Vec<View>,
+    pub current: usize,
+    pub mode: Mode,
+    pub query: String,
+    pub status: String,
+    pub should_quit: bool,
+}
+
+impl App {
+    #[must_use]
+    pub fn new(torrent: &Torrent, value: &BValue) -> Self {
+        Self {
+            title: format!("{} ({})", torrent.info.display_name(), torrent.info_hash()),
+            views: vec![
+                View::new("Files", files_tree(torrent)),
+                View::new("Trackers", trackers_tree(torrent)),
+                View::new("Pieces", pieces_tree(torrent)),
+                View::new("Bencode", bencode_tree(value)),
+            ],
+            current: 0,
+            mode: Mode::Normal,
+            query: String::new(),
+            status: String::new(),
+            should_quit: false,
+        }
+    }
+
+    pub fn view_mut(&mut self) -> &mut View {
+        &mut self.views[self.current]
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match self.mode {
+            Mode::Normal => self.handle_normal_key(key.code),
+            Mode::Search => self.handle_search_key(key.code),
+        }
+    }
+
+    fn handle_normal_key(&mut self, code: KeyCode) {
+        self.status.clear();
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Tab => self.current = (self.current + 1) % self.views.len(),
+            KeyCode::BackTab => {
+                self.current = (self.current + self.views.len() - 1) % self.views.len();
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < self.views.len() {
+                    self.current = index;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.view_mut().move_by(1),
+            KeyCode::Up | KeyCode::Char('k') => self.view_mut().move_by(-1),
+            KeyCode::PageDown => self.view_mut().move_by(PAGE_SIZE.cast_signed()),
+            KeyCode::PageUp => self.view_mut().move_by(-PAGE_SIZE.cast_signed()),
+            KeyCode::Home | KeyCode::Char('g') => self.view_mut().move_by(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.view_mut().move_by(isize::MAX),
+            KeyCode::Right | KeyCode::Char('l') => self.view_mut().set_expanded(true),
+            KeyCode::Left | KeyCode::Char('h') => self.view_mut().set_expanded(false),
+            KeyCode::Enter | KeyCode::Char(' ') => self.view_mut().toggle(),
+            KeyCode::Char('/') => {
+                self.mode = Mode::Search;
+                self.query.clear();
+            }
+            KeyCode::Char('n') => self.search(true),
+            KeyCode::Char('N') => self.search(false),
+            _ => {}
+        }
+    }
+
+    fn handle_search_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                self.search(true);
+            }
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.query.clear();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Char(c) => self.query.push(c),
+            _ => {}
+        }
+    }
+
+    fn search(&mut self, forward: bool) {
+        let query = self.query.clone();
+        if query.is_empty() {
+            return;
+        }
+        if !self.view_mut().search(&query, forward) {
+            self.status = format!("Pattern not found: {query}");
+        }
+    }
+}
+
+fn files_tree(torrent: &Torrent) -> Tree {
+    let mut tree = Tree::default();
+
+    let root = tree.add(
+        None,
+        format!(
+            "{} ({})",
+            torrent.info.display_name(),
+            format_size(torrent.content_size())
+        ),
+    );
+    tree.nodes[root].expanded = true;
+
+    let mut directories: HashMap<Vec<ByteString>, usize> = HashMap::new();
+
+    for file in torrent.content_files() {
+        let mut parent = root;
+
+        if let Some((file_name, directory)) = file.display_path().split_last() {
+            for depth in 1..=directory.len() {
+                let key = directory[..depth].to_vec();
+                parent = *directories.entry(key).or_insert_with(|| {
+                    tree.add(Some(parent), format!("{}/", directory[depth - 1]))
+                });
+            }
+
+            tree.add(
+                Some(parent),
+                format!("{file_name} ({})", format_size(file.length)),
+            );
+        }
+    }
+
+    tree
+}
+
+fn trackers_tree(torrent: &Torrent) -> Tree {
+    let mut tree = Tree::default();
+
+    if let Some(announce) = &torrent.announce {
+        tree.add(None, format!("Announce: {announce}"));
+    }
+
+    if let Some(announce_list) = &torrent.announce_list {
+        for (index, tier) in announce_list.iter().enumerate() {
+            let node = tree.add(None, format!("Tier {}", index + 1));
+            tree.nodes[node].expanded = true;
+            for url in tier {
+                tree.add(Some(node), url.clone());
+            }
+        }
+    }
+
+    let web_seeds = torrent.web_seeds();
+    if !web_seeds.is_empty() {
+        let node = tree.add(None, "Web seeds");
+        tree.nodes[node].expanded = true;
+        for web_seed in web_seeds {
+            match web_seed.error {
+                None => tree.add(Some(node), web_seed.url),
+                Some(error) => tree.add(Some(node), format!("{} (invalid: {error})", web_seed.url)),
+            };
+        }
+    }
+
+    if let Some(nodes) = &torrent.nodes {
+        let node = tree.add(None, "DHT nodes");
+        tree.nodes[node].expanded = true;
+        for dht_node in nodes {
+            tree.add(Some(node), format!("{}:{}", dht_node.0, dht_node.1));
+        }
+    }
+
+    if tree.nodes.is_empty() {
+        tree.add(None, "No trackers (trackerless torrent)");
+    }
+
+    tree
+}
+
+fn pieces_tree(torrent: &Torrent) -> Tree {
+    let mut tree = Tree::default();
+
+    let piece_length = torrent.info.piece_length;
+    let pieces: &[u8] = torrent
+        .info
+        .pieces
+        .as_ref()
+        .map_or(&[], |pieces| pieces.as_slice());
+    let total_size = torrent.file_size();
+
+    tree.add(
+        None,
+        format!(
+            "Piece length: {} ({piece_length} bytes)",
+            format_piece_length(piece_length)
+        ),
+    );
+    tree.add(None, format!("Total size: {}", format_size(total_size)));
+
+    if !pieces.len().is_multiple_of(20) {
+        tree.add(
+            None,
+            format!(
+                "Warning: pieces field length ({}) is not a multiple of 20",
+                pieces.len()
+            ),
+        );
+    }
+
+    if piece_length > 0 {
+        let expected = total_size.div_ceil(piece_length);
+        tree.add(None, format!("Expected piece count: {expected}"));
+
+        let last_piece = total_size % piece_length;
+        if total_size > 0 {
+            let last_piece = if last_piece == 0 {
+                piece_length
+            } else {
+                last_piece
+            };
+            tree.add(
+                None,
+                format!("Last piece size: {}", format_size(last_piece)),
+            );
+        }
+    }
+
+    let hashes = tree.add(None, format!("Piece hashes ({})", pieces.len() / 20));
+
+    for (index, hash) in pieces.chunks_exact(20).enumerate() {
+        tree.add(
+            Some(hashes),
+            format!("#{index:<8} {}", from_bytes(hash).to_lowercase()),
+        );
+    }
+
+    tree
+}
+
+fn bencode_tree(value: &BValue) -> Tree {
+    let mut tree = Tree::default();
+    let root = tree.add(None, summarize(value));
+    tree.nodes[root].expanded = true;
+    add_bencode_children(&mut tree, root, value);
+    tree
+}
+
+fn add_bencode_children(tree: &mut Tree, parent: usize, value: &BValue) {
+    match value {
+        BValue::Dict(dict) => {
+            let mut entries: Vec<_> = dict.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            for (key, value) in entries {
+                let label = format!("{}: {}", String::from_utf8_lossy(key), summarize(value));
+                let node = tree.add(Some(parent), label);
+                add_bencode_children(tree, node, value);
+            }
+        }
+        BValue::List(list) => {
+            for (index, value) in list.iter().enumerate() {
+                let node = tree.add(Some(parent), format!("[{index}]: {}", summarize(value)));
+                add_bencode_children(tree, node, value);
+            }
+        }
+        BValue::Bytes(_) | BValue::Int(_) => {}
+    }
+}
+
+fn summarize(value: &BValue) -> String {
+    match value {
+        BValue::Dict(dict) => format!("{{{} keys}}", dict.len()),
+        BValue::List(list) => format!("[{} items]", list.len()),
+        BValue::Int(int) => int.to_string(),
+        BValue::Bytes(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) if text.len() <= 200 && !text.chars().any(char::is_control) => {
+                format!("\"{text}\"")
+            }
+            _ => {
+                let preview = &bytes[..bytes.len().min(8)];
+                format!(
+                    "<{} bytes> {}…",
+                    bytes.len(),
+                    from_bytes(preview).to_lowercase()
+                )
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+    use serde_bencode::value::Value as BValue;
+
+    use super::App;
+    use torrust_parse_torrent::utils::parse_torrent_verbose;
+
+    fn app_for(path: &str) -> App {
+        let bytes = std::fs::read(path).unwrap();
+        let value: BValue = serde_bencode::from_bytes(&bytes).unwrap();
+        let torrent = parse_torrent_verbose::decode_torrent(value.clone()).unwrap();
+        App::new(&torrent, &value)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code));
+    }
+
+    #[test]
+    fn it_should_build_the_file_tree_of_a_multi_file_torrent() {
+        let app = app_for(
+            // cspell:disable-next-line
+            "tests/fixtures/torrents/6c690018c5786dbbb00161f62b0712d69296df97_with_custom_info_dict_key.torrent",
+        );
+
+        let files = &app.views[0];
+
+        // The root directory and the three files
+        assert_eq!(files.rows.len(), 4);
+    }
+
+    #[test]
+    fn it_should_expand_collapsed_nodes_when_searching() {
+        let mut app = app_for("torrents/mandelbrot_set_01.torrent");
+
+        // Go to the "Pieces" tab and search for the second piece hash
+        press(&mut app, KeyCode::Char('3'));
+        let rows_before = app.views[app.current].rows.len();
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in "#1 ".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+
+        let selected = app.views[app.current].selected_node().unwrap();
+
+        assert!(app.views[app.current].rows.len() > rows_before);
+        assert!(app.views[app.current].tree.nodes[selected]
+            .label
+            .starts_with("#1 "));
+    }
+
+    #[test]
+    fn it_should_report_when_the_search_pattern_is_not_found() {
+        let mut app = app_for("torrents/mandelbrot_set_01.torrent");
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in "not-found".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+
+        assert!(app.status.contains("not found"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Interactive terminal browser for torrent contents.
+//!
+//! It's only available when the crate is built with the `tui` feature:
+//!
+//! ```text
+//! cargo run --features tui -- tui ./torrents/mandelbrot_set_01.torrent
+//! ```
+pub mod app;
+pub mod ui;
+
+use std::error::Error;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+
+use torrust_parse_torrent::utils::bencode;
+use torrust_parse_torrent::utils::parse_mode::ParseMode;
+use torrust_parse_torrent::utils::parse_torrent_verbose;
+
+use self::app::App;
+
+/// Opens the torrent file and runs the browser until the user quits.
+///
+/// # Errors
+///
+/// This function will return an error if the file can't be read, it's not a
+/// bencoded dictionary or there is a problem with the terminal.
+pub fn run(path: &str, mode: ParseMode) -> Result<(), Box<dyn Error>> {
+    let bytes = crate::console::commands::read_input(path)?;
+
+    let value = bencode::decode(&bytes)?;
+
+    let torrent = parse_torrent_verbose::decode_torrent_with_mode(value.clone(), mode)?;
+
+    let mut app = App::new(&torrent, &value);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<(), Box<dyn Error>> {
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Rendering of the torrent browser.
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, Paragraph, Tabs};
+use ratatui::Frame;
+
+use super::app::{App, Mode};
+
+const HELP: &str =
+    "q quit | Tab/1-4 switch tab | ↑↓ move | ←→ collapse/expand | / search | n/N next/prev match";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let tabs = Tabs::new(app.views.iter().map(|view| view.title))
+        .select(app.current)
+        .highlight_style(Style::new().bold().reversed())
+        .block(Block::bordered().title(format!(" {} ", app.title)));
+    frame.render_widget(tabs, header);
+
+    let view = &mut app.views[app.current];
+
+    let items: Vec<ListItem> = view
+        .rows
+        .iter()
+        .map(|id| {
+            let node = &view.tree.nodes[*id];
+            let marker = match (node.children.is_empty(), node.expanded) {
+                (true, _) => "  ",
+                (false, true) => "▾ ",
+                (false, false) => "▸ ",
+            };
+            ListItem::new(format!("{}{marker}{}", "  ".repeat(node.depth), node.label))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" {} ", view.title)))
+        .highlight_style(Style::new().reversed());
+    frame.render_stateful_widget(list, body, &mut view.state);
+
+    let footer_line = match app.mode {
+        Mode::Search => Line::from(format!("/{}", app.query)),
+        Mode::Normal if !app.status.is_empty() => Line::from(app.status.as_str()).red(),
+        Mode::Normal => Line::from(HELP).dim(),
+    };
+    frame.render_widget(Paragraph::new(footer_line), footer);
+}
@@ -0,0 +1,361 @@
+//! Checks for file paths that could harm the computer that downloads a
+//! torrent.
+//!
+//! Clients join the name and the path components of each file to get the
+//! location where it's written. A malicious torrent can use those components
+//! to write outside the download directory, overwrite one of its own files
+//! or disguise an executable as a document.
+//!
+//! Dangerous paths are reported as errors, suspicious ones as warnings. Names
+//! that Windows can't create are reported as warnings too, so that indexers
+//! can ask uploaders to fix them before the torrent is distributed.
+use std::collections::HashSet;
+
+use crate::models::byte_string::ByteString;
+use crate::models::torrent_file::Torrent;
+use crate::utils::diagnostics::Diagnostic;
+
+/// Longest path component most file systems accept, in bytes.
+pub const MAX_COMPONENT_LENGTH: usize = 255;
+
+/// Longest path, in bytes, that can be opened on Linux.
+pub const MAX_PATH_LENGTH: usize = 4096;
+
+/// Longest path Windows programs can open unless they opt in to long paths,
+/// in characters.
+pub const WINDOWS_MAX_PATH: usize = 260;
+
+/// Device names that Windows reserves in every directory, with or without an
+/// extension.
+pub(crate) const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "AUX", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "CON", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9", "NUL", "PRN",
+];
+
+/// Extensions of files that run when they are opened.
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "bat", "cmd", "com", "cpl", "exe", "hta", "jar", "js", "lnk", "msi", "pif", "ps1", "scr",
+    "vbe", "vbs", "wsf",
+];
+
+/// Extensions that make a file look harmless when an executable extension
+/// follows them, like `invoice.pdf.exe`.
+const DECOY_EXTENSIONS: &[&str] = &[
+    "avi", "bmp", "doc", "docx", "flac", "gif", "jpeg", "jpg", "mkv", "mov", "mp3", "mp4", "pdf",
+    "png", "ppt", "pptx", "rar", "rtf", "txt", "wav", "xls", "xlsx", "zip",
+];
+
+/// Returns the problems found in the paths of the torrent. Downloading it is
+/// dangerous if any of them is an error.
+///
+/// Both `name` and `name.utf-8`, and `path` and `path.utf-8`, are checked
+/// because clients use either of them. The file tree of v2 torrents and the
+/// targets of symbolic links are checked too.
+#[must_use]
+pub fn audit(torrent: &Torrent) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let info = &torrent.info;
+
+    audit_components(
+        "info.name",
+        std::slice::from_ref(&info.name),
+        &mut diagnostics,
+    );
+    if let Some(name_utf8) = &info.name_utf8 {
+        audit_components(
+            "info.name.utf-8",
+            std::slice::from_ref(name_utf8),
+            &mut diagnostics,
+        );
+    }
+
+    for (index, file) in info.files.iter().flatten().enumerate() {
+        let key = format!("info.files[{index}]");
+        audit_components(&format!("{key}.path"), &file.path, &mut diagnostics);
+        if let Some(path_utf8) = &file.path_utf8 {
+            audit_components(&format!("{key}.path.utf-8"), path_utf8, &mut diagnostics);
+        }
+        if let Some(symlink_path) = &file.symlink_path {
+            audit_components(
+                &format!("{key}.symlink path"),
+                symlink_path,
+                &mut diagnostics,
+            );
+        }
+    }
+
+    if let Some(file_tree) = &info.file_tree {
+        for (path, _) in file_tree.iter_files() {
+            let components: Vec<_> = path
+                .iter()
+                .map(|component| ByteString::from(*component))
+                .collect();
+            audit_components("info.file tree", &components, &mut diagnostics);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (path, _) in torrent.content_paths(None) {
+        if path.len() > MAX_PATH_LENGTH {
+            diagnostics.push(Diagnostic::warning(
+                "info",
+                format!(
+                    "path of {} bytes is longer than {MAX_PATH_LENGTH}: `{path}`",
+                    path.len()
+                ),
+            ));
+        }
+        let characters = path.chars().count();
+        if characters > WINDOWS_MAX_PATH {
+            diagnostics.push(Diagnostic::warning(
+                "info",
+                format!(
+                    "path of {characters} characters is longer than {WINDOWS_MAX_PATH}, the limit on Windows: `{path}`"
+                ),
+            ));
+        }
+        if let Some(extensions) = double_extension(&path) {
+            diagnostics.push(Diagnostic::warning(
+                "info",
+                format!("suspicious double extension `{extensions}`: `{path}`"),
+            ));
+        }
+        if !seen.insert(path.clone()) {
+            diagnostics.push(Diagnostic::error(
+                "info.files",
+                format!("duplicate path, one file overwrites the other: `{path}`"),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks each component of a path on its own. The components are joined
+/// with `/` in the messages.
+fn audit_components(key: &str, components: &[ByteString], diagnostics: &mut Vec<Diagnostic>) {
+    let path = components
+        .iter()
+        .map(ByteString::to_str_lossy)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if components.is_empty() {
+        diagnostics.push(Diagnostic::error(key, "empty path"));
+    }
+
+    for component in components {
+        let text = component.to_str_lossy();
+
+        if let Some(problem) = dangerous_component(&text) {
+            diagnostics.push(Diagnostic::error(key, format!("{problem}: `{path}`")));
+        } else if let Some(problem) = windows_problem(&text) {
+            diagnostics.push(Diagnostic::warning(
+                key,
+                format!("not a valid file name on Windows, {problem}: `{path}`"),
+            ));
+        }
+
+        if component.as_bytes().len() > MAX_COMPONENT_LENGTH {
+            diagnostics.push(Diagnostic::warning(
+                key,
+                format!(
+                    "path component of {} bytes is longer than {MAX_COMPONENT_LENGTH}: `{path}`",
+                    component.as_bytes().len()
+                ),
+            ));
+        }
+    }
+}
+
+/// Returns why a path component is dangerous, if it is.
+fn dangerous_component(component: &str) -> Option<&'static str> {
+    let bytes = component.as_bytes();
+    let has_drive_letter = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+
+    if component.is_empty() {
+        Some("zero-length path component")
+    } else if component == "." || component == ".." {
+        Some("path traversal")
+    } else if component.starts_with(['/', '\\']) {
+        Some("absolute path")
+    } else if has_drive_letter {
+        Some("drive letter in path")
+    } else if component.split(['/', '\\']).any(|part| part == "..") {
+        Some("path traversal")
+    } else if component.contains(['/', '\\']) {
+        Some("path separator in a path component")
+    } else if component.contains('\0') {
+        Some("null byte in a path component")
+    } else {
+        None
+    }
+}
+
+/// Returns why Windows can't create a file or directory with this name, if it
+/// can't. Components that are already dangerous are not checked.
+fn windows_problem(component: &str) -> Option<String> {
+    // `CON.txt` is reserved too, and so is `CON .txt`.
+    let stem = component.split('.').next().unwrap_or_default().trim_end();
+
+    if let Some(reserved) = WINDOWS_RESERVED_NAMES
+        .iter()
+        .find(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        Some(format!("`{reserved}` is a reserved name"))
+    } else if component.ends_with(['.', ' ']) {
+        Some("it ends with a dot or a space".to_owned())
+    } else {
+        component
+            .chars()
+            .find(|character| {
+                matches!(character, '<' | '>' | ':' | '"' | '|' | '?' | '*')
+                    || character.is_ascii_control()
+            })
+            .map(|character| format!("it contains `{}`", character.escape_default()))
+    }
+}
+
+/// Returns the last two extensions of the file name, like `pdf.exe`, if an
+/// executable extension follows the extension of a document or a media file.
+fn double_extension(path: &str) -> Option<String> {
+    let file_name = path.rsplit('/').next()?;
+    let mut extensions = file_name.rsplit('.');
+    let last = extensions.next()?.to_ascii_lowercase();
+    let previous = extensions.next()?.to_ascii_lowercase();
+
+    // The file name must have a stem before both extensions.
+    extensions.next().filter(|stem| !stem.is_empty())?;
+
+    (EXECUTABLE_EXTENSIONS.contains(&last.as_str())
+        && DECOY_EXTENSIONS.contains(&previous.as_str()))
+    .then(|| format!("{previous}.{last}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{audit, double_extension, windows_problem};
+    use crate::parse_torrent;
+    use crate::utils::diagnostics::{has_errors, Severity};
+
+    /// Bencodes a multi-file torrent with the given file paths.
+    fn torrent_with_paths(name: &str, paths: &[&[&str]]) -> Vec<u8> {
+        let mut files = String::new();
+        for path in paths {
+            let components: String = path
+                .iter()
+                .map(|component| format!("{}:{component}", component.len()))
+                .collect();
+            files.push_str(&format!("d6:lengthi1e4:pathl{components}ee"));
+        }
+        format!(
+            "d4:infod5:filesl{files}e4:name{}:{name}12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+            name.len()
+        )
+        .into_bytes()
+    }
+
+    fn audit_paths(name: &str, paths: &[&[&str]]) -> Vec<String> {
+        let torrent = parse_torrent(&torrent_with_paths(name, paths)).unwrap();
+        audit(&torrent).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn it_should_accept_a_torrent_with_safe_paths() {
+        let torrent = parse_torrent(&torrent_with_paths(
+            "content",
+            &[&["a.txt"], &["dir", "b.v1.2.exe"]],
+        ))
+        .unwrap();
+
+        assert!(audit(&torrent).is_empty());
+    }
+
+    #[test]
+    fn it_should_flag_path_traversal_and_absolute_paths() {
+        assert_eq!(
+            audit_paths("content", &[&["..", "a"]]),
+            vec!["error: `info.files[0].path`: path traversal: `../a`"]
+        );
+        assert_eq!(
+            audit_paths("content", &[&["a/../../b"]]),
+            vec!["error: `info.files[0].path`: path traversal: `a/../../b`"]
+        );
+        assert_eq!(
+            audit_paths("/etc", &[&["passwd"]]),
+            vec!["error: `info.name`: absolute path: `/etc`"]
+        );
+        assert_eq!(
+            audit_paths("content", &[&["C:", "a"]]),
+            vec!["error: `info.files[0].path`: drive letter in path: `C:/a`"]
+        );
+        assert_eq!(
+            audit_paths("content", &[&["a", ""]]),
+            vec!["error: `info.files[0].path`: zero-length path component: `a/`"]
+        );
+    }
+
+    #[test]
+    fn it_should_flag_duplicate_paths() {
+        let diagnostics = audit_paths("content", &[&["a"], &["b"], &["a"]]);
+
+        assert_eq!(
+            diagnostics,
+            vec!["error: `info.files`: duplicate path, one file overwrites the other: `content/a`"]
+        );
+    }
+
+    #[test]
+    fn it_should_warn_about_long_paths_and_double_extensions() {
+        let long = "a".repeat(300);
+        let torrent = parse_torrent(&torrent_with_paths(
+            "content",
+            &[&[&long], &["Invoice.PDF.exe"]],
+        ))
+        .unwrap();
+        let diagnostics = audit(&torrent);
+
+        // The long path is too long for Windows too.
+        assert!(!has_errors(&diagnostics));
+        assert_eq!(diagnostics.len(), 3);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+        assert_eq!(
+            double_extension("a/Invoice.PDF.exe"),
+            Some("pdf.exe".to_owned())
+        );
+        assert_eq!(double_extension("a/.pdf.exe"), None);
+        assert_eq!(double_extension("a/setup.exe"), None);
+    }
+
+    #[test]
+    fn it_should_warn_about_names_that_are_not_valid_on_windows() {
+        assert_eq!(
+            audit_paths("content", &[&["dir", "con.txt"]]),
+            vec!["warning: `info.files[0].path`: not a valid file name on Windows, `CON` is a reserved name: `dir/con.txt`"]
+        );
+        assert_eq!(
+            windows_problem("lpt1"),
+            Some("`LPT1` is a reserved name".to_owned())
+        );
+        assert_eq!(
+            windows_problem("a."),
+            Some("it ends with a dot or a space".to_owned())
+        );
+        assert_eq!(
+            windows_problem("a "),
+            Some("it ends with a dot or a space".to_owned())
+        );
+        assert_eq!(
+            windows_problem("what?.txt"),
+            Some("it contains `?`".to_owned())
+        );
+        assert_eq!(
+            windows_problem("a\tb"),
+            Some("it contains `\\t`".to_owned())
+        );
+        assert_eq!(windows_problem("console.txt"), None);
+        assert_eq!(windows_problem("COM10"), None);
+    }
+}
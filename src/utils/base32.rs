@@ -0,0 +1,99 @@
+//! RFC 4648 base32 encoding without padding.
+//!
+//! Old magnet links encode the info-hash in base32 (32 characters for 20
+//! bytes) instead of hex.
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Encodes bytes into an uppercase base32 string without padding.
+#[must_use]
+pub fn encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len().div_ceil(5) * 8);
+
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(char::from(ALPHABET[usize::from((buffer >> bits) & 0x1F)]));
+        }
+    }
+
+    if bits > 0 {
+        output.push(char::from(
+            ALPHABET[usize::from((buffer << (5 - bits)) & 0x1F)],
+        ));
+    }
+
+    output
+}
+
+/// Decodes a base32 string. It's case-insensitive and trailing `=` padding is
+/// ignored.
+///
+/// # Errors
+///
+/// Will return an error with the offending character if the input contains
+/// characters outside the base32 alphabet.
+pub fn decode(s: &str) -> Result<Vec<u8>, char> {
+    let mut output = Vec::with_capacity(s.len() * 5 / 8);
+
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+
+    for c in s.trim_end_matches('=').chars() {
+        let value = match c.to_ascii_uppercase() {
+            upper @ 'A'..='Z' => upper as u16 - u16::from(b'A'),
+            digit @ '2'..='7' => digit as u16 - u16::from(b'2') + 26,
+            _ => return Err(c),
+        };
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            output.push(u8::try_from((buffer >> bits) & 0xFF).expect("masked to 8 bits"));
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn it_should_encode_and_decode_the_rfc_4648_test_vectors() {
+        // cspell:disable
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+        // cspell:enable
+
+        for (plain, encoded) in vectors {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn it_should_decode_lowercase_and_padded_input() {
+        // cspell:disable-next-line
+        assert_eq!(decode("mzxw6ytboi======").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn it_should_fail_decoding_characters_outside_the_alphabet() {
+        assert_eq!(decode("MZXW1"), Err('1'));
+    }
+}
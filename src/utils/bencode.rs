@@ -0,0 +1,720 @@
+//! Bencode decoder that reports where a malformed file breaks.
+//!
+//! `serde_bencode` only says what went wrong. This decoder produces the same
+//! [`Value`](BValue) tree but its errors also contain the byte offset, the
+//! path of the key being decoded (for example `info.files[3].path`) and a
+//! hexdump of the bytes around the offset:
+//!
+//! ```text
+//! unexpected byte 0x78 at offset 27 in `info.files[0].length`
+//! 00000000  64 34 3a 69 6e 66 6f 64  35 3a 66 69 6c 65 73 6c  |d4:infod5:filesl|
+//! 00000010  64 36 3a 6c 65 6e 67 74  68 69 31 78 65 34 3a 70  |d6:lengthi1xe4:p|
+//!                                             ^^
+//! ```
+//!
+//! Only the first value is decoded. Bytes after it are ignored.
+//!
+//! Unsorted and duplicate dictionary keys, and integers with leading zeros or
+//! `i-0e`, are decoded, clients disagree on what to do with them, and
+//! [`decode_with_diagnostics`] reports them.
+//!
+//! The input size, the nesting depth, the length of the byte strings and the
+//! number of entries of lists and dictionaries are limited, see
+//! [`ParseOptions`].
+//!
+//! The decoder also records where the top-level `info` dictionary is in the
+//! input (see [`info_span`]), so that the info-hash can be calculated from the
+//! original bytes.
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use serde_bencode::value::Value as BValue;
+use thiserror::Error;
+
+use crate::utils::bencode_events::{self, InfoSpan};
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::parse_options::ParseOptions;
+
+/// Number of bytes in a hexdump row.
+const ROW_LEN: usize = 16;
+
+/// What is wrong with the bencoded data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BencodeErrorKind {
+    /// The data ends before the value is complete.
+    UnexpectedEof,
+    /// A byte that can't start or continue the current value.
+    UnexpectedByte(u8),
+    /// An integer without digits.
+    InvalidInteger,
+    /// An integer that does not fit in an `i64`.
+    IntegerOverflow,
+    /// A byte string length that is not a number or is too long for the data.
+    InvalidLength,
+    /// A dictionary key that is not a byte string.
+    InvalidKey,
+    /// The input is larger than [`ParseOptions::max_input_size`].
+    InputTooLarge { size: usize, limit: usize },
+    /// Lists and dictionaries are nested deeper than
+    /// [`ParseOptions::max_depth`].
+    TooDeep { limit: usize },
+    /// A byte string is longer than [`ParseOptions::max_string_length`].
+    StringTooLong { length: usize, limit: usize },
+    /// A list or a dictionary has more than [`ParseOptions::max_entries`]
+    /// entries.
+    TooManyEntries { limit: usize },
+}
+
+impl fmt::Display for BencodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BencodeErrorKind::UnexpectedEof => write!(f, "unexpected end of data"),
+            BencodeErrorKind::UnexpectedByte(byte) => write!(f, "unexpected byte {byte:#04x}"),
+            BencodeErrorKind::InvalidInteger => write!(f, "invalid integer"),
+            BencodeErrorKind::IntegerOverflow => write!(f, "integer does not fit in 64 bits"),
+            BencodeErrorKind::InvalidLength => write!(f, "invalid byte string length"),
+            BencodeErrorKind::InvalidKey => write!(f, "dictionary key is not a byte string"),
+            BencodeErrorKind::InputTooLarge { size, limit } => {
+                write!(
+                    f,
+                    "input of {size} bytes is larger than the limit of {limit}"
+                )
+            }
+            BencodeErrorKind::TooDeep { limit } => {
+                write!(f, "values are nested deeper than the limit of {limit}")
+            }
+            BencodeErrorKind::StringTooLong { length, limit } => write!(
+                f,
+                "byte string of {length} bytes is longer than the limit of {limit}"
+            ),
+            BencodeErrorKind::TooManyEntries { limit } => {
+                write!(f, "more entries than the limit of {limit}")
+            }
+        }
+    }
+}
+
+/// Error decoding bencoded data, with its location.
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[error("{kind} at offset {offset}{}\n{hexdump}", in_path(.path))]
+pub struct BencodeError {
+    pub kind: BencodeErrorKind,
+    /// Offset of the offending byte in the input.
+    pub offset: usize,
+    /// Path of the value being decoded, empty for the root value.
+    pub path: String,
+    /// Hexdump of the bytes around the offset.
+    pub hexdump: String,
+}
+
+fn in_path(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" in `{path}`")
+    }
+}
+
+/// Decodes the first bencoded value in `bytes`, with the default limits.
+///
+/// # Errors
+///
+/// Will return an error with the location of the problem if the bytes are
+/// not valid bencode or exceed one of the limits.
+pub fn decode(bytes: &[u8]) -> Result<BValue, BencodeError> {
+    decode_with_options(bytes, &ParseOptions::default())
+}
+
+/// Decodes the first bencoded value in `bytes`, with the limits of the
+/// options.
+///
+/// # Errors
+///
+/// Will return an error with the location of the problem if the bytes are
+/// not valid bencode or exceed one of the limits.
+pub fn decode_with_options(bytes: &[u8], options: &ParseOptions) -> Result<BValue, BencodeError> {
+    Decoder::new(bytes, options).decode_root()
+}
+
+/// A value decoded by [`decode_with_diagnostics`].
+#[derive(Debug, PartialEq)]
+pub struct Decoded {
+    pub value: BValue,
+    /// Length of the value. The bytes after it are not decoded.
+    pub length: usize,
+    /// Byte range of the top-level `info` dictionary, see [`info_span`].
+    pub info_span: Option<Range<usize>>,
+    /// Byte range of the `pieces` of the `info` dictionary, without their
+    /// length. In lenient mode they are left out of the value with
+    /// [`ParseOptions::skip_pieces`].
+    pub pieces_span: Option<Range<usize>>,
+    /// Problems with the encoding that don't prevent decoding it: dictionary
+    /// keys that are not sorted, which are warnings, and duplicate keys,
+    /// which are errors because only the last value is kept, and integers
+    /// with leading zeros or negative zero, which are errors too.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Decodes the first bencoded value in `bytes`, with the limits of the
+/// options, and reports the problems with its encoding.
+///
+/// # Errors
+///
+/// Will return an error with the location of the problem if the bytes are
+/// not valid bencode or exceed one of the limits.
+pub fn decode_with_diagnostics(
+    bytes: &[u8],
+    options: &ParseOptions,
+) -> Result<Decoded, BencodeError> {
+    let mut decoder = Decoder::new(bytes, options);
+    let value = decoder.decode_root()?;
+    Ok(Decoded {
+        value,
+        length: decoder.pos,
+        info_span: decoder.info_span,
+        pieces_span: decoder.pieces_span,
+        diagnostics: decoder.diagnostics,
+    })
+}
+
+/// Decodes the first bencoded value in `bytes` and returns it with its
+/// length, to read the data that follows it, like in the `ut_metadata`
+/// messages of BEP 9.
+///
+/// # Errors
+///
+/// Will return an error with the location of the problem if the bytes don't
+/// start with a valid bencoded value.
+pub fn decode_prefix(bytes: &[u8]) -> Result<(BValue, usize), BencodeError> {
+    let options = ParseOptions::default();
+    let mut decoder = Decoder::new(bytes, &options);
+    let value = decoder.decode_root()?;
+    Ok((value, decoder.pos))
+}
+
+/// Returns the byte range of the value of the `info` key of the top-level
+/// dictionary, or `None` if there isn't one.
+///
+/// Hashing these bytes gives the info-hash other clients calculate, even if
+/// the dictionary contains keys the parsers don't know about or its keys are
+/// not sorted.
+///
+/// # Errors
+///
+/// Will return an error with the location of the problem if the bytes are
+/// not valid bencode.
+pub fn info_span(bytes: &[u8]) -> Result<Option<Range<usize>>, BencodeError> {
+    // The events don't build the tree, so the memory used doesn't depend on
+    // the size of the torrent.
+    let mut info = InfoSpan::default();
+    bencode_events::parse(bytes, &ParseOptions::default(), &mut info)?;
+    Ok(info.span)
+}
+
+/// Returns a hexdump of the row containing `offset` and the previous one,
+/// with a marker under the byte at `offset`.
+#[must_use]
+pub fn hexdump(bytes: &[u8], offset: usize) -> String {
+    let row = offset / ROW_LEN;
+    let first_row = row.saturating_sub(1);
+
+    let mut output = String::new();
+
+    for row_index in first_row..=row {
+        let start = row_index * ROW_LEN;
+        let chunk = bytes
+            .get(start..bytes.len().min(start + ROW_LEN))
+            .unwrap_or(&[]);
+
+        let mut hex = String::new();
+        let mut ascii = String::new();
+        for (index, byte) in chunk.iter().enumerate() {
+            if index == ROW_LEN / 2 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{byte:02x} "));
+            ascii.push(if byte.is_ascii_graphic() || *byte == b' ' {
+                char::from(*byte)
+            } else {
+                '.'
+            });
+        }
+
+        output.push_str(&format!("{start:08x}  {hex:<49} |{ascii}|\n"));
+    }
+
+    let column = offset % ROW_LEN;
+    let marker_indent = 10 + column * 3 + usize::from(column >= ROW_LEN / 2);
+    output.push_str(&format!("{}^^", " ".repeat(marker_indent)));
+
+    output
+}
+
+/// A step in the path to the value being decoded.
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    options: &'a ParseOptions,
+    pos: usize,
+    path: Vec<Segment>,
+    depth: usize,
+    info_span: Option<Range<usize>>,
+    pieces_span: Option<Range<usize>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8], options: &'a ParseOptions) -> Self {
+        Self {
+            bytes,
+            options,
+            pos: 0,
+            path: vec![],
+            depth: 0,
+            info_span: None,
+            pieces_span: None,
+            diagnostics: vec![],
+        }
+    }
+
+    fn decode_root(&mut self) -> Result<BValue, BencodeError> {
+        let limit = self.options.max_input_size;
+        if self.bytes.len() > limit {
+            return Err(self.error_at(
+                limit,
+                BencodeErrorKind::InputTooLarge {
+                    size: self.bytes.len(),
+                    limit,
+                },
+            ));
+        }
+
+        self.decode_value()
+    }
+
+    fn decode_value(&mut self) -> Result<BValue, BencodeError> {
+        match self.peek()? {
+            b'i' => self.decode_int().map(BValue::Int),
+            b'0'..=b'9' => self.decode_bytes().map(BValue::Bytes),
+            b'l' => self.decode_list(),
+            b'd' => self.decode_dict(),
+            byte => Err(self.error(BencodeErrorKind::UnexpectedByte(byte))),
+        }
+    }
+
+    fn decode_int(&mut self) -> Result<i64, BencodeError> {
+        let start = self.pos;
+        self.pos += 1; // i
+
+        let digits_start = self.pos;
+        if self.peek()? == b'-' {
+            self.pos += 1;
+        }
+        while self.peek()?.is_ascii_digit() {
+            self.pos += 1;
+        }
+
+        let end = self.pos;
+        match self.peek()? {
+            b'e' => self.pos += 1,
+            byte => return Err(self.error(BencodeErrorKind::UnexpectedByte(byte))),
+        }
+
+        let digits = &self.bytes[digits_start..end];
+        let magnitude = digits.strip_prefix(b"-").unwrap_or(digits);
+        if magnitude.is_empty() {
+            return Err(self.error_at(start, BencodeErrorKind::InvalidInteger));
+        }
+
+        let text = String::from_utf8_lossy(digits);
+        let Ok(int) = text.parse() else {
+            return Err(self.error_at(start, BencodeErrorKind::IntegerOverflow));
+        };
+
+        if magnitude.len() > 1 && magnitude[0] == b'0' {
+            self.diagnostics.push(Diagnostic::error(
+                &self.path_string(),
+                format!("integer `i{text}e` at offset {start} has leading zeros"),
+            ));
+        } else if digits == b"-0" {
+            self.diagnostics.push(Diagnostic::error(
+                &self.path_string(),
+                format!("integer `i-0e` at offset {start} is negative zero"),
+            ));
+        }
+
+        Ok(int)
+    }
+
+    fn decode_bytes(&mut self) -> Result<Vec<u8>, BencodeError> {
+        let range = self.decode_bytes_range()?;
+        Ok(self.bytes[range].to_vec())
+    }
+
+    /// Decodes a byte string without copying it and returns its range in the
+    /// input.
+    fn decode_bytes_range(&mut self) -> Result<Range<usize>, BencodeError> {
+        let start = self.pos;
+        while self.peek()?.is_ascii_digit() {
+            self.pos += 1;
+        }
+
+        let length_end = self.pos;
+        match self.peek()? {
+            b':' => self.pos += 1,
+            byte => return Err(self.error(BencodeErrorKind::UnexpectedByte(byte))),
+        }
+
+        let length: usize = std::str::from_utf8(&self.bytes[start..length_end])
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(|| self.error_at(start, BencodeErrorKind::InvalidLength))?;
+
+        let limit = self.options.max_string_length;
+        if length > limit {
+            return Err(self.error_at(start, BencodeErrorKind::StringTooLong { length, limit }));
+        }
+
+        let end = self
+            .pos
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error_at(start, BencodeErrorKind::InvalidLength))?;
+
+        let range = self.pos..end;
+        self.pos = end;
+        Ok(range)
+    }
+
+    fn decode_list(&mut self) -> Result<BValue, BencodeError> {
+        self.enter()?;
+        self.pos += 1; // l
+
+        let mut list = vec![];
+        while self.peek()? != b'e' {
+            self.check_entries(list.len())?;
+            self.path.push(Segment::Index(list.len()));
+            list.push(self.decode_value()?);
+            self.path.pop();
+        }
+        self.pos += 1; // e
+        self.depth -= 1;
+
+        Ok(BValue::List(list))
+    }
+
+    fn decode_dict(&mut self) -> Result<BValue, BencodeError> {
+        self.enter()?;
+        self.pos += 1; // d
+
+        let mut dict = HashMap::new();
+        let mut previous_key: Option<Vec<u8>> = None;
+        while self.peek()? != b'e' {
+            self.check_entries(dict.len())?;
+            if !self.peek()?.is_ascii_digit() {
+                return Err(self.error(BencodeErrorKind::InvalidKey));
+            }
+            let key_offset = self.pos;
+            let key = self.decode_bytes()?;
+
+            let is_info = self.path.is_empty() && key == b"info";
+            let is_pieces = key == b"pieces"
+                && matches!(self.path.as_slice(), [Segment::Key(parent)] if parent == "info");
+
+            self.path
+                .push(Segment::Key(String::from_utf8_lossy(&key).into_owned()));
+
+            // Keys must be unique and sorted as raw byte strings.
+            if dict.contains_key(&key) {
+                self.diagnostics.push(Diagnostic::error(
+                    &self.path_string(),
+                    format!("duplicate key at offset {key_offset}, only the last value is kept"),
+                ));
+            } else if let Some(previous) = previous_key.as_ref().filter(|previous| key < **previous)
+            {
+                self.diagnostics.push(Diagnostic::warning(
+                    &self.path_string(),
+                    format!(
+                        "key at offset {key_offset} is not sorted, it comes after `{}`",
+                        String::from_utf8_lossy(previous)
+                    ),
+                ));
+            }
+            let start = self.pos;
+            let value = if is_pieces && self.peek()?.is_ascii_digit() {
+                let range = self.decode_bytes_range()?;
+                self.pieces_span = Some(range.clone());
+                (!self.options.skip_pieces || self.options.mode.is_strict())
+                    .then(|| BValue::Bytes(self.bytes[range].to_vec()))
+            } else {
+                Some(self.decode_value()?)
+            };
+            self.path.pop();
+
+            if is_info {
+                self.info_span = Some(start..self.pos);
+            }
+
+            previous_key = Some(key.clone());
+            if let Some(value) = value {
+                dict.insert(key, value);
+            }
+        }
+        self.pos += 1; // e
+        self.depth -= 1;
+
+        Ok(BValue::Dict(dict))
+    }
+
+    /// Enters a list or a dictionary, checking the nesting depth.
+    fn enter(&mut self) -> Result<(), BencodeError> {
+        let limit = self.options.max_depth;
+        if self.depth == limit {
+            return Err(self.error(BencodeErrorKind::TooDeep { limit }));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Checks that another entry can be added to a list or a dictionary with
+    /// `entries` entries.
+    fn check_entries(&self, entries: usize) -> Result<(), BencodeError> {
+        let limit = self.options.max_entries;
+        if entries == limit {
+            return Err(self.error(BencodeErrorKind::TooManyEntries { limit }));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Result<u8, BencodeError> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error(BencodeErrorKind::UnexpectedEof))
+    }
+
+    fn error(&self, kind: BencodeErrorKind) -> BencodeError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn error_at(&self, offset: usize, kind: BencodeErrorKind) -> BencodeError {
+        BencodeError {
+            kind,
+            offset,
+            path: self.path_string(),
+            hexdump: hexdump(self.bytes, offset),
+        }
+    }
+
+    fn path_string(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                Segment::Key(key) if path.is_empty() => path.push_str(key),
+                Segment::Key(key) => {
+                    path.push('.');
+                    path.push_str(key);
+                }
+                Segment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bencode::value::Value as BValue;
+
+    use super::{
+        decode, decode_prefix, decode_with_diagnostics, decode_with_options, hexdump, info_span,
+        BencodeErrorKind,
+    };
+    use crate::utils::diagnostics::{Diagnostic, Severity};
+    use crate::utils::parse_options::ParseOptions;
+
+    #[test]
+    fn it_should_decode_the_same_values_as_serde_bencode() {
+        let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+
+        assert_eq!(
+            decode(&bytes).unwrap(),
+            serde_bencode::from_bytes::<BValue>(&bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_return_the_length_of_the_first_value() {
+        let (value, len) = decode_prefix(b"d1:ai1eeDATA").unwrap();
+
+        assert!(matches!(value, BValue::Dict(_)));
+        assert_eq!(len, 8);
+    }
+
+    #[test]
+    fn it_should_return_the_span_of_the_top_level_info_dictionary() {
+        let bytes = b"d1:ad4:infoi1ee4:infod4:name1:b1:ai1eee";
+
+        assert_eq!(info_span(bytes).unwrap(), Some(21..38));
+        assert_eq!(&bytes[21..38], b"d4:name1:b1:ai1ee");
+        assert_eq!(info_span(b"d4:name1:ae").unwrap(), None);
+    }
+
+    #[test]
+    fn it_should_report_the_offset_and_path_of_an_invalid_byte() {
+        let error = decode(b"d4:infod5:filesld6:lengthi1xe4:pathl1:aeeeeee").unwrap_err();
+
+        assert_eq!(error.kind, BencodeErrorKind::UnexpectedByte(b'x'));
+        assert_eq!(error.offset, 27);
+        assert_eq!(error.path, "info.files[0].length");
+    }
+
+    #[test]
+    fn it_should_report_truncated_data() {
+        let error = decode(b"d4:name10:abce").unwrap_err();
+
+        assert_eq!(error.kind, BencodeErrorKind::InvalidLength);
+        assert_eq!(error.offset, 7);
+        assert_eq!(error.path, "name");
+
+        assert_eq!(
+            decode(b"d4:name").unwrap_err().kind,
+            BencodeErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn it_should_reject_keys_that_are_not_byte_strings() {
+        let error = decode(b"di1ei2ee").unwrap_err();
+
+        assert_eq!(error.kind, BencodeErrorKind::InvalidKey);
+        assert_eq!(error.offset, 1);
+    }
+
+    #[test]
+    fn it_should_print_a_hexdump_pointing_at_the_offset() {
+        let bytes = b"d4:infod5:filesld6:lengthi1xe4:pathl1:aeeeeee";
+
+        assert_eq!(
+            hexdump(bytes, 27),
+            [
+                "00000000  64 34 3a 69 6e 66 6f 64  35 3a 66 69 6c 65 73 6c  |d4:infod5:filesl|",
+                "00000010  64 36 3a 6c 65 6e 67 74  68 69 31 78 65 34 3a 70  |d6:lengthi1xe4:p|",
+                "                                            ^^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_should_enforce_the_limits_of_the_options() {
+        let bytes = b"d1:ald1:bli1ei2ei3eeee1:c5:abcdee";
+        let decode_with = |options: ParseOptions| decode_with_options(bytes, &options);
+
+        assert!(decode_with(ParseOptions::new()).is_ok());
+        assert_eq!(
+            decode_with(ParseOptions::new().max_input_size(10))
+                .unwrap_err()
+                .kind,
+            BencodeErrorKind::InputTooLarge {
+                size: bytes.len(),
+                limit: 10
+            }
+        );
+
+        let error = decode_with(ParseOptions::new().max_depth(3)).unwrap_err();
+        assert_eq!(error.kind, BencodeErrorKind::TooDeep { limit: 3 });
+        assert_eq!(error.path, "a[0].b");
+
+        let error = decode_with(ParseOptions::new().max_string_length(4)).unwrap_err();
+        assert_eq!(
+            error.kind,
+            BencodeErrorKind::StringTooLong {
+                length: 5,
+                limit: 4
+            }
+        );
+        assert_eq!(error.path, "c");
+
+        let error = decode_with(ParseOptions::new().max_entries(2)).unwrap_err();
+        assert_eq!(error.kind, BencodeErrorKind::TooManyEntries { limit: 2 });
+        assert_eq!(error.path, "a[0].b");
+    }
+
+    #[test]
+    fn it_should_not_overflow_the_stack_with_deeply_nested_lists() {
+        let bytes = [vec![b'l'; 100_000], vec![b'e'; 100_000]].concat();
+
+        assert!(matches!(
+            decode(&bytes).unwrap_err().kind,
+            BencodeErrorKind::TooDeep { .. }
+        ));
+    }
+
+    #[test]
+    fn it_should_report_unsorted_and_duplicate_keys() {
+        let bytes = b"d4:infod4:name1:a6:lengthi1e4:name1:bee";
+
+        let decoded = decode_with_diagnostics(bytes, &ParseOptions::new()).unwrap();
+
+        assert_eq!(
+            decoded.diagnostics,
+            vec![
+                Diagnostic::warning(
+                    "info.length",
+                    "key at offset 17 is not sorted, it comes after `name`"
+                ),
+                Diagnostic::error(
+                    "info.name",
+                    "duplicate key at offset 28, only the last value is kept"
+                ),
+            ]
+        );
+        assert_eq!(decoded.length, bytes.len());
+        assert!(
+            decode_with_diagnostics(b"d1:ai1e1:bi2ee", &ParseOptions::new())
+                .unwrap()
+                .diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.severity < Severity::Warning)
+        );
+    }
+
+    #[test]
+    fn it_should_report_integers_with_leading_zeros_or_negative_zero() {
+        let bytes = b"d1:ai03e1:bi-0e1:ci0e1:di-10ee";
+
+        let decoded = decode_with_diagnostics(bytes, &ParseOptions::new()).unwrap();
+
+        assert_eq!(
+            decoded.diagnostics,
+            vec![
+                Diagnostic::error("a", "integer `i03e` at offset 4 has leading zeros"),
+                Diagnostic::error("b", "integer `i-0e` at offset 11 is negative zero"),
+            ]
+        );
+        assert_eq!(decoded.value, decode(bytes).unwrap());
+    }
+
+    #[test]
+    fn it_should_report_integers_that_overflow() {
+        let error = decode(b"d6:lengthi9223372036854775808ee").unwrap_err();
+
+        assert_eq!(error.kind, BencodeErrorKind::IntegerOverflow);
+        assert_eq!(error.offset, 9);
+        assert_eq!(error.path, "length");
+        assert_eq!(
+            decode(b"i-9223372036854775808e").unwrap(),
+            BValue::Int(i64::MIN)
+        );
+        assert_eq!(
+            decode(b"i-e").unwrap_err().kind,
+            BencodeErrorKind::InvalidInteger
+        );
+    }
+}
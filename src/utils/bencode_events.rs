@@ -0,0 +1,535 @@
+//! Event-based bencode decoder.
+//!
+//! Unlike [`bencode::decode`](crate::utils::bencode::decode), it doesn't build
+//! a [`Value`](serde_bencode::value::Value) tree: it calls a [`Handler`] for
+//! every value it finds, with the byte strings borrowed from the input. A
+//! handler that only keeps the fields it needs extracts them in constant
+//! memory, even from torrents of hundreds of megabytes.
+//!
+//! The input is checked like the tree decoder does, with the limits of the
+//! [`ParseOptions`] and the same errors, but the problems reported by
+//! [`decode_with_diagnostics`](crate::utils::bencode::decode_with_diagnostics)
+//! are not.
+//!
+//! ```
+//! use std::ops::Range;
+//!
+//! use torrust_parse_torrent::utils::bencode_events::{parse, Handler};
+//! use torrust_parse_torrent::utils::parse_options::ParseOptions;
+//!
+//! /// Reads the name of the torrent, in `info.name`.
+//! #[derive(Default)]
+//! struct Name {
+//!     depth: usize,
+//!     next_is_name: bool,
+//!     name: Option<String>,
+//! }
+//!
+//! impl Handler<'_> for Name {
+//!     fn on_dict_start(&mut self, _offset: usize) {
+//!         self.depth += 1;
+//!     }
+//!     fn on_key(&mut self, key: &[u8]) {
+//!         self.next_is_name = self.depth == 2 && key == b"name";
+//!     }
+//!     fn on_bytes(&mut self, value: &[u8], _span: Range<usize>) {
+//!         if std::mem::take(&mut self.next_is_name) {
+//!             self.name = Some(String::from_utf8_lossy(value).into_owned());
+//!         }
+//!     }
+//!     fn on_dict_end(&mut self, _end: usize) {
+//!         self.depth -= 1;
+//!     }
+//! }
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//! let mut name = Name::default();
+//!
+//! parse(&bytes, &ParseOptions::default(), &mut name).unwrap();
+//!
+//! assert_eq!(name.name.as_deref(), Some("mandelbrot_set_01"));
+//! ```
+use std::ops::Range;
+
+use crate::utils::bencode::{hexdump, BencodeError, BencodeErrorKind};
+use crate::utils::parse_options::ParseOptions;
+
+/// Receives the values found by [`parse`], in the order they appear in the
+/// input, `'a`. All the methods do nothing by default.
+///
+/// The containers get a start and an end event, with the values they contain
+/// in between. In a dictionary each value is preceded by [`on_key`](Self::on_key).
+#[allow(unused_variables)]
+pub trait Handler<'a> {
+    /// An integer, with the byte range of its encoding.
+    fn on_int(&mut self, value: i64, span: Range<usize>) {}
+
+    /// A byte string that is not a dictionary key, with the byte range of
+    /// its encoding.
+    fn on_bytes(&mut self, value: &'a [u8], span: Range<usize>) {}
+
+    /// The start of a list, at `offset`.
+    fn on_list_start(&mut self, offset: usize) {}
+
+    /// The end of a list. `end` is the offset after its last byte.
+    fn on_list_end(&mut self, end: usize) {}
+
+    /// The start of a dictionary, at `offset`.
+    fn on_dict_start(&mut self, offset: usize) {}
+
+    /// A dictionary key. The next value is the one for this key.
+    fn on_key(&mut self, key: &'a [u8]) {}
+
+    /// The end of a dictionary. `end` is the offset after its last byte.
+    fn on_dict_end(&mut self, end: usize) {}
+}
+
+/// Decodes the first bencoded value in `bytes`, with the limits of the
+/// options, calling the handler for each value. It returns the length of the
+/// value. The bytes after it are not decoded.
+///
+/// The handler may have received some events when an error is returned.
+///
+/// # Errors
+///
+/// Will return an error with the location of the problem if the bytes are
+/// not valid bencode or exceed one of the limits.
+pub fn parse<'a, H: Handler<'a> + ?Sized>(
+    bytes: &'a [u8],
+    options: &ParseOptions,
+    handler: &mut H,
+) -> Result<usize, BencodeError> {
+    let limit = options.max_input_size;
+    let mut parser = Parser {
+        bytes,
+        options,
+        pos: 0,
+        path: vec![],
+    };
+
+    if bytes.len() > limit {
+        return Err(parser.error_at(
+            limit,
+            BencodeErrorKind::InputTooLarge {
+                size: bytes.len(),
+                limit,
+            },
+        ));
+    }
+
+    parser.parse_value(handler)?;
+
+    Ok(parser.pos)
+}
+
+/// A step in the path to the value being decoded. Keys are kept as offsets
+/// in the input, so that the path doesn't allocate a string per key.
+enum Segment {
+    Key(Range<usize>),
+    Index(usize),
+}
+
+struct Parser<'a, 'o> {
+    bytes: &'a [u8],
+    options: &'o ParseOptions,
+    pos: usize,
+    path: Vec<Segment>,
+}
+
+impl<'a> Parser<'a, '_> {
+    fn parse_value<H: Handler<'a> + ?Sized>(
+        &mut self,
+        handler: &mut H,
+    ) -> Result<(), BencodeError> {
+        let start = self.pos;
+        match self.peek()? {
+            b'i' => {
+                let value = self.parse_int()?;
+                handler.on_int(value, start..self.pos);
+            }
+            b'0'..=b'9' => {
+                let value = self.parse_bytes()?;
+                handler.on_bytes(&self.bytes[value], start..self.pos);
+            }
+            b'l' => {
+                self.enter()?;
+                handler.on_list_start(start);
+                self.pos += 1; // l
+
+                let mut entries = 0;
+                while self.peek()? != b'e' {
+                    self.check_entries(entries)?;
+                    self.path.push(Segment::Index(entries));
+                    self.parse_value(handler)?;
+                    self.path.pop();
+                    entries += 1;
+                }
+                self.pos += 1; // e
+
+                handler.on_list_end(self.pos);
+            }
+            b'd' => {
+                self.enter()?;
+                handler.on_dict_start(start);
+                self.pos += 1; // d
+
+                let mut entries = 0;
+                while self.peek()? != b'e' {
+                    self.check_entries(entries)?;
+                    if !self.peek()?.is_ascii_digit() {
+                        return Err(self.error(BencodeErrorKind::InvalidKey));
+                    }
+                    let key = self.parse_bytes()?;
+                    handler.on_key(&self.bytes[key.clone()]);
+
+                    self.path.push(Segment::Key(key));
+                    self.parse_value(handler)?;
+                    self.path.pop();
+                    entries += 1;
+                }
+                self.pos += 1; // e
+
+                handler.on_dict_end(self.pos);
+            }
+            byte => return Err(self.error(BencodeErrorKind::UnexpectedByte(byte))),
+        }
+
+        Ok(())
+    }
+
+    fn parse_int(&mut self) -> Result<i64, BencodeError> {
+        let start = self.pos;
+        self.pos += 1; // i
+
+        let digits_start = self.pos;
+        if self.peek()? == b'-' {
+            self.pos += 1;
+        }
+        while self.peek()?.is_ascii_digit() {
+            self.pos += 1;
+        }
+
+        let end = self.pos;
+        match self.peek()? {
+            b'e' => self.pos += 1,
+            byte => return Err(self.error(BencodeErrorKind::UnexpectedByte(byte))),
+        }
+
+        let digits = &self.bytes[digits_start..end];
+        if digits.strip_prefix(b"-").unwrap_or(digits).is_empty() {
+            return Err(self.error_at(start, BencodeErrorKind::InvalidInteger));
+        }
+
+        // The digits are ASCII.
+        String::from_utf8_lossy(digits)
+            .parse()
+            .map_err(|_| self.error_at(start, BencodeErrorKind::IntegerOverflow))
+    }
+
+    /// Parses a byte string and returns its range in the input.
+    fn parse_bytes(&mut self) -> Result<Range<usize>, BencodeError> {
+        let start = self.pos;
+        while self.peek()?.is_ascii_digit() {
+            self.pos += 1;
+        }
+
+        let length_end = self.pos;
+        match self.peek()? {
+            b':' => self.pos += 1,
+            byte => return Err(self.error(BencodeErrorKind::UnexpectedByte(byte))),
+        }
+
+        let length: usize = std::str::from_utf8(&self.bytes[start..length_end])
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(|| self.error_at(start, BencodeErrorKind::InvalidLength))?;
+
+        let limit = self.options.max_string_length;
+        if length > limit {
+            return Err(self.error_at(start, BencodeErrorKind::StringTooLong { length, limit }));
+        }
+
+        let end = self
+            .pos
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error_at(start, BencodeErrorKind::InvalidLength))?;
+
+        let range = self.pos..end;
+        self.pos = end;
+        Ok(range)
+    }
+
+    /// Enters a list or a dictionary, checking the nesting depth. The path
+    /// has a segment per container the value is in, the root excluded.
+    fn enter(&self) -> Result<(), BencodeError> {
+        let limit = self.options.max_depth;
+        if self.path.len() == limit {
+            return Err(self.error(BencodeErrorKind::TooDeep { limit }));
+        }
+        Ok(())
+    }
+
+    /// Checks that another entry can be added to a list or a dictionary with
+    /// `entries` entries.
+    fn check_entries(&self, entries: usize) -> Result<(), BencodeError> {
+        let limit = self.options.max_entries;
+        if entries == limit {
+            return Err(self.error(BencodeErrorKind::TooManyEntries { limit }));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Result<u8, BencodeError> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error(BencodeErrorKind::UnexpectedEof))
+    }
+
+    fn error(&self, kind: BencodeErrorKind) -> BencodeError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn error_at(&self, offset: usize, kind: BencodeErrorKind) -> BencodeError {
+        BencodeError {
+            kind,
+            offset,
+            path: self.path_string(),
+            hexdump: hexdump(self.bytes, offset),
+        }
+    }
+
+    fn path_string(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                Segment::Key(key) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(&String::from_utf8_lossy(&self.bytes[key.clone()]));
+                }
+                Segment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+}
+
+/// Finds the byte range of the value of the `info` key of the top-level
+/// dictionary, like [`bencode::info_span`](crate::utils::bencode::info_span),
+/// and the one of the `pieces` in it, like [`Decoded`](crate::utils::bencode::Decoded)
+/// without building the tree.
+#[derive(Default)]
+pub(crate) struct InfoSpan {
+    /// Number of containers the next value is in.
+    depth: usize,
+    /// Whether the next value is the one of the top-level `info` key.
+    next_is_info: bool,
+    /// Whether the next value is the one of the `pieces` key of the `info`.
+    next_is_pieces: bool,
+    /// Start of the `info` value while it's being parsed.
+    start: Option<usize>,
+    pub(crate) span: Option<Range<usize>>,
+    /// The `pieces` without their length, when they are a byte string.
+    pub(crate) pieces_span: Option<Range<usize>>,
+}
+
+impl InfoSpan {
+    /// Records the start of a value. It returns whether it's the `info` one.
+    fn start(&mut self, offset: usize) -> bool {
+        self.next_is_pieces = false;
+        let is_info = std::mem::take(&mut self.next_is_info);
+        if is_info {
+            self.start = Some(offset);
+        }
+        is_info
+    }
+
+    /// Records the end of a container.
+    fn end(&mut self, end: usize) {
+        self.depth -= 1;
+        if self.depth == 1 {
+            if let Some(start) = self.start.take() {
+                self.span = Some(start..end);
+            }
+        }
+    }
+}
+
+impl Handler<'_> for InfoSpan {
+    fn on_int(&mut self, _value: i64, span: Range<usize>) {
+        if self.start(span.start) {
+            self.start = None;
+            self.span = Some(span);
+        }
+    }
+
+    fn on_bytes(&mut self, value: &[u8], span: Range<usize>) {
+        if self.next_is_pieces {
+            self.pieces_span = Some(span.end - value.len()..span.end);
+        }
+        if self.start(span.start) {
+            self.start = None;
+            self.span = Some(span);
+        }
+    }
+
+    fn on_list_start(&mut self, offset: usize) {
+        self.start(offset);
+        self.depth += 1;
+    }
+
+    fn on_list_end(&mut self, end: usize) {
+        self.end(end);
+    }
+
+    fn on_dict_start(&mut self, offset: usize) {
+        self.start(offset);
+        self.depth += 1;
+    }
+
+    fn on_key(&mut self, key: &[u8]) {
+        self.next_is_info = self.depth == 1 && key == b"info";
+        self.next_is_pieces = self.depth == 2 && self.start.is_some() && key == b"pieces";
+    }
+
+    fn on_dict_end(&mut self, end: usize) {
+        self.end(end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::{parse, Handler, InfoSpan};
+    use crate::utils::bencode::{self, BencodeErrorKind};
+    use crate::utils::parse_options::ParseOptions;
+
+    /// Records the events as strings.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Handler<'_> for Recorder {
+        fn on_int(&mut self, value: i64, span: Range<usize>) {
+            self.0.push(format!("int {value} {span:?}"));
+        }
+        fn on_bytes(&mut self, value: &[u8], span: Range<usize>) {
+            self.0
+                .push(format!("bytes {} {span:?}", String::from_utf8_lossy(value)));
+        }
+        fn on_list_start(&mut self, offset: usize) {
+            self.0.push(format!("list start {offset}"));
+        }
+        fn on_list_end(&mut self, end: usize) {
+            self.0.push(format!("list end {end}"));
+        }
+        fn on_dict_start(&mut self, offset: usize) {
+            self.0.push(format!("dict start {offset}"));
+        }
+        fn on_key(&mut self, key: &[u8]) {
+            self.0.push(format!("key {}", String::from_utf8_lossy(key)));
+        }
+        fn on_dict_end(&mut self, end: usize) {
+            self.0.push(format!("dict end {end}"));
+        }
+    }
+
+    #[test]
+    fn it_should_report_the_values_in_order() {
+        let mut recorder = Recorder::default();
+
+        let length = parse(
+            b"d1:ai-1e1:bl2:xyeeDATA",
+            &ParseOptions::default(),
+            &mut recorder,
+        )
+        .unwrap();
+
+        assert_eq!(length, 18);
+        assert_eq!(
+            recorder.0,
+            [
+                "dict start 0",
+                "key a",
+                "int -1 4..8",
+                "key b",
+                "list start 11",
+                "bytes xy 12..16",
+                "list end 17",
+                "dict end 18",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_return_the_same_errors_as_the_tree_decoder() {
+        let options = ParseOptions::new().max_depth(3).max_entries(2);
+        let inputs: [&[u8]; 8] = [
+            b"d4:infod5:filesld6:lengthi1xe4:pathl1:aeeeeee",
+            b"d4:name10:abce",
+            b"d4:name",
+            b"i-e",
+            b"i99999999999999999999e",
+            b"di1ei2ee",
+            b"llllee",
+            b"li1ei2ei3ee",
+        ];
+
+        for input in inputs {
+            assert_eq!(
+                parse(input, &options, &mut Recorder::default()).unwrap_err(),
+                bencode::decode_with_options(input, &options).unwrap_err(),
+                "{}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_find_the_span_of_the_top_level_info_value() {
+        let bytes = b"d1:ad4:infoi1ee4:infod4:name1:b1:ai1eee";
+        let mut info = InfoSpan::default();
+
+        parse(bytes, &ParseOptions::default(), &mut info).unwrap();
+
+        assert_eq!(info.span, Some(21..38));
+
+        let mut info = InfoSpan::default();
+        parse(b"d4:infoi1ee", &ParseOptions::default(), &mut info).unwrap();
+        assert_eq!(info.span, Some(7..10));
+    }
+
+    #[test]
+    fn it_should_find_the_span_of_the_pieces_like_the_tree_decoder() {
+        let bytes = b"d1:ad6:pieces1:xe4:infod1:ad6:pieces1:xe6:pieces3:abcee";
+        let mut info = InfoSpan::default();
+
+        parse(bytes, &ParseOptions::default(), &mut info).unwrap();
+
+        assert_eq!(&bytes[info.pieces_span.clone().unwrap()], b"abc");
+        assert_eq!(
+            info.pieces_span,
+            bencode::decode_with_diagnostics(bytes, &ParseOptions::default())
+                .unwrap()
+                .pieces_span
+        );
+    }
+
+    #[test]
+    fn it_should_enforce_the_input_size_limit() {
+        let error = parse(
+            b"i1e",
+            &ParseOptions::new().max_input_size(2),
+            &mut Recorder::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            BencodeErrorKind::InputTooLarge { size: 3, limit: 2 }
+        );
+    }
+}
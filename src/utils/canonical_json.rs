@@ -0,0 +1,134 @@
+//! Canonical JSON export of a torrent file.
+//!
+//! The output is designed to compare two torrent files with plain `diff`:
+//!
+//! - Dictionary keys are sorted.
+//! - Byte strings that are valid UTF-8 text are exported as JSON strings.
+//!   Binary byte strings are exported as `{"hex": "<lowercase hex>"}`.
+//! - Hashes are lowercase hex strings.
+//! - The `pieces` field is summarized (length, number of pieces and the SHA-1
+//!   of the whole field) instead of exported byte by byte.
+//!
+//! The document contains the info-hash and the whole bencoded tree, including
+//! non-standard keys.
+use serde_bencode::value::Value as BValue;
+use serde_json::{json, Map, Value};
+use sha1::{Digest, Sha1};
+
+/// Returns the canonical JSON document for the bencoded torrent.
+#[must_use]
+pub fn to_canonical_json(metainfo: &BValue) -> Value {
+    let info_hash = match metainfo {
+        BValue::Dict(dict) => dict
+            .get(b"info".as_slice())
+            .and_then(|info| serde_bencode::to_bytes(info).ok())
+            .map(|info_bytes| sha1_hex(&info_bytes)),
+        _ => None,
+    };
+
+    json!({
+        "info_hash": info_hash,
+        "metainfo": convert(metainfo, None),
+    })
+}
+
+/// Returns the canonical JSON document as a pretty printed string ending with
+/// a new line.
+///
+/// # Panics
+///
+/// This function will panic if the JSON value cannot be serialized, which
+/// should never happen.
+#[must_use]
+pub fn to_canonical_json_string(metainfo: &BValue) -> String {
+    let mut output = serde_json::to_string_pretty(&to_canonical_json(metainfo))
+        .expect("canonical JSON should be serializable");
+    output.push('\n');
+    output
+}
+
+fn convert(value: &BValue, key: Option<&[u8]>) -> Value {
+    match value {
+        BValue::Int(int) => Value::from(*int),
+        BValue::Bytes(bytes) => match key {
+            Some(b"pieces") => summarize_pieces(bytes),
+            Some(b"md5sum" | b"root hash") => bytes_to_json(bytes, true),
+            _ => bytes_to_json(bytes, false),
+        },
+        BValue::List(list) => Value::Array(list.iter().map(|item| convert(item, None)).collect()),
+        BValue::Dict(dict) => {
+            let mut entries: Vec<_> = dict.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+
+            let mut map = Map::new();
+            for (key, value) in entries {
+                map.insert(
+                    String::from_utf8_lossy(key).into_owned(),
+                    convert(value, Some(key)),
+                );
+            }
+            Value::Object(map)
+        }
+    }
+}
+
+fn bytes_to_json(bytes: &[u8], lowercase: bool) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+            if lowercase {
+                Value::String(text.to_lowercase())
+            } else {
+                Value::String(text.to_owned())
+            }
+        }
+        _ => json!({ "hex": hex(bytes) }),
+    }
+}
+
+fn summarize_pieces(pieces: &[u8]) -> Value {
+    json!({
+        "length": pieces.len(),
+        "count": pieces.len() / 20,
+        "sha1": sha1_hex(pieces),
+    })
+}
+
+fn sha1_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    hex(&hasher.finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    crate::utils::hex::from_bytes(bytes).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bencode::value::Value as BValue;
+
+    use super::to_canonical_json_string;
+
+    fn canonical(bytes: &[u8]) -> String {
+        let value: BValue = serde_bencode::from_bytes(bytes).unwrap();
+        to_canonical_json_string(&value)
+    }
+
+    #[test]
+    fn it_should_sort_keys_and_export_binary_fields_as_hex() {
+        let output = canonical(b"d1:bi1e1:a2:\xff\x00e");
+
+        assert_eq!(
+            output,
+            "{\n  \"info_hash\": null,\n  \"metainfo\": {\n    \"a\": {\n      \"hex\": \"ff00\"\n    },\n    \"b\": 1\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn it_should_summarize_the_pieces_and_include_the_lowercase_info_hash() {
+        let output = canonical(&std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap());
+
+        assert!(output.contains("\"count\": 19"));
+        assert!(output.contains("\"info_hash\": \"0c90fbf036e28370c1ec773401bc7620146b1d48\""));
+    }
+}
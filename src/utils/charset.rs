@@ -0,0 +1,70 @@
+//! Decoding of text in legacy encodings.
+//!
+//! The top-level `encoding` key tells which encoding the names, paths and
+//! comment of a torrent use, for example `GBK` or `Shift_JIS`. Decoding
+//! anything other than UTF-8 requires the `charset` feature. Without it,
+//! every text is decoded as UTF-8.
+use std::borrow::Cow;
+
+/// Returns `true` if text in the `charset` encoding can be decoded. The
+/// labels are the ones defined by the WHATWG Encoding Standard, and they are
+/// case-insensitive.
+#[must_use]
+pub fn is_supported(charset: &str) -> bool {
+    #[cfg(feature = "charset")]
+    {
+        encoding_rs::Encoding::for_label(charset.as_bytes()).is_some()
+    }
+    #[cfg(not(feature = "charset"))]
+    {
+        charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("utf8")
+    }
+}
+
+/// Decodes `bytes` from the `charset` encoding, or from UTF-8 if it's `None`
+/// or not supported.
+///
+/// It also returns whether any malformed sequence had to be replaced with
+/// `U+FFFD`.
+#[must_use]
+pub fn decode<'a>(bytes: &'a [u8], charset: Option<&str>) -> (Cow<'a, str>, bool) {
+    #[cfg(feature = "charset")]
+    if let Some(encoding) =
+        charset.and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+    {
+        let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+        return (text, had_errors);
+    }
+    #[cfg(not(feature = "charset"))]
+    let _ = charset;
+
+    let text = String::from_utf8_lossy(bytes);
+    let lossy = matches!(text, Cow::Owned(_));
+    (text, lossy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, is_supported};
+
+    #[test]
+    fn it_should_decode_utf8_by_default() {
+        assert_eq!(
+            decode("\u{554a}".as_bytes(), None),
+            ("\u{554a}".into(), false)
+        );
+        assert_eq!(decode(b"\xb0\xa1", None), ("\u{FFFD}\u{FFFD}".into(), true));
+        assert!(is_supported("UTF-8"));
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn it_should_decode_legacy_encodings() {
+        assert_eq!(decode(b"\xb0\xa1", Some("GBK")), ("\u{554a}".into(), false));
+        assert_eq!(
+            decode(b"\x82\xa0", Some("shift_jis")),
+            ("\u{3042}".into(), false)
+        );
+        assert!(!is_supported("klingon"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Differential testing of the two parsers: the verbose one
+//! ([`parse_torrent_verbose`]) and the serde one ([`parse_torrent`]).
+//!
+//! Both should produce the same [`Torrent`](crate::models::torrent_file::Torrent)
+//! for the same input. [`compare_parsers`] runs both and reports every field
+//! where their [JSON exports](crate::utils::json) differ, and the inputs only
+//! one of them accepts.
+//!
+//! ```
+//! use torrust_parse_torrent::utils::compare::compare_parsers;
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//!
+//! assert!(compare_parsers(&bytes).is_empty());
+//! ```
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::utils::{bencode, json, parse_torrent, parse_torrent_verbose};
+
+/// A difference between the outputs of the two parsers.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Difference {
+    /// Only one of the parsers accepts the input. It holds the error of the
+    /// other one.
+    Outcome {
+        verbose_error: Option<String>,
+        serde_error: Option<String>,
+    },
+    /// Both parsers accept the input but the value of a field differs. The
+    /// field is the path of bencode keys and list indexes from the root, and
+    /// the values are `None` when a parser doesn't set the field.
+    Field {
+        field: String,
+        verbose: Option<Value>,
+        serde: Option<Value>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| {
+            value
+                .as_ref()
+                .map_or_else(|| "(none)".to_owned(), ToString::to_string)
+        };
+
+        match self {
+            Self::Outcome {
+                verbose_error: Some(error),
+                ..
+            } => write!(f, "only the serde parser accepts it, verbose: {error}"),
+            Self::Outcome {
+                serde_error: Some(error),
+                ..
+            } => write!(f, "only the verbose parser accepts it, serde: {error}"),
+            Self::Outcome { .. } => write!(f, "the parsers disagree"),
+            Self::Field {
+                field,
+                verbose,
+                serde,
+            } => write!(
+                f,
+                "`{field}`: verbose {}, serde {}",
+                show(verbose),
+                show(serde)
+            ),
+        }
+    }
+}
+
+/// Parses the torrent with both parsers, in lenient mode, and returns their
+/// differences. It's empty if they agree, including when both reject the
+/// input.
+#[must_use]
+pub fn compare_parsers(bytes: &[u8]) -> Vec<Difference> {
+    let verbose = bencode::decode(bytes)
+        .map_err(|e| e.to_string())
+        .and_then(|value| parse_torrent_verbose::decode_torrent(value).map_err(|e| e.to_string()));
+    let serde = parse_torrent::decode_torrent(bytes).map_err(|e| e.to_string());
+
+    match (verbose, serde) {
+        (Ok(verbose), Ok(serde)) => {
+            let mut differences = vec![];
+            compare_values(
+                "",
+                Some(&json::to_json(&verbose)),
+                Some(&json::to_json(&serde)),
+                &mut differences,
+            );
+            differences
+        }
+        (Err(_), Err(_)) => vec![],
+        (verbose, serde) => vec![Difference::Outcome {
+            verbose_error: verbose.err(),
+            serde_error: serde.err(),
+        }],
+    }
+}
+
+/// Compares two JSON values, descending into the objects and arrays to
+/// report the innermost fields that differ.
+fn compare_values(
+    field: &str,
+    verbose: Option<&Value>,
+    serde: Option<&Value>,
+    differences: &mut Vec<Difference>,
+) {
+    let child = |key: &str| {
+        if field.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{field}.{key}")
+        }
+    };
+
+    match (verbose, serde) {
+        (Some(Value::Object(verbose)), Some(Value::Object(serde))) => {
+            let mut keys: Vec<&String> = verbose.keys().chain(serde.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                compare_values(&child(key), verbose.get(key), serde.get(key), differences);
+            }
+        }
+        (Some(Value::Array(verbose)), Some(Value::Array(serde))) => {
+            for index in 0..verbose.len().max(serde.len()) {
+                compare_values(
+                    &child(&index.to_string()),
+                    verbose.get(index),
+                    serde.get(index),
+                    differences,
+                );
+            }
+        }
+        (verbose, serde) if verbose != serde => differences.push(Difference::Field {
+            field: field.to_owned(),
+            verbose: verbose.cloned(),
+            serde: serde.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::{compare_parsers, compare_values, Difference};
+
+    #[test]
+    fn it_should_find_no_differences_between_the_parsers_in_the_fixtures() {
+        // The serde parser can't decode the `nodes` of these torrents.
+        let known = [
+            "MC_GRID.zip-3cd18ff2d3eec881207dcc5ca5a2c3a2a3afe462.torrent",
+            "not-working-with-two-nodes.torrent",
+        ];
+
+        for directory in ["torrents", "tests/fixtures/torrents"] {
+            for entry in fs::read_dir(directory).unwrap() {
+                let path = entry.unwrap().path();
+                if path
+                    .extension()
+                    .is_none_or(|extension| extension != "torrent")
+                {
+                    continue;
+                }
+
+                let differences = compare_parsers(&fs::read(&path).unwrap());
+
+                let file_name = path.file_name().unwrap().to_string_lossy();
+                if known.contains(&file_name.as_ref()) {
+                    assert!(
+                        matches!(
+                            differences.as_slice(),
+                            [Difference::Outcome {
+                                verbose_error: None,
+                                serde_error: Some(_)
+                            }]
+                        ),
+                        "{file_name}: {differences:?}"
+                    );
+                } else {
+                    assert!(differences.is_empty(), "{file_name}: {differences:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_report_the_innermost_fields_that_differ() {
+        let mut differences = vec![];
+
+        compare_values(
+            "",
+            Some(&json!({"info": {"name": "a", "files": [1, 2]}, "comment": "x"})),
+            Some(&json!({"info": {"name": "b", "files": [1]}, "comment": "x"})),
+            &mut differences,
+        );
+
+        assert_eq!(
+            differences,
+            [
+                Difference::Field {
+                    field: "info.files.1".to_owned(),
+                    verbose: Some(json!(2)),
+                    serde: None,
+                },
+                Difference::Field {
+                    field: "info.name".to_owned(),
+                    verbose: Some(json!("a")),
+                    serde: Some(json!("b")),
+                },
+            ]
+        );
+        assert_eq!(
+            differences[1].to_string(),
+            r#"`info.name`: verbose "a", serde "b""#
+        );
+    }
+
+    #[test]
+    fn it_should_report_inputs_only_one_parser_accepts() {
+        // The verbose parser ignores a `nodes` key that isn't a list.
+        let bytes = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae5:nodes1:xe";
+
+        let differences = compare_parsers(bytes);
+
+        assert!(matches!(
+            differences.as_slice(),
+            [Difference::Outcome {
+                verbose_error: None,
+                serde_error: Some(_)
+            }]
+        ));
+        assert!(compare_parsers(b"not bencode").is_empty());
+    }
+}
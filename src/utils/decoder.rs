@@ -0,0 +1,295 @@
+//! The parsers behind [`parse_torrent_with_options`](crate::parse_torrent_with_options),
+//! selected with [`ParseOptions::backend`].
+//!
+//! Both check the bytes with the limits of the options first, and in strict
+//! mode they reject the torrents that violate the specification, so they
+//! only differ in how they build the [`Torrent`]:
+//!
+//! - [`SerdeDecoder`] deserializes it with `serde_bencode`. In lenient mode
+//!   it only scans the bytes before, without decoding them into a tree, but
+//!   its errors don't say which key is wrong.
+//! - [`VerboseDecoder`] walks the decoded bencode with
+//!   [`parse_torrent_verbose`]. It names the key and the type of the
+//!   offending values, and in lenient mode it skips the values it can't
+//!   decode instead of failing.
+//!
+//! In lenient mode [`SerdeDecoder`] is faster on most torrents, but
+//! `serde_bencode` is slow with large byte strings, so [`VerboseDecoder`] is
+//! faster on torrents with many pieces. The `parse` benchmark compares them.
+//!
+//! ```
+//! use torrust_parse_torrent::parse_torrent_with_options;
+//! use torrust_parse_torrent::utils::decoder::Backend;
+//! use torrust_parse_torrent::utils::parse_options::ParseOptions;
+//!
+//! let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+//!
+//! let options = ParseOptions::new().backend(Backend::Verbose);
+//! let torrent = parse_torrent_with_options(&bytes, &options).unwrap();
+//!
+//! assert_eq!(torrent.info.name, "mandelbrot_set_01");
+//! ```
+use std::ops::Range;
+
+use crate::error::Error;
+use crate::models::torrent_file::Torrent;
+use crate::utils::bencode::{self, Decoded};
+use crate::utils::bencode_events::{self, InfoSpan};
+use crate::utils::diagnostics::Severity;
+use crate::utils::parse_options::ParseOptions;
+use crate::utils::parse_torrent_verbose::{self, TorrentParseError};
+use crate::utils::validate;
+
+/// Decodes a torrent from its bencoded bytes.
+pub trait TorrentDecoder {
+    /// Decodes the torrent with the mode and the limits of the options. The
+    /// [`raw_info`](Torrent::raw_info) holds the original bytes of the
+    /// `info` dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`parse_torrent_with_options`](crate::parse_torrent_with_options).
+    fn decode(&self, bytes: &[u8], options: &ParseOptions) -> Result<Torrent, Error>;
+}
+
+/// The parser used to build the [`Torrent`], see the [module](self)
+/// documentation.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Backend {
+    /// [`SerdeDecoder`].
+    #[default]
+    Serde,
+    /// [`VerboseDecoder`].
+    Verbose,
+}
+
+impl Backend {
+    /// Returns the decoder of the backend.
+    #[must_use]
+    pub fn decoder(self) -> &'static dyn TorrentDecoder {
+        match self {
+            Self::Serde => &SerdeDecoder,
+            Self::Verbose => &VerboseDecoder,
+        }
+    }
+}
+
+/// Deserializes the torrent with `serde_bencode`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SerdeDecoder;
+
+impl TorrentDecoder for SerdeDecoder {
+    fn decode(&self, bytes: &[u8], options: &ParseOptions) -> Result<Torrent, Error> {
+        // `serde_bencode` has no limits and its errors don't say where the
+        // problem is, so the bytes are checked first with the limits, which
+        // reports malformed bencode with its location. Only the strict mode
+        // needs the tree, to validate it.
+        let (info_span, pieces_span) = if options.mode.is_strict() {
+            let decoded = decode_checked(bytes, options)?;
+            (decoded.info_span, decoded.pieces_span)
+        } else {
+            let mut spans = InfoSpan::default();
+            bencode_events::parse(bytes, options, &mut spans)?;
+            (spans.span, spans.pieces_span)
+        };
+
+        let mut torrent = match pieces_span.as_ref() {
+            // The pieces are cut out of a copy of the input, which is small
+            // without them, instead of being copied by `serde_bencode`.
+            Some(pieces) if options.skip_pieces && !options.mode.is_strict() => {
+                serde_bencode::from_bytes::<Torrent>(&without_pieces(bytes, pieces))?
+            }
+            _ => serde_bencode::from_bytes::<Torrent>(bytes)?,
+        };
+
+        torrent.raw_info = info_span.map(|span| bytes[span].to_vec());
+        skip_pieces(&mut torrent, pieces_span, options);
+
+        Ok(torrent)
+    }
+}
+
+/// Builds the torrent from the decoded bencode with [`parse_torrent_verbose`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VerboseDecoder;
+
+impl TorrentDecoder for VerboseDecoder {
+    fn decode(&self, bytes: &[u8], options: &ParseOptions) -> Result<Torrent, Error> {
+        let Decoded {
+            value,
+            info_span,
+            pieces_span,
+            ..
+        } = decode_checked(bytes, options)?;
+
+        let mut torrent = parse_torrent_verbose::decode_torrent_with_mode(value, options.mode)?;
+
+        torrent.raw_info = info_span.map(|span| bytes[span].to_vec());
+        skip_pieces(&mut torrent, pieces_span, options);
+
+        Ok(torrent)
+    }
+}
+
+/// Returns a copy of the input where the `pieces` are an empty byte string.
+fn without_pieces(bytes: &[u8], pieces: &Range<usize>) -> Vec<u8> {
+    // The length of the pieces is the digits before the `:` that precedes
+    // them. The byte before the digits is the last one of the `pieces` key.
+    let length_start = bytes[..pieces.start - 1]
+        .iter()
+        .rposition(|byte| !byte.is_ascii_digit())
+        .map_or(0, |index| index + 1);
+
+    [&bytes[..length_start], b"0:", &bytes[pieces.end..]].concat()
+}
+
+/// Leaves the pieces out of the torrent with [`ParseOptions::skip_pieces`].
+fn skip_pieces(torrent: &mut Torrent, pieces_span: Option<Range<usize>>, options: &ParseOptions) {
+    if options.skip_pieces {
+        torrent.info.pieces = None;
+        torrent.pieces_span = pieces_span;
+    }
+}
+
+/// Decodes the bencode with the limits of the options and, in strict mode,
+/// rejects the torrents that violate the specification, have duplicate keys
+/// or have bytes after them.
+fn decode_checked(bytes: &[u8], options: &ParseOptions) -> Result<Decoded, Error> {
+    let mut decoded = bencode::decode_with_diagnostics(bytes, options)?;
+
+    if options.mode.is_strict() {
+        if let Some(diagnostic) = std::mem::take(&mut decoded.diagnostics)
+            .into_iter()
+            .chain(validate::validate(&decoded.value))
+            .chain(validate::validate_text(&decoded.value, options.mode))
+            .chain(validate::validate_trailing_data(
+                bytes.len() - decoded.length,
+                options.mode,
+            ))
+            .find(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            return Err(TorrentParseError::Invalid(diagnostic).into());
+        }
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Backend;
+    use crate::builder::TorrentBuilder;
+    use crate::error::Error;
+    use crate::models::torrent_file::{ProtocolVersion, Torrent, TorrentInfo};
+    use crate::utils::parse_mode::ParseMode;
+    use crate::utils::parse_options::ParseOptions;
+
+    #[test]
+    fn it_should_decode_the_same_torrent_with_both_backends() {
+        let bytes = std::fs::read("tests/fixtures/torrents/working-with-one-node.torrent").unwrap();
+        let options = ParseOptions::new();
+
+        let serde = Backend::Serde.decoder().decode(&bytes, &options).unwrap();
+        let verbose = Backend::Verbose.decoder().decode(&bytes, &options).unwrap();
+
+        assert_eq!(serde, verbose);
+        assert!(verbose.raw_info.is_some());
+    }
+
+    #[test]
+    fn it_should_skip_the_values_it_cannot_decode_with_the_verbose_backend() {
+        // `nodes` is a string instead of a list.
+        let bytes = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae5:nodes1:xe";
+        let options = ParseOptions::new();
+
+        assert!(matches!(
+            Backend::Serde.decoder().decode(bytes, &options),
+            Err(Error::Decode(_))
+        ));
+        assert_eq!(
+            Backend::Verbose
+                .decoder()
+                .decode(bytes, &options)
+                .unwrap()
+                .nodes,
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_leave_the_pieces_out_with_skip_pieces() {
+        let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+        let full = Backend::Serde
+            .decoder()
+            .decode(&bytes, &ParseOptions::new())
+            .unwrap();
+        let pieces = full.info.pieces.as_ref().unwrap().as_slice();
+
+        for backend in [Backend::Serde, Backend::Verbose] {
+            for mode in [ParseMode::Lenient, ParseMode::Strict] {
+                let options = ParseOptions::new().mode(mode).skip_pieces(true);
+
+                let torrent = backend.decoder().decode(&bytes, &options).unwrap();
+
+                assert_eq!(torrent.info.pieces, None);
+                assert_eq!(&bytes[torrent.pieces_span.clone().unwrap()], pieces);
+                assert_eq!(torrent.info_hash(), full.info_hash());
+                assert_eq!(
+                    Torrent {
+                        info: TorrentInfo {
+                            pieces: full.info.pieces.clone(),
+                            ..torrent.info
+                        },
+                        pieces_span: None,
+                        ..torrent
+                    },
+                    full
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_count_the_pieces_of_a_torrent_parsed_without_them() {
+        let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+        let full = Backend::Serde
+            .decoder()
+            .decode(&bytes, &ParseOptions::new())
+            .unwrap();
+
+        for backend in [Backend::Serde, Backend::Verbose] {
+            let options = ParseOptions::new().skip_pieces(true);
+
+            let torrent = backend.decoder().decode(&bytes, &options).unwrap();
+
+            assert_eq!(torrent.info.piece_count(), 0);
+            assert_eq!(torrent.piece_count(), full.info.piece_count());
+            assert_eq!(torrent.to_string(), full.to_string());
+        }
+    }
+
+    #[test]
+    fn it_should_keep_the_protocol_version_of_a_hybrid_torrent_without_its_pieces() {
+        let path = std::env::temp_dir().join(format!(
+            "torrust-parse-torrent-decoder-hybrid-{}",
+            std::process::id()
+        ));
+        std::fs::write(&path, vec![1; 20000]).unwrap();
+        let bytes = TorrentBuilder::new(&path)
+            .piece_length(16384)
+            .version(ProtocolVersion::Hybrid)
+            .build()
+            .unwrap()
+            .encode()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for backend in [Backend::Serde, Backend::Verbose] {
+            let options = ParseOptions::new().skip_pieces(true);
+
+            let torrent = backend.decoder().decode(&bytes, &options).unwrap();
+
+            assert_eq!(torrent.protocol_version(), ProtocolVersion::Hybrid);
+        }
+    }
+}
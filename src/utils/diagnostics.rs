@@ -0,0 +1,83 @@
+//! Problems found in a torrent file that don't prevent it from being read.
+use std::fmt;
+
+use serde_derive::Serialize;
+
+/// How serious a problem is.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Information about the torrent that does not need any action.
+    Info,
+    /// The torrent works but it does not follow a recommendation.
+    Warning,
+    /// The torrent violates the specification.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in a torrent file.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Path of the offending key, for example `info.piece length`. It's empty
+    /// for problems with the whole file.
+    pub key: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    #[must_use]
+    pub fn info(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Info,
+            key: key.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn warning(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            key: key.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn error(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key: key.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(f, "{}: `{}`: {}", self.severity, self.key, self.message)
+        }
+    }
+}
+
+/// Returns `true` if any of the diagnostics is an error.
+#[must_use]
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
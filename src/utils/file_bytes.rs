@@ -0,0 +1,165 @@
+//! Reading whole files, and ranges of them, with memory maps when the `mmap`
+//! feature is enabled.
+//!
+//! A memory-mapped file isn't copied into the memory of the process: its
+//! pages are read from the page cache when they are accessed. This avoids
+//! holding a copy of very large torrents and content files.
+//!
+//! The maps are read-only, but the file can still be modified by another
+//! process while it's mapped, which changes the bytes under the parser. This
+//! module is meant for files nobody is writing, like the content being
+//! verified or hashed.
+use std::fs::File;
+use std::io;
+#[cfg(not(feature = "mmap"))]
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::path::Path;
+
+/// The bytes of a file, mapped or read into memory.
+#[derive(Debug)]
+pub enum FileBytes {
+    /// Read into memory, without the `mmap` feature, for empty files, which
+    /// can't be mapped, and for the inputs that are not files.
+    Read(Vec<u8>),
+    /// Mapped into memory.
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Read(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map,
+        }
+    }
+}
+
+impl AsRef<[u8]> for FileBytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Vec<u8>> for FileBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Read(bytes)
+    }
+}
+
+/// Returns the bytes of a file: it's mapped with the `mmap` feature and read
+/// into memory without it.
+///
+/// # Errors
+///
+/// Will return an error if the file can't be opened, read or mapped.
+pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<FileBytes> {
+    let file = File::open(path)?;
+    open(&file)
+}
+
+#[cfg(feature = "mmap")]
+fn open(file: &File) -> io::Result<FileBytes> {
+    if file.metadata()?.len() == 0 {
+        return Ok(FileBytes::Read(vec![]));
+    }
+
+    // SAFETY: the map is read-only. The module documentation warns that the
+    // file must not be modified while it's mapped.
+    let map = unsafe { memmap2::Mmap::map(file)? };
+    Ok(FileBytes::Mapped(map))
+}
+
+#[cfg(not(feature = "mmap"))]
+fn open(mut file: &File) -> io::Result<FileBytes> {
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)?;
+    Ok(FileBytes::Read(bytes))
+}
+
+/// Opens a file to read it sequentially: its map with the `mmap` feature,
+/// the file itself without it.
+///
+/// # Errors
+///
+/// Will return an error if the file can't be opened or mapped.
+pub fn reader<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn io::Read>> {
+    let file = File::open(path)?;
+
+    #[cfg(feature = "mmap")]
+    let reader: Box<dyn io::Read> = Box::new(io::Cursor::new(open(&file)?));
+    #[cfg(not(feature = "mmap"))]
+    let reader: Box<dyn io::Read> = Box::new(file);
+
+    Ok(reader)
+}
+
+/// Fills `buffer` with the bytes of the file starting at `offset`.
+///
+/// # Errors
+///
+/// Will return an error if the file can't be opened or read, with the
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) kind if it ends before the
+/// buffer is full.
+pub fn read_exact_at<P: AsRef<Path>>(path: P, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    read_range(&File::open(path)?, offset, buffer)
+}
+
+#[cfg(feature = "mmap")]
+fn read_range(file: &File, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    let bytes = open(file)?;
+    let range = usize::try_from(offset)
+        .ok()
+        .and_then(|start| Some(start..start.checked_add(buffer.len())?))
+        .filter(|range| range.end <= bytes.len())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    buffer.copy_from_slice(&bytes[range]);
+    Ok(())
+}
+
+#[cfg(not(feature = "mmap"))]
+fn read_range(mut file: &File, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{self, Read};
+
+    use super::{read_exact_at, read_file, reader};
+
+    #[test]
+    fn it_should_read_whole_files_and_ranges_of_them() {
+        let path = std::env::temp_dir().join(format!(
+            "torrust-parse-torrent-file-bytes-{}",
+            std::process::id()
+        ));
+        fs::write(&path, b"0123456789").unwrap();
+
+        assert_eq!(&*read_file(&path).unwrap(), b"0123456789");
+
+        let mut bytes = vec![];
+        reader(&path).unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, b"0123456789");
+
+        let mut buffer = [0; 3];
+        read_exact_at(&path, 4, &mut buffer).unwrap();
+        assert_eq!(&buffer, b"456");
+        assert_eq!(
+            read_exact_at(&path, 8, &mut buffer).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        fs::write(&path, b"").unwrap();
+        assert!(read_file(&path).unwrap().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
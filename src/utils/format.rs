@@ -0,0 +1,154 @@
+//! Human-readable formatting of sizes and dates, shared by the command line
+//! outputs.
+
+/// Binary units above bytes, each 1024 times the previous one.
+const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Formats a size in bytes with the largest binary unit that keeps it above
+/// one, with two decimals. Sizes under 1 KiB are printed in bytes.
+///
+/// ```
+/// use torrust_parse_torrent::utils::format::format_size;
+///
+/// assert_eq!(format_size(1000), "1000 B");
+/// assert_eq!(format_size(1536 * 1024), "1.50 MiB");
+/// ```
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+
+    format!("{size:.2} {unit}")
+}
+
+/// Formats a piece length. Piece lengths are usually powers of two, printed
+/// exactly in the largest unit that divides them, like `256 KiB`. Other
+/// lengths are printed like [`format_size`] does.
+///
+/// ```
+/// use torrust_parse_torrent::utils::format::format_piece_length;
+///
+/// assert_eq!(format_piece_length(256 * 1024), "256 KiB");
+/// assert_eq!(format_piece_length(1000), "1000 B");
+/// ```
+#[must_use]
+pub fn format_piece_length(piece_length: u64) -> String {
+    let mut length = piece_length;
+    let mut unit = "B";
+    for next in UNITS {
+        if length == 0 || !length.is_multiple_of(1024) {
+            break;
+        }
+        length /= 1024;
+        unit = next;
+    }
+
+    if unit == "B" {
+        format_size(piece_length)
+    } else {
+        format!("{length} {unit}")
+    }
+}
+
+/// Formats a Unix timestamp, like the `creation date` of a torrent, as an
+/// RFC 3339 date in UTC, like `2023-09-01T12:30:00Z`.
+///
+/// It returns `None` for dates before the year 0 or after the year 9999,
+/// which RFC 3339 can't represent.
+///
+/// ```
+/// use torrust_parse_torrent::utils::format::rfc3339;
+///
+/// assert_eq!(rfc3339(0).as_deref(), Some("1970-01-01T00:00:00Z"));
+/// ```
+#[must_use]
+pub fn rfc3339(timestamp: i64) -> Option<String> {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+
+    // Civil date from the days since the epoch, see
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (0..=9999).contains(&year).then(|| {
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_piece_length, format_size, rfc3339};
+
+    #[test]
+    fn it_should_format_sizes_in_binary_units() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(256 * 1024), "256.00 KiB");
+        assert_eq!(
+            format_size(5 * 1024 * 1024 * 1024 + 512 * 1024 * 1024),
+            "5.50 GiB"
+        );
+        assert_eq!(format_size(u64::MAX), "16384.00 PiB");
+    }
+
+    #[test]
+    fn it_should_format_piece_lengths_exactly() {
+        assert_eq!(format_piece_length(16 * 1024), "16 KiB");
+        assert_eq!(format_piece_length(4 * 1024 * 1024), "4 MiB");
+        assert_eq!(format_piece_length(3 * 1024 * 1024), "3 MiB");
+        assert_eq!(format_piece_length(1 << 62), "4096 PiB");
+        assert_eq!(format_piece_length(0), "0 B");
+        assert_eq!(format_piece_length(100_000), "97.66 KiB");
+    }
+
+    #[test]
+    fn it_should_format_timestamps_as_rfc_3339() {
+        assert_eq!(rfc3339(0).as_deref(), Some("1970-01-01T00:00:00Z"));
+        assert_eq!(
+            rfc3339(1_693_571_400).as_deref(),
+            Some("2023-09-01T12:30:00Z")
+        );
+        assert_eq!(
+            rfc3339(951_782_400).as_deref(),
+            Some("2000-02-29T00:00:00Z")
+        );
+        assert_eq!(rfc3339(-1).as_deref(), Some("1969-12-31T23:59:59Z"));
+    }
+
+    #[test]
+    fn it_should_not_format_timestamps_out_of_the_rfc_3339_range() {
+        assert_eq!(
+            rfc3339(253_402_300_799).as_deref(),
+            Some("9999-12-31T23:59:59Z")
+        );
+        assert_eq!(rfc3339(253_402_300_800), None);
+        assert_eq!(rfc3339(i64::MAX), None);
+        assert_eq!(rfc3339(i64::MIN), None);
+    }
+}
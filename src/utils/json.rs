@@ -0,0 +1,132 @@
+//! JSON export of the parsed [`Torrent`] model.
+//!
+//! Unlike the [canonical JSON](crate::utils::canonical_json) export, which
+//! contains the whole bencoded tree, this one only contains the fields the
+//! model knows about, so it's what other tools see after parsing.
+//!
+//! - Keys are the bencode keys (`piece length`, `announce-list`, ...), sorted.
+//! - Byte strings that are valid UTF-8 text are exported as JSON strings.
+//!   Binary byte strings are exported as lowercase hex strings.
+//! - Hash fields (`pieces`, `pieces root`, `root hash` and the `piece
+//!   layers`) are always exported as lowercase hex strings.
+use std::collections::HashMap;
+
+use serde_bencode::value::Value as BValue;
+use serde_json::{Map, Value};
+
+use crate::models::torrent_file::Torrent;
+
+/// Returns the JSON document for the torrent.
+///
+/// # Panics
+///
+/// This function will panic if the torrent cannot be bencoded, which should
+/// never happen.
+#[must_use]
+pub fn to_json(torrent: &Torrent) -> Value {
+    let bytes = serde_bencode::to_bytes(torrent).expect("torrent should be bencodable");
+    let value: BValue =
+        serde_bencode::from_bytes(&bytes).expect("bencoded torrent should be decodable");
+
+    convert(&value, false)
+}
+
+fn convert(value: &BValue, binary: bool) -> Value {
+    match value {
+        BValue::Int(int) => Value::from(*int),
+        BValue::Bytes(bytes) => bytes_to_json(bytes, binary),
+        BValue::List(list) => Value::Array(list.iter().map(|item| convert(item, false)).collect()),
+        BValue::Dict(dict) => {
+            let mut entries: Vec<_> = dict.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let value = match (key.as_slice(), value) {
+                    (b"piece layers", BValue::Dict(layers)) => convert_piece_layers(layers),
+                    (b"pieces" | b"pieces root" | b"root hash", _) => convert(value, true),
+                    _ => convert(value, false),
+                };
+                map.insert(String::from_utf8_lossy(key).into_owned(), value);
+            }
+            Value::Object(map)
+        }
+    }
+}
+
+fn convert_piece_layers(layers: &HashMap<Vec<u8>, BValue>) -> Value {
+    let mut entries: Vec<_> = layers.iter().collect();
+    entries.sort_by_key(|(pieces_root, _)| *pieces_root);
+
+    let mut map = Map::new();
+    for (pieces_root, layer) in entries {
+        map.insert(hex(pieces_root), convert(layer, true));
+    }
+    Value::Object(map)
+}
+
+fn bytes_to_json(bytes: &[u8], binary: bool) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !binary => Value::String(text.to_owned()),
+        _ => Value::String(hex(bytes)),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    crate::utils::hex::from_bytes(bytes).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::to_json;
+    use crate::parse_torrent;
+
+    #[test]
+    fn it_should_export_the_torrent_with_hash_fields_as_hex() {
+        let torrent = parse_torrent(
+            b"d8:announce15:http://t.io/ann4:infod6:lengthi5e4:name5:a.txt12:piece lengthi16384e6:pieces20:\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x11\x12\x13ee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_json(&torrent),
+            json!({
+                "announce": "http://t.io/ann",
+                "info": {
+                    "length": 5,
+                    "name": "a.txt",
+                    "piece length": 16384,
+                    "pieces": "000102030405060708090a0b0c0d0e0f10111213",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_export_the_v2_piece_layers_as_hex() {
+        let root = [b'a'; 32];
+        let mut bytes = b"d4:infod9:file treed5:a.txtd0:d6:lengthi32768e11:pieces root32:".to_vec();
+        bytes.extend_from_slice(&root);
+        bytes.extend_from_slice(
+            b"eee12:meta versioni2e4:name5:a.txt12:piece lengthi16384ee12:piece layersd32:",
+        );
+        bytes.extend_from_slice(&root);
+        bytes.extend_from_slice(b"64:");
+        bytes.extend_from_slice(&[b'b'; 64]);
+        bytes.extend_from_slice(b"ee");
+
+        let json = to_json(&parse_torrent(&bytes).unwrap());
+
+        let root_hex = "61".repeat(32);
+        assert_eq!(
+            json["piece layers"],
+            json!({ root_hex.clone(): "62".repeat(64) })
+        );
+        assert_eq!(
+            json["info"]["file tree"]["a.txt"][""]["pieces root"],
+            json!(root_hex)
+        );
+    }
+}
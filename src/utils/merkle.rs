@@ -0,0 +1,160 @@
+//! SHA-256 merkle trees of `BitTorrent` v2 files ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html)).
+//!
+//! Each file is split in 16 KiB blocks. The leaves of its tree are the
+//! SHA-256 hashes of the blocks, and the number of leaves is padded to a
+//! power of two with zero hashes. The root of the tree is the `pieces root`
+//! of the file, and the layer whose nodes cover one piece each is its entry
+//! in the `piece layers`.
+use sha2::{Digest, Sha256};
+
+/// Size of the blocks hashed to get the leaves of the tree.
+pub const BLOCK_SIZE: usize = 16 * 1024;
+
+/// A SHA-256 hash.
+pub type Hash = [u8; 32];
+
+/// The hash of a leaf beyond the end of the file.
+pub const ZERO_HASH: Hash = [0; 32];
+
+/// Returns the SHA-256 hash of a block, a leaf of the tree.
+#[must_use]
+pub fn hash_block(block: &[u8]) -> Hash {
+    Sha256::digest(block).into()
+}
+
+/// Returns the root of a tree whose lowest layer contains `hashes` followed
+/// by `pad` until there are `width` nodes.
+///
+/// `pad` is the root of the subtrees beyond the end of the file: the
+/// [`ZERO_HASH`] for leaves, or [`pad_hash`] for upper layers.
+///
+/// # Panics
+///
+/// Will panic if `width` is not a power of two or it's smaller than the
+/// number of hashes.
+#[must_use]
+pub fn root(hashes: &[Hash], width: usize, pad: Hash) -> Hash {
+    assert!(width.is_power_of_two() && width >= hashes.len());
+
+    let mut layer = hashes.to_vec();
+    let mut pad = pad;
+    let mut width = width;
+
+    while width > 1 {
+        if layer.len() % 2 == 1 {
+            layer.push(pad);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        pad = hash_pair(&pad, &pad);
+        width /= 2;
+    }
+
+    layer.first().copied().unwrap_or(pad)
+}
+
+/// Returns the root of a subtree with `width` leaves beyond the end of the
+/// file.
+#[must_use]
+pub fn pad_hash(width: usize) -> Hash {
+    root(&[], width, ZERO_HASH)
+}
+
+/// Returns the `pieces root` of a file from the hashes of its blocks.
+///
+/// # Panics
+///
+/// Will panic if there are no leaves: empty files don't have a root.
+#[must_use]
+pub fn pieces_root(leaves: &[Hash]) -> Hash {
+    assert!(!leaves.is_empty(), "empty files don't have a pieces root");
+    root(leaves, leaves.len().next_power_of_two(), ZERO_HASH)
+}
+
+/// Returns the layer of the tree whose nodes cover `piece_length` bytes
+/// each, from the hashes of the blocks of the file.
+///
+/// # Panics
+///
+/// Will panic if `piece_length` is not a power of two multiple of the block
+/// size.
+#[must_use]
+pub fn piece_layer(leaves: &[Hash], piece_length: usize) -> Vec<Hash> {
+    let blocks_per_piece = piece_length / BLOCK_SIZE;
+    assert!(blocks_per_piece.is_power_of_two());
+
+    leaves
+        .chunks(blocks_per_piece)
+        .map(|piece| root(piece, blocks_per_piece, ZERO_HASH))
+        .collect()
+}
+
+/// Returns the `pieces root` of a file from its entry in the `piece layers`,
+/// the hashes of the subtrees that cover `piece_length` bytes each.
+///
+/// # Panics
+///
+/// Will panic if `piece_length` is not a power of two multiple of the block
+/// size.
+#[must_use]
+pub fn layer_root(layer: &[Hash], piece_length: usize) -> Hash {
+    let blocks_per_piece = piece_length / BLOCK_SIZE;
+    assert!(blocks_per_piece.is_power_of_two());
+
+    root(
+        layer,
+        layer.len().next_power_of_two(),
+        pad_hash(blocks_per_piece),
+    )
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        hash_block, hash_pair, layer_root, pad_hash, piece_layer, pieces_root, root, ZERO_HASH,
+    };
+
+    #[test]
+    fn it_should_use_the_block_hash_as_the_root_of_a_single_block_file() {
+        let leaf = hash_block(b"a");
+
+        assert_eq!(pieces_root(&[leaf]), leaf);
+    }
+
+    #[test]
+    fn it_should_pad_the_leaves_with_zero_hashes() {
+        let leaves = [hash_block(b"a"), hash_block(b"b"), hash_block(b"c")];
+
+        assert_eq!(
+            pieces_root(&leaves),
+            hash_pair(
+                &hash_pair(&leaves[0], &leaves[1]),
+                &hash_pair(&leaves[2], &ZERO_HASH)
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_get_the_same_root_from_the_piece_layer() {
+        // 5 pieces of 4 blocks, the last one with a single block.
+        let leaves: Vec<_> = (0..17u8).map(|block| hash_block(&[block])).collect();
+
+        let layer = piece_layer(&leaves, 4 * super::BLOCK_SIZE);
+
+        assert_eq!(layer.len(), 5);
+        assert_eq!(root(&layer, 8, pad_hash(4)), pieces_root(&leaves));
+        assert_eq!(
+            layer_root(&layer, 4 * super::BLOCK_SIZE),
+            pieces_root(&leaves)
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Merkle torrents ([BEP 30](https://www.bittorrent.org/beps/bep_0030.html)).
+//!
+//! Instead of the `pieces` list, the info dictionary of a Merkle torrent
+//! only contains the `root hash`: the root of a SHA-1 tree whose leaves are
+//! the hashes of the pieces. Peers send the hashes needed to check a piece
+//! with it, as a chain from the piece to the root.
+//!
+//! The nodes are numbered from the root, `0`, and the children of node `i`
+//! are `2i + 1` and `2i + 2`. The leaves are padded with zero hashes to a
+//! power of two.
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use crate::models::torrent_file::Torrent;
+
+/// A SHA-1 hash.
+pub type Hash = [u8; 20];
+
+/// Why a piece doesn't match the root hash.
+#[derive(Error, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HashChainError {
+    #[error("the torrent has no valid `root hash`")]
+    NotMerkle,
+    #[error("piece {index} is out of range, the torrent has {count} pieces")]
+    PieceOutOfRange { index: usize, count: usize },
+    #[error("the hash chain lacks node {0}")]
+    MissingNode(usize),
+    #[error("the hash of the piece doesn't match the one in the chain")]
+    WrongPieceHash,
+    #[error("the hash chain doesn't lead to the root hash")]
+    WrongRoot,
+}
+
+/// Returns the `root hash` of a Merkle torrent, or `None` if it's missing or
+/// it isn't a SHA-1 hash.
+#[must_use]
+pub fn root_hash(torrent: &Torrent) -> Option<Hash> {
+    torrent
+        .info
+        .root_hash
+        .as_ref()
+        .and_then(|root_hash| Hash::try_from(root_hash.as_slice()).ok())
+}
+
+/// Returns `true` if the torrent is a Merkle torrent: its pieces are
+/// checked against a `root hash` instead of a `pieces` list.
+#[must_use]
+pub fn is_merkle(torrent: &Torrent) -> bool {
+    root_hash(torrent).is_some()
+}
+
+/// Checks a downloaded piece of a Merkle torrent with the hash chain sent by
+/// a peer, as `(node, hash)` pairs. The chain must contain the sibling of the
+/// piece and of each of its ancestors. It may also contain the hash of the
+/// piece, which must then match the data.
+///
+/// # Errors
+///
+/// Will return an error if the torrent is not a Merkle torrent, the piece
+/// doesn't exist, a hash is missing from the chain or the piece doesn't lead
+/// to the root hash.
+pub fn verify_piece(
+    torrent: &Torrent,
+    index: usize,
+    piece: &[u8],
+    chain: &[(usize, Hash)],
+) -> Result<(), HashChainError> {
+    let root = root_hash(torrent).ok_or(HashChainError::NotMerkle)?;
+
+    let piece_length = torrent.info.piece_length;
+    let length = torrent.file_size();
+    let count = if piece_length == 0 {
+        0
+    } else {
+        usize::try_from(length.div_ceil(piece_length)).unwrap_or(usize::MAX)
+    };
+    if index >= count {
+        return Err(HashChainError::PieceOutOfRange { index, count });
+    }
+
+    let find = |node: usize| {
+        chain
+            .iter()
+            .find(|(position, _)| *position == node)
+            .map(|(_, hash)| *hash)
+    };
+
+    let mut node = count.next_power_of_two() - 1 + index;
+    let mut hash: Hash = Sha1::digest(piece).into();
+    if find(node).is_some_and(|expected| expected != hash) {
+        return Err(HashChainError::WrongPieceHash);
+    }
+
+    while node > 0 {
+        let is_left = node % 2 == 1;
+        let sibling = if is_left { node + 1 } else { node - 1 };
+        let sibling_hash = find(sibling).ok_or(HashChainError::MissingNode(sibling))?;
+
+        hash = if is_left {
+            hash_pair(&hash, &sibling_hash)
+        } else {
+            hash_pair(&sibling_hash, &hash)
+        };
+        node = (node - 1) / 2;
+    }
+
+    if hash == root {
+        Ok(())
+    } else {
+        Err(HashChainError::WrongRoot)
+    }
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha1::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bytes::ByteBuf;
+    use sha1::{Digest, Sha1};
+
+    use super::{hash_pair, is_merkle, verify_piece, Hash, HashChainError};
+    use crate::models::torrent_file::Torrent;
+
+    /// A Merkle torrent with three pieces, `a`, `b` and `c`, of one byte. The
+    /// fourth leaf is padding.
+    fn torrent() -> (Torrent, [Hash; 4]) {
+        let leaves: [Hash; 4] = [
+            Sha1::digest(b"a").into(),
+            Sha1::digest(b"b").into(),
+            Sha1::digest(b"c").into(),
+            [0; 20],
+        ];
+        let root = hash_pair(
+            &hash_pair(&leaves[0], &leaves[1]),
+            &hash_pair(&leaves[2], &leaves[3]),
+        );
+
+        let mut torrent = Torrent::default();
+        torrent.info.piece_length = 1;
+        torrent.info.length = Some(3);
+        torrent.info.pieces = None;
+        torrent.info.root_hash = Some(ByteBuf::from(root.to_vec()));
+
+        (torrent, leaves)
+    }
+
+    #[test]
+    fn it_should_accept_a_piece_whose_chain_leads_to_the_root_hash() {
+        let (torrent, leaves) = torrent();
+        let uncle = hash_pair(&leaves[0], &leaves[1]);
+
+        assert!(is_merkle(&torrent));
+        assert_eq!(
+            verify_piece(
+                &torrent,
+                2,
+                b"c",
+                &[(5, leaves[2]), (6, leaves[3]), (1, uncle)]
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_should_reject_pieces_that_do_not_match_the_chain() {
+        let (torrent, leaves) = torrent();
+        let uncle = hash_pair(&leaves[0], &leaves[1]);
+
+        assert_eq!(
+            verify_piece(&torrent, 2, b"x", &[(6, leaves[3]), (1, uncle)]),
+            Err(HashChainError::WrongRoot)
+        );
+        assert_eq!(
+            verify_piece(
+                &torrent,
+                2,
+                b"x",
+                &[(5, leaves[2]), (6, leaves[3]), (1, uncle)]
+            ),
+            Err(HashChainError::WrongPieceHash)
+        );
+        assert_eq!(
+            verify_piece(&torrent, 2, b"c", &[(6, leaves[3])]),
+            Err(HashChainError::MissingNode(1))
+        );
+        assert_eq!(
+            verify_piece(&torrent, 3, b"", &[]),
+            Err(HashChainError::PieceOutOfRange { index: 3, count: 3 })
+        );
+        assert_eq!(
+            verify_piece(&Torrent::default(), 0, b"a", &[]),
+            Err(HashChainError::NotMerkle)
+        );
+    }
+}
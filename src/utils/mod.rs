@@ -1,3 +1,21 @@
+pub mod audit;
+pub mod base32;
+pub mod bencode;
+pub mod bencode_events;
+pub mod canonical_json;
+pub mod charset;
+pub mod compare;
+pub mod decoder;
+pub mod diagnostics;
+pub mod file_bytes;
+pub mod format;
 pub mod hex;
+pub mod json;
+pub mod merkle;
+pub mod merkle_torrent;
+pub mod parse_mode;
+pub mod parse_options;
 pub mod parse_torrent;
 pub mod parse_torrent_verbose;
+pub mod sanitize;
+pub mod validate;
@@ -0,0 +1,19 @@
+//! How strictly the parsers follow the specification.
+
+/// Parsing mode shared by both parser implementations.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub enum ParseMode {
+    /// Reject known keys with unexpected types and files that violate the
+    /// specification (see [`validate`](crate::utils::validate::validate)).
+    Strict,
+    /// Best effort: keep whatever can be decoded and skip the rest.
+    #[default]
+    Lenient,
+}
+
+impl ParseMode {
+    #[must_use]
+    pub fn is_strict(self) -> bool {
+        self == ParseMode::Strict
+    }
+}
@@ -0,0 +1,135 @@
+//! Options of the parsers, including the limits that protect them from
+//! crafted files.
+use crate::utils::decoder::Backend;
+use crate::utils::parse_mode::ParseMode;
+
+/// Default for [`ParseOptions::max_input_size`], 100 MiB. Torrents are rarely
+/// larger than a few megabytes, even with millions of pieces.
+pub const DEFAULT_MAX_INPUT_SIZE: usize = 100 * 1024 * 1024;
+
+/// Default for [`ParseOptions::max_depth`]. Torrents nest values a few
+/// levels deep, except for the file tree of v2 torrents, which has a level
+/// per directory.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Default for [`ParseOptions::max_string_length`]. The largest strings are
+/// the `pieces` and the `piece layers`, bounded by the input size.
+pub const DEFAULT_MAX_STRING_LENGTH: usize = DEFAULT_MAX_INPUT_SIZE;
+
+/// Default for [`ParseOptions::max_entries`].
+pub const DEFAULT_MAX_ENTRIES: usize = 1_000_000;
+
+/// How to parse a torrent, see [`parse_torrent_with_options`](crate::parse_torrent_with_options).
+///
+/// The limits are checked while the bencode is decoded, before anything is
+/// allocated for the offending value, so that a crafted torrent can't
+/// exhaust the memory or overflow the stack.
+///
+/// ```
+/// use torrust_parse_torrent::utils::parse_options::ParseOptions;
+/// use torrust_parse_torrent::utils::parse_mode::ParseMode;
+///
+/// let options = ParseOptions::new()
+///     .mode(ParseMode::Strict)
+///     .max_input_size(1024 * 1024)
+///     .max_depth(16);
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseOptions {
+    pub(crate) mode: ParseMode,
+    pub(crate) backend: Backend,
+    pub(crate) max_input_size: usize,
+    pub(crate) max_depth: usize,
+    pub(crate) max_string_length: usize,
+    pub(crate) max_entries: usize,
+    pub(crate) skip_pieces: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::default(),
+            backend: Backend::default(),
+            max_input_size: DEFAULT_MAX_INPUT_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_string_length: DEFAULT_MAX_STRING_LENGTH,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            skip_pieces: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Creates the default options: lenient mode, the serde backend and the
+    /// default limits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the [`ParseMode`]. It defaults to [`ParseMode::Lenient`].
+    #[must_use]
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the parser that builds the torrent. It defaults to
+    /// [`Backend::Serde`], the faster on most torrents. [`Backend::Verbose`]
+    /// reports which key is wrong.
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets the largest input accepted, in bytes. It defaults to
+    /// [`DEFAULT_MAX_INPUT_SIZE`].
+    #[must_use]
+    pub fn max_input_size(mut self, max_input_size: usize) -> Self {
+        self.max_input_size = max_input_size;
+        self
+    }
+
+    /// Sets how many lists and dictionaries can be nested, the top-level
+    /// dictionary included. It defaults to [`DEFAULT_MAX_DEPTH`].
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the longest byte string accepted, in bytes. It defaults to
+    /// [`DEFAULT_MAX_STRING_LENGTH`].
+    #[must_use]
+    pub fn max_string_length(mut self, max_string_length: usize) -> Self {
+        self.max_string_length = max_string_length;
+        self
+    }
+
+    /// Sets the largest number of items in a list, or of keys in a
+    /// dictionary. It defaults to [`DEFAULT_MAX_ENTRIES`].
+    #[must_use]
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Sets whether to leave the `pieces` out of the torrent, for tools that
+    /// only need its metadata. They are often tens of megabytes. The
+    /// [`pieces_span`](crate::models::torrent_file::Torrent::pieces_span)
+    /// has their location in the input instead. It defaults to `false`.
+    ///
+    /// In strict mode they are still decoded to validate them.
+    #[must_use]
+    pub fn skip_pieces(mut self, skip_pieces: bool) -> Self {
+        self.skip_pieces = skip_pieces;
+        self
+    }
+}
+
+impl From<ParseMode> for ParseOptions {
+    fn from(mode: ParseMode) -> Self {
+        Self::new().mode(mode)
+    }
+}
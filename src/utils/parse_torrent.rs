@@ -1,27 +1,30 @@
 //! Parse a torrent file data using the standard serde deserialization functions.
 use std::error;
 
-use serde_bencode::value::Value;
-use serde_bencode::{de, Error};
-use serde_derive::{Deserialize, Serialize};
+use serde_bencode::de;
 use sha1::{Digest, Sha1};
+use sha2::Sha256;
 
+use crate::error::Error;
 use crate::models::info_hash::InfoHash;
+use crate::models::info_hash_v2::InfoHashV2;
 use crate::models::torrent_file::Torrent;
+use crate::utils::bencode;
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::parse_torrent_verbose::TorrentParseError;
 
 /// Decode a Torrent from Bencoded Bytes.
 ///
+/// The [`raw_info`](Torrent::raw_info) holds the original bytes of the info
+/// dictionary, so the info-hashes are the ones of the file.
+///
 /// # Errors
 ///
 /// This function will return an error if unable to parse bytes into torrent.
 pub fn decode_torrent(bytes: &[u8]) -> Result<Torrent, Box<dyn error::Error>> {
-    match de::from_bytes::<Torrent>(bytes) {
-        Ok(torrent) => Ok(torrent),
-        Err(e) => {
-            println!("{e:?}");
-            Err(e.into())
-        }
-    }
+    let mut torrent = de::from_bytes::<Torrent>(bytes)?;
+    torrent.raw_info = bencode::info_span(bytes)?.map(|span| bytes[span].to_vec());
+    Ok(torrent)
 }
 
 /// Encode a Torrent into Bencoded Bytes.
@@ -29,43 +32,43 @@ pub fn decode_torrent(bytes: &[u8]) -> Result<Torrent, Box<dyn error::Error>> {
 /// # Errors
 ///
 /// This function will return an error if unable to bencode torrent.
-pub fn encode_torrent(torrent: &Torrent) -> Result<Vec<u8>, Error> {
-    match serde_bencode::to_bytes(torrent) {
-        Ok(bencode_bytes) => Ok(bencode_bytes),
-        Err(e) => {
-            eprintln!("{e:?}");
-            Err(e)
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-struct MetainfoFile {
-    pub info: Value,
+pub fn encode_torrent(torrent: &Torrent) -> Result<Vec<u8>, serde_bencode::Error> {
+    serde_bencode::to_bytes(torrent)
 }
 
 /// Calculates the `InfoHash` from a the torrent file binary data.
 ///
-/// # Panics
+/// It hashes the original bytes of the info dictionary, so the result is the
+/// same other clients calculate even if the dictionary contains non-standard
+/// keys or its keys are not sorted.
 ///
-/// This function will panic if the torrent file is not a valid bencoded file
-/// or if the info dictionary cannot be bencoded.
-#[must_use]
-pub fn calculate_info_hash(bytes: &[u8]) -> InfoHash {
-    // Extract the info dictionary
-    let metainfo: MetainfoFile = serde_bencode::from_bytes(bytes)
-        .expect("Torrent file cannot be parsed from bencoded format");
-
-    // Bencode the info dictionary
-    let info_dict_bytes =
-        serde_bencode::to_bytes(&metainfo.info).expect("Info dictionary cannot by bencoded");
-
-    // Calculate the SHA-1 hash of the bencoded info dictionary
+/// # Errors
+///
+/// Will return an error if the bytes are not valid bencode or the torrent
+/// has no `info` key.
+pub fn calculate_info_hash(bytes: &[u8]) -> Result<InfoHash, Error> {
     let mut hasher = Sha1::new();
-    hasher.update(&info_dict_bytes);
-    let result = hasher.finalize();
+    hasher.update(info_bytes(bytes)?);
+    Ok(InfoHash(hasher.finalize().into()))
+}
+
+/// Calculates the v2 `InfoHashV2` (SHA-256) from the torrent file binary
+/// data, hashing the original bytes of the info dictionary.
+///
+/// # Errors
+///
+/// Same as [`calculate_info_hash`].
+pub fn calculate_info_hash_v2(bytes: &[u8]) -> Result<InfoHashV2, Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(info_bytes(bytes)?);
+    Ok(InfoHashV2::from(<[u8; 32]>::from(hasher.finalize())))
+}
 
-    InfoHash::from_bytes(&result)
+fn info_bytes(bytes: &[u8]) -> Result<&[u8], Error> {
+    let span = bencode::info_span(bytes)?.ok_or_else(|| {
+        TorrentParseError::Invalid(Diagnostic::error("info", "missing required key"))
+    })?;
+    Ok(&bytes[span])
 }
 
 #[cfg(test)]
@@ -73,7 +76,12 @@ mod tests {
     use std::path::Path;
     use std::str::FromStr;
 
+    use sha1::{Digest, Sha1};
+    use sha2::Sha256;
+
+    use crate::error::Error;
     use crate::models::info_hash::InfoHash;
+    use crate::models::info_hash_v2::InfoHashV2;
 
     #[test]
     fn it_should_calculate_the_original_info_hash_using_all_fields_in_the_info_key_dictionary() {
@@ -82,7 +90,8 @@ mod tests {
             "tests/fixtures/torrents/6c690018c5786dbbb00161f62b0712d69296df97_with_custom_info_dict_key.torrent",
         );
 
-        let original_info_hash = super::calculate_info_hash(&std::fs::read(torrent_path).unwrap());
+        let original_info_hash =
+            super::calculate_info_hash(&std::fs::read(torrent_path).unwrap()).unwrap();
 
         assert_eq!(
             original_info_hash,
@@ -91,7 +100,25 @@ mod tests {
     }
 
     #[test]
-    fn it_should_calculate_the_new_info_hash_ignoring_non_standard_fields_in_the_info_key_dictionary(
+    fn it_should_calculate_the_info_hash_from_the_original_bytes_of_the_info_dictionary() {
+        // The keys of the info dictionary are not sorted, so bencoding it
+        // again would produce different bytes.
+        let bytes =
+            b"d4:infod4:name1:a6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        let info = b"d4:name1:a6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+
+        assert_eq!(
+            super::calculate_info_hash(bytes).unwrap(),
+            InfoHash(Sha1::digest(info).into())
+        );
+        assert_eq!(
+            super::calculate_info_hash_v2(bytes).unwrap(),
+            InfoHashV2::from(<[u8; 32]>::from(Sha256::digest(info)))
+        );
+    }
+
+    #[test]
+    fn it_should_keep_the_original_info_hash_of_a_torrent_with_non_standard_fields_in_the_info_key_dictionary(
     ) {
         let torrent_path = Path::new(
             // cspell:disable-next-line
@@ -100,11 +127,44 @@ mod tests {
 
         let torrent = super::decode_torrent(&std::fs::read(torrent_path).unwrap()).unwrap();
 
-        // The infohash is not the original infohash of the torrent file,
-        // but the infohash of the info dictionary without the custom keys.
         assert_eq!(
-            torrent.info_hash(),
-            "8aa01a4c816332045ffec83247ccbc654547fedf".to_string()
+            torrent.info_hash_hex(),
+            // cspell:disable-next-line
+            "6c690018c5786dbbb00161f62b0712d69296df97".to_string()
         );
     }
+
+    #[test]
+    fn it_should_fail_to_calculate_the_info_hash_of_invalid_torrents() {
+        assert!(matches!(
+            super::calculate_info_hash(b"d4:info"),
+            Err(Error::Bencode(_))
+        ));
+        assert!(matches!(
+            super::calculate_info_hash_v2(b"d8:announce1:ae"),
+            Err(Error::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn it_should_decode_a_torrent_with_non_utf8_md5sum_and_source_fields() {
+        let bytes =
+            b"d4:infod6:lengthi1e6:md5sum2:\xff\xfe4:name1:a12:piece lengthi16384e6:source1:\xffee";
+
+        let torrent = super::decode_torrent(bytes).unwrap();
+
+        assert_eq!(torrent.info.md5sum.unwrap().as_bytes(), b"\xff\xfe");
+        assert_eq!(torrent.info.source.unwrap().as_bytes(), b"\xff");
+    }
+
+    #[test]
+    fn it_should_decode_the_v2_metadata() {
+        let bytes = b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee12:meta versioni2e4:name1:a12:piece lengthi16384ee12:piece layersd32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa32:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbee";
+
+        let torrent = super::decode_torrent(bytes).unwrap();
+
+        assert_eq!(torrent.info.meta_version, Some(2));
+        assert!(torrent.info.file_tree.is_some());
+        assert!(torrent.piece_layers.is_some());
+    }
 }
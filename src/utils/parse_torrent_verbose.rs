@@ -1,260 +1,796 @@
 //! Parse a torrent file data using low-level serde capabilities to show better
 //! error messages.
 
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+use crate::models::byte_string::ByteString;
+use crate::models::file_tree::{FileTree, FileTreeFile, FileTreeNode};
+use crate::models::piece_layers::PieceLayers;
+use crate::models::signature::Signature;
 use crate::models::torrent_file::{Torrent, TorrentFile, TorrentInfo, TorrentNode};
+use crate::utils::diagnostics::{Diagnostic, Severity};
+use crate::utils::parse_mode::ParseMode;
+use crate::utils::validate::{validate, validate_text};
 
 use serde_bencode::value::Value as BValue;
 use serde_bytes::ByteBuf;
 
+/// Errors that can occur when decoding a torrent with the verbose parser.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TorrentParseError {
+    /// A value does not have the bencode type required by the specification.
+    #[error("unexpected type for `{key}`: found {found}, expected {expected}")]
+    UnexpectedType {
+        key: String,
+        found: &'static str,
+        expected: &'static str,
+    },
+    /// A value has the right type but it's not one of the allowed values.
+    #[error("unexpected value for `{key}`: found {found}, expected {expected}")]
+    UnexpectedValue {
+        key: String,
+        found: String,
+        expected: &'static str,
+    },
+    /// The torrent violates the specification (strict mode only).
+    #[error("invalid torrent: {0}")]
+    Invalid(Diagnostic),
+}
+
+/// Returns the name of the bencode type of the value, for error messages.
+#[must_use]
+pub fn type_name(value: &BValue) -> &'static str {
+    match value {
+        BValue::Bytes(_) => "byte string",
+        BValue::Int(_) => "integer",
+        BValue::List(_) => "list",
+        BValue::Dict(_) => "dictionary",
+    }
+}
+
+/// Parses a torrent file into a `Torrent` struct using low-level serde
+/// capabilities, in [lenient](ParseMode::Lenient) mode.
+///
+/// # Errors
+///
+/// This function will return an error if the bencoded value is not a
+/// dictionary or if the `private` flag is not `0` or `1`.
+pub fn decode_torrent(bvalue: BValue) -> Result<Torrent, TorrentParseError> {
+    decode_torrent_with_mode(bvalue, ParseMode::Lenient)
+}
+
 /// Parses a torrent file into a `Torrent` struct using low-level serde
 /// capabilities.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if the torrent file is not a valid bencoded file.
-#[allow(clippy::too_many_lines)]
-#[allow(clippy::cast_possible_truncation)]
-#[must_use]
-pub fn decode_torrent(bvalue: BValue) -> Torrent {
-    let mut torrent = Torrent::default();
-
-    match bvalue {
-        BValue::Dict(dict) => {
-            for (key, value) in dict {
-                let key = String::from_utf8_lossy(&key).into_owned();
-                match &key[..] {
-                    "info" => {
-                        if let BValue::Dict(info_dict) = value {
-                            let mut info = TorrentInfo {
-                                name: String::new(),
-                                pieces: None,
-                                piece_length: 0,
-                                md5sum: None,
-                                length: None,
-                                files: None,
-                                private: None,
-                                path: None,
-                                root_hash: None,
-                                source: None,
-                            };
-                            for (info_key, info_value) in info_dict {
-                                let info_key = String::from_utf8_lossy(&info_key).into_owned();
-                                match info_key.as_str() {
-                                    "name" => {
-                                        if let BValue::Bytes(bytes) = &info_value {
-                                            info.name = String::from_utf8_lossy(bytes).into_owned();
-                                        }
-                                    }
-                                    "pieces" => {
-                                        if let BValue::Bytes(bytes) = &info_value {
-                                            info.pieces = Some(ByteBuf::from(bytes.clone()));
-                                            println!("Pieces length: {}", bytes.len());
-                                        }
-                                    }
-                                    "piece length" => {
-                                        if let BValue::Int(int) = info_value {
-                                            info.piece_length = int;
-                                        }
-                                    }
-                                    "md5sum" => {
-                                        if let BValue::Bytes(bytes) = &info_value {
-                                            info.md5sum =
-                                                Some(String::from_utf8_lossy(bytes).into_owned());
-                                        }
-                                    }
-                                    "length" => {
-                                        if let BValue::Int(int) = info_value {
-                                            info.length = Some(int);
-                                        }
-                                    }
-                                    "files" => {
-                                        if let BValue::List(files) = &info_value {
-                                            let mut torrent_files = vec![];
-                                            for file in files {
-                                                if let BValue::Dict(file_dict) = file {
-                                                    let mut torrent_file = TorrentFile {
-                                                        path: vec![],
-                                                        length: 0,
-                                                        md5sum: None,
-                                                    };
-                                                    for (file_key, file_value) in file_dict {
-                                                        let file_key =
-                                                            String::from_utf8_lossy(file_key)
-                                                                .into_owned();
-                                                        match file_key.as_str() {
-                                                            "path" => {
-                                                                if let BValue::List(path_list) =
-                                                                    file_value
-                                                                {
-                                                                    for path_item in path_list {
-                                                                        if let BValue::Bytes(
-                                                                            path_bytes,
-                                                                        ) = path_item
-                                                                        {
-                                                                            torrent_file.path.push(String::from_utf8_lossy(path_bytes).into_owned());
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                            "length" => {
-                                                                if let BValue::Int(length) =
-                                                                    file_value
-                                                                {
-                                                                    torrent_file.length = *length;
-                                                                }
-                                                            }
-                                                            "md5sum" => {
-                                                                if let BValue::Bytes(md5sum_bytes) =
-                                                                    file_value
-                                                                {
-                                                                    torrent_file.md5sum = Some(
-                                                                        String::from_utf8_lossy(
-                                                                            md5sum_bytes,
-                                                                        )
-                                                                        .into_owned(),
-                                                                    );
-                                                                }
-                                                            }
-                                                            _ => {
-                                                                println!(
-                                                                    "Skipped file key: {file_key}"
-                                                                );
-                                                            }
-                                                        }
-                                                    }
-                                                    torrent_files.push(torrent_file);
-                                                }
-                                            }
-                                            info.files = Some(torrent_files);
-                                        }
-                                    }
-                                    "private" => {
-                                        if let BValue::Int(private) = info_value {
-                                            match private {
-                                                0 => info.private = Some(0),
-                                                1 => info.private = Some(1),
-                                                _ => {
-                                                    panic!("Unexpected private value: {private}");
-                                                }
-                                            }
-                                        }
-                                    }
-                                    "path" => {
-                                        if let BValue::List(path_list) = &info_value {
-                                            let mut path = vec![];
-                                            for path_item in path_list {
-                                                if let BValue::Bytes(path_bytes) = path_item {
-                                                    path.push(
-                                                        String::from_utf8_lossy(path_bytes)
-                                                            .into_owned(),
-                                                    );
-                                                }
-                                            }
-                                            info.path = Some(path);
-                                        }
-                                    }
-                                    "root hash" => {
-                                        if let BValue::Bytes(bytes) = &info_value {
-                                            info.root_hash =
-                                                Some(String::from_utf8_lossy(bytes).into_owned());
-                                        }
-                                    }
-                                    "source" => {
-                                        if let BValue::Bytes(bytes) = &info_value {
-                                            info.source =
-                                                Some(String::from_utf8_lossy(bytes).into_owned());
-                                        }
-                                    }
-                                    _ => {
-                                        println!("Skipped info key: {info_key}");
-                                    }
-                                }
-                            }
-                            torrent.info = info;
-                        }
-                    }
-                    "announce" => {
-                        if let BValue::Bytes(bytes) = value {
-                            torrent.announce = Some(String::from_utf8_lossy(&bytes).into_owned());
-                        }
-                    }
-                    "nodes" => {
-                        if let BValue::List(nodes) = &value {
-                            let mut nodes_vec = vec![];
-                            for node in nodes {
-                                if let BValue::List(node_list) = node {
-                                    if let Some(BValue::Bytes(host)) = node_list.get(0) {
-                                        if let Some(BValue::Int(port)) = node_list.get(1) {
-                                            nodes_vec.push(TorrentNode(
-                                                String::from_utf8_lossy(host).into_owned(),
-                                                *port,
-                                            ));
-                                        }
-                                    }
-                                }
-                            }
-                            torrent.nodes = Some(nodes_vec);
-                        }
-                    }
-                    "encoding" => {
-                        if let BValue::Bytes(bytes) = value {
-                            torrent.encoding = Some(String::from_utf8_lossy(&bytes).into_owned());
-                        }
-                    }
-                    "httpseeds" => {
-                        if let BValue::List(seeds) = &value {
-                            let mut httpseeds_vec = vec![];
-                            for seed in seeds {
-                                if let BValue::Bytes(bytes) = seed {
-                                    httpseeds_vec.push(String::from_utf8_lossy(bytes).into_owned());
-                                }
-                            }
-                            torrent.httpseeds = Some(httpseeds_vec);
-                        }
-                    }
-                    "announce-list" => {
-                        if let BValue::List(lists) = &value {
-                            let mut announce_list_vec = vec![];
-                            for list in lists {
-                                if let BValue::List(announce_list) = list {
-                                    let mut inner_vec = vec![];
-                                    for announce in announce_list {
-                                        if let BValue::Bytes(bytes) = announce {
-                                            inner_vec
-                                                .push(String::from_utf8_lossy(bytes).into_owned());
-                                        }
-                                    }
-                                    announce_list_vec.push(inner_vec);
-                                }
-                            }
-                            torrent.announce_list = Some(announce_list_vec);
-                        }
-                    }
-                    "creation date" => {
-                        if let BValue::Int(int) = value {
-                            torrent.creation_date = Some(int);
+/// This function will return an error if the bencoded value is not a
+/// dictionary or if the `private` flag is not `0` or `1`. In strict mode it
+/// will also return an error if a known key has an unexpected type or if the
+/// torrent violates the specification.
+pub fn decode_torrent_with_mode(
+    bvalue: BValue,
+    mode: ParseMode,
+) -> Result<Torrent, TorrentParseError> {
+    decode_torrent_with_diagnostics(bvalue, mode).map(|(torrent, _)| torrent)
+}
+
+/// Parses a torrent file like [`decode_torrent_with_mode`] and also returns
+/// the problems found while decoding it, sorted by key: values skipped
+/// because they have an unexpected type, keys that are not part of the model
+/// and a summary of the pieces.
+///
+/// # Errors
+///
+/// Same as [`decode_torrent_with_mode`].
+pub fn decode_torrent_with_diagnostics(
+    bvalue: BValue,
+    mode: ParseMode,
+) -> Result<(Torrent, Vec<Diagnostic>), TorrentParseError> {
+    let mut ctx = Context {
+        mode,
+        diagnostics: vec![],
+    };
+
+    let torrent = decode_root(bvalue, &mut ctx)?;
+
+    let mut diagnostics = ctx.diagnostics;
+    diagnostics.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok((torrent, diagnostics))
+}
+
+/// State shared by the decoding functions.
+struct Context {
+    mode: ParseMode,
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn decode_root(bvalue: BValue, ctx: &mut Context) -> Result<Torrent, TorrentParseError> {
+    if ctx.mode.is_strict() {
+        if let Some(diagnostic) = validate(&bvalue)
+            .into_iter()
+            .chain(validate_text(&bvalue, ctx.mode))
+            .find(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            return Err(TorrentParseError::Invalid(diagnostic));
+        }
+    }
+
+    // Like the serde parser, without an `announce-list` unless the torrent
+    // has one.
+    let mut torrent = Torrent {
+        announce_list: None,
+        ..Torrent::default()
+    };
+
+    let BValue::Dict(dict) = bvalue else {
+        return Err(TorrentParseError::UnexpectedType {
+            key: String::new(),
+            found: type_name(&bvalue),
+            expected: "dictionary",
+        });
+    };
+
+    for (key, value) in dict {
+        let key = String::from_utf8_lossy(&key).into_owned();
+        match (key.as_str(), value) {
+            ("info", BValue::Dict(info_dict)) => {
+                torrent.info = decode_info(info_dict, ctx)?;
+            }
+            ("announce", BValue::Bytes(bytes)) => {
+                torrent.announce = Some(String::from_utf8_lossy(&bytes).into_owned());
+            }
+            ("nodes", BValue::List(nodes)) => {
+                let mut nodes_vec = vec![];
+                for (index, node) in nodes.iter().enumerate() {
+                    let node_list = match node {
+                        BValue::List(node_list) => node_list.as_slice(),
+                        _ => &[],
+                    };
+                    match (node_list.first(), node_list.get(1)) {
+                        (Some(BValue::Bytes(host)), Some(BValue::Int(port))) => {
+                            nodes_vec.push(TorrentNode(
+                                String::from_utf8_lossy(host).into_owned(),
+                                *port,
+                            ));
                         }
+                        _ => skip(ctx, &format!("nodes[{index}]"), node, "[host, port] list")?,
                     }
-                    "comment" => {
-                        if let BValue::Bytes(bytes) = value {
-                            torrent.comment = Some(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                torrent.nodes = Some(nodes_vec);
+            }
+            ("encoding", BValue::Bytes(bytes)) => {
+                torrent.encoding = Some(String::from_utf8_lossy(&bytes).into_owned());
+            }
+            ("httpseeds", BValue::List(seeds)) => {
+                torrent.httpseeds = Some(decode_string_list(&seeds, "httpseeds", ctx)?);
+            }
+            ("announce-list", BValue::List(lists)) => {
+                let mut announce_list_vec = vec![];
+                for (index, list) in lists.iter().enumerate() {
+                    let tier_key = format!("announce-list[{index}]");
+                    match list {
+                        BValue::List(announce_list) => {
+                            announce_list_vec.push(decode_string_list(
+                                announce_list,
+                                &tier_key,
+                                ctx,
+                            )?);
                         }
+                        _ => skip(ctx, &tier_key, list, "list")?,
                     }
-                    "created by" => {
-                        if let BValue::Bytes(bytes) = value {
-                            torrent.created_by = Some(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                torrent.announce_list = Some(announce_list_vec);
+            }
+            ("creation date", BValue::Int(int)) => {
+                torrent.creation_date = Some(int);
+            }
+            ("comment", BValue::Bytes(bytes)) => {
+                torrent.comment = Some(ByteString::from(bytes));
+            }
+            ("created by", BValue::Bytes(bytes)) => {
+                torrent.created_by = Some(ByteString::from(bytes));
+            }
+            ("piece layers", BValue::Dict(layers)) => {
+                torrent.piece_layers = Some(decode_piece_layers(layers, ctx)?);
+            }
+            ("url-list", BValue::Bytes(bytes)) => {
+                torrent.url_list = Some(vec![String::from_utf8_lossy(&bytes).into_owned()]);
+            }
+            ("url-list", BValue::List(urls)) => {
+                torrent.url_list = Some(decode_string_list(&urls, "url-list", ctx)?);
+            }
+            ("url-list", value) => skip(ctx, &key, &value, "byte string or list")?,
+            ("signatures", BValue::Dict(signatures)) => {
+                torrent.signatures = Some(decode_signatures(signatures, ctx)?);
+            }
+            (
+                "info" | "announce" | "nodes" | "encoding" | "httpseeds" | "announce-list"
+                | "creation date" | "comment" | "created by" | "piece layers" | "signatures",
+                value,
+            ) => skip(ctx, &key, &value, expected_type(&key))?,
+            (_, value) => {
+                unknown_key(ctx, &key, "kept in extra fields");
+                torrent.extra_fields.insert(key, value);
+            }
+        }
+    }
+
+    Ok(torrent)
+}
+
+/// Handles a known key whose value does not have the expected type: it's an
+/// error in strict mode and the value is skipped in lenient mode.
+fn skip(
+    ctx: &mut Context,
+    key: &str,
+    value: &BValue,
+    expected: &'static str,
+) -> Result<(), TorrentParseError> {
+    if ctx.mode.is_strict() {
+        return Err(TorrentParseError::UnexpectedType {
+            key: key.to_owned(),
+            found: type_name(value),
+            expected,
+        });
+    }
+    ctx.diagnostics.push(Diagnostic::warning(
+        key,
+        format!(
+            "skipped value with unexpected type: found {}, expected {expected}",
+            type_name(value)
+        ),
+    ));
+    Ok(())
+}
+
+/// Converts a length, which can't be negative.
+fn length(key: &str, int: i64) -> Result<u64, TorrentParseError> {
+    u64::try_from(int).map_err(|_| TorrentParseError::UnexpectedValue {
+        key: key.to_owned(),
+        found: int.to_string(),
+        expected: "a non-negative integer",
+    })
+}
+
+/// Records a key that is not part of the model.
+fn unknown_key(ctx: &mut Context, key: &str, action: &str) {
+    ctx.diagnostics
+        .push(Diagnostic::info(key, format!("non-standard key {action}")));
+}
+
+/// Returns the bencode type of the known keys, for error messages.
+fn expected_type(key: &str) -> &'static str {
+    match key {
+        "info" | "piece layers" | "file tree" | "signatures" => "dictionary",
+        "nodes" | "httpseeds" | "announce-list" | "files" | "path" | "path.utf-8"
+        | "symlink path" => "list",
+        "creation date" | "piece length" | "length" | "private" | "meta version" => "integer",
+        _ => "byte string",
+    }
+}
+
+fn decode_info(
+    info_dict: HashMap<Vec<u8>, BValue>,
+    ctx: &mut Context,
+) -> Result<TorrentInfo, TorrentParseError> {
+    let mut info = TorrentInfo {
+        name: ByteString::default(),
+        name_utf8: None,
+        pieces: None,
+        piece_length: 0,
+        md5sum: None,
+        length: None,
+        files: None,
+        private: None,
+        path: None,
+        root_hash: None,
+        source: None,
+        meta_version: None,
+        file_tree: None,
+        extra_fields: BTreeMap::new(),
+    };
+
+    for (info_key, info_value) in info_dict {
+        let info_key = String::from_utf8_lossy(&info_key).into_owned();
+        match (info_key.as_str(), info_value) {
+            ("name", BValue::Bytes(bytes)) => {
+                info.name = ByteString::from(bytes);
+            }
+            ("name.utf-8", BValue::Bytes(bytes)) => {
+                info.name_utf8 = Some(ByteString::from(bytes));
+            }
+            ("pieces", BValue::Bytes(bytes)) => {
+                ctx.diagnostics.push(Diagnostic::info(
+                    "info.pieces",
+                    format!("length {}, hash count {}", bytes.len(), bytes.len() / 20),
+                ));
+                info.pieces = Some(ByteBuf::from(bytes));
+            }
+            ("piece length", BValue::Int(int)) => {
+                info.piece_length = length("info.piece length", int)?;
+            }
+            ("md5sum", BValue::Bytes(bytes)) => {
+                info.md5sum = Some(ByteString::from(bytes));
+            }
+            ("length", BValue::Int(int)) => {
+                info.length = Some(length("info.length", int)?);
+            }
+            ("files", BValue::List(files)) => {
+                let mut torrent_files = vec![];
+                for (index, file) in files.iter().enumerate() {
+                    let file_key = format!("info.files[{index}]");
+                    match file {
+                        BValue::Dict(file_dict) => {
+                            torrent_files.push(decode_file(file_dict, &file_key, ctx)?);
                         }
+                        _ => skip(ctx, &file_key, file, "dictionary")?,
                     }
-                    _ => {
-                        println!("Skipped Dict key: {key}");
-                    }
-                };
+                }
+                info.files = Some(torrent_files);
+            }
+            ("private", BValue::Int(private)) => match private {
+                0 => info.private = Some(0),
+                1 => info.private = Some(1),
+                _ => {
+                    return Err(TorrentParseError::UnexpectedValue {
+                        key: "info.private".to_string(),
+                        found: private.to_string(),
+                        expected: "0 or 1",
+                    });
+                }
+            },
+            ("path", BValue::List(path_list)) => {
+                info.path = Some(decode_string_list(&path_list, "info.path", ctx)?);
+            }
+            ("root hash", BValue::Bytes(bytes)) => {
+                info.root_hash = Some(ByteBuf::from(bytes));
+            }
+            ("source", BValue::Bytes(bytes)) => {
+                info.source = Some(ByteString::from(bytes));
+            }
+            ("meta version", BValue::Int(int)) => {
+                info.meta_version = Some(int);
+            }
+            ("file tree", BValue::Dict(tree_dict)) => {
+                info.file_tree = Some(decode_file_tree(&tree_dict, "info.file tree", ctx)?);
+            }
+            (
+                "name" | "name.utf-8" | "pieces" | "piece length" | "md5sum" | "length" | "files"
+                | "private" | "path" | "root hash" | "source" | "meta version" | "file tree",
+                value,
+            ) => skip(
+                ctx,
+                &format!("info.{info_key}"),
+                &value,
+                expected_type(&info_key),
+            )?,
+            (_, value) => {
+                unknown_key(ctx, &format!("info.{info_key}"), "kept in extra fields");
+                info.extra_fields.insert(info_key, value);
             }
         }
-        BValue::Bytes(_) => panic!("Unexpected Bytes value"),
-        BValue::Int(_) => panic!("Unexpected Int value"),
-        BValue::List(_) => panic!("Unexpected List value"),
+    }
+
+    Ok(info)
+}
+
+fn decode_file(
+    file_dict: &HashMap<Vec<u8>, BValue>,
+    key: &str,
+    ctx: &mut Context,
+) -> Result<TorrentFile, TorrentParseError> {
+    let mut torrent_file = TorrentFile {
+        path: vec![],
+        path_utf8: None,
+        length: 0,
+        md5sum: None,
+        attr: None,
+        sha1: None,
+        symlink_path: None,
+        extra_fields: BTreeMap::new(),
     };
 
-    torrent
+    for (file_key, file_value) in file_dict {
+        let file_key = String::from_utf8_lossy(file_key).into_owned();
+        match (file_key.as_str(), file_value) {
+            ("path", BValue::List(path_list)) => {
+                torrent_file.path =
+                    decode_byte_string_list(path_list, &format!("{key}.path"), ctx)?;
+            }
+            ("path.utf-8", BValue::List(path_list)) => {
+                torrent_file.path_utf8 = Some(decode_byte_string_list(
+                    path_list,
+                    &format!("{key}.path.utf-8"),
+                    ctx,
+                )?);
+            }
+            ("length", BValue::Int(int)) => {
+                torrent_file.length = length(&format!("{key}.length"), *int)?;
+            }
+            ("md5sum", BValue::Bytes(md5sum_bytes)) => {
+                torrent_file.md5sum = Some(ByteString::from(md5sum_bytes.clone()));
+            }
+            ("attr", BValue::Bytes(bytes)) => {
+                torrent_file.attr = Some(String::from_utf8_lossy(bytes).into_owned());
+            }
+            ("sha1", BValue::Bytes(bytes)) => {
+                torrent_file.sha1 = Some(ByteBuf::from(bytes.clone()));
+            }
+            ("symlink path", BValue::List(path_list)) => {
+                torrent_file.symlink_path = Some(decode_byte_string_list(
+                    path_list,
+                    &format!("{key}.symlink path"),
+                    ctx,
+                )?);
+            }
+            (
+                "path" | "path.utf-8" | "length" | "md5sum" | "attr" | "sha1" | "symlink path",
+                value,
+            ) => skip(
+                ctx,
+                &format!("{key}.{file_key}"),
+                value,
+                expected_type(&file_key),
+            )?,
+            _ => {
+                unknown_key(ctx, &format!("{key}.{file_key}"), "kept in extra fields");
+                torrent_file
+                    .extra_fields
+                    .insert(file_key, file_value.clone());
+            }
+        }
+    }
+
+    Ok(torrent_file)
+}
+
+/// Decodes a BEP 52 file tree directory. In lenient mode, nodes that are not
+/// dictionaries are skipped.
+fn decode_file_tree(
+    tree_dict: &HashMap<Vec<u8>, BValue>,
+    key: &str,
+    ctx: &mut Context,
+) -> Result<FileTree, TorrentParseError> {
+    let mut file_tree = FileTree::default();
+
+    for (name, node) in tree_dict {
+        let name = String::from_utf8_lossy(name).into_owned();
+        let node_key = format!("{key}.{name}");
+
+        let BValue::Dict(node_dict) = node else {
+            skip(ctx, &node_key, node, "dictionary")?;
+            continue;
+        };
+
+        match node_dict.get(b"".as_slice()) {
+            Some(BValue::Dict(file_dict)) => {
+                file_tree.0.insert(
+                    name,
+                    FileTreeNode::File(decode_file_tree_file(file_dict, &node_key, ctx)?),
+                );
+            }
+            Some(file) => {
+                skip(ctx, &node_key, file, "dictionary")?;
+            }
+            None => {
+                file_tree.0.insert(
+                    name,
+                    FileTreeNode::Directory(decode_file_tree(node_dict, &node_key, ctx)?),
+                );
+            }
+        }
+    }
+
+    Ok(file_tree)
+}
+
+fn decode_file_tree_file(
+    file_dict: &HashMap<Vec<u8>, BValue>,
+    key: &str,
+    ctx: &mut Context,
+) -> Result<FileTreeFile, TorrentParseError> {
+    let mut file = FileTreeFile {
+        length: 0,
+        pieces_root: None,
+        attr: None,
+        extra_fields: BTreeMap::new(),
+    };
+
+    for (file_key, file_value) in file_dict {
+        let file_key = String::from_utf8_lossy(file_key).into_owned();
+        match (file_key.as_str(), file_value) {
+            ("length", BValue::Int(int)) => file.length = length(&format!("{key}.length"), *int)?,
+            ("pieces root", BValue::Bytes(bytes)) => {
+                file.pieces_root = Some(ByteBuf::from(bytes.clone()));
+            }
+            ("attr", BValue::Bytes(bytes)) => {
+                file.attr = Some(String::from_utf8_lossy(bytes).into_owned());
+            }
+            ("length" | "pieces root" | "attr", value) => skip(
+                ctx,
+                &format!("{key}.{file_key}"),
+                value,
+                expected_type(&file_key),
+            )?,
+            _ => {
+                unknown_key(ctx, &format!("{key}.{file_key}"), "kept in extra fields");
+                file.extra_fields.insert(file_key, file_value.clone());
+            }
+        }
+    }
+
+    Ok(file)
+}
+
+fn decode_piece_layers(
+    layers: HashMap<Vec<u8>, BValue>,
+    ctx: &mut Context,
+) -> Result<PieceLayers, TorrentParseError> {
+    let mut piece_layers = PieceLayers::default();
+
+    for (pieces_root, layer) in layers {
+        match layer {
+            BValue::Bytes(hashes) => {
+                piece_layers
+                    .0
+                    .insert(ByteBuf::from(pieces_root), ByteBuf::from(hashes));
+            }
+            _ => skip(ctx, "piece layers", &layer, "byte string")?,
+        }
+    }
+
+    Ok(piece_layers)
+}
+
+/// Decodes the BEP 35 signatures. In lenient mode, the ones without a
+/// `signature` byte string are skipped.
+fn decode_signatures(
+    signatures: HashMap<Vec<u8>, BValue>,
+    ctx: &mut Context,
+) -> Result<BTreeMap<String, Signature>, TorrentParseError> {
+    let mut decoded = BTreeMap::new();
+
+    for (signer, value) in signatures {
+        let key = format!("signatures.{}", String::from_utf8_lossy(&signer));
+
+        let BValue::Dict(mut signature) = value else {
+            skip(ctx, &key, &value, "dictionary")?;
+            continue;
+        };
+
+        let Some(BValue::Bytes(bytes)) = signature.remove(b"signature".as_slice()) else {
+            if ctx.mode.is_strict() {
+                return Err(TorrentParseError::Invalid(Diagnostic::error(
+                    &format!("{key}.signature"),
+                    "missing required key",
+                )));
+            }
+            ctx.diagnostics.push(Diagnostic::warning(
+                &key,
+                "skipped signature without a `signature` byte string",
+            ));
+            continue;
+        };
+
+        let certificate = match signature.remove(b"certificate".as_slice()) {
+            Some(BValue::Bytes(certificate)) => Some(ByteBuf::from(certificate)),
+            Some(value) => {
+                skip(ctx, &format!("{key}.certificate"), &value, "byte string")?;
+                None
+            }
+            None => None,
+        };
+
+        decoded.insert(
+            String::from_utf8_lossy(&signer).into_owned(),
+            Signature {
+                certificate,
+                info: signature.remove(b"info".as_slice()),
+                signature: ByteBuf::from(bytes),
+            },
+        );
+    }
+
+    Ok(decoded)
+}
+
+/// Decodes the byte strings in a list. In lenient mode, any other type of
+/// value is skipped.
+fn decode_string_list(
+    list: &[BValue],
+    key: &str,
+    ctx: &mut Context,
+) -> Result<Vec<String>, TorrentParseError> {
+    let mut strings = vec![];
+
+    for (index, item) in list.iter().enumerate() {
+        match item {
+            BValue::Bytes(bytes) => strings.push(String::from_utf8_lossy(bytes).into_owned()),
+            _ => skip(ctx, &format!("{key}[{index}]"), item, "byte string")?,
+        }
+    }
+
+    Ok(strings)
+}
+
+/// Like [`decode_string_list`], but keeps the bytes of each item.
+fn decode_byte_string_list(
+    list: &[BValue],
+    key: &str,
+    ctx: &mut Context,
+) -> Result<Vec<ByteString>, TorrentParseError> {
+    let mut strings = vec![];
+
+    for (index, item) in list.iter().enumerate() {
+        match item {
+            BValue::Bytes(bytes) => strings.push(ByteString::from(bytes.as_slice())),
+            _ => skip(ctx, &format!("{key}[{index}]"), item, "byte string")?,
+        }
+    }
+
+    Ok(strings)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bencode::value::Value as BValue;
+
+    use super::{
+        decode_torrent, decode_torrent_with_diagnostics, decode_torrent_with_mode,
+        TorrentParseError,
+    };
+    use crate::utils::diagnostics::{Diagnostic, Severity};
+    use crate::utils::parse_mode::ParseMode;
+
+    fn decode(bytes: &[u8]) -> Result<crate::models::torrent_file::Torrent, TorrentParseError> {
+        decode_torrent(serde_bencode::from_bytes::<BValue>(bytes).unwrap())
+    }
+
+    #[test]
+    fn it_should_fail_when_the_torrent_is_not_a_dictionary() {
+        assert_eq!(
+            decode(b"li1ee").unwrap_err(),
+            TorrentParseError::UnexpectedType {
+                key: String::new(),
+                found: "list",
+                expected: "dictionary"
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_fail_when_the_private_flag_is_not_zero_or_one() {
+        assert_eq!(
+            decode(b"d4:infod4:name1:a7:privatei2eee").unwrap_err(),
+            TorrentParseError::UnexpectedValue {
+                key: "info.private".to_string(),
+                found: "2".to_string(),
+                expected: "0 or 1"
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_decode_the_v2_metadata() {
+        let torrent = decode(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaeee12:meta versioni2e4:name1:ae12:piece layersd32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa32:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbee",
+        )
+        .unwrap();
+
+        assert_eq!(torrent.info.meta_version, Some(2));
+        assert!(torrent.info.file_tree.unwrap().0.contains_key("a"));
+        assert_eq!(
+            torrent.piece_layers.unwrap().get(&[b'a'; 32]),
+            Some([b'b'; 32].as_slice())
+        );
+    }
+
+    #[test]
+    fn it_should_decode_a_valid_torrent() {
+        let bytes = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+
+        let torrent = decode(&bytes).unwrap();
+
+        assert_eq!(torrent.info.name, "mandelbrot_set_01");
+        assert_eq!(torrent.info.private, Some(0));
+    }
+
+    #[test]
+    fn it_should_skip_known_keys_with_unexpected_types_only_in_lenient_mode() {
+        let bytes = b"d8:announcei1e4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        let value = || serde_bencode::from_bytes::<BValue>(bytes).unwrap();
+
+        let torrent = decode_torrent_with_mode(value(), ParseMode::Lenient).unwrap();
+        assert_eq!(torrent.announce, None);
+
+        assert_eq!(
+            decode_torrent_with_mode(value(), ParseMode::Strict).unwrap_err(),
+            TorrentParseError::UnexpectedType {
+                key: "announce".to_string(),
+                found: "integer",
+                expected: "byte string"
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_reject_spec_violations_in_strict_mode() {
+        let value = serde_bencode::from_bytes::<BValue>(b"d4:infod4:name1:aee").unwrap();
+
+        assert!(matches!(
+            decode_torrent_with_mode(value, ParseMode::Strict),
+            Err(TorrentParseError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn it_should_keep_the_non_standard_keys_in_the_extra_fields() {
+        let torrent = decode(b"d4:infod4:name1:a9:publisher3:fooe12:x_cross_seedi1ee").unwrap();
+
+        assert_eq!(
+            torrent.extra_fields.get("x_cross_seed"),
+            Some(&BValue::Int(1))
+        );
+        assert_eq!(
+            torrent.info.extra_fields.get("publisher"),
+            Some(&BValue::Bytes(b"foo".to_vec()))
+        );
+    }
+
+    #[test]
+    fn it_should_decode_the_utf8_names_and_paths() {
+        let torrent = decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl2:\xb0\xa1e10:path.utf-8l3:\xe5\x95\x8aeee4:name1:\xb010:name.utf-83:\xe5\x95\x8aee",
+        )
+        .unwrap();
+
+        assert_eq!(torrent.info.display_name(), "\u{554a}");
+        assert_eq!(torrent.info.files.unwrap()[0].display_path(), ["\u{554a}"]);
+    }
+
+    #[test]
+    fn it_should_decode_the_bep_47_file_attributes() {
+        let torrent = decode(
+            b"d4:infod5:filesld4:attr2:hl6:lengthi0e4:pathl1:ae4:sha120:bbbbbbbbbbbbbbbbbbbb12:symlink pathl1:beee4:name1:dee",
+        )
+        .unwrap();
+
+        let file = &torrent.info.files.unwrap()[0];
+        assert!(file.is_hidden() && file.is_symlink());
+        assert_eq!(file.sha1.as_ref().unwrap().as_slice(), [b'b'; 20]);
+        assert_eq!(file.symlink_path.as_ref().unwrap(), &["b"]);
+    }
+
+    #[test]
+    fn it_should_return_diagnostics_instead_of_printing_them() {
+        let value = serde_bencode::from_bytes::<BValue>(
+            b"d8:announcei1e4:infod5:filesld6:lengthi1e5:mtime1:14:pathl1:aeee4:name1:d6:pieces20:aaaaaaaaaaaaaaaaaaaa9:publisher3:fooee",
+        )
+        .unwrap();
+
+        let (_, diagnostics) = decode_torrent_with_diagnostics(value, ParseMode::Lenient).unwrap();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::warning(
+                    "announce",
+                    "skipped value with unexpected type: found integer, expected byte string"
+                ),
+                Diagnostic::info(
+                    "info.files[0].mtime",
+                    "non-standard key kept in extra fields"
+                ),
+                Diagnostic::info("info.pieces", "length 20, hash count 1"),
+                Diagnostic::info("info.publisher", "non-standard key kept in extra fields"),
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity != Severity::Error));
+    }
 }
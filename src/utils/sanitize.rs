@@ -0,0 +1,176 @@
+//! Maps the paths of a torrent to names that are safe to create on any file
+//! system.
+//!
+//! The mapping is deterministic, so two clients built on this crate store the
+//! same torrent under the same paths. Paths that
+//! [`audit`](crate::utils::audit::audit) reports as dangerous, or as not
+//! valid on Windows, are mapped to safe equivalents.
+use std::path::PathBuf;
+
+use crate::utils::audit::{MAX_COMPONENT_LENGTH, WINDOWS_RESERVED_NAMES};
+
+/// Name used for a path, or a component, with nothing left after it's
+/// sanitized.
+const PLACEHOLDER: &str = "_";
+
+/// Longest extension kept when a component is truncated, in bytes.
+const MAX_KEPT_EXTENSION_LENGTH: usize = 16;
+
+/// Joins the path components of a file, sanitized with
+/// [`sanitize_component`], into a relative path.
+///
+/// Empty, `.` and `..` components are dropped, so the path never points
+/// outside the directory it's joined to. A path with no components left is
+/// `_`.
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// use torrust_parse_torrent::utils::sanitize::sanitize_path;
+///
+/// let path = sanitize_path(&["..".to_owned(), "C:".to_owned(), "CON.txt".to_owned()]);
+///
+/// assert_eq!(path, PathBuf::from("C_").join("CON_.txt"));
+/// ```
+#[must_use]
+pub fn sanitize_path(components: &[String]) -> PathBuf {
+    let path: PathBuf = components
+        .iter()
+        .filter(|component| !matches!(component.as_str(), "" | "." | ".."))
+        .map(|component| sanitize_component(component))
+        .collect();
+
+    if path.as_os_str().is_empty() {
+        PathBuf::from(PLACEHOLDER)
+    } else {
+        path
+    }
+}
+
+/// Returns a file or directory name that is valid on Linux, macOS and
+/// Windows:
+///
+/// - Path separators, control characters and `<>:"|?*` are replaced with `_`.
+/// - Trailing dots and spaces are removed.
+/// - Reserved Windows device names get a `_` suffix: `CON.txt` is `CON_.txt`.
+/// - Names longer than 255 bytes are truncated, keeping the extension.
+///
+/// A name with nothing left, like `...`, is `_`.
+#[must_use]
+pub fn sanitize_component(component: &str) -> String {
+    let replaced: String = component
+        .chars()
+        .map(|character| {
+            if matches!(
+                character,
+                '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*'
+            ) || character.is_control()
+            {
+                '_'
+            } else {
+                character
+            }
+        })
+        .collect();
+
+    let trimmed = replaced.trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        return PLACEHOLDER.to_owned();
+    }
+
+    // Windows reserves the part before the first dot, `CON.tar.gz` and
+    // `CON .txt` included.
+    let base = trimmed.split('.').next().unwrap_or_default();
+    let name = if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| base.trim_end().eq_ignore_ascii_case(reserved))
+    {
+        format!("{base}_{}", &trimmed[base.len()..])
+    } else {
+        trimmed.to_owned()
+    };
+
+    truncate(name)
+}
+
+/// Removes characters from the end of the stem until the name fits in
+/// [`MAX_COMPONENT_LENGTH`] bytes. Long extensions are not kept.
+fn truncate(name: String) -> String {
+    if name.len() <= MAX_COMPONENT_LENGTH {
+        return name;
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 && name.len() - dot <= MAX_KEPT_EXTENSION_LENGTH => name.split_at(dot),
+        _ => (name.as_str(), ""),
+    };
+
+    let mut end = MAX_COMPONENT_LENGTH - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    match stem[..end].trim_end_matches(['.', ' ']) {
+        "" => PLACEHOLDER.to_owned(),
+        stem => format!("{stem}{extension}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{sanitize_component, sanitize_path};
+
+    #[test]
+    fn it_should_keep_safe_names() {
+        assert_eq!(sanitize_component("movie.mkv"), "movie.mkv");
+        assert_eq!(sanitize_component(".hidden"), ".hidden");
+        assert_eq!(sanitize_component("console.txt"), "console.txt");
+        assert_eq!(
+            sanitize_path(&["dir".to_owned(), "a.txt".to_owned()]),
+            PathBuf::from("dir").join("a.txt")
+        );
+    }
+
+    #[test]
+    fn it_should_drop_the_components_that_leave_the_directory() {
+        assert_eq!(
+            sanitize_path(&[
+                "..".to_owned(),
+                String::new(),
+                ".".to_owned(),
+                "a".to_owned()
+            ]),
+            PathBuf::from("a")
+        );
+        assert_eq!(sanitize_path(&["..".to_owned()]), PathBuf::from("_"));
+        assert_eq!(sanitize_path(&["/etc".to_owned()]), PathBuf::from("_etc"));
+        assert_eq!(
+            sanitize_path(&["a/../b".to_owned()]),
+            PathBuf::from("a_.._b")
+        );
+    }
+
+    #[test]
+    fn it_should_map_names_that_are_not_valid_on_windows() {
+        assert_eq!(sanitize_component("what?<>.txt"), "what___.txt");
+        assert_eq!(sanitize_component("a\tb. . "), "a_b");
+        assert_eq!(sanitize_component("..."), "_");
+        assert_eq!(sanitize_component("nul"), "nul_");
+        assert_eq!(sanitize_component("CON.tar.gz"), "CON_.tar.gz");
+        assert_eq!(sanitize_component("lpt1 .txt"), "lpt1 _.txt");
+    }
+
+    #[test]
+    fn it_should_truncate_long_names_keeping_the_extension() {
+        let name = sanitize_component(&format!("{}.mkv", "é".repeat(200)));
+
+        assert_eq!(name.len(), 254);
+        assert!(name.ends_with("é.mkv"));
+
+        let name = sanitize_component(&format!("a.{}", "b".repeat(300)));
+
+        assert_eq!(name.len(), 255);
+    }
+}
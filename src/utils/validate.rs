@@ -0,0 +1,607 @@
+//! Specification compliance checks ([BEP 3](https://www.bittorrent.org/beps/bep_0003.html)
+//! and [BEP 52](https://www.bittorrent.org/beps/bep_0052.html)).
+//!
+//! The checks run on the bencoded value instead of the parsed [`Torrent`](crate::models::torrent_file::Torrent),
+//! so that a missing or malformed key is reported instead of making the whole
+//! file unreadable.
+use std::collections::HashMap;
+
+use serde_bencode::value::Value as BValue;
+
+use crate::models::announce::TrackerUrl;
+use crate::models::piece_layers::PieceLayers;
+use crate::models::torrent_file::Torrent;
+use crate::utils::diagnostics::{Diagnostic, Severity};
+use crate::utils::parse_mode::ParseMode;
+use crate::utils::parse_torrent_verbose::type_name;
+
+/// Length of a SHA-1 hash in the `pieces` field.
+const PIECE_HASH_LEN: usize = 20;
+
+/// Returns the problems found in the bencoded torrent. The torrent is valid if
+/// none of them is an error.
+#[must_use]
+pub fn validate(metainfo: &BValue) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    let BValue::Dict(root) = metainfo else {
+        diagnostics.push(unexpected_type("", metainfo, "dictionary"));
+        return diagnostics;
+    };
+
+    match root.get(b"info".as_slice()) {
+        Some(BValue::Dict(info)) => validate_info(info, &mut diagnostics),
+        Some(info) => diagnostics.push(unexpected_type("info", info, "dictionary")),
+        None => diagnostics.push(Diagnostic::error("info", "missing required key")),
+    }
+
+    if let Some(announce) = root.get(b"announce".as_slice()) {
+        validate_tracker_url("announce", announce, None, &mut diagnostics);
+    }
+
+    if let Some(announce_list) = root.get(b"announce-list".as_slice()) {
+        validate_announce_list(announce_list, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+/// Returns a problem if `trailing` bytes follow the top-level dictionary.
+/// They don't change the info-hash, but they often mean that the file is
+/// corrupt or that something was appended to it. It's an error in strict
+/// mode and a warning otherwise.
+#[must_use]
+pub fn validate_trailing_data(trailing: usize, mode: ParseMode) -> Option<Diagnostic> {
+    if trailing == 0 {
+        return None;
+    }
+
+    let message = format!("{trailing} bytes of trailing data after the torrent");
+    Some(if mode.is_strict() {
+        Diagnostic::error("", message)
+    } else {
+        Diagnostic::warning("", message)
+    })
+}
+
+/// Returns a problem for each text field that is not valid UTF-8: the
+/// `source` and the `md5sum` of the torrent and of its files. The parsers
+/// decode them lossily, so they are readable, but they should be text. It's
+/// an error in strict mode and a warning otherwise.
+#[must_use]
+pub fn validate_text(metainfo: &BValue, mode: ParseMode) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut check = |key: &str, value: Option<&BValue>| {
+        if let Some(BValue::Bytes(bytes)) = value {
+            if std::str::from_utf8(bytes).is_err() {
+                let message = "is not valid UTF-8 text";
+                diagnostics.push(if mode.is_strict() {
+                    Diagnostic::error(key, message)
+                } else {
+                    Diagnostic::warning(key, message)
+                });
+            }
+        }
+    };
+
+    let BValue::Dict(root) = metainfo else {
+        return diagnostics;
+    };
+    let Some(BValue::Dict(info)) = root.get(b"info".as_slice()) else {
+        return diagnostics;
+    };
+
+    check("info.source", info.get(b"source".as_slice()));
+    check("info.md5sum", info.get(b"md5sum".as_slice()));
+    if let Some(BValue::List(files)) = info.get(b"files".as_slice()) {
+        for (index, file) in files.iter().enumerate() {
+            if let BValue::Dict(file) = file {
+                check(
+                    &format!("info.files[{index}].md5sum"),
+                    file.get(b"md5sum".as_slice()),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Returns an error for each file of a v2 or hybrid torrent whose entry in
+/// the `piece layers` is missing or doesn't match its `pieces root` (see
+/// [`PieceLayers::check`]).
+///
+/// Unlike [`validate`], it needs the parsed torrent: the merkle roots are
+/// recomputed from the layers.
+#[must_use]
+pub fn validate_piece_layers(torrent: &Torrent) -> Vec<Diagnostic> {
+    let Some(file_tree) = &torrent.info.file_tree else {
+        return vec![];
+    };
+
+    let no_layers = PieceLayers::default();
+
+    torrent
+        .piece_layers
+        .as_ref()
+        .unwrap_or(&no_layers)
+        .check(file_tree, torrent.info.piece_length)
+        .into_iter()
+        .map(|inconsistent| {
+            Diagnostic::error(
+                "piece layers",
+                format!("`{}`: {}", inconsistent.path, inconsistent.error),
+            )
+        })
+        .collect()
+}
+
+/// Tracker problems are warnings: clients skip the unusable trackers and the
+/// torrent still works with the others, or with DHT.
+fn validate_announce_list(announce_list: &BValue, diagnostics: &mut Vec<Diagnostic>) {
+    let BValue::List(tiers) = announce_list else {
+        diagnostics.push(unexpected_tracker_type(
+            "announce-list",
+            announce_list,
+            "list",
+        ));
+        return;
+    };
+
+    for (tier_index, tier) in tiers.iter().enumerate() {
+        let key = format!("announce-list[{tier_index}]");
+
+        let BValue::List(urls) = tier else {
+            diagnostics.push(unexpected_tracker_type(&key, tier, "list"));
+            continue;
+        };
+
+        for (index, url) in urls.iter().enumerate() {
+            validate_tracker_url(
+                &format!("{key}[{index}]"),
+                url,
+                Some(tier_index),
+                diagnostics,
+            );
+        }
+    }
+}
+
+/// Checks that a tracker URL has a supported scheme, a host and a port.
+fn validate_tracker_url(
+    key: &str,
+    url: &BValue,
+    tier: Option<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let BValue::Bytes(bytes) = url else {
+        diagnostics.push(unexpected_tracker_type(key, url, "byte string"));
+        return;
+    };
+
+    let location = tier
+        .map(|tier| format!(" in tier {tier}"))
+        .unwrap_or_default();
+
+    let Ok(url) = std::str::from_utf8(bytes) else {
+        diagnostics.push(Diagnostic::warning(
+            key,
+            format!("tracker URL{location} is not valid UTF-8"),
+        ));
+        return;
+    };
+
+    if url.is_empty() {
+        return;
+    }
+
+    if let Err(error) = TrackerUrl::parse(url) {
+        diagnostics.push(Diagnostic::warning(
+            key,
+            format!("invalid tracker URL `{url}`{location}: {error}"),
+        ));
+    }
+}
+
+fn validate_info(info: &HashMap<Vec<u8>, BValue>, diagnostics: &mut Vec<Diagnostic>) {
+    match info.get(b"name".as_slice()) {
+        Some(BValue::Bytes(_)) => {}
+        Some(name) => diagnostics.push(unexpected_type("info.name", name, "byte string")),
+        None => diagnostics.push(Diagnostic::error("info.name", "missing required key")),
+    }
+
+    let piece_length = match info.get(b"piece length".as_slice()) {
+        Some(BValue::Int(piece_length)) if *piece_length > 0 => {
+            if !piece_length.unsigned_abs().is_power_of_two() {
+                diagnostics.push(Diagnostic::warning(
+                    "info.piece length",
+                    format!("{piece_length} is not a power of two"),
+                ));
+            }
+            Some(piece_length.unsigned_abs())
+        }
+        Some(BValue::Int(piece_length)) => {
+            diagnostics.push(Diagnostic::error(
+                "info.piece length",
+                format!("{piece_length} is not a positive integer"),
+            ));
+            None
+        }
+        Some(piece_length) => {
+            diagnostics.push(unexpected_type(
+                "info.piece length",
+                piece_length,
+                "integer",
+            ));
+            None
+        }
+        None => {
+            diagnostics.push(Diagnostic::error(
+                "info.piece length",
+                "missing required key",
+            ));
+            None
+        }
+    };
+
+    let has_file_tree = info.contains_key(b"file tree".as_slice());
+
+    // BEP 30 Merkle torrents have a root hash instead of the pieces.
+    let has_root_hash = match info.get(b"root hash".as_slice()) {
+        Some(BValue::Bytes(root_hash)) if root_hash.len() == PIECE_HASH_LEN => true,
+        Some(BValue::Bytes(root_hash)) => {
+            diagnostics.push(Diagnostic::error(
+                "info.root hash",
+                format!("length {} is not {PIECE_HASH_LEN}", root_hash.len()),
+            ));
+            true
+        }
+        Some(root_hash) => {
+            diagnostics.push(unexpected_type("info.root hash", root_hash, "byte string"));
+            true
+        }
+        None => false,
+    };
+
+    let pieces = match info.get(b"pieces".as_slice()) {
+        Some(BValue::Bytes(pieces)) => {
+            if pieces.len() % PIECE_HASH_LEN != 0 {
+                diagnostics.push(Diagnostic::error(
+                    "info.pieces",
+                    format!(
+                        "length {} is not a multiple of {PIECE_HASH_LEN}",
+                        pieces.len()
+                    ),
+                ));
+            }
+            Some(pieces)
+        }
+        Some(pieces) => {
+            diagnostics.push(unexpected_type("info.pieces", pieces, "byte string"));
+            None
+        }
+        None if has_file_tree || has_root_hash => None,
+        None => {
+            diagnostics.push(Diagnostic::error(
+                "info.pieces",
+                "missing required key (or `file tree` for v2 torrents, or `root hash` for Merkle torrents)",
+            ));
+            None
+        }
+    };
+
+    let total_length = match (
+        info.get(b"length".as_slice()),
+        info.get(b"files".as_slice()),
+    ) {
+        (Some(_), Some(_)) => {
+            diagnostics.push(Diagnostic::error(
+                "info",
+                "`length` and `files` must not be both present",
+            ));
+            None
+        }
+        (Some(length), None) => validate_length("info.length", length, diagnostics),
+        (None, Some(files)) => validate_files(files, diagnostics),
+        (None, None) if has_file_tree => None,
+        (None, None) => {
+            diagnostics.push(Diagnostic::error(
+                "info",
+                "missing required key `length` (single-file) or `files` (multi-file)",
+            ));
+            None
+        }
+    };
+
+    if let (Some(pieces), Some(piece_length), Some(total_length)) =
+        (pieces, piece_length, total_length)
+    {
+        let expected = total_length.div_ceil(piece_length);
+        let found = (pieces.len() / PIECE_HASH_LEN) as u64;
+        if found != expected {
+            diagnostics.push(Diagnostic::error(
+                "info.pieces",
+                format!(
+                    "{found} piece hashes found, expected {expected} for {total_length} bytes in pieces of {piece_length} bytes"
+                ),
+            ));
+        }
+    }
+}
+
+/// Returns the total length of the files, if all of them are valid.
+fn validate_files(files: &BValue, diagnostics: &mut Vec<Diagnostic>) -> Option<u64> {
+    let BValue::List(files) = files else {
+        diagnostics.push(unexpected_type("info.files", files, "list"));
+        return None;
+    };
+
+    let mut total_length = Some(0u64);
+
+    for (index, file) in files.iter().enumerate() {
+        let key = format!("info.files[{index}]");
+
+        let BValue::Dict(file) = file else {
+            diagnostics.push(unexpected_type(&key, file, "dictionary"));
+            total_length = None;
+            continue;
+        };
+
+        let length = match file.get(b"length".as_slice()) {
+            Some(length) => validate_length(&format!("{key}.length"), length, diagnostics),
+            None => {
+                diagnostics.push(Diagnostic::error(
+                    &format!("{key}.length"),
+                    "missing required key",
+                ));
+                None
+            }
+        };
+        total_length = match total_length.zip(length) {
+            Some((total, length)) => total.checked_add(length).or_else(|| {
+                diagnostics.push(Diagnostic::error(
+                    "info.files",
+                    "the total length of the files does not fit in 64 bits",
+                ));
+                None
+            }),
+            None => None,
+        };
+
+        match file.get(b"path".as_slice()) {
+            Some(BValue::List(_)) => {}
+            Some(path) => diagnostics.push(unexpected_type(&format!("{key}.path"), path, "list")),
+            None => diagnostics.push(Diagnostic::error(
+                &format!("{key}.path"),
+                "missing required key",
+            )),
+        }
+    }
+
+    total_length
+}
+
+fn validate_length(key: &str, length: &BValue, diagnostics: &mut Vec<Diagnostic>) -> Option<u64> {
+    match length {
+        BValue::Int(length) => u64::try_from(*length).ok().or_else(|| {
+            diagnostics.push(Diagnostic::error(key, format!("{length} is negative")));
+            None
+        }),
+        _ => {
+            diagnostics.push(unexpected_type(key, length, "integer"));
+            None
+        }
+    }
+}
+
+fn unexpected_type(key: &str, value: &BValue, expected: &str) -> Diagnostic {
+    Diagnostic::error(
+        key,
+        format!(
+            "unexpected type: found {}, expected {expected}",
+            type_name(value)
+        ),
+    )
+}
+
+fn unexpected_tracker_type(key: &str, value: &BValue, expected: &str) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        ..unexpected_type(key, value, expected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bencode::value::Value as BValue;
+
+    use super::{validate, validate_text, validate_trailing_data};
+    use crate::utils::diagnostics::{has_errors, Diagnostic, Severity};
+    use crate::utils::parse_mode::ParseMode;
+
+    fn diagnostics(bytes: &[u8]) -> Vec<Diagnostic> {
+        let value: BValue = serde_bencode::from_bytes(bytes).unwrap();
+        validate(&value)
+    }
+
+    #[test]
+    fn it_should_accept_a_valid_torrent() {
+        let torrent = std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap();
+
+        assert_eq!(diagnostics(&torrent), vec![]);
+    }
+
+    #[test]
+    fn it_should_report_missing_required_keys() {
+        assert_eq!(
+            diagnostics(b"d8:announce23:udp://tracker.test:6969e"),
+            vec![Diagnostic::error("info", "missing required key")]
+        );
+
+        let keys: Vec<String> = diagnostics(b"d4:infodee")
+            .into_iter()
+            .map(|diagnostic| diagnostic.key)
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec!["info.name", "info.piece length", "info.pieces", "info"]
+        );
+    }
+
+    #[test]
+    fn it_should_report_a_pieces_length_that_is_not_a_multiple_of_20() {
+        let found =
+            diagnostics(b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces3:abcee");
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].key, "info.pieces");
+        assert!(found[0].message.contains("not a multiple of 20"));
+    }
+
+    #[test]
+    fn it_should_report_a_piece_count_that_does_not_match_the_total_length() {
+        let found = diagnostics(
+            b"d4:infod5:filesld6:lengthi16384e4:pathl1:aeed6:lengthi1e4:pathl1:beee4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        );
+
+        assert_eq!(found.len(), 1);
+        assert!(found[0]
+            .message
+            .starts_with("1 piece hashes found, expected 2"));
+    }
+
+    #[test]
+    fn it_should_report_length_and_files_both_present() {
+        let found = diagnostics(
+            b"d4:infod5:filesld6:lengthi1e4:pathl1:aeee6:lengthi1e4:name1:d12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        );
+
+        assert_eq!(
+            found,
+            vec![Diagnostic::error(
+                "info",
+                "`length` and `files` must not be both present"
+            )]
+        );
+    }
+
+    #[test]
+    fn it_should_accept_a_root_hash_instead_of_the_pieces() {
+        assert_eq!(
+            diagnostics(
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e9:root hash20:aaaaaaaaaaaaaaaaaaaaee"
+            ),
+            vec![]
+        );
+        assert_eq!(
+            diagnostics(b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e9:root hash3:abcee")[0]
+                .key,
+            "info.root hash"
+        );
+    }
+
+    #[test]
+    fn it_should_warn_about_a_piece_length_that_is_not_a_power_of_two() {
+        let found = diagnostics(
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi1000e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        );
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].severity, Severity::Warning);
+        assert!(!has_errors(&found));
+    }
+
+    #[test]
+    fn it_should_accept_a_v2_only_torrent_without_pieces() {
+        let found = diagnostics(
+            b"d4:infod9:file treed1:ad0:d6:lengthi0eeee12:meta versioni2e4:name1:a12:piece lengthi16384eee",
+        );
+
+        assert_eq!(found, vec![]);
+    }
+
+    #[test]
+    fn it_should_warn_about_malformed_tracker_urls_with_their_tier() {
+        let found = diagnostics(
+            b"d8:announce7:tracker13:announce-listll23:udp://tracker.test:6969el0:22:udp://tracker.test/anni1eee4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        );
+
+        let keys: Vec<&str> = found
+            .iter()
+            .map(|diagnostic| diagnostic.key.as_str())
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec!["announce", "announce-list[1][1]", "announce-list[1][2]"]
+        );
+        assert!(found[1].message.contains("in tier 1: missing port"));
+        assert!(!has_errors(&found));
+    }
+
+    #[test]
+    fn it_should_report_trailing_data_as_an_error_only_in_strict_mode() {
+        assert_eq!(validate_trailing_data(0, ParseMode::Strict), None);
+        assert_eq!(
+            validate_trailing_data(3, ParseMode::Lenient),
+            Some(Diagnostic::warning(
+                "",
+                "3 bytes of trailing data after the torrent"
+            ))
+        );
+        assert_eq!(
+            validate_trailing_data(3, ParseMode::Strict).map(|diagnostic| diagnostic.severity),
+            Some(Severity::Error)
+        );
+    }
+
+    #[test]
+    fn it_should_report_negative_lengths_and_a_total_length_that_overflows() {
+        let max = i64::MAX;
+        let bytes = format!(
+            "d4:infod5:filesld6:lengthi{max}e4:pathl1:aeed6:lengthi{max}e4:pathl1:beed6:lengthi{max}e4:pathl1:ceee4:name1:d12:piece lengthi-1e6:pieces20:aaaaaaaaaaaaaaaaaaaaee"
+        );
+
+        assert_eq!(
+            diagnostics(bytes.as_bytes()),
+            vec![
+                Diagnostic::error("info.piece length", "-1 is not a positive integer"),
+                Diagnostic::error(
+                    "info.files",
+                    "the total length of the files does not fit in 64 bits"
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics(b"d4:infod6:lengthi-1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee"),
+            vec![Diagnostic::error("info.length", "-1 is negative")]
+        );
+    }
+
+    #[test]
+    fn it_should_report_text_fields_that_are_not_utf8_as_errors_only_in_strict_mode() {
+        let value: BValue = serde_bencode::from_bytes(
+            b"d4:infod5:filesld6:lengthi1e6:md5sum1:\xff4:pathl1:aeee6:md5sum2:\xff\xfe4:name1:a12:piece lengthi16384e6:source1:\xffee",
+        )
+        .unwrap();
+
+        let strict = validate_text(&value, ParseMode::Strict);
+        let lenient = validate_text(&value, ParseMode::Lenient);
+
+        let keys: Vec<&str> = strict
+            .iter()
+            .map(|diagnostic| diagnostic.key.as_str())
+            .collect();
+        assert_eq!(keys, ["info.source", "info.md5sum", "info.files[0].md5sum"]);
+        assert!(has_errors(&strict));
+        assert!(!has_errors(&lenient));
+        assert_eq!(lenient.len(), 3);
+
+        let valid: BValue = serde_bencode::from_bytes(
+            &std::fs::read("torrents/mandelbrot_set_01.torrent").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(validate_text(&valid, ParseMode::Strict), vec![]);
+    }
+}
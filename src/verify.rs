@@ -0,0 +1,826 @@
+//! Verification of downloaded content against the pieces of a torrent.
+//!
+//! ```no_run
+//! use torrust_parse_torrent::parse_torrent;
+//! use torrust_parse_torrent::verify::verify;
+//!
+//! let torrent = parse_torrent(&std::fs::read("content.torrent").unwrap()).unwrap();
+//!
+//! let report = verify(&torrent, "./content").unwrap();
+//!
+//! println!("{:.1}% complete", report.completion() * 100.0);
+//! ```
+//!
+//! Use a [`Verifier`] to choose how many threads hash the pieces and how much
+//! data is read ahead of them.
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use crate::builder::thread_pool;
+use crate::models::byte_string::ByteString;
+use crate::models::torrent_file::Torrent;
+use crate::progress::{NoProgress, ProgressHandler, ProgressTracker};
+use crate::utils::file_bytes;
+
+/// Errors that prevent the content from being verified.
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    /// The torrent has no v1 `pieces`, like v2-only torrents.
+    #[error("the torrent has no v1 pieces to verify")]
+    NoPieces,
+    /// The piece length is zero.
+    #[error("invalid piece length {0}")]
+    InvalidPieceLength(u64),
+    /// The sum of the file lengths does not fit in a `u64`.
+    #[error("the total length of the files does not fit in 64 bits")]
+    ContentTooLarge,
+    /// The number of pieces doesn't match the size of the content.
+    #[error("the torrent has {actual} pieces but its files need {expected}")]
+    PieceCount { expected: usize, actual: usize },
+    /// A file selection pattern is not a valid glob.
+    #[error("invalid pattern: {0}")]
+    Pattern(#[from] globset::Error),
+    /// A file path would be outside the content directory, like `../a`.
+    #[error("unsafe file path `{0}`")]
+    UnsafePath(String),
+    /// The thread pool to hash the pieces could not be created.
+    #[error("unable to start the hashing threads: {0}")]
+    ThreadPool(rayon::ThreadPoolBuildError),
+    /// A file exists but could not be read.
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Result of checking one piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceStatus {
+    /// The data has the expected hash.
+    Ok,
+    /// The data has a different hash.
+    Corrupt,
+    /// Part of the data is in a file that doesn't exist or is too short.
+    Missing,
+    /// The piece has no data of the selected files, so it wasn't checked.
+    Skipped,
+}
+
+/// Result of checking one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// All the pieces of the file are valid and it has the expected size.
+    Ok,
+    /// The file doesn't exist.
+    Missing,
+    /// Some pieces of the file are missing data, because it's shorter than
+    /// expected or a neighbouring file sharing a piece is missing. The rest
+    /// are valid.
+    Incomplete,
+    /// Some pieces of the file are corrupt, or it's larger than expected.
+    Corrupt,
+}
+
+/// Result of checking one file of the torrent. Padding files, and the files
+/// that weren't selected, are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    /// Path of the file relative to the content directory. For single-file
+    /// torrents, it's the name of the file.
+    pub path: PathBuf,
+    /// Length declared in the torrent.
+    pub length: u64,
+    /// Length of the file on disk, if it exists.
+    pub actual_length: Option<u64>,
+    /// Indexes of the pieces with data of the file. The first and last ones
+    /// can be shared with the neighbouring files.
+    pub pieces: Range<usize>,
+    pub status: FileStatus,
+}
+
+/// Result of checking all the pieces of a torrent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Status of each piece, by index.
+    pub pieces: Vec<PieceStatus>,
+    pub files: Vec<FileReport>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if every checked piece and file is valid.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.pieces
+            .iter()
+            .all(|status| matches!(status, PieceStatus::Ok | PieceStatus::Skipped))
+            && self.files.iter().all(|file| file.status == FileStatus::Ok)
+    }
+
+    /// Returns the number of pieces that were checked.
+    #[must_use]
+    pub fn checked_pieces(&self) -> usize {
+        self.pieces
+            .iter()
+            .filter(|status| **status != PieceStatus::Skipped)
+            .count()
+    }
+
+    /// Returns the number of valid pieces.
+    #[must_use]
+    pub fn ok_pieces(&self) -> usize {
+        self.pieces
+            .iter()
+            .filter(|status| **status == PieceStatus::Ok)
+            .count()
+    }
+
+    /// Returns the fraction of the checked pieces that are valid, between 0
+    /// and 1.
+    #[must_use]
+    pub fn completion(&self) -> f64 {
+        let checked = self.checked_pieces();
+        if checked == 0 {
+            return 1.0;
+        }
+        self.ok_pieces() as f64 / checked as f64
+    }
+}
+
+/// Default number of bytes read ahead of the hashing threads.
+pub const DEFAULT_READ_AHEAD: usize = 64 * 1024 * 1024;
+
+/// Checks the content at `content` against the v1 pieces of the torrent,
+/// with the default [`Verifier`] options.
+///
+/// `content` is the file of a single-file torrent, or the directory with the
+/// files of a multi-file torrent, like the path given to the
+/// [`TorrentBuilder`](crate::builder::TorrentBuilder).
+///
+/// # Errors
+///
+/// Will return an error if the torrent has no v1 pieces, it's inconsistent
+/// or has unsafe paths, or a file exists but can't be read.
+pub fn verify(torrent: &Torrent, content: impl AsRef<Path>) -> Result<VerifyReport, VerifyError> {
+    Verifier::new(torrent, content.as_ref()).verify()
+}
+
+/// Like [`verify`], but reports the progress of the hashing to `progress`.
+///
+/// # Errors
+///
+/// Will return an error if the torrent has no v1 pieces, it's inconsistent
+/// or has unsafe paths, or a file exists but can't be read.
+pub fn verify_with_progress(
+    torrent: &Torrent,
+    content: impl AsRef<Path>,
+    progress: &dyn ProgressHandler,
+) -> Result<VerifyReport, VerifyError> {
+    Verifier::new(torrent, content.as_ref()).verify_with_progress(progress)
+}
+
+/// Checks content against a torrent with custom options.
+///
+/// Pieces are read in order, which is what disks do best, and hashed in
+/// parallel while the next ones are read. At most `read_ahead` bytes, or two
+/// pieces per thread if they are larger, are held in memory at once.
+#[derive(Debug, Clone)]
+pub struct Verifier<'a> {
+    torrent: &'a Torrent,
+    content: PathBuf,
+    threads: usize,
+    read_ahead: usize,
+    files: Vec<String>,
+}
+
+impl<'a> Verifier<'a> {
+    /// Creates a verifier of the content at `content`. See [`verify`].
+    #[must_use]
+    pub fn new(torrent: &'a Torrent, content: impl Into<PathBuf>) -> Self {
+        Self {
+            torrent,
+            content: content.into(),
+            threads: 0,
+            read_ahead: DEFAULT_READ_AHEAD,
+            files: vec![],
+        }
+    }
+
+    /// Sets the number of threads that hash the pieces. `0`, the default,
+    /// uses one per CPU. `1` reads and hashes them on the calling thread.
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the number of bytes read before they are hashed, which bounds
+    /// the memory used. It defaults to [`DEFAULT_READ_AHEAD`].
+    #[must_use]
+    pub fn read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+
+    /// Selects the files whose path matches a glob pattern, like
+    /// `Season 01/*.mkv`. If there are any, only the pieces with data of the
+    /// selected files are checked, including the ones they share with their
+    /// neighbours, and only those files are reported.
+    #[must_use]
+    pub fn file(mut self, pattern: impl Into<String>) -> Self {
+        self.files.push(pattern.into());
+        self
+    }
+
+    /// Reads the content and checks every piece.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the torrent has no v1 pieces, it's
+    /// inconsistent or has unsafe paths, a pattern is not valid or a file
+    /// exists but can't be read.
+    pub fn verify(&self) -> Result<VerifyReport, VerifyError> {
+        self.verify_with_progress(&NoProgress)
+    }
+
+    /// Like [`verify`](Verifier::verify), but reports the progress of the
+    /// hashing to `progress`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the torrent has no v1 pieces, it's
+    /// inconsistent or has unsafe paths, a pattern is not valid or a file
+    /// exists but can't be read.
+    pub fn verify_with_progress(
+        &self,
+        progress: &dyn ProgressHandler,
+    ) -> Result<VerifyReport, VerifyError> {
+        let info = &self.torrent.info;
+        let hashes = info.pieces.as_ref().ok_or(VerifyError::NoPieces)?;
+        let piece_length = Some(info.piece_length)
+            .filter(|length| *length > 0)
+            .ok_or(VerifyError::InvalidPieceLength(info.piece_length))?;
+
+        let entries = content_entries(self.torrent, &self.content)?;
+        let total_length = entries
+            .last()
+            .map_or(0, |entry| entry.offset + entry.length);
+
+        let piece_count = usize::try_from(total_length.div_ceil(piece_length))
+            .expect("piece count should fit in usize");
+        if hashes.len() != piece_count * 20 {
+            return Err(VerifyError::PieceCount {
+                expected: piece_count,
+                actual: hashes.len() / 20,
+            });
+        }
+
+        let piece_range = |index: usize| {
+            let start = index as u64 * piece_length;
+            start..(start + piece_length).min(total_length)
+        };
+
+        let selected = self.select(&entries)?;
+        let mut wanted = vec![selected.is_none(); piece_count];
+        for entry in selected.iter().flatten() {
+            wanted[entry.pieces(piece_length)].fill(true);
+        }
+        let indexes: Vec<usize> = (0..piece_count).filter(|index| wanted[*index]).collect();
+
+        let pool = thread_pool(self.threads).map_err(VerifyError::ThreadPool)?;
+        let progress = ProgressTracker::start(
+            progress,
+            indexes
+                .iter()
+                .map(|index| {
+                    let range = piece_range(*index);
+                    range.end - range.start
+                })
+                .sum(),
+        );
+        let check = |index: usize, data: Option<Vec<u8>>| {
+            let status = match data {
+                Some(data) if Sha1::digest(&data).as_slice() == &hashes[index * 20..][..20] => {
+                    PieceStatus::Ok
+                }
+                Some(_) => PieceStatus::Corrupt,
+                None => PieceStatus::Missing,
+            };
+            let range = piece_range(index);
+            progress.advance(range.end - range.start);
+            status
+        };
+
+        let mut pieces = vec![PieceStatus::Skipped; piece_count];
+
+        if let Some(pool) = pool {
+            // Enough pieces to keep every thread busy. The next batch is read
+            // while one is hashed, so each holds half of `read_ahead` bytes
+            // unless the pieces are very large.
+            let batch_len = pool
+                .current_num_threads()
+                .max(self.read_ahead / 2 / usize::try_from(piece_length).unwrap_or(usize::MAX));
+            let (indexes, entries, progress) = (&indexes, &entries, &progress);
+
+            thread::scope(|scope| {
+                // Without capacity, the reader holds the batch it read until
+                // the previous one is hashed.
+                let (sender, batches) = mpsc::sync_channel(0);
+                scope.spawn(move || {
+                    for batch_indexes in indexes.chunks(batch_len) {
+                        let batch = batch_indexes
+                            .iter()
+                            .map(|index| {
+                                let data = read_piece(entries, piece_range(*index), progress)?;
+                                Ok((*index, data))
+                            })
+                            .collect::<Result<Vec<_>, VerifyError>>();
+                        let failed = batch.is_err();
+                        if sender.send(batch).is_err() || failed {
+                            break;
+                        }
+                    }
+                });
+
+                for batch in batches {
+                    let batch = batch?;
+                    let statuses: Vec<_> = pool.install(|| {
+                        batch
+                            .into_par_iter()
+                            .map(|(index, data)| (index, check(index, data)))
+                            .collect()
+                    });
+                    for (index, status) in statuses {
+                        pieces[index] = status;
+                    }
+                }
+
+                Ok::<_, VerifyError>(())
+            })?;
+        } else {
+            for index in indexes {
+                let data = read_piece(&entries, piece_range(index), &progress)?;
+                pieces[index] = check(index, data);
+            }
+        }
+
+        progress.finish();
+
+        let files = match selected {
+            Some(selected) => selected
+                .iter()
+                .map(|entry| entry.report(piece_length, &pieces))
+                .collect(),
+            None => entries
+                .iter()
+                .filter(|entry| !entry.padding)
+                .map(|entry| entry.report(piece_length, &pieces))
+                .collect(),
+        };
+
+        Ok(VerifyReport { pieces, files })
+    }
+
+    /// Returns the files that match the patterns, or `None` if there are no
+    /// patterns.
+    fn select<'e>(&self, entries: &'e [Entry]) -> Result<Option<Vec<&'e Entry>>, VerifyError> {
+        if self.files.is_empty() {
+            return Ok(None);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.files {
+            builder.add(Glob::new(pattern)?);
+        }
+        let patterns: GlobSet = builder.build()?;
+
+        Ok(Some(
+            entries
+                .iter()
+                .filter(|entry| !entry.padding)
+                .filter(|entry| {
+                    let path = entry
+                        .relative_path
+                        .components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    patterns.is_match(path)
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// A file of the torrent and where its data is on disk.
+struct Entry {
+    /// Path relative to the content directory.
+    relative_path: PathBuf,
+    disk_path: PathBuf,
+    /// Position of the file in the content, as a single stream.
+    offset: u64,
+    length: u64,
+    /// BEP 47 padding file, read as zeros.
+    padding: bool,
+    actual_length: Option<u64>,
+}
+
+impl Entry {
+    /// Returns the indexes of the pieces with data of the file.
+    fn pieces(&self, piece_length: u64) -> Range<usize> {
+        let first = usize::try_from(self.offset / piece_length).expect("piece index should fit");
+        let last = if self.length == 0 {
+            first
+        } else {
+            usize::try_from((self.offset + self.length - 1) / piece_length + 1)
+                .expect("piece index should fit")
+        };
+        first..last
+    }
+
+    fn report(&self, piece_length: u64, pieces: &[PieceStatus]) -> FileReport {
+        let range = self.pieces(piece_length);
+        let statuses = &pieces[range.clone()];
+
+        let status = match self.actual_length {
+            None => FileStatus::Missing,
+            Some(actual) if actual > self.length => FileStatus::Corrupt,
+            Some(_) if statuses.contains(&PieceStatus::Corrupt) => FileStatus::Corrupt,
+            Some(actual) if actual < self.length || statuses.contains(&PieceStatus::Missing) => {
+                FileStatus::Incomplete
+            }
+            Some(_) => FileStatus::Ok,
+        };
+
+        FileReport {
+            path: self.relative_path.clone(),
+            length: self.length,
+            actual_length: self.actual_length,
+            pieces: range,
+            status,
+        }
+    }
+
+    fn io_error(&self, source: io::Error) -> VerifyError {
+        VerifyError::Io {
+            path: self.disk_path.clone(),
+            source,
+        }
+    }
+}
+
+/// Returns the files of the torrent in order, with their position in the
+/// content and their length on disk.
+fn content_entries(torrent: &Torrent, content: &Path) -> Result<Vec<Entry>, VerifyError> {
+    let files: Vec<(PathBuf, u64, bool)> = match &torrent.info.files {
+        Some(files) => files
+            .iter()
+            .map(|file| {
+                Ok((
+                    relative_path(file.display_path())?,
+                    file.length,
+                    file.is_padding(),
+                ))
+            })
+            .collect::<Result<_, VerifyError>>()?,
+        None => vec![(
+            relative_path(std::slice::from_ref(torrent.info.display_name()))?,
+            torrent.info.length.unwrap_or(0),
+            false,
+        )],
+    };
+    let single_file = torrent.info.files.is_none();
+
+    let mut entries = Vec::with_capacity(files.len());
+    let mut offset = 0;
+
+    for (relative_path, length, padding) in files {
+        let disk_path = if single_file {
+            content.to_path_buf()
+        } else {
+            content.join(&relative_path)
+        };
+
+        let actual_length = if padding {
+            Some(length)
+        } else {
+            match fs::metadata(&disk_path) {
+                Ok(metadata) if metadata.is_file() => Some(metadata.len()),
+                Ok(_) => None,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(source) => {
+                    return Err(VerifyError::Io {
+                        path: disk_path,
+                        source,
+                    })
+                }
+            }
+        };
+
+        entries.push(Entry {
+            relative_path,
+            disk_path,
+            offset,
+            length,
+            padding,
+            actual_length,
+        });
+        offset = offset
+            .checked_add(length)
+            .ok_or(VerifyError::ContentTooLarge)?;
+    }
+
+    Ok(entries)
+}
+
+/// Joins the components of a path of the torrent, rejecting the ones that
+/// could point outside the content directory.
+fn relative_path(components: &[ByteString]) -> Result<PathBuf, VerifyError> {
+    let mut path = PathBuf::new();
+
+    for component in components {
+        let text = component.to_str_lossy();
+        if text.is_empty()
+            || text == "."
+            || text == ".."
+            || text.contains(['/', '\\', '\0'])
+            || Path::new(text.as_ref()).is_absolute()
+        {
+            return Err(VerifyError::UnsafePath(
+                components
+                    .iter()
+                    .map(ByteString::to_str_lossy)
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            ));
+        }
+        path.push(os_string(component));
+    }
+
+    Ok(path)
+}
+
+#[cfg(unix)]
+fn os_string(component: &ByteString) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    std::ffi::OsString::from_vec(component.as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn os_string(component: &ByteString) -> std::ffi::OsString {
+    component.to_str_lossy().into_owned().into()
+}
+
+/// Reads the bytes of the content in `range`. Returns `None` if part of them
+/// is missing.
+fn read_piece(
+    entries: &[Entry],
+    range: Range<u64>,
+    progress: &ProgressTracker,
+) -> Result<Option<Vec<u8>>, VerifyError> {
+    let mut data =
+        vec![0; usize::try_from(range.end - range.start).expect("piece should fit in memory")];
+    let first = entries.partition_point(|entry| entry.offset + entry.length <= range.start);
+
+    for entry in &entries[first..] {
+        if entry.offset >= range.end {
+            break;
+        }
+        let from = range.start.max(entry.offset);
+        let to = range.end.min(entry.offset + entry.length);
+        if entry.padding || from == to {
+            continue;
+        }
+        if entry
+            .actual_length
+            .is_none_or(|actual| actual < to - entry.offset)
+        {
+            return Ok(None);
+        }
+
+        if from == entry.offset {
+            progress.open_file(&entry.disk_path);
+        }
+
+        let buffer = &mut data[(from - range.start) as usize..(to - range.start) as usize];
+        match file_bytes::read_exact_at(&entry.disk_path, from - entry.offset, buffer) {
+            Ok(()) => {}
+            // The file was removed or truncated after its length was read.
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::UnexpectedEof
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(entry.io_error(e)),
+        }
+    }
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{verify, FileStatus, PieceStatus, Verifier, VerifyError};
+    use crate::builder::TorrentBuilder;
+    use crate::models::byte_string::ByteString;
+    use crate::models::torrent_file::ProtocolVersion;
+    use crate::test_helpers::test_dir;
+
+    #[test]
+    fn it_should_verify_the_content_a_torrent_was_created_from() {
+        let dir = test_dir("verify-ok");
+        fs::write(dir.join("file"), vec![7; 40000]).unwrap();
+
+        let torrent = TorrentBuilder::new(dir.join("file"))
+            .piece_length(16384)
+            .build()
+            .unwrap();
+        let report = verify(&torrent, dir.join("file")).unwrap();
+
+        assert!(report.is_ok());
+        assert_eq!(report.pieces.len(), 3);
+        assert_eq!(report.files[0].path, PathBuf::from("file"));
+        assert_eq!(report.files[0].pieces, 0..3);
+    }
+
+    #[test]
+    fn it_should_report_corrupt_missing_and_incomplete_files() {
+        let dir = test_dir("verify-bad");
+        let content = dir.join("content");
+        fs::create_dir_all(&content).unwrap();
+        fs::write(content.join("a"), vec![1; 20000]).unwrap();
+        fs::write(content.join("b"), vec![2; 20000]).unwrap();
+        fs::write(content.join("c"), vec![3; 20000]).unwrap();
+        fs::write(content.join("d"), vec![4; 40000]).unwrap();
+
+        let torrent = TorrentBuilder::new(&content)
+            .piece_length(16384)
+            .version(ProtocolVersion::Hybrid)
+            .build()
+            .unwrap();
+
+        let mut corrupt = vec![1; 20000];
+        corrupt[10] = 0;
+        fs::write(content.join("a"), corrupt).unwrap();
+        fs::remove_file(content.join("b")).unwrap();
+        fs::write(content.join("d"), vec![4; 20000]).unwrap();
+
+        let report = verify(&torrent, &content).unwrap();
+
+        // Each file is padded to 2 pieces of 16 KiB, except `d`, which has 3.
+        assert_eq!(
+            report.pieces,
+            [
+                PieceStatus::Corrupt,
+                PieceStatus::Ok,
+                PieceStatus::Missing,
+                PieceStatus::Missing,
+                PieceStatus::Ok,
+                PieceStatus::Ok,
+                PieceStatus::Ok,
+                PieceStatus::Missing,
+                PieceStatus::Missing,
+            ]
+        );
+        let statuses: Vec<_> = report.files.iter().map(|file| file.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                FileStatus::Corrupt,
+                FileStatus::Missing,
+                FileStatus::Ok,
+                FileStatus::Incomplete
+            ]
+        );
+        assert_eq!(report.files[3].pieces, 6..9);
+        assert!(!report.is_ok());
+        assert!((report.completion() - 4.0 / 9.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn it_should_get_the_same_report_in_parallel_as_sequentially() {
+        let dir = test_dir("verify-parallel");
+        let content = dir.join("content");
+        fs::create_dir_all(&content).unwrap();
+        for (index, name) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+            fs::write(content.join(name), vec![index as u8; 30000 + index]).unwrap();
+        }
+        let torrent = TorrentBuilder::new(&content)
+            .piece_length(16384)
+            .build()
+            .unwrap();
+        fs::write(content.join("b"), vec![9; 30001]).unwrap();
+        fs::remove_file(content.join("d")).unwrap();
+
+        let sequential = Verifier::new(&torrent, &content)
+            .threads(1)
+            .verify()
+            .unwrap();
+        // Batches of 4 pieces.
+        let parallel = Verifier::new(&torrent, &content)
+            .threads(4)
+            .read_ahead(3 * 16384)
+            .verify()
+            .unwrap();
+
+        assert_eq!(parallel, sequential);
+        assert_eq!(sequential.pieces.len(), 10);
+        assert!(sequential.pieces.contains(&PieceStatus::Corrupt));
+        assert!(sequential.pieces.contains(&PieceStatus::Missing));
+    }
+
+    #[test]
+    fn it_should_only_check_the_pieces_of_the_selected_files() {
+        let dir = test_dir("verify-selected");
+        let content = dir.join("content");
+        fs::create_dir_all(content.join("Season 01")).unwrap();
+        fs::write(content.join("Season 01/e01.mkv"), vec![1; 20000]).unwrap();
+        fs::write(content.join("Season 01/e01.nfo"), vec![2; 20000]).unwrap();
+        fs::write(content.join("Season 01/e02.mkv"), vec![3; 20000]).unwrap();
+        fs::write(content.join("extra.mkv"), vec![4; 40000]).unwrap();
+        let torrent = TorrentBuilder::new(&content)
+            .piece_length(16384)
+            .build()
+            .unwrap();
+        fs::remove_file(content.join("extra.mkv")).unwrap();
+
+        let report = Verifier::new(&torrent, &content)
+            .file("Season 01/*.mkv")
+            .verify()
+            .unwrap();
+
+        let paths: Vec<_> = report.files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("Season 01/e01.mkv"),
+                PathBuf::from("Season 01/e02.mkv")
+            ]
+        );
+        // e01 is in pieces 0-1 and e02 in 2-3. Pieces 1 and 2 are shared
+        // with the nfo file, and piece 3 with the missing extra.mkv.
+        assert_eq!(
+            report.pieces,
+            [
+                PieceStatus::Ok,
+                PieceStatus::Ok,
+                PieceStatus::Ok,
+                PieceStatus::Missing,
+                PieceStatus::Skipped,
+                PieceStatus::Skipped,
+                PieceStatus::Skipped,
+            ]
+        );
+        assert_eq!(report.checked_pieces(), 4);
+        assert_eq!(report.files[1].status, FileStatus::Incomplete);
+    }
+
+    #[test]
+    fn it_should_reject_paths_outside_the_content_directory() {
+        let dir = test_dir("verify-unsafe");
+        fs::create_dir_all(dir.join("content")).unwrap();
+        fs::write(dir.join("content/a"), b"a").unwrap();
+
+        let mut torrent = TorrentBuilder::new(dir.join("content")).build().unwrap();
+        torrent.info.files.as_mut().unwrap()[0].path =
+            vec![ByteString::from(".."), ByteString::from("a")];
+
+        assert!(matches!(
+            verify(&torrent, dir.join("content")),
+            Err(VerifyError::UnsafePath(path)) if path == "../a"
+        ));
+    }
+
+    #[test]
+    fn it_should_not_verify_v2_only_torrents_yet() {
+        let dir = test_dir("verify-v2");
+        fs::write(dir.join("file"), b"a").unwrap();
+
+        let torrent = TorrentBuilder::new(dir.join("file"))
+            .version(ProtocolVersion::V2)
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            verify(&torrent, dir.join("file")),
+            Err(VerifyError::NoPieces)
+        ));
+    }
+}